@@ -125,6 +125,7 @@ fn main() -> Result<(), Error> {
         device_id,
         sample_rate,
         buffer_size,
+        auto_reconnect: true,
     })?;
 
     // Create player and play file
@@ -132,6 +133,15 @@ fn main() -> Result<(), Error> {
     let mut player = Player::new(output, status_sender);
     player.stop();
 
+    // Report device errors and reconnects
+    let (device_status_sender, device_status_receiver) = sync_channel(32);
+    player.set_output_device_status_sender(Some(device_status_sender));
+    std::thread::spawn(move || {
+        while let Ok(event) = device_status_receiver.recv() {
+            println!("Output device status: {event:?}");
+        }
+    });
+
     let handle = player.play_file(
         &file_path,
         FilePlaybackOptions::default()
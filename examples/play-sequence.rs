@@ -1,12 +1,12 @@
 //! An example showcasing how to build a simple sequencer by scheduling preloaded audio samples
-//! and using sample glide playback parameters.
+//! at musical transport positions and using sample glide playback parameters.
 
 use std::time::Duration;
 
 use phonic::{
     generators::Sampler,
     utils::{ahdsr::AhdsrParameters, speed_from_note},
-    Error, GeneratorPlaybackOptions,
+    Bars, Beats, Error, GeneratorPlaybackOptions,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -63,27 +63,25 @@ fn main() -> Result<(), Error> {
     )?;
 
     // Sequencer timing
-    const BPM: f64 = 120.0;
-    const BEATS_PER_BAR: usize = 4;
     const BARS_TO_PLAY: usize = 4;
 
-    let samples_per_sec = player.output_sample_rate();
-    let samples_per_beat = (60.0 / BPM * samples_per_sec as f64) as u64;
+    let transport = player.transport().clone();
+    transport.set_tempo(120.0)?;
+    let beats_per_bar = transport.time_signature().beats_per_bar() as usize;
 
-    let output_start_time = player.output_sample_frame_position() + samples_per_sec as u64;
+    // Start the transport in a second from now
+    transport.start(player.output_sample_frame_position() + player.output_sample_rate() as u64);
 
     // Schedule metronome beats
-    let mut current_time = output_start_time;
-    for beat in 0..(BEATS_PER_BAR * BARS_TO_PLAY) {
+    for beat in 0..(beats_per_bar * BARS_TO_PLAY) {
         let note = match beat {
-            _ if beat.is_multiple_of(BEATS_PER_BAR) => 72,
+            _ if beat.is_multiple_of(beats_per_bar) => 72,
             _ => 60,
         };
-        metronome.note_on(note, Some(1.0), None, current_time)?;
-        current_time += samples_per_beat;
+        metronome.note_on(note, Some(1.0), None, Beats(beat as f64))?;
     }
     // Stop sampler at the end of the sequence
-    metronome.stop(current_time)?;
+    metronome.stop(Bars(BARS_TO_PLAY as f64))?;
 
     // Schedule bass line with glides (midi_note, duration_in_beats, glide, volume, pan)
     let bass_line = [
@@ -97,30 +95,30 @@ fn main() -> Result<(), Error> {
     ];
 
     // Start bass with the first metronome beat
-    current_time = output_start_time;
+    let mut position = Beats(0.0);
     let mut bass_note_id = None;
 
     for (note, beats, glide, volume, panning) in &bass_line {
         match bass_note_id {
             // Glide existing note
             Some(bass_note_id) if glide.is_some() => {
-                bass.set_note_speed(bass_note_id, speed_from_note(*note), *glide, current_time)?;
+                bass.set_note_speed(bass_note_id, speed_from_note(*note), *glide, position)?;
                 if let Some(volume) = volume {
-                    bass.set_note_volume(bass_note_id, *volume, current_time)?;
+                    bass.set_note_volume(bass_note_id, *volume, position)?;
                 }
                 if let Some(panning) = panning {
-                    bass.set_note_panning(bass_note_id, *panning, current_time)?;
+                    bass.set_note_panning(bass_note_id, *panning, position)?;
                 }
             }
             // Play new note
             _ => {
-                bass_note_id = Some(bass.note_on(*note, *volume, *panning, current_time)?);
+                bass_note_id = Some(bass.note_on(*note, *volume, *panning, position)?);
             }
         }
-        current_time += (*beats * samples_per_beat as f64) as u64;
+        position = position + Beats(*beats);
     }
-    // Stop sampler at the end of the metronome sequence
-    bass.stop((BEATS_PER_BAR * BARS_TO_PLAY + 1) as u64 * samples_per_beat)?;
+    // Stop sampler a beat after the end of the metronome sequence
+    bass.stop(transport.bars_to_beats(Bars(BARS_TO_PLAY as f64)) + Beats(1.0))?;
 
     // Print player graph
     println!("\nPlayer Graph:\n{}", player);
@@ -2,7 +2,11 @@ use std::any::Any;
 
 use four_cc::FourCC;
 
-use crate::{parameter::ParameterValueUpdate, Error, Parameter, SourceTime};
+use crate::{
+    modulation::{ModulationSource, ModulationTarget},
+    parameter::ParameterValueUpdate,
+    Error, Parameter, SourceTime,
+};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -17,6 +21,8 @@ pub mod gate;
 pub mod pan;
 pub mod reverb;
 
+pub(crate) mod modulation;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Carries [`Effect`]-specific payloads/automation, which can't or should not be expressed as
@@ -63,6 +69,9 @@ pub type EffectTime = SourceTime;
 /// in series. Effects are added to mixers via [`Player::add_effect()`](crate::Player::add_effect),
 /// which returns an [`EffectHandle`](crate::EffectHandle) for controlling the effect at runtime.
 ///
+/// Effects can also be inserted into single file, synth or generator sources, e.g. via
+/// [`Player::play_file_source_with_effects()`](crate::Player::play_file_source_with_effects).
+///
 /// Buffers are processed in-place in the audio real-time thread. Effects can be `Send` and
 /// `Sync`ed across threads.
 ///
@@ -81,6 +90,21 @@ pub type EffectTime = SourceTime;
 /// If you need to pass around custom shared data from the effect to UIs (e.g. playback states,
 /// audio meter values), use channels or atomics instead - as usual in Rust.
 ///
+/// ## Modulation
+///
+/// Like generators, effects can optionally provide a modulation system where a set of modulation
+/// sources (LFOs, envelope followers, step sequencers) can be routed to modulatable target
+/// parameters with an user-configurable depth. Routings are configured at runtime via
+/// [`EffectHandle::set_modulation`](crate::EffectHandle::set_modulation).
+///
+/// To enable modulation in custom effects:
+/// - Implement [`modulation_sources()`](Self::modulation_sources) to define available modulation sources
+/// - Implement [`modulation_targets()`](Self::modulation_targets) to define parameters that can be modulated
+/// - Implement [`set_modulation()`](Self::set_modulation) and [`clear_modulation()`](Self::clear_modulation)
+///   to configure modulation routings
+///
+/// See [`ModulationSource`] and [`ModulationTarget`] for more details.
+///
 /// NB: all `process_XXX` functions are called in realtime audio threads, so they must not
 /// block! All other functions are called in the main thread to initialize the effect.
 pub trait Effect: Send + Sync + 'static {
@@ -154,6 +178,29 @@ pub trait Effect: Send + Sync + 'static {
     /// representations of the given output buffer as needed.
     fn process(&mut self, output: &mut [f32], time: &EffectTime);
 
+    /// Returns true when the effect makes use of an external sidechain input signal.
+    ///
+    /// Only effects which support sidechains can get a sidechain input assigned via
+    /// [`Player::set_effect_sidechain`](crate::Player::set_effect_sidechain).
+    fn supports_sidechain(&self) -> bool {
+        false
+    }
+
+    /// Processes an audio buffer in-place, using the given sidechain signal as secondary input.
+    ///
+    /// `sidechain` is an interleaved buffer with the same channel layout and length as `output`,
+    /// which contains the output of the mixer that got assigned as sidechain source. Effects
+    /// typically use it to drive their detectors, e.g. for ducking compressors or keyed gates.
+    ///
+    /// This is only called for effects which [support sidechains](Self::supports_sidechain) and
+    /// when a sidechain got assigned. The default impl ignores the sidechain and calls `process`.
+    ///
+    /// Like `process`, this method must not block, allocate memory, or do other time-consuming tasks.
+    fn process_with_sidechain(&mut self, output: &mut [f32], sidechain: &[f32], time: &EffectTime) {
+        let _ = sidechain;
+        self.process(output, time)
+    }
+
     /// Returns the number of audible sample frames this effect will produce, after it received
     /// silence.
     ///
@@ -168,6 +215,17 @@ pub trait Effect: Send + Sync + 'static {
         None
     }
 
+    /// Returns the processing latency of the effect in sample frames, e.g. the lookahead time of
+    /// a compressor or the delay of a linear phase filter.
+    ///
+    /// Mixers use this to delay-compensate parallel signal paths, so all signals which get summed
+    /// stay sample-aligned. The latency may change at runtime, e.g. after parameter changes.
+    ///
+    /// Like `process`, this method must not block, allocate memory, or do other time-consuming tasks.
+    fn latency(&self) -> usize {
+        0
+    }
+
     /// Handles a parameter update in the real-time thread.
     ///
     /// This method is called on the real-time audio thread when a parameter change is scheduled
@@ -199,6 +257,55 @@ pub trait Effect: Send + Sync + 'static {
         Ok(())
     }
 
+    /// Optional modulation sources for this effect. By default none.
+    ///
+    /// When returning sources here, implement the rest of the modulation interface as well!
+    fn modulation_sources(&self) -> Vec<ModulationSource> {
+        vec![]
+    }
+
+    /// Returns parameters that can receive modulation. By default none.
+    ///
+    /// When returning targets here, implement the rest of the modulation interface as well!
+    fn modulation_targets(&self) -> Vec<ModulationTarget> {
+        vec![]
+    }
+
+    /// Set or update a modulation routing in the real-time thread.
+    ///
+    /// # Arguments
+    /// * `source` - Modulation source ID (must be one in `Self::modulation_sources()`)
+    /// * `target` - Target parameter ID (must be one in `Self::modulation_targets()`)
+    /// * `amount` - Modulation amount (-1.0..=1.0)
+    /// * `bipolar` - If true, transforms unipolar sources (0.0-1.0) to bipolar (-1.0..1.0)
+    ///   centered at 0.5.
+    ///
+    /// Returns error if source or target is invalid.
+    ///
+    /// Like `process`, this method must not block, allocate memory, or do other time-consuming tasks.
+    fn set_modulation(
+        &mut self,
+        _source: FourCC,
+        _target: FourCC,
+        _amount: f32,
+        _bipolar: bool,
+    ) -> Result<(), Error> {
+        // Default: not supported
+        Err(Error::ParameterError(
+            "Modulation routing not supported by this effect".to_string(),
+        ))
+    }
+
+    /// Remove a modulation routing in the real-time thread.
+    ///
+    /// Like `process`, this method must not block, allocate memory, or do other time-consuming tasks.
+    fn clear_modulation(&mut self, _source: FourCC, _target: FourCC) -> Result<(), Error> {
+        // Default: not supported
+        Err(Error::ParameterError(
+            "Modulation routing not supported by this effect".to_string(),
+        ))
+    }
+
     /// Handles optional effect-specific messages in the real-time thread. This can be used to pass
     /// payloads to the effects, which can or should not be expressed as a trivial parameter change.
     ///
@@ -255,10 +362,22 @@ impl Effect for Box<dyn Effect> {
         (**self).process(output, time)
     }
 
+    fn supports_sidechain(&self) -> bool {
+        (**self).supports_sidechain()
+    }
+
+    fn process_with_sidechain(&mut self, output: &mut [f32], sidechain: &[f32], time: &EffectTime) {
+        (**self).process_with_sidechain(output, sidechain, time)
+    }
+
     fn process_tail(&self) -> Option<usize> {
         (**self).process_tail()
     }
 
+    fn latency(&self) -> usize {
+        (**self).latency()
+    }
+
     fn process_parameter_update(
         &mut self,
         id: FourCC,
@@ -267,6 +386,28 @@ impl Effect for Box<dyn Effect> {
         (**self).process_parameter_update(id, value)
     }
 
+    fn modulation_sources(&self) -> Vec<ModulationSource> {
+        (**self).modulation_sources()
+    }
+
+    fn modulation_targets(&self) -> Vec<ModulationTarget> {
+        (**self).modulation_targets()
+    }
+
+    fn set_modulation(
+        &mut self,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+    ) -> Result<(), Error> {
+        (**self).set_modulation(source, target, amount, bipolar)
+    }
+
+    fn clear_modulation(&mut self, source: FourCC, target: FourCC) -> Result<(), Error> {
+        (**self).clear_modulation(source, target)
+    }
+
     fn process_message(&mut self, message: &EffectMessagePayload) -> Result<(), Error> {
         (**self).process_message(message)
     }
@@ -4,7 +4,10 @@ use four_cc::FourCC;
 use strum::VariantNames;
 
 use crate::{
-    effect::{Effect, EffectMessage, EffectMessagePayload, EffectTime},
+    effect::{
+        modulation::EffectModulation, Effect, EffectMessage, EffectMessagePayload, EffectTime,
+    },
+    modulation::{ModulationSource, ModulationTarget},
     parameter::{
         formatters, EnumParameter, EnumParameterValue, FloatParameter, ParameterValueUpdate,
         SmoothedParameterValue,
@@ -48,6 +51,9 @@ pub type ChorusEffectFilterType = SvfFilterType;
 // -------------------------------------------------------------------------------------------------
 
 /// Stereo chorus effect with an filtered, interpolated delay-line.
+///
+/// Depth, feedback and wet mix can be modulated by an LFO, an envelope follower and a step
+/// sequencer, see [`EffectHandle::set_modulation`](crate::EffectHandle::set_modulation).
 pub struct ChorusEffect {
     sample_rate: u32,
     channel_count: usize,
@@ -61,6 +67,7 @@ pub struct ChorusEffect {
     filter_type: EnumParameterValue<ChorusEffectFilterType>,
     filter_freq: SmoothedParameterValue,
     filter_resonance: SmoothedParameterValue,
+    modulation: EffectModulation,
     // Runtime data
     lfo_range: f32,
     current_phase: f64,
@@ -136,6 +143,11 @@ impl ChorusEffect {
     pub const FILTER_RESONANCE: FloatParameter =
         FloatParameter::new(FourCC(*b"fltq"), "Filter Resonance", 0.0..=1.0, 0.);
 
+    // Modulation sources
+    pub const MOD_SOURCE_LFO: FourCC = EffectModulation::SOURCE_LFO;
+    pub const MOD_SOURCE_FOLLOWER: FourCC = EffectModulation::SOURCE_FOLLOWER;
+    pub const MOD_SOURCE_STEPS: FourCC = EffectModulation::SOURCE_STEPS;
+
     const MAX_APPLIED_RANGE_IN_SAMPLES: f32 = 256.0;
     const MAX_APPLIED_DELAY_IN_MS: f32 = 100.0;
 
@@ -157,6 +169,7 @@ impl ChorusEffect {
             filter_type: EnumParameterValue::from_description(Self::FILTER_TYPE),
             filter_freq: SmoothedParameterValue::from_description(Self::FILTER_FREQ),
             filter_resonance: SmoothedParameterValue::from_description(Self::FILTER_RESONANCE),
+            modulation: EffectModulation::new(&[Self::DEPTH, Self::FEEDBACK, Self::WET_MIX]),
 
             lfo_range: 0.0,
             current_phase: 0.0,
@@ -247,7 +260,7 @@ impl Effect for ChorusEffect {
     }
 
     fn parameters(&self) -> Vec<&dyn Parameter> {
-        vec![
+        let mut parameters: Vec<&dyn Parameter> = vec![
             self.rate.description(),
             self.depth.description(),
             self.feedback.description(),
@@ -257,7 +270,9 @@ impl Effect for ChorusEffect {
             self.filter_type.description(),
             self.filter_freq.description(),
             self.filter_resonance.description(),
-        ]
+        ];
+        parameters.extend(self.modulation.parameters());
+        parameters
     }
 
     fn initialize(
@@ -282,6 +297,7 @@ impl Effect for ChorusEffect {
         self.wet_mix.set_sample_rate(sample_rate);
         self.filter_freq.set_sample_rate(sample_rate);
         self.filter_resonance.set_sample_rate(sample_rate);
+        self.modulation.initialize(sample_rate, channel_count);
 
         self.lfo_range = Self::MAX_APPLIED_RANGE_IN_SAMPLES * (self.sample_rate as f32 / 44100.0);
         let max_depth_in_samples = self.lfo_range.ceil() as usize;
@@ -308,27 +324,42 @@ impl Effect for ChorusEffect {
         Ok(())
     }
 
-    fn process(&mut self, mut output: &mut [f32], _time: &EffectTime) {
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
         assert!(self.channel_count == 2);
-        for frame in output.as_frames_mut::<2>() {
-            let left_input = frame[0];
-            let right_input = frame[1];
-
-            let delay_ms = self.delay.next_value();
-            let depth = self.depth.next_value();
-            let feedback = self.feedback.next_value().clamp(-0.999, 0.999);
-            let wet_mix = self.wet_mix.next_value();
-            let wet_amount = wet_mix;
-            let dry_amount = 1.0 - wet_mix;
-
-            // ramp and update lfos, if needed
-            if self.rate.value_need_ramp() || self.phase.value_need_ramp() {
-                self.update_lfos();
-            }
-
-            // Filter the inputs
-            let (filtered_left, filtered_right) =
-                if self.filter_freq.value_need_ramp() || self.filter_resonance.value_need_ramp() {
+        let block_len = EffectModulation::BLOCK_SIZE * self.channel_count;
+        for mut block in output.chunks_mut(block_len) {
+            self.modulation.process(block);
+            for (frame_index, frame) in block.as_frames_mut::<2>().iter_mut().enumerate() {
+                let left_input = frame[0];
+                let right_input = frame[1];
+
+                let delay_ms = self.delay.next_value();
+                let depth = self.modulation.modulated_value(
+                    &Self::DEPTH,
+                    self.depth.next_value(),
+                    frame_index,
+                );
+                let feedback = self
+                    .modulation
+                    .modulated_value(&Self::FEEDBACK, self.feedback.next_value(), frame_index)
+                    .clamp(-0.999, 0.999);
+                let wet_mix = self.modulation.modulated_value(
+                    &Self::WET_MIX,
+                    self.wet_mix.next_value(),
+                    frame_index,
+                );
+                let wet_amount = wet_mix;
+                let dry_amount = 1.0 - wet_mix;
+
+                // ramp and update lfos, if needed
+                if self.rate.value_need_ramp() || self.phase.value_need_ramp() {
+                    self.update_lfos();
+                }
+
+                // Filter the inputs
+                let (filtered_left, filtered_right) = if self.filter_freq.value_need_ramp()
+                    || self.filter_resonance.value_need_ramp()
+                {
                     let cutoff = self
                         .filter_freq
                         .next_value()
@@ -359,30 +390,35 @@ impl Effect for ChorusEffect {
                     (filtered_left, filtered_right)
                 };
 
-            // Run the LFOs
-            let delay_in_samples = delay_ms * self.sample_rate as f32 * 0.001;
-            let depth_in_samples = self.lfo_range * depth;
-
-            let left_lfo = self.left_osc.run();
-            let right_lfo = self.right_osc.run();
-
-            let left_delay_pos = 2.0 + delay_in_samples + (1.0 + left_lfo) * depth_in_samples;
-            let right_delay_pos = 2.0 + delay_in_samples + (1.0 + right_lfo) * depth_in_samples;
-
-            // Feed the delays
-            let left_output =
-                self.delay_buffer_left
-                    .process([filtered_left as f32], feedback, left_delay_pos)[0];
-            let right_output =
-                self.delay_buffer_right
-                    .process([filtered_right as f32], feedback, right_delay_pos)[0];
-
-            // Calc the Output
-            let out_l = left_input * dry_amount + left_output * wet_amount;
-            let out_r = right_input * dry_amount + right_output * wet_amount;
-
-            frame[0] = out_l;
-            frame[1] = out_r;
+                // Run the LFOs
+                let delay_in_samples = delay_ms * self.sample_rate as f32 * 0.001;
+                let depth_in_samples = self.lfo_range * depth;
+
+                let left_lfo = self.left_osc.run();
+                let right_lfo = self.right_osc.run();
+
+                let left_delay_pos = 2.0 + delay_in_samples + (1.0 + left_lfo) * depth_in_samples;
+                let right_delay_pos = 2.0 + delay_in_samples + (1.0 + right_lfo) * depth_in_samples;
+
+                // Feed the delays
+                let left_output = self.delay_buffer_left.process(
+                    [filtered_left as f32],
+                    feedback,
+                    left_delay_pos,
+                )[0];
+                let right_output = self.delay_buffer_right.process(
+                    [filtered_right as f32],
+                    feedback,
+                    right_delay_pos,
+                )[0];
+
+                // Calc the Output
+                let out_l = left_input * dry_amount + left_output * wet_amount;
+                let out_r = right_input * dry_amount + right_output * wet_amount;
+
+                frame[0] = out_l;
+                frame[1] = out_r;
+            }
         }
 
         // Move our LFO offset to keep our oscillators updated when changing the rate or phase
@@ -443,6 +479,9 @@ impl Effect for ChorusEffect {
             _ if id == Self::FILTER_TYPE.id() => self.filter_type.apply_update(value),
             _ if id == Self::FILTER_FREQ.id() => self.filter_freq.apply_update(value),
             _ if id == Self::FILTER_RESONANCE.id() => self.filter_resonance.apply_update(value),
+            _ if self.modulation.is_source_parameter(id) => {
+                self.modulation.apply_parameter_update(id, value)
+            }
             _ => {
                 return Err(Error::ParameterError(format!(
                     "Unknown parameter: '{id}' for effect '{}'",
@@ -457,4 +496,27 @@ impl Effect for ChorusEffect {
             _ => Ok(()),
         }
     }
+
+    fn modulation_sources(&self) -> Vec<ModulationSource> {
+        self.modulation.sources()
+    }
+
+    fn modulation_targets(&self) -> Vec<ModulationTarget> {
+        self.modulation.targets()
+    }
+
+    fn set_modulation(
+        &mut self,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+    ) -> Result<(), Error> {
+        self.modulation
+            .set_modulation(source, target, amount, bipolar)
+    }
+
+    fn clear_modulation(&mut self, source: FourCC, target: FourCC) -> Result<(), Error> {
+        self.modulation.clear_modulation(source, target)
+    }
 }
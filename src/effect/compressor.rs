@@ -19,7 +19,11 @@ use crate::{
 /// Stereo compressor effect with limiter mode, lookahead and soft-knee.
 ///
 /// When ratio is above 20.0 it acts as a hard-limiter.
-/// Note that the compressor will introduce latency when lookahead is used.
+/// Note that the compressor will introduce latency when lookahead is used, which is reported
+/// via [`Effect::latency`] and compensated by the mixer graph.
+///
+/// When a sidechain input is assigned, the sidechain signal instead of the input signal drives
+/// the compressor's detector, e.g. to duck music under a voice-over.
 pub struct CompressorEffect {
     // Effect configuration
     sample_rate: u32,
@@ -164,6 +168,87 @@ impl CompressorEffect {
                 .set_release_time(self.release_time.value());
         }
     }
+
+    fn process_frames(&mut self, mut output: &mut [f32], sidechain: Option<&[f32]>) {
+        assert!(self.channel_count == 2);
+
+        // Copy input to a temporary buffer because we read from it while writing to `output`
+        let input = &mut self.input_buffer[..output.len()];
+        copy_buffers(input, output);
+        let input_frames = input.as_frames::<2>();
+        let sidechain_frames = sidechain.map(|sidechain| sidechain.as_frames::<2>());
+
+        for (index, (out_frame, in_frame)) in output
+            .as_frames_mut::<2>()
+            .iter_mut()
+            .zip(input_frames)
+            .enumerate()
+        {
+            // Get delayed frame from delay line (or original frame if no delay)
+            let delayed_frame = self.delay_line.process(in_frame);
+
+            // Envelope detection on current (undelayed) input or sidechain
+            let input_db = if let Some(sidechain_frames) = sidechain_frames {
+                // Sidechain mode: use peak of current sidechain frame.
+                let sidechain_frame = &sidechain_frames[index];
+                let frame_peak = sidechain_frame[0].abs().max(sidechain_frame[1].abs());
+                if frame_peak > 1e-6 {
+                    20.0 * frame_peak.log10()
+                } else {
+                    -120.0
+                }
+            } else if self.ratio.value() >= 20.0 {
+                // Limiter mode: use peak from the entire lookahead buffer to prevent overshoots.
+                let lookahead_peak = self.delay_line.peak_value();
+                if lookahead_peak > 1e-6 {
+                    20.0 * lookahead_peak.log10()
+                } else {
+                    -120.0
+                }
+            } else {
+                // Compressor mode: use peak of current frame.
+                let frame_peak = in_frame[0].abs().max(in_frame[1].abs());
+                if frame_peak > 1e-6 {
+                    20.0 * frame_peak.log10()
+                } else {
+                    -120.0
+                }
+            };
+
+            // Process envelope
+            let envelope = self.envelope_follower.run(input_db);
+
+            // Gain reduction calculation
+            let t = self.threshold.value();
+            let w = self.knee_width.value();
+            let slope = if self.ratio.value() >= 20.0 {
+                1.0
+            } else {
+                1.0 - 1.0 / self.ratio.value()
+            };
+
+            let gr_db = if w > 0.0 && envelope > (t - w / 2.0) && envelope < (t + w / 2.0) {
+                // In knee (soft knee)
+                let knee_lower = t - w / 2.0;
+                let x = (envelope - knee_lower) / w;
+                x * x * slope * w / 2.0
+            } else if envelope > (t + w / 2.0) {
+                // Above knee (hard knee part)
+                (envelope - t) * slope
+            } else {
+                // Below knee
+                0.0
+            };
+
+            // Apply gain to delayed signal
+            let makeup_gain = self.makeup_gain.next_value();
+            let total_gain_db = makeup_gain - gr_db;
+            let total_gain = db_to_linear(total_gain_db);
+
+            out_frame[0] = delayed_frame[0] * total_gain;
+            out_frame[1] = delayed_frame[1] * total_gain;
+        }
+    }
 }
 
 impl Default for CompressorEffect {
@@ -227,70 +312,22 @@ impl Effect for CompressorEffect {
         Ok(())
     }
 
-    fn process(&mut self, mut output: &mut [f32], _time: &EffectTime) {
-        assert!(self.channel_count == 2);
-
-        // Copy input to a temporary buffer because we read from it while writing to `output`
-        let input = &mut self.input_buffer[..output.len()];
-        copy_buffers(input, output);
-        let input_frames = input.as_frames::<2>();
-
-        for (out_frame, in_frame) in output.as_frames_mut::<2>().iter_mut().zip(input_frames) {
-            // Get delayed frame from delay line (or original frame if no delay)
-            let delayed_frame = self.delay_line.process(in_frame);
-
-            // Envelope detection on current (undelayed) input
-            let input_db = if self.ratio.value() >= 20.0 {
-                // Limiter mode: use peak from the entire lookahead buffer to prevent overshoots.
-                let lookahead_peak = self.delay_line.peak_value();
-                if lookahead_peak > 1e-6 {
-                    20.0 * lookahead_peak.log10()
-                } else {
-                    -120.0
-                }
-            } else {
-                // Compressor mode: use peak of current frame.
-                let frame_peak = in_frame[0].abs().max(in_frame[1].abs());
-                if frame_peak > 1e-6 {
-                    20.0 * frame_peak.log10()
-                } else {
-                    -120.0
-                }
-            };
-
-            // Process envelope
-            let envelope = self.envelope_follower.run(input_db);
-
-            // Gain reduction calculation
-            let t = self.threshold.value();
-            let w = self.knee_width.value();
-            let slope = if self.ratio.value() >= 20.0 {
-                1.0
-            } else {
-                1.0 - 1.0 / self.ratio.value()
-            };
-
-            let gr_db = if w > 0.0 && envelope > (t - w / 2.0) && envelope < (t + w / 2.0) {
-                // In knee (soft knee)
-                let knee_lower = t - w / 2.0;
-                let x = (envelope - knee_lower) / w;
-                x * x * slope * w / 2.0
-            } else if envelope > (t + w / 2.0) {
-                // Above knee (hard knee part)
-                (envelope - t) * slope
-            } else {
-                // Below knee
-                0.0
-            };
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        self.process_frames(output, None);
+    }
 
-            // Apply gain to delayed signal
-            let makeup_gain = self.makeup_gain.next_value();
-            let total_gain_db = makeup_gain - gr_db;
-            let total_gain = db_to_linear(total_gain_db);
+    fn supports_sidechain(&self) -> bool {
+        true
+    }
 
-            out_frame[0] = delayed_frame[0] * total_gain;
-            out_frame[1] = delayed_frame[1] * total_gain;
-        }
+    fn process_with_sidechain(
+        &mut self,
+        output: &mut [f32],
+        sidechain: &[f32],
+        _time: &EffectTime,
+    ) {
+        debug_assert_eq!(output.len(), sidechain.len(), "Invalid sidechain buffer");
+        self.process_frames(output, Some(sidechain));
     }
 
     fn process_tail(&self) -> Option<usize> {
@@ -301,6 +338,10 @@ impl Effect for CompressorEffect {
         Some(lookahead_samples + release_samples)
     }
 
+    fn latency(&self) -> usize {
+        self.delay_line.delay_frames()
+    }
+
     fn process_parameter_update(
         &mut self,
         id: FourCC,
@@ -2,7 +2,8 @@ use four_cc::FourCC;
 use strum::VariantNames;
 
 use crate::{
-    effect::{Effect, EffectTime},
+    effect::{modulation::EffectModulation, Effect, EffectTime},
+    modulation::{ModulationSource, ModulationTarget},
     parameter::{
         EnumParameter, EnumParameterValue, FloatParameter, ParameterValueUpdate,
         SmoothedParameterValue,
@@ -44,6 +45,9 @@ impl From<FilterEffectType> for BiquadFilterType {
 // -------------------------------------------------------------------------------------------------
 
 /// Multi-channel filter effect tht applies an SVF biquad filter with configurable filter types.
+///
+/// Cutoff and resonance can be modulated by an LFO, an envelope follower and a step sequencer,
+/// see [`EffectHandle::set_modulation`](crate::EffectHandle::set_modulation).
 #[derive(Clone)]
 pub struct FilterEffect {
     channel_count: usize,
@@ -53,6 +57,7 @@ pub struct FilterEffect {
     filter_type: EnumParameterValue<FilterEffectType>,
     cutoff: SmoothedParameterValue,
     q: SmoothedParameterValue<LinearSmoothedValue>,
+    modulation: EffectModulation,
 }
 
 impl FilterEffect {
@@ -80,6 +85,11 @@ impl FilterEffect {
         0.707, //
     );
 
+    // Modulation sources
+    pub const MOD_SOURCE_LFO: FourCC = EffectModulation::SOURCE_LFO;
+    pub const MOD_SOURCE_FOLLOWER: FourCC = EffectModulation::SOURCE_FOLLOWER;
+    pub const MOD_SOURCE_STEPS: FourCC = EffectModulation::SOURCE_STEPS;
+
     /// Creates a new `FilterEffect` with default parameter values.
     pub fn new() -> Self {
         Self {
@@ -97,6 +107,7 @@ impl FilterEffect {
             filter_type: EnumParameterValue::from_description(Self::TYPE),
             cutoff: SmoothedParameterValue::from_description(Self::CUTOFF),
             q: SmoothedParameterValue::from_description(Self::Q),
+            modulation: EffectModulation::new(&[Self::CUTOFF, Self::Q]),
         }
     }
 
@@ -113,6 +124,26 @@ impl FilterEffect {
             .expect("Invalid filter parameters");
         filter
     }
+
+    /// Restore unmodulated filter coefficients after all modulation routings got removed.
+    fn reset_modulated_coefficients(&mut self) {
+        if !self.modulation.is_active() {
+            let cutoff = self
+                .cutoff
+                .current_value()
+                .clamp(20.0, self.sample_rate as f32 / 2.0);
+            let q = self.q.current_value();
+            if let Err(err) = self.filter_coefficients.set(
+                self.filter_type.value().into(),
+                self.sample_rate,
+                cutoff,
+                q,
+                0.0,
+            ) {
+                log::error!("Failed to apply new filter parameters: {err}");
+            }
+        }
+    }
 }
 
 impl Default for FilterEffect {
@@ -131,11 +162,13 @@ impl Effect for FilterEffect {
     }
 
     fn parameters(&self) -> Vec<&dyn Parameter> {
-        vec![
+        let mut parameters: Vec<&dyn Parameter> = vec![
             self.filter_type.description(),
             self.cutoff.description(),
             self.q.description(),
-        ]
+        ];
+        parameters.extend(self.modulation.parameters());
+        parameters
     }
 
     fn initialize(
@@ -160,12 +193,44 @@ impl Effect for FilterEffect {
         self.cutoff.set_sample_rate(sample_rate);
         self.q.set_sample_rate(sample_rate);
 
+        self.modulation.initialize(sample_rate, channel_count);
+
         Ok(())
     }
 
     fn process(&mut self, mut buffer: &mut [f32], _time: &EffectTime) {
-        // Apply filter with parameter ramping
-        if self.cutoff.value_need_ramp() || self.q.value_need_ramp() {
+        if self.modulation.is_active() {
+            // Apply filter with parameter ramping and modulation
+            let block_len = EffectModulation::BLOCK_SIZE * self.channel_count;
+            for mut block in buffer.chunks_mut(block_len) {
+                self.modulation.process(block);
+                for (frame_index, frame) in block.frames_mut(self.channel_count).enumerate() {
+                    let cutoff = self
+                        .modulation
+                        .modulated_value(&Self::CUTOFF, self.cutoff.next_value(), frame_index)
+                        .clamp(20.0, self.sample_rate as f32 / 2.0);
+                    let q =
+                        self.modulation
+                            .modulated_value(&Self::Q, self.q.next_value(), frame_index);
+                    self.filter_coefficients
+                        .set(
+                            self.filter_type.value().into(),
+                            self.sample_rate,
+                            cutoff,
+                            q,
+                            0.0,
+                        )
+                        .expect("Invalid filter parameters");
+                    for (sample, filter) in frame.zip(self.filters.iter_mut()) {
+                        *sample = filter.process_sample(
+                            &self.filter_coefficients,
+                            *sample as f64, //
+                        ) as f32;
+                    }
+                }
+            }
+        } else if self.cutoff.value_need_ramp() || self.q.value_need_ramp() {
+            // Apply filter with parameter ramping
             for frame in buffer.frames_mut(self.channel_count) {
                 let cutoff = self
                     .cutoff
@@ -215,6 +280,9 @@ impl Effect for FilterEffect {
             _ if id == Self::TYPE.id() => self.filter_type.apply_update(value),
             _ if id == Self::CUTOFF.id() => self.cutoff.apply_update(value),
             _ if id == Self::Q.id() => self.q.apply_update(value),
+            _ if self.modulation.is_source_parameter(id) => {
+                self.modulation.apply_parameter_update(id, value)
+            }
             _ => {
                 return Err(Error::ParameterError(format!(
                     "Unknown parameter: '{id}' for effect '{}'",
@@ -235,4 +303,31 @@ impl Effect for FilterEffect {
         }
         Ok(())
     }
+
+    fn modulation_sources(&self) -> Vec<ModulationSource> {
+        self.modulation.sources()
+    }
+
+    fn modulation_targets(&self) -> Vec<ModulationTarget> {
+        self.modulation.targets()
+    }
+
+    fn set_modulation(
+        &mut self,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+    ) -> Result<(), Error> {
+        self.modulation
+            .set_modulation(source, target, amount, bipolar)?;
+        self.reset_modulated_coefficients();
+        Ok(())
+    }
+
+    fn clear_modulation(&mut self, source: FourCC, target: FourCC) -> Result<(), Error> {
+        self.modulation.clear_modulation(source, target)?;
+        self.reset_modulated_coefficients();
+        Ok(())
+    }
 }
@@ -3,13 +3,17 @@ use four_cc::FourCC;
 use crate::{
     effect::{Effect, EffectTime},
     parameter::{formatters, FloatParameter, FloatParameterValue, ParameterValueUpdate},
-    utils::{buffer::InterleavedBufferMut, db_to_linear, dsp::envelope::EnvelopeFollower},
+    utils::{
+        buffer::{InterleavedBuffer, InterleavedBufferMut},
+        db_to_linear,
+        dsp::envelope::EnvelopeFollower,
+    },
     Error, Parameter,
 };
 
 // -------------------------------------------------------------------------------------------------
 
-/// Stereo noise gate effect with hold and configurable floor level.
+/// Stereo noise gate effect with hold, configurable floor level and optional sidechain input.
 pub struct GateEffect {
     // Parameters
     threshold: FloatParameterValue,
@@ -92,6 +96,59 @@ impl GateEffect {
             self.release_coeff = (-1.0 / (self.release_time.value() * sr)).exp();
         }
     }
+
+    fn process_frames(&mut self, mut output: &mut [f32], sidechain: Option<&[f32]>) {
+        debug_assert!(self.channel_count == 2);
+
+        let threshold = self.threshold.value();
+        let range_db = self.range.value();
+        let hold_samples = (self.hold_time.value() * self.sample_rate as f32) as u32;
+
+        let sidechain_frames = sidechain.map(|sidechain| sidechain.as_frames::<2>());
+
+        for (index, frame) in output.as_frames_mut::<2>().iter_mut().enumerate() {
+            // Peak detection in dB, using the sidechain signal as key input when present
+            let key_frame = sidechain_frames.map_or(&*frame, |frames| &frames[index]);
+            let frame_peak = key_frame[0].abs().max(key_frame[1].abs());
+            let input_db = if frame_peak > 1e-6 {
+                20.0 * frame_peak.log10()
+            } else {
+                -120.0
+            };
+
+            // Envelope detection (smoothed level)
+            let envelope = self.envelope_follower.run(input_db);
+
+            // Gate state: open / hold / closed
+            let target_gain_db = if envelope >= threshold {
+                self.hold_counter = hold_samples;
+                0.0 // gate open
+            } else if self.hold_counter > 0 {
+                self.hold_counter -= 1;
+                0.0 // hold phase
+            } else {
+                range_db // gate closed
+            };
+
+            // Smooth gate gain with attack/release coefficients
+            self.gate_gain_db = if target_gain_db > self.gate_gain_db {
+                // Opening: attack
+                self.attack_coeff * self.gate_gain_db + (1.0 - self.attack_coeff) * target_gain_db
+            } else {
+                // Closing: release
+                self.release_coeff * self.gate_gain_db + (1.0 - self.release_coeff) * target_gain_db
+            };
+
+            // Apply gain
+            let gain = if self.gate_gain_db <= -60.0 {
+                0.0
+            } else {
+                db_to_linear(self.gate_gain_db)
+            };
+            frame[0] *= gain;
+            frame[1] *= gain;
+        }
+    }
 }
 
 impl Default for GateEffect {
@@ -144,54 +201,22 @@ impl Effect for GateEffect {
         Ok(())
     }
 
-    fn process(&mut self, mut output: &mut [f32], _time: &EffectTime) {
-        debug_assert!(self.channel_count == 2);
-
-        let threshold = self.threshold.value();
-        let range_db = self.range.value();
-        let hold_samples = (self.hold_time.value() * self.sample_rate as f32) as u32;
-
-        for frame in output.as_frames_mut::<2>() {
-            // Peak detection in dB
-            let frame_peak = frame[0].abs().max(frame[1].abs());
-            let input_db = if frame_peak > 1e-6 {
-                20.0 * frame_peak.log10()
-            } else {
-                -120.0
-            };
-
-            // Envelope detection (smoothed level)
-            let envelope = self.envelope_follower.run(input_db);
-
-            // Gate state: open / hold / closed
-            let target_gain_db = if envelope >= threshold {
-                self.hold_counter = hold_samples;
-                0.0 // gate open
-            } else if self.hold_counter > 0 {
-                self.hold_counter -= 1;
-                0.0 // hold phase
-            } else {
-                range_db // gate closed
-            };
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        self.process_frames(output, None);
+    }
 
-            // Smooth gate gain with attack/release coefficients
-            self.gate_gain_db = if target_gain_db > self.gate_gain_db {
-                // Opening: attack
-                self.attack_coeff * self.gate_gain_db + (1.0 - self.attack_coeff) * target_gain_db
-            } else {
-                // Closing: release
-                self.release_coeff * self.gate_gain_db + (1.0 - self.release_coeff) * target_gain_db
-            };
+    fn supports_sidechain(&self) -> bool {
+        true
+    }
 
-            // Apply gain
-            let gain = if self.gate_gain_db <= -60.0 {
-                0.0
-            } else {
-                db_to_linear(self.gate_gain_db)
-            };
-            frame[0] *= gain;
-            frame[1] *= gain;
-        }
+    fn process_with_sidechain(
+        &mut self,
+        output: &mut [f32],
+        sidechain: &[f32],
+        _time: &EffectTime,
+    ) {
+        debug_assert_eq!(output.len(), sidechain.len(), "Invalid sidechain buffer");
+        self.process_frames(output, Some(sidechain));
     }
 
     fn process_tail(&self) -> Option<usize> {
@@ -0,0 +1,394 @@
+//! Modulation sources and state management for effects.
+
+use four_cc::FourCC;
+use strum::VariantNames;
+
+use crate::{
+    modulation::{
+        matrix::ModulationMatrix, processor::MODULATION_PROCESSOR_BLOCK_SIZE,
+        state::ModulationState, ModulationConfig, ModulationSource, ModulationTarget,
+    },
+    parameter::{
+        EnumParameter, EnumParameterValue, FloatParameter, FloatParameterValue,
+        ParameterValueUpdate,
+    },
+    utils::dsp::lfo::LfoWaveform,
+    Error, Parameter, ParameterScaling,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Per-effect modulation state, which provides a standard set of modulation sources for effects:
+/// an LFO, an envelope follower which tracks the effect's input and a step sequencer.
+///
+/// Effects process their buffers in chunks of [`Self::BLOCK_SIZE`] frames, pass each chunk to
+/// [`Self::process`] **before** processing it, and then fetch the modulated target parameter
+/// values per frame via [`Self::modulated_value`].
+#[derive(Clone)]
+pub(crate) struct EffectModulation {
+    state: ModulationState,
+    matrix: Option<ModulationMatrix>,
+    channel_count: usize,
+    is_active: bool,
+    lfo_rate: FloatParameterValue,
+    lfo_waveform: EnumParameterValue<LfoWaveform>,
+    follower_attack: FloatParameterValue,
+    follower_release: FloatParameterValue,
+    step_rate: FloatParameterValue,
+    step_values: Vec<FloatParameterValue>,
+}
+
+impl EffectModulation {
+    /// Max number of frames which can be processed at once.
+    pub const BLOCK_SIZE: usize = MODULATION_PROCESSOR_BLOCK_SIZE;
+
+    pub const SOURCE_LFO: FourCC = FourCC(*b"LFO1");
+    pub const SOURCE_FOLLOWER: FourCC = FourCC(*b"FLWR");
+    pub const SOURCE_STEPS: FourCC = FourCC(*b"STEP");
+
+    pub const LFO_RATE: FloatParameter =
+        FloatParameter::new(FourCC(*b"MLFR"), "LFO Rate", 0.01..=20.0, 1.0)
+            .with_scaling(ParameterScaling::Exponential(2.0))
+            .with_unit("Hz");
+    pub const LFO_WAVEFORM: EnumParameter = EnumParameter::new(
+        FourCC(*b"MLFW"),
+        "LFO Waveform",
+        LfoWaveform::VARIANTS,
+        LfoWaveform::Sine as usize,
+    );
+
+    pub const FOLLOWER_ATTACK: FloatParameter =
+        FloatParameter::new(FourCC(*b"MFLA"), "Follower Attack", 0.0..=1.0, 0.01)
+            .with_scaling(ParameterScaling::Exponential(2.0))
+            .with_unit("s");
+    pub const FOLLOWER_RELEASE: FloatParameter =
+        FloatParameter::new(FourCC(*b"MFLR"), "Follower Release", 0.0..=5.0, 0.25)
+            .with_scaling(ParameterScaling::Exponential(2.0))
+            .with_unit("s");
+
+    pub const STEP_RATE: FloatParameter =
+        FloatParameter::new(FourCC(*b"MSQR"), "Step Rate", 0.1..=32.0, 4.0)
+            .with_scaling(ParameterScaling::Exponential(2.0))
+            .with_unit("Hz");
+    pub const STEP_VALUES: [FloatParameter; 8] = [
+        FloatParameter::new(FourCC(*b"MSQ1"), "Step 1", 0.0..=1.0, 1.0),
+        FloatParameter::new(FourCC(*b"MSQ2"), "Step 2", 0.0..=1.0, 0.25),
+        FloatParameter::new(FourCC(*b"MSQ3"), "Step 3", 0.0..=1.0, 0.75),
+        FloatParameter::new(FourCC(*b"MSQ4"), "Step 4", 0.0..=1.0, 0.0),
+        FloatParameter::new(FourCC(*b"MSQ5"), "Step 5", 0.0..=1.0, 1.0),
+        FloatParameter::new(FourCC(*b"MSQ6"), "Step 6", 0.0..=1.0, 0.5),
+        FloatParameter::new(FourCC(*b"MSQ7"), "Step 7", 0.0..=1.0, 0.75),
+        FloatParameter::new(FourCC(*b"MSQ8"), "Step 8", 0.0..=1.0, 0.25),
+    ];
+
+    /// Create a new modulation state for the given modulatable effect parameters.
+    pub fn new(targets: &[FloatParameter]) -> Self {
+        let config = ModulationConfig {
+            sources: vec![
+                ModulationSource::Lfo {
+                    id: Self::SOURCE_LFO,
+                    name: "LFO",
+                    rate_param: Self::LFO_RATE,
+                    waveform_param: Self::LFO_WAVEFORM,
+                },
+                ModulationSource::EnvelopeFollower {
+                    id: Self::SOURCE_FOLLOWER,
+                    name: "Envelope Follower",
+                    attack_param: Self::FOLLOWER_ATTACK,
+                    release_param: Self::FOLLOWER_RELEASE,
+                },
+                ModulationSource::StepSequencer {
+                    id: Self::SOURCE_STEPS,
+                    name: "Step Sequencer",
+                    rate_param: Self::STEP_RATE,
+                    step_params: Self::STEP_VALUES.to_vec(),
+                },
+            ],
+            targets: targets
+                .iter()
+                .map(|target| ModulationTarget::new(target.id(), target.name()))
+                .collect(),
+        };
+        Self {
+            state: ModulationState::new(config),
+            matrix: None,
+            channel_count: 0,
+            is_active: false,
+            lfo_rate: FloatParameterValue::from_description(Self::LFO_RATE),
+            lfo_waveform: EnumParameterValue::from_description(Self::LFO_WAVEFORM),
+            follower_attack: FloatParameterValue::from_description(Self::FOLLOWER_ATTACK),
+            follower_release: FloatParameterValue::from_description(Self::FOLLOWER_RELEASE),
+            step_rate: FloatParameterValue::from_description(Self::STEP_RATE),
+            step_values: Self::STEP_VALUES
+                .into_iter()
+                .map(FloatParameterValue::from_description)
+                .collect(),
+        }
+    }
+
+    /// Parameter descriptors of all modulation sources.
+    pub fn parameters(&self) -> Vec<&dyn Parameter> {
+        let mut parameters: Vec<&dyn Parameter> = vec![
+            self.lfo_rate.description(),
+            self.lfo_waveform.description(),
+            self.follower_attack.description(),
+            self.follower_release.description(),
+            self.step_rate.description(),
+        ];
+        parameters.extend(
+            self.step_values
+                .iter()
+                .map(|value| value.description() as &dyn Parameter),
+        );
+        parameters
+    }
+
+    /// Modulation source descriptors for the Effect trait.
+    pub fn sources(&self) -> Vec<ModulationSource> {
+        self.state.sources()
+    }
+
+    /// Modulatable parameter IDs for the Effect trait.
+    pub fn targets(&self) -> Vec<ModulationTarget> {
+        self.state.targets()
+    }
+
+    /// Create the modulation matrix. Must be called when the effect gets initialized.
+    pub fn initialize(&mut self, sample_rate: u32, channel_count: usize) {
+        let mut matrix = self.state.create_matrix(sample_rate);
+        matrix.update_lfo_rate(0, self.lfo_rate.value() as f64);
+        matrix.update_lfo_waveform(0, self.lfo_waveform.value());
+        matrix.update_follower_attack(0, self.follower_attack.value());
+        matrix.update_follower_release(0, self.follower_release.value());
+        matrix.update_step_rate(0, self.step_rate.value() as f64);
+        for (index, value) in self.step_values.iter().enumerate() {
+            matrix.update_step_value(0, index, value.value());
+        }
+        self.is_active = matrix.has_targets();
+        self.matrix = Some(matrix);
+        self.channel_count = channel_count;
+    }
+
+    /// True when at least one modulation routing is set.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    /// Check if a parameter ID belongs to a modulation source.
+    pub fn is_source_parameter(&self, id: FourCC) -> bool {
+        self.state.is_source_parameter(id)
+    }
+
+    /// Apply a modulation source parameter update.
+    pub fn apply_parameter_update(&mut self, id: FourCC, value: &ParameterValueUpdate) {
+        if id == Self::LFO_RATE.id() {
+            self.lfo_rate.apply_update(value);
+            if let Some(matrix) = &mut self.matrix {
+                matrix.update_lfo_rate(0, self.lfo_rate.value() as f64);
+            }
+        } else if id == Self::LFO_WAVEFORM.id() {
+            self.lfo_waveform.apply_update(value);
+            if let Some(matrix) = &mut self.matrix {
+                matrix.update_lfo_waveform(0, self.lfo_waveform.value());
+            }
+        } else if id == Self::FOLLOWER_ATTACK.id() {
+            self.follower_attack.apply_update(value);
+            if let Some(matrix) = &mut self.matrix {
+                matrix.update_follower_attack(0, self.follower_attack.value());
+            }
+        } else if id == Self::FOLLOWER_RELEASE.id() {
+            self.follower_release.apply_update(value);
+            if let Some(matrix) = &mut self.matrix {
+                matrix.update_follower_release(0, self.follower_release.value());
+            }
+        } else if id == Self::STEP_RATE.id() {
+            self.step_rate.apply_update(value);
+            if let Some(matrix) = &mut self.matrix {
+                matrix.update_step_rate(0, self.step_rate.value() as f64);
+            }
+        } else if let Some(index) = Self::STEP_VALUES.iter().position(|p| p.id() == id) {
+            self.step_values[index].apply_update(value);
+            if let Some(matrix) = &mut self.matrix {
+                matrix.update_step_value(0, index, self.step_values[index].value());
+            }
+        }
+    }
+
+    /// Set or update a modulation routing.
+    pub fn set_modulation(
+        &mut self,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+    ) -> Result<(), Error> {
+        let matrix = self.matrix.as_mut().ok_or_else(|| {
+            Error::ParameterError("Effect modulation is not yet initialized".to_string())
+        })?;
+        self.state
+            .set_modulation(matrix, source, target, amount, bipolar)?;
+        self.is_active = matrix.has_targets();
+        Ok(())
+    }
+
+    /// Clear a modulation routing.
+    pub fn clear_modulation(&mut self, source: FourCC, target: FourCC) -> Result<(), Error> {
+        self.set_modulation(source, target, 0.0, false)
+    }
+
+    /// Process all modulation sources for the given interleaved chunk of the effect's input,
+    /// which must not be larger than [`Self::BLOCK_SIZE`] frames.
+    pub fn process(&mut self, input: &[f32]) {
+        if !self.is_active {
+            return;
+        }
+        if let Some(matrix) = &mut self.matrix {
+            let frame_count = input.len() / self.channel_count;
+            matrix.set_follower_input(input, self.channel_count);
+            matrix.process(frame_count);
+        }
+    }
+
+    /// Apply the modulation of the given parameter at the given frame index of the last
+    /// processed chunk to the given parameter value. Modulation is applied in the parameter's
+    /// normalized value range.
+    #[inline]
+    pub fn modulated_value(
+        &self,
+        parameter: &FloatParameter,
+        value: f32,
+        frame_index: usize,
+    ) -> f32 {
+        if !self.is_active {
+            return value;
+        }
+        let Some(matrix) = &self.matrix else {
+            return value;
+        };
+        let modulation = matrix.output_at(parameter.id(), frame_index);
+        if modulation == 0.0 {
+            return value;
+        }
+        let normalized = parameter.normalize_value(value) + modulation;
+        parameter.denormalize_value(normalized.clamp(0.0, 1.0))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{
+        effects::{FilterEffect, FilterEffectType},
+        Effect, EffectTime,
+    };
+
+    const SAMPLE_RATE: u32 = 48000;
+    const BLOCK_SIZE: usize = EffectModulation::BLOCK_SIZE;
+
+    #[test]
+    fn lfo_modulation() {
+        let mut modulation = EffectModulation::new(&[FilterEffect::CUTOFF, FilterEffect::Q]);
+        modulation.initialize(SAMPLE_RATE, 2);
+        let (id, value) = EffectModulation::LFO_RATE.value_update(20.0);
+        modulation.apply_parameter_update(id, &value);
+
+        // process two LFO periods and collect the modulated cutoff values
+        let input = [0.0; BLOCK_SIZE * 2];
+        let modulated_cutoffs = |modulation: &mut EffectModulation| {
+            let mut cutoffs = Vec::new();
+            for _ in 0..SAMPLE_RATE as usize / 10 / BLOCK_SIZE {
+                modulation.process(&input);
+                for frame_index in 0..BLOCK_SIZE {
+                    let value =
+                        modulation.modulated_value(&FilterEffect::CUTOFF, 1000.0, frame_index);
+                    cutoffs.push(value);
+                }
+            }
+            cutoffs
+        };
+
+        // unrouted sources don't modulate
+        assert!(!modulation.is_active());
+        assert!(modulated_cutoffs(&mut modulation)
+            .iter()
+            .all(|cutoff| *cutoff == 1000.0));
+
+        // a routed LFO moves the cutoff up and down
+        modulation
+            .set_modulation(
+                EffectModulation::SOURCE_LFO,
+                FilterEffect::CUTOFF.id(),
+                0.5,
+                true,
+            )
+            .unwrap();
+        assert!(modulation.is_active());
+        let cutoffs = modulated_cutoffs(&mut modulation);
+        let min_cutoff = cutoffs.iter().copied().fold(f32::MAX, f32::min);
+        let max_cutoff = cutoffs.iter().copied().fold(f32::MIN, f32::max);
+        assert!(min_cutoff < 500.0, "min cutoff is {min_cutoff}");
+        assert!(max_cutoff > 2000.0, "max cutoff is {max_cutoff}");
+
+        // cleared routings stop modulating
+        modulation
+            .clear_modulation(EffectModulation::SOURCE_LFO, FilterEffect::CUTOFF.id())
+            .unwrap();
+        assert!(!modulation.is_active());
+    }
+
+    #[test]
+    fn filter_cutoff_modulation() {
+        fn process_filter(lfo_amount: Option<f32>) -> Vec<f32> {
+            let mut filter = FilterEffect::with_parameters(FilterEffectType::Lowpass, 500.0, 0.707);
+            filter.initialize(SAMPLE_RATE, 2, 1024).unwrap();
+            if let Some(amount) = lfo_amount {
+                let (id, value) = EffectModulation::LFO_RATE.value_update(10.0);
+                filter.process_parameter_update(id, &value).unwrap();
+                filter
+                    .set_modulation(
+                        EffectModulation::SOURCE_LFO,
+                        FilterEffect::CUTOFF.id(),
+                        amount,
+                        true,
+                    )
+                    .unwrap();
+            }
+            let time = EffectTime {
+                pos_in_frames: 0,
+                pos_instant: Instant::now(),
+            };
+            // filter a 5 kHz sine, which the unmodulated filter attenuates
+            let mut buffer = (0..SAMPLE_RATE as usize / 10)
+                .flat_map(|frame| {
+                    let phase = 2.0 * std::f32::consts::PI * 5000.0 * frame as f32;
+                    let value = (phase / SAMPLE_RATE as f32).sin();
+                    [value, value]
+                })
+                .collect::<Vec<_>>();
+            for chunk in buffer.chunks_mut(1024 * 2) {
+                filter.process(chunk, &time);
+            }
+            buffer
+        }
+
+        // a routed LFO opens the lowpass filter's cutoff within its first half period, so the
+        // sine passes. skip the first chunk, which contains the filter's transient.
+        let peak = |samples: &[f32]| {
+            samples[1024 * 2..]
+                .iter()
+                .fold(0.0_f32, |peak, s| peak.max(s.abs()))
+        };
+        let unmodulated_peak = peak(&process_filter(None));
+        let modulated_peak = peak(&process_filter(Some(1.0)));
+        assert!(
+            unmodulated_peak < 0.1,
+            "unmodulated peak {unmodulated_peak}"
+        );
+        assert!(modulated_peak > 0.5, "modulated peak {modulated_peak}");
+    }
+}
@@ -7,15 +7,19 @@ use std::{error, fmt, io};
 #[allow(clippy::enum_variant_names)]
 pub enum Error {
     SourceNotPlaying,
+    TransportNotRunning,
     MediaFileNotFound,
     MediaFileProbeError,
     MediaFileSeekError,
     AudioDecodingError(Box<dyn error::Error + Send + Sync>),
     OutputDeviceError(Box<dyn error::Error + Send + Sync>),
+    InputDeviceError(Box<dyn error::Error + Send + Sync>),
     ResamplingError(Box<dyn error::Error + Send + Sync>),
+    SerializationError(Box<dyn error::Error + Send + Sync>),
     GeneratorNotFoundError(usize),
     EffectNotFoundError(usize),
     MixerNotFoundError(usize),
+    MixerRoutingError(String),
     ParameterError(String),
     SendError(String),
     IoError(io::Error),
@@ -27,16 +31,20 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::SourceNotPlaying => write!(f, "Source is no longer playing"),
+            Self::TransportNotRunning => write!(f, "Transport is not running"),
             Self::MediaFileNotFound => write!(f, "Audio file not found"),
             Self::MediaFileProbeError => write!(f, "Audio file failed to probe"),
             Self::MediaFileSeekError => write!(f, "Audio file failed to seek"),
             Self::AudioDecodingError(err)
             | Self::OutputDeviceError(err)
-            | Self::ResamplingError(err) => err.fmt(f),
+            | Self::InputDeviceError(err)
+            | Self::ResamplingError(err)
+            | Self::SerializationError(err) => err.fmt(f),
             Self::GeneratorNotFoundError(playback_id) => {
                 write!(f, "Generator with id {playback_id} not found")
             }
             Self::MixerNotFoundError(mixer_id) => write!(f, "Mixer with id {mixer_id} not found"),
+            Self::MixerRoutingError(str) => write!(f, "Invalid mixer routing: {str}"),
             Self::EffectNotFoundError(effect_id) => {
                 write!(f, "Effect with id {effect_id} not found")
             }
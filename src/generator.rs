@@ -208,6 +208,9 @@ pub enum GeneratorPlaybackEvent {
     SetParameters {
         values: Owned<Vec<(FourCC, ParameterValueUpdate)>>,
     },
+    /// Update a single generator parameter with a normalized value. Used by mixers to apply
+    /// parameter ramps and automation lanes without allocating.
+    SetNormalizedParameter { id: FourCC, value: f32 },
 
     /// Set or update a modulation routing.
     SetModulation {
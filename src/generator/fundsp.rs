@@ -510,6 +510,12 @@ impl FunDspGenerator {
                                     log::warn!("Failed to process parameter updates: {err}");
                                 }
                             }
+                            GeneratorPlaybackEvent::SetNormalizedParameter { id, value } => {
+                                let update = ParameterValueUpdate::Normalized(value);
+                                if let Err(err) = self.process_parameter_update(id, &update) {
+                                    log::warn!("Failed to process parameter '{id}' update: {err}");
+                                }
+                            }
                             GeneratorPlaybackEvent::SetModulation {
                                 source,
                                 target,
@@ -123,6 +123,57 @@ impl FunDspModulationState {
                         }
                     }
                 }
+                ModulationSource::EnvelopeFollower {
+                    attack_param,
+                    release_param,
+                    ..
+                } => {
+                    if let Some(ModulationSlotType::EnvelopeFollower(follower_index)) =
+                        self.inner.source_slot_map().get(&source_config.id())
+                    {
+                        // Followers get no audio input in generators, but keep them in sync
+                        if param_id == attack_param.id() {
+                            if let Some(param) = shared_params.get(&param_id) {
+                                matrix.update_follower_attack(
+                                    *follower_index,
+                                    param.shared().value(),
+                                );
+                            }
+                        } else if param_id == release_param.id() {
+                            if let Some(param) = shared_params.get(&param_id) {
+                                matrix.update_follower_release(
+                                    *follower_index,
+                                    param.shared().value(),
+                                );
+                            }
+                        }
+                    }
+                }
+                ModulationSource::StepSequencer {
+                    rate_param,
+                    step_params,
+                    ..
+                } => {
+                    if let Some(ModulationSlotType::StepSequencer(step_index)) =
+                        self.inner.source_slot_map().get(&source_config.id())
+                    {
+                        if param_id == rate_param.id() {
+                            if let Some(param) = shared_params.get(&param_id) {
+                                matrix.update_step_rate(*step_index, param.shared().value() as f64);
+                            }
+                        } else if let Some(index) =
+                            step_params.iter().position(|p| p.id() == param_id)
+                        {
+                            if let Some(param) = shared_params.get(&param_id) {
+                                matrix.update_step_value(
+                                    *step_index,
+                                    index,
+                                    param.shared().value(),
+                                );
+                            }
+                        }
+                    }
+                }
                 ModulationSource::Velocity { .. } | ModulationSource::Keytracking { .. } => {
                     // Velocity and keytracking have no parameters
                 }
@@ -101,6 +101,10 @@ impl SamplerModulationState {
                 ModulationSource::Envelope { .. } => {
                     panic!("Not expecting envelope modulation source for a sampler");
                 }
+                ModulationSource::EnvelopeFollower { .. }
+                | ModulationSource::StepSequencer { .. } => {
+                    panic!("Not expecting follower or step modulation sources for a sampler");
+                }
                 ModulationSource::Velocity { .. } | ModulationSource::Keytracking { .. } => {
                     // No parameters to update
                 }
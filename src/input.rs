@@ -0,0 +1,117 @@
+//! Audio input device implementations and traits.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use rb::{Producer, RbInspector, RbProducer, SpscRb, RB};
+
+#[cfg(feature = "cpal-input")]
+pub mod cpal;
+#[cfg(feature = "wav-input")]
+pub mod wav;
+
+// -------------------------------------------------------------------------------------------------
+
+/// The default audio input device for the current platform and feature configuration.
+///
+/// Determined by compile-time feature flags:
+/// - `cpal-input` (if not wasm32)
+#[cfg(feature = "cpal-input")]
+pub type DefaultInputDevice = cpal::CpalInput;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Platform-agnostic audio input device interface, abstracts platform-specific audio capture
+/// backends such as microphones or line-ins.
+///
+/// Input devices push captured audio into an [`InputStreamWriter`], which feeds an
+/// [`InputSource`](crate::sources::InputSource). Use [`Player::play_input`](crate::Player::play_input)
+/// to monitor and process captured audio in the player's mixers.
+pub trait InputDevice: Send {
+    /// Returns the captured buffer channel count (e.g., 1 for mono, 2 for stereo).
+    fn channel_count(&self) -> usize;
+    /// Returns the capture sample rate in Hz.
+    fn sample_rate(&self) -> u32;
+    /// Returns the current capture position in **samples** (not frames).
+    /// Tracks the total number of samples captured since device creation.
+    fn sample_position(&self) -> u64;
+
+    /// Returns `true` if audio is currently captured (not paused).
+    fn is_running(&self) -> bool;
+    /// Pauses capturing while keeping the stream writer active.
+    fn pause(&mut self);
+    /// Resumes capturing from a paused state.
+    fn resume(&mut self);
+
+    /// Starts capturing audio into the given stream writer, replacing a previous one.
+    fn capture(&mut self, writer: InputStreamWriter);
+    /// Stops capturing and drops the current stream writer.
+    fn stop(&mut self);
+
+    /// Releases audio resources and stops capturing.
+    fn close(&mut self);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Writing end of the ring buffer between an [`InputDevice`] and its
+/// [`InputSource`](crate::sources::InputSource).
+///
+/// Input devices write captured, interleaved audio into the writer from their capture thread.
+/// Writing never blocks: samples which no longer fit into the ring buffer get dropped. When the
+/// writer gets dropped, the input source finishes playing after reading all pending samples.
+pub struct InputStreamWriter {
+    buffer: Arc<SpscRb<f32>>,
+    producer: Producer<f32>,
+    channel_count: usize,
+    sample_rate: u32,
+    closed: Arc<AtomicBool>,
+}
+
+impl InputStreamWriter {
+    pub(crate) fn new(
+        buffer: Arc<SpscRb<f32>>,
+        channel_count: usize,
+        sample_rate: u32,
+        closed: Arc<AtomicBool>,
+    ) -> Self {
+        let producer = buffer.producer();
+        Self {
+            buffer,
+            producer,
+            channel_count,
+            sample_rate,
+            closed,
+        }
+    }
+
+    /// Channel count of the samples the writer expects.
+    pub fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    /// Sample rate of the samples the writer expects.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Write captured interleaved samples into the ring buffer. Only whole sample frames get
+    /// written. Returns the number of samples which got written.
+    pub fn write(&mut self, input: &[f32]) -> usize {
+        let samples =
+            input.len().min(self.buffer.slots_free()) / self.channel_count * self.channel_count;
+        if samples > 0 {
+            self.producer.write(&input[..samples]).unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+impl Drop for InputStreamWriter {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
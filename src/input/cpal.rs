@@ -0,0 +1,575 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::{
+    error::Error,
+    input::{InputDevice, InputStreamWriter},
+    output::cpal::{CpalDeviceId, CpalOutput, CpalOutputDeviceDriver},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Prefered cpal device config when using the default/auto config.
+const PREFERRED_SAMPLE_FORMAT: cpal::SampleFormat = cpal::SampleFormat::F32;
+const PREFERRED_SAMPLE_RATE: cpal::SampleRate = 44100;
+const PREFERRED_CHANNELS: cpal::ChannelCount = 2;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Configuration for a [`CpalInput`] device.
+///
+/// Use with [`CpalInput::open_with_config`] to select a specific audio driver, device,
+/// sample rate and buffer size from a UI or configuration file.
+#[derive(Debug, Default)]
+pub struct CpalInputConfig {
+    /// Audio host/driver to use. Defaults to [`CpalOutputDeviceDriver::Default`].
+    pub driver: CpalOutputDeviceDriver,
+    /// Id of the input device to open. `None` selects the driver's default device.
+    pub device_id: Option<CpalDeviceId>,
+    /// Desired sample rate in Hz. `None` uses the preferred rate (44100) or device default.
+    pub sample_rate: Option<u32>,
+    /// Audio buffer size in frames. `None` uses the platform default buffer size.
+    pub buffer_size: Option<u32>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Audio input device impl using [cpal](https://github.com/RustAudio/cpal).
+pub struct CpalInput {
+    is_running: bool,
+    stream_config: cpal::StreamConfig,
+    capture_pos: Arc<AtomicU64>,
+    callback_sender: SyncSender<CallbackMessage>,
+    stream_sender: SyncSender<StreamMessage>,
+    stream_handle: StreamThreadHandle,
+}
+
+impl CpalInput {
+    /// Open an audio input device using the default configuration.
+    pub fn open() -> Result<Self, Error> {
+        Self::open_with_config(CpalInputConfig::default())
+    }
+
+    /// Open an audio input device using the given configuration.
+    ///
+    /// Use [`CpalOutput::available_drivers`], [`CpalInput::available_devices`] and
+    /// [`CpalInput::supported_sample_rates`] to enumerate available options dynamically.
+    pub fn open_with_config(config: CpalInputConfig) -> Result<Self, Error> {
+        let host = CpalOutput::open_host(config.driver)?;
+
+        // Find device by name or use the host default.
+        let open_device = || -> Result<cpal::Device, Error> {
+            Self::find_device(&host, config.device_id.as_ref())
+        };
+
+        let device = open_device()?;
+        if let Ok(description) = device.description() {
+            log::info!("Using audio input device: {description}");
+        }
+
+        // Get the preferred stream config for the requested (or default) sample rate.
+        let supported_stream_config = Self::select_stream_config(&device, config.sample_rate)?;
+
+        // Shared capture position counter
+        let capture_pos = Arc::new(AtomicU64::new(0));
+
+        // Channel to send stream messages (pause/resume/close)
+        const STREAM_MESSAGE_QUEUE_SIZE: usize = 32;
+        let (stream_sender, stream_receiver) = sync_channel(STREAM_MESSAGE_QUEUE_SIZE);
+
+        // Try opening the stream with the given buffer size
+        const MESSAGE_QUEUE_SIZE: usize = 16;
+        let try_open_stream = |device: cpal::Device,
+                               buffer_size: Option<u32>|
+         -> Result<
+            (Stream, SyncSender<CallbackMessage>, cpal::StreamConfig),
+            Error,
+        > {
+            let (callback_sender, callback_receiver) = sync_channel(MESSAGE_QUEUE_SIZE);
+            let stream_config = cpal::StreamConfig {
+                channels: supported_stream_config.channels(),
+                sample_rate: supported_stream_config.sample_rate(),
+                buffer_size: buffer_size
+                    .map(cpal::BufferSize::Fixed)
+                    .unwrap_or(cpal::BufferSize::Default),
+            };
+            let sample_format = supported_stream_config.sample_format();
+            let stream = Stream::open(
+                device,
+                stream_config.clone(),
+                sample_format,
+                Arc::clone(&capture_pos),
+                callback_receiver,
+            )?;
+            Ok((stream, callback_sender, stream_config))
+        };
+
+        let (stream, callback_sender, stream_config) =
+            match try_open_stream(device, config.buffer_size) {
+                Ok(result) => result,
+                Err(err) if config.buffer_size.is_some() => {
+                    log::warn!(
+                        "Failed to open audio input stream with fixed buffer size ({err}), \
+                     retrying with default buffer size..."
+                    );
+                    let fallback_device = open_device()?;
+                    try_open_stream(fallback_device, None)?
+                }
+                Err(err) => return Err(err),
+            };
+
+        // Move the stream to a new thread
+        let stream_handle = StreamThreadHandle {
+            sender: stream_sender,
+            thread: Some(
+                thread::Builder::new()
+                    .name("audio_input".to_string())
+                    .spawn(move || stream.process_messages(stream_receiver))
+                    .expect("failed to spawn audio thread"),
+            ),
+        };
+
+        let is_running = false;
+        let stream_sender = stream_handle.sender.clone();
+
+        Ok(Self {
+            is_running,
+            stream_config,
+            capture_pos,
+            stream_sender,
+            callback_sender,
+            stream_handle,
+        })
+    }
+
+    /// Returns `(id, name)`s of all input devices available for the given driver.
+    pub fn available_devices(
+        driver: CpalOutputDeviceDriver,
+    ) -> Result<Vec<(cpal::DeviceId, String)>, Error> {
+        let host = CpalOutput::open_host(driver)?;
+        let mut devices = Vec::new();
+        for device in host
+            .input_devices()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+        {
+            match (device.id(), device.description()) {
+                (Ok(id), Ok(description)) => {
+                    devices.push((id, description.to_string()));
+                }
+                (Ok(id), Err(_)) => {
+                    devices.push((id.clone(), id.to_string()));
+                }
+                (Err(err), _) => {
+                    log::warn!("Failed to query audio device id {err}")
+                }
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Returns unique sample rates supported by an input device, sorted ascending.
+    ///
+    /// Pass `device_id = None` to query the driver's default device.
+    pub fn supported_sample_rates(
+        driver: CpalOutputDeviceDriver,
+        device_id: Option<CpalDeviceId>,
+    ) -> Result<Vec<u32>, Error> {
+        let host = CpalOutput::open_host(driver)?;
+        let device = Self::find_device(&host, device_id.as_ref())?;
+        let mut rates: Vec<u32> = device
+            .supported_input_configs()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+            .flat_map(|s| [s.min_sample_rate(), s.max_sample_rate()])
+            .collect();
+        rates.sort_unstable();
+        rates.dedup();
+        Ok(rates)
+    }
+
+    /// Returns the actual buffer size the device was opened with,
+    /// or `None` if the platform default is being used.
+    pub fn buffer_size(&self) -> Option<u32> {
+        match self.stream_config.buffer_size {
+            cpal::BufferSize::Fixed(n) => Some(n),
+            cpal::BufferSize::Default => None,
+        }
+    }
+
+    fn find_device(
+        host: &cpal::Host,
+        device_id: Option<&CpalDeviceId>,
+    ) -> Result<cpal::Device, Error> {
+        let device = if let Some(device_id) = device_id {
+            host.input_devices()
+                .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+                .find(|d| d.id().ok().as_ref() == Some(device_id))
+        } else {
+            host.default_input_device()
+        };
+        device.ok_or_else(|| {
+            Error::InputDeviceError(Box::new(cpal::DefaultStreamConfigError::DeviceNotAvailable))
+        })
+    }
+
+    fn select_stream_config(
+        device: &cpal::Device,
+        sample_rate: Option<u32>,
+    ) -> Result<cpal::SupportedStreamConfig, Error> {
+        let target_rate = sample_rate.unwrap_or(PREFERRED_SAMPLE_RATE);
+        // Get supported configs and sort them in terms of their priority of use as a default stream format.
+        let mut configs = device
+            .supported_input_configs()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+            .collect::<Vec<_>>();
+        configs.sort_by(|a, b| b.cmp_default_heuristics(a));
+        // Match preferred 'rate + format + channels' first, then 'rate + channels' then 'rate' only
+        let supports_rate = |s: &cpal::SupportedStreamConfigRange| {
+            (s.min_sample_rate()..=s.max_sample_rate()).contains(&target_rate)
+        };
+        let best_match = configs
+            .iter()
+            .find(|s| {
+                supports_rate(s)
+                    && s.channels() == PREFERRED_CHANNELS
+                    && s.sample_format() == PREFERRED_SAMPLE_FORMAT
+            })
+            .or_else(|| {
+                configs
+                    .iter()
+                    .find(|s| supports_rate(s) && s.channels() == PREFERRED_CHANNELS)
+            })
+            .or_else(|| configs.iter().find(|s| supports_rate(s)));
+        match best_match {
+            Some(s) => Ok(s.with_sample_rate(target_rate)),
+            None => {
+                log::warn!("Found no matching audio input device config which fits the prefered one. Using the device's default config instead...");
+                device
+                    .default_input_config()
+                    .map_err(|err| Error::InputDeviceError(Box::new(err)))
+            }
+        }
+    }
+
+    fn send_to_callback(&self, msg: CallbackMessage) {
+        if let Err(err) = self.callback_sender.send(msg) {
+            log::error!("Failed to send callback message: {err}");
+        }
+    }
+
+    fn send_to_stream(&self, msg: StreamMessage) {
+        if let Err(err) = self.stream_sender.send(msg) {
+            log::error!("Failed to send stream message: {err}");
+        }
+    }
+}
+
+impl InputDevice for CpalInput {
+    fn channel_count(&self) -> usize {
+        self.stream_config.channels as usize
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.stream_config.sample_rate
+    }
+
+    fn sample_position(&self) -> u64 {
+        self.capture_pos.load(Ordering::Relaxed)
+    }
+
+    fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    fn pause(&mut self) {
+        self.is_running = false;
+        self.send_to_stream(StreamMessage::Pause);
+        self.send_to_callback(CallbackMessage::Pause);
+    }
+
+    fn resume(&mut self) {
+        self.send_to_stream(StreamMessage::Resume);
+        self.send_to_callback(CallbackMessage::Resume);
+        self.is_running = true;
+    }
+
+    fn capture(&mut self, writer: InputStreamWriter) {
+        // ensure writer has our sample rate and channel layout
+        assert_eq!(writer.channel_count(), self.channel_count());
+        assert_eq!(writer.sample_rate(), self.sample_rate());
+        // send message to activate it in the reader
+        self.send_to_callback(CallbackMessage::SetWriter(Some(writer)));
+        // auto-start with the first set writer
+        if !self.is_running {
+            self.resume();
+        }
+    }
+
+    fn stop(&mut self) {
+        self.send_to_callback(CallbackMessage::SetWriter(None));
+    }
+
+    fn close(&mut self) {
+        self.send_to_stream(StreamMessage::Close);
+        if let Some(handle) = self.stream_handle.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct StreamThreadHandle {
+    sender: SyncSender<StreamMessage>,
+    thread: Option<JoinHandle<()>>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[derive(PartialEq)]
+enum StreamMessage {
+    Pause,
+    Resume,
+    Close,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+enum CallbackMessage {
+    SetWriter(Option<InputStreamWriter>),
+    Pause,
+    Resume,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[derive(PartialEq)]
+enum CallbackState {
+    Capturing,
+    Paused,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct Stream {
+    stream: cpal::Stream,
+    // keep device alive with the stream
+    #[allow(dead_code)]
+    device: cpal::Device,
+}
+
+impl Stream {
+    fn open(
+        device: cpal::Device,
+        config: cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        capture_pos: Arc<AtomicU64>,
+        callback_receiver: Receiver<CallbackMessage>,
+    ) -> Result<Self, Error> {
+        let mut callback = StreamCallback {
+            callback_receiver,
+            writer: None,
+            capture_pos,
+            temp_buffer: Vec::with_capacity(StreamCallback::required_buffer_size(
+                sample_format,
+                &config,
+            )),
+            state: CallbackState::Paused,
+        };
+
+        log::info!("Opening input stream: {:?}", &config);
+        let stream = match sample_format {
+            cpal::SampleFormat::I8 => {
+                Self::build_input_stream::<i8, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::I16 => {
+                Self::build_input_stream::<i16, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::I32 => {
+                Self::build_input_stream::<i32, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::I64 => {
+                Self::build_input_stream::<i64, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::U8 => {
+                Self::build_input_stream::<u8, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::U16 => {
+                Self::build_input_stream::<u16, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::U32 => {
+                Self::build_input_stream::<u32, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::U64 => {
+                Self::build_input_stream::<u64, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            cpal::SampleFormat::F32 => {
+                Self::build_input_stream::<f32, _>(&device, &config, move |input| {
+                    callback.read_samples_f32(input) // use specialized read function
+                })
+            }
+            cpal::SampleFormat::F64 => {
+                Self::build_input_stream::<f64, _>(&device, &config, move |input| {
+                    callback.read_samples(input)
+                })
+            }
+            sample_format => panic!("Unsupported/unexpected sample format '{sample_format}'"),
+        }
+        .map_err(|err| Error::InputDeviceError(Box::new(err)))?;
+
+        Ok(Self { device, stream })
+    }
+
+    fn process_messages(self, receiver: Receiver<StreamMessage>) {
+        while let Ok(msg) = receiver.recv() {
+            match msg {
+                StreamMessage::Pause => {
+                    log::debug!("Pausing audio input stream...");
+                    if let Err(err) = self.stream.pause() {
+                        log::error!("Failed to stop stream: {err}");
+                    }
+                }
+                StreamMessage::Resume => {
+                    log::debug!("Resuming audio input stream...");
+                    if let Err(err) = self.stream.play() {
+                        log::error!("Failed to start stream: {err}");
+                    }
+                }
+                StreamMessage::Close => {
+                    log::debug!("Closing audio input stream...");
+                    if let Err(err) = self.stream.pause() {
+                        log::error!("Failed to pause stream before stopping: {err}");
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    fn build_input_stream<T, F>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mut reader: F,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError>
+    where
+        T: cpal::SizedSample,
+        F: FnMut(&[T]) + Send + 'static,
+    {
+        device.build_input_stream(
+            config,
+            move |input: &[T], _: &cpal::InputCallbackInfo| {
+                reader(input);
+            },
+            |err| {
+                log::error!("Audio input error: {err}");
+            },
+            None,
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct StreamCallback {
+    callback_receiver: Receiver<CallbackMessage>,
+    writer: Option<InputStreamWriter>,
+    capture_pos: Arc<AtomicU64>,
+    temp_buffer: Vec<f32>,
+    state: CallbackState,
+}
+
+impl StreamCallback {
+    fn required_buffer_size(
+        sample_format: cpal::SampleFormat,
+        config: &cpal::StreamConfig,
+    ) -> usize {
+        if sample_format != cpal::SampleFormat::F32 {
+            let max_frames = match config.buffer_size {
+                cpal::BufferSize::Default => 2048,
+                cpal::BufferSize::Fixed(fixed) => fixed,
+            };
+            max_frames as usize * config.channels as usize
+        } else {
+            0 // no temp buffer needed with read_samples_f32
+        }
+    }
+
+    fn read_samples_f32(&mut self, input: &[f32]) {
+        // Handle messages
+        self.process_messages();
+        // Only capture when running
+        if self.state != CallbackState::Capturing {
+            return;
+        }
+        // Avoid temp buffers and write the given buffer directly
+        if let Some(writer) = &mut self.writer {
+            writer.write(input);
+        }
+        // Advance capture pos
+        self.capture_pos
+            .fetch_add(input.len() as u64, Ordering::Relaxed);
+    }
+
+    fn read_samples<T>(&mut self, input: &[T])
+    where
+        T: cpal::SizedSample,
+        f32: cpal::FromSample<T>,
+    {
+        // Handle messages
+        self.process_messages();
+        // Only capture when running
+        if self.state != CallbackState::Capturing {
+            return;
+        }
+        if let Some(writer) = &mut self.writer {
+            // Convert from the captured sample type to f32
+            self.temp_buffer.clear();
+            self.temp_buffer
+                .extend(input.iter().map(|sample| sample.to_sample::<f32>()));
+            writer.write(&self.temp_buffer);
+        }
+        // Advance capture pos
+        self.capture_pos
+            .fetch_add(input.len() as u64, Ordering::Relaxed);
+    }
+
+    fn process_messages(&mut self) {
+        // Process any pending data messages.
+        while let Ok(msg) = self.callback_receiver.try_recv() {
+            match msg {
+                CallbackMessage::SetWriter(writer) => {
+                    self.writer = writer;
+                }
+                CallbackMessage::Pause => {
+                    self.state = CallbackState::Paused;
+                }
+                CallbackMessage::Resume => {
+                    self.state = CallbackState::Capturing;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use hound::{SampleFormat, WavReader};
+
+use crate::{
+    error::Error,
+    input::{InputDevice, InputStreamWriter},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+const BUFFER_SIZE_FRAMES: usize = 128;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Audio input device, which reads audio from a wav file instead of capturing it.
+///
+/// The file's content gets streamed in real-time, in small blocks like an audio device would
+/// deliver them, so it can stand in for real hardware in tests or headless environments.
+/// When the file's end is reached, the device stops capturing into the current writer,
+/// unless it got opened via [`WavInput::open_looped`].
+pub struct WavInput {
+    stream: Arc<Mutex<WavInputStream>>,
+    channel_count: usize,
+    sample_rate: u32,
+}
+
+impl WavInput {
+    /// Open a wav input device, which plays the given wav file once.
+    pub fn open<P: AsRef<Path>>(file_path: P) -> Result<Self, Error> {
+        Self::open_with_looping(file_path, false)
+    }
+
+    /// Open a wav input device, which endlessly repeats the given wav file.
+    pub fn open_looped<P: AsRef<Path>>(file_path: P) -> Result<Self, Error> {
+        Self::open_with_looping(file_path, true)
+    }
+
+    fn open_with_looping<P: AsRef<Path>>(file_path: P, looping: bool) -> Result<Self, Error> {
+        let mut reader =
+            WavReader::open(file_path).map_err(|err| Error::InputDeviceError(Box::new(err)))?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+            SampleFormat::Int => {
+                let scale = 1.0 / (1_u64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        }
+        .map_err(|err| Error::InputDeviceError(Box::new(err)))?;
+
+        let channel_count = spec.channels as usize;
+        let sample_rate = spec.sample_rate;
+        if channel_count == 0 || samples.is_empty() {
+            return Err(Error::InputDeviceError(
+                "Wav input file contains no audio".into(),
+            ));
+        }
+
+        let stream = Arc::new(Mutex::new(WavInputStream {
+            writer: None,
+            samples,
+            channel_count,
+            looping,
+            started: false,
+            finished: false,
+            read_pos: 0,
+            capture_pos: 0,
+        }));
+
+        // Start the stream in a new detached thread
+        thread::spawn({
+            let stream = Arc::clone(&stream);
+            move || {
+                let block_duration =
+                    Duration::from_secs_f64(BUFFER_SIZE_FRAMES as f64 / sample_rate as f64);
+                let mut next_block_instant = Instant::now();
+                loop {
+                    // process the next audio block
+                    {
+                        let mut stream = stream.lock().unwrap();
+                        if stream.finished {
+                            break;
+                        }
+                        stream.process();
+                    }
+                    // wait until the next block is due, like a real-time audio device
+                    next_block_instant += block_duration;
+                    thread::sleep(next_block_instant.saturating_duration_since(Instant::now()));
+                }
+            }
+        });
+
+        Ok(Self {
+            stream,
+            channel_count,
+            sample_rate,
+        })
+    }
+}
+
+impl InputDevice for WavInput {
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn sample_position(&self) -> u64 {
+        let inner = self.stream.lock().unwrap();
+        inner.capture_pos
+    }
+
+    fn is_running(&self) -> bool {
+        let inner = self.stream.lock().unwrap();
+        inner.started
+    }
+
+    fn pause(&mut self) {
+        let mut inner = self.stream.lock().unwrap();
+        inner.started = false;
+    }
+
+    fn resume(&mut self) {
+        let mut inner = self.stream.lock().unwrap();
+        inner.started = true;
+    }
+
+    fn capture(&mut self, writer: InputStreamWriter) {
+        // ensure writer has our sample rate and channel layout
+        assert_eq!(writer.channel_count(), self.channel_count);
+        assert_eq!(writer.sample_rate(), self.sample_rate);
+        let mut inner = self.stream.lock().unwrap();
+        inner.writer = Some(writer);
+        inner.read_pos = 0;
+        // auto-start with the first set writer
+        inner.started = true;
+    }
+
+    fn stop(&mut self) {
+        let mut inner = self.stream.lock().unwrap();
+        inner.writer = None;
+    }
+
+    fn close(&mut self) {
+        let mut inner = self.stream.lock().unwrap();
+        inner.writer = None;
+        inner.finished = true;
+    }
+}
+
+impl Drop for WavInput {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct WavInputStream {
+    writer: Option<InputStreamWriter>,
+    samples: Vec<f32>,
+    channel_count: usize,
+    looping: bool,
+    started: bool,
+    finished: bool,
+    read_pos: usize,
+    capture_pos: u64,
+}
+
+impl WavInputStream {
+    fn process(&mut self) {
+        // Do nothing when paused
+        if !self.started || self.finished {
+            return;
+        }
+        let block_len = BUFFER_SIZE_FRAMES * self.channel_count;
+        let mut remaining = block_len;
+        while remaining > 0 {
+            if self.read_pos >= self.samples.len() {
+                // Rewind, and stop capturing into the writer when not looping
+                self.read_pos = 0;
+                if !self.looping {
+                    self.writer = None;
+                    break;
+                }
+            }
+            let end = (self.read_pos + remaining).min(self.samples.len());
+            if let Some(writer) = &mut self.writer {
+                writer.write(&self.samples[self.read_pos..end]);
+            }
+            remaining -= end - self.read_pos;
+            self.read_pos = end;
+        }
+        self.capture_pos += block_len as u64;
+    }
+}
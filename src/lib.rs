@@ -9,6 +9,9 @@
 //! - **[`OutputDevice`]** represents the audio backend stream. phonic provides implementations
 //!   for different platforms, such as `cpal` for native applications and `web-audio` for WebAssembly.
 //!
+//! - **[`InputDevice`]** represents an audio capture stream, e.g. a microphone or line-in.
+//!   Captured audio can be monitored and processed in the player via [`Player::play_input`].
+//!
 //! - **[`Source`]** produces audio signals. You can use the built-in [`FileSource`] for playing
 //!   back one-shot audio files, [`SynthSource`] for generating synthesized one-shot tones, or create
 //!   your own custom source implementation. Files can be played preloaded from RAM or streamed
@@ -128,35 +131,53 @@
 mod effect;
 mod error;
 mod generator;
+mod input;
 mod modulation;
 mod output;
 mod parameter;
 mod player;
 mod source;
+mod transport;
 
 // public, flat re-exports (common types and traits)
 pub use error::Error;
 
 #[cfg(any(feature = "cpal-output", feature = "web-output"))]
 pub use output::DefaultOutputDevice;
-pub use output::OutputDevice;
+pub use output::{OutputDevice, OutputDeviceStatusEvent};
+
+#[cfg(feature = "cpal-input")]
+pub use input::DefaultInputDevice;
+pub use input::{InputDevice, InputStreamWriter};
 
 pub use player::{
-    EffectHandle, EffectId, EffectMovement, FilePlaybackHandle, GeneratorPlaybackHandle,
-    MixerHandle, MixerId, NotePlaybackId, PanicHandler, PlaybackId, Player, PlayerConfig,
-    SourcePlaybackHandle, SynthPlaybackHandle,
+    EffectHandle, EffectId, EffectMovement, EffectSnapshot, FilePlaybackHandle,
+    GeneratorPlaybackHandle, GeneratorSnapshot, MixerHandle, MixerId, MixerOutput, MixerSendMode,
+    MixerSnapshot, NotePlaybackId, PanicHandler, ParameterSnapshot, PlaybackId, Player,
+    PlayerConfig, PlayerSnapshot, RestoredPlayerSnapshot, SendSnapshot, SourcePlaybackHandle,
+    StemMode, SynthPlaybackHandle,
 };
 
+#[cfg(feature = "wav-output")]
+pub use player::RenderStem;
+
 pub use effect::{Effect, EffectMessage, EffectMessagePayload, EffectTime};
 
+pub use transport::{Bars, Beats, ScheduleTime, TimeSignature, Transport};
+
 pub use parameter::{
-    Parameter, ParameterPolarity, ParameterScaling, ParameterType, ParameterValueUpdate,
+    AutomationLane, AutomationPoint, Parameter, ParameterPolarity, ParameterScaling, ParameterType,
+    ParameterValueUpdate, RampShape,
 };
 
 pub use source::{
     file::{AudioFileBuffer, AudioFileInfo, FilePlaybackOptions, FileSource},
     measured::{CpuLoad, CpuLoadState, SharedCpuLoadState},
-    metered::{AudioLevel, AudioLevelState, SharedAudioLevelState},
+    metered::{
+        AudioLevel, AudioLevelState, Loudness, SharedAudioLevelState, SharedSpectrumState,
+        SharedWaveformState, Spectrum, SpectrumConfig, SpectrumState, SpectrumWindow, StereoImage,
+        WaveformState, WaveformTrigger,
+    },
     resampled::ResamplingQuality,
     status::{PlaybackStatusContext, PlaybackStatusEvent},
     synth::{SynthPlaybackMessage, SynthPlaybackOptions, SynthSource},
@@ -180,11 +201,25 @@ pub mod outputs {
         CpalDeviceId, CpalOutput, CpalOutputConfig, CpalOutputDeviceDriver,
     };
 
+    pub use super::output::memory::MemoryOutput;
+
     #[cfg(feature = "web-output")]
     pub use super::output::web::WebOutput;
 
     #[cfg(feature = "wav-output")]
-    pub use super::output::wav::WavOutput;
+    pub use super::output::wav::{WavOutput, WavSampleFormat};
+
+    pub use crate::utils::dither::DitherMode;
+}
+
+pub mod inputs {
+    //! Default [`InputDevice`](super::InputDevice) implementations.
+
+    #[cfg(feature = "cpal-input")]
+    pub use super::input::cpal::{CpalInput, CpalInputConfig};
+
+    #[cfg(feature = "wav-input")]
+    pub use super::input::wav::WavInput;
 }
 
 pub mod sources {
@@ -192,6 +227,8 @@ pub mod sources {
 
     pub use super::source::empty::EmptySource;
 
+    pub use super::source::input::InputSource;
+
     pub use super::source::file::{
         common::FileSourceImpl, preloaded::PreloadedFileSource, streamed::StreamedFileSource,
         FilePlaybackMessage,
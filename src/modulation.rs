@@ -1,7 +1,8 @@
 //! Modulation system for parameter automation.
 //!
-//! Provides modulation matrix architecture where sources (LFOs, envelopes, velocity, keytracking) can route to
-//! target parameters with configurable depth and polarity.
+//! Provides modulation matrix architecture where sources (LFOs, envelopes, envelope followers,
+//! step sequencers, velocity, keytracking) can route to target parameters of generators and
+//! effects with configurable depth and polarity.
 
 use four_cc::FourCC;
 
@@ -16,7 +17,8 @@ pub(crate) mod state;
 // -------------------------------------------------------------------------------------------------
 
 /// Configuration for a modulation source for a modulation source (e.g. LFO, AHDSR envelope,
-/// velocity, keytracking) within a [`ModulationConfig`] as used by [`Generator`](crate::Generator).
+/// velocity, keytracking) within a [`ModulationConfig`] as used by [`Generator`](crate::Generator)
+/// and [`Effect`](crate::Effect).
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum ModulationSource {
@@ -41,6 +43,21 @@ pub enum ModulationSource {
     Velocity { id: FourCC, name: &'static str },
     /// Keytracking (static per note, no parameters).
     Keytracking { id: FourCC, name: &'static str },
+    /// Envelope follower with attack and release parameters, which tracks the level of an
+    /// effect's audio input. Only available in effects.
+    EnvelopeFollower {
+        id: FourCC,
+        name: &'static str,
+        attack_param: FloatParameter,
+        release_param: FloatParameter,
+    },
+    /// Step sequencer with a rate in steps per second and one value parameter per step.
+    StepSequencer {
+        id: FourCC,
+        name: &'static str,
+        rate_param: FloatParameter,
+        step_params: Vec<FloatParameter>,
+    },
 }
 
 impl ModulationSource {
@@ -51,6 +68,8 @@ impl ModulationSource {
             Self::Envelope { id, .. } => *id,
             Self::Velocity { id, .. } => *id,
             Self::Keytracking { id, .. } => *id,
+            Self::EnvelopeFollower { id, .. } => *id,
+            Self::StepSequencer { id, .. } => *id,
         }
     }
 
@@ -61,6 +80,8 @@ impl ModulationSource {
             Self::Envelope { name, .. } => name,
             Self::Velocity { name, .. } => name,
             Self::Keytracking { name, .. } => name,
+            Self::EnvelopeFollower { name, .. } => name,
+            Self::StepSequencer { name, .. } => name,
         }
     }
 
@@ -86,6 +107,18 @@ impl ModulationSource {
                 sustain_param,
                 release_param,
             ],
+            Self::EnvelopeFollower {
+                attack_param,
+                release_param,
+                ..
+            } => vec![attack_param as &dyn Parameter, release_param],
+            Self::StepSequencer {
+                rate_param,
+                step_params,
+                ..
+            } => std::iter::once(rate_param as &dyn Parameter)
+                .chain(step_params.iter().map(|p| p as &dyn Parameter))
+                .collect(),
             Self::Velocity { .. } | Self::Keytracking { .. } => vec![],
         }
     }
@@ -94,9 +127,11 @@ impl ModulationSource {
     pub fn polarity(&self) -> ParameterPolarity {
         match self {
             Self::Lfo { .. } => ParameterPolarity::Bipolar,
-            Self::Envelope { .. } | Self::Velocity { .. } | Self::Keytracking { .. } => {
-                ParameterPolarity::Unipolar
-            }
+            Self::Envelope { .. }
+            | Self::Velocity { .. }
+            | Self::Keytracking { .. }
+            | Self::EnvelopeFollower { .. }
+            | Self::StepSequencer { .. } => ParameterPolarity::Unipolar,
         }
     }
 }
@@ -104,7 +139,7 @@ impl ModulationSource {
 // -------------------------------------------------------------------------------------------------
 
 /// Identifies a modulatable target parameter by ID and name within a [`ModulationSource`]
-/// as used by [`Generator`](crate::Generator).
+/// as used by [`Generator`](crate::Generator) and [`Effect`](crate::Effect).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ModulationTarget {
     id: FourCC,
@@ -132,7 +167,8 @@ impl ModulationTarget {
 
 // -------------------------------------------------------------------------------------------------
 
-/// Defines available modulation sources and targets for a [`Generator`](crate::Generator).
+/// Defines available modulation sources and targets for a [`Generator`](crate::Generator) or
+/// an [`Effect`](crate::Effect).
 #[derive(Debug, Clone, Default)]
 pub struct ModulationConfig {
     /// Available modulation sources
@@ -5,9 +5,9 @@ use four_cc::FourCC;
 use crate::utils::dsp::lfo::LfoWaveform;
 
 use super::processor::{
-    AhdsrModulationProcessor, KeytrackingModulationProcessor, LfoModulationProcessor,
-    ModulationProcessor, ModulationProcessorTarget, VelocityModulationProcessor,
-    MODULATION_PROCESSOR_BLOCK_SIZE,
+    AhdsrModulationProcessor, EnvelopeFollowerModulationProcessor, KeytrackingModulationProcessor,
+    LfoModulationProcessor, ModulationProcessor, ModulationProcessorTarget,
+    StepSequencerModulationProcessor, VelocityModulationProcessor, MODULATION_PROCESSOR_BLOCK_SIZE,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -113,6 +113,10 @@ pub struct ModulationMatrix {
     pub velocity_slot: Option<ModulationMatrixSlot<VelocityModulationProcessor>>,
     /// Keytracking slot (single instance, optional)
     pub keytracking_slot: Option<ModulationMatrixSlot<KeytrackingModulationProcessor>>,
+    /// Envelope follower slots (used by effects only)
+    pub follower_slots: Vec<ModulationMatrixSlot<EnvelopeFollowerModulationProcessor>>,
+    /// Step sequencer slots
+    pub step_slots: Vec<ModulationMatrixSlot<StepSequencerModulationProcessor>>,
     /// Current block size: may be less than MAX_MODULATION_BLOCK_SIZE, but never more
     current_output_size: usize,
 }
@@ -127,6 +131,8 @@ impl ModulationMatrix {
             envelope_slots: Vec::with_capacity(2),
             velocity_slot: None,
             keytracking_slot: None,
+            follower_slots: Vec::new(),
+            step_slots: Vec::new(),
             current_output_size: 0,
         }
     }
@@ -154,6 +160,42 @@ impl ModulationMatrix {
         self.keytracking_slot = Some(slot);
     }
 
+    /// Add an envelope follower slot.
+    pub fn add_follower_slot(
+        &mut self,
+        slot: ModulationMatrixSlot<EnvelopeFollowerModulationProcessor>,
+    ) {
+        self.follower_slots.push(slot);
+    }
+
+    /// Add a step sequencer slot.
+    pub fn add_step_slot(&mut self, slot: ModulationMatrixSlot<StepSequencerModulationProcessor>) {
+        self.step_slots.push(slot);
+    }
+
+    /// True when any of the slots has at least one target routing.
+    pub fn has_targets(&self) -> bool {
+        self.lfo_slots.iter().any(|s| !s.targets.is_empty())
+            || self.envelope_slots.iter().any(|s| !s.targets.is_empty())
+            || self.follower_slots.iter().any(|s| !s.targets.is_empty())
+            || self.step_slots.iter().any(|s| !s.targets.is_empty())
+            || self
+                .velocity_slot
+                .as_ref()
+                .is_some_and(|s| !s.targets.is_empty())
+            || self
+                .keytracking_slot
+                .as_ref()
+                .is_some_and(|s| !s.targets.is_empty())
+    }
+
+    /// Pass the interleaved audio input of the next processed chunk to all envelope followers.
+    pub fn set_follower_input(&mut self, input: &[f32], channel_count: usize) {
+        for slot in &mut self.follower_slots {
+            slot.processor.set_input(input, channel_count);
+        }
+    }
+
     /// Process all enabled modulation processors for the next chunk of samples.
     ///
     /// # Arguments
@@ -177,6 +219,12 @@ impl ModulationMatrix {
         if let Some(slot) = &mut self.keytracking_slot {
             slot.process(chunk_size);
         }
+        for slot in &mut self.follower_slots {
+            slot.process(chunk_size);
+        }
+        for slot in &mut self.step_slots {
+            slot.process(chunk_size);
+        }
 
         // Memorize valid size
         self.current_output_size = chunk_size;
@@ -300,6 +348,32 @@ impl ModulationMatrix {
                 }
             }
         }
+
+        // Accumulate modulation from all envelope follower and step sequencer slots
+        let unipolar_slot_outputs = self
+            .follower_slots
+            .iter()
+            .filter(|slot| slot.enabled)
+            .map(|slot| (&slot.targets, &slot.block_buffer))
+            .chain(
+                self.step_slots
+                    .iter()
+                    .filter(|slot| slot.enabled)
+                    .map(|slot| (&slot.targets, &slot.block_buffer)),
+            );
+        for (targets, block_buffer) in unipolar_slot_outputs {
+            for target in targets {
+                if target.parameter_id == parameter_id {
+                    // unipolar follower or steps to unipolar or bipolar target
+                    apply_unipolar_block(
+                        &mut output[..block_size],
+                        &block_buffer[..block_size],
+                        target.amount,
+                        target.bipolar,
+                    );
+                }
+            }
+        }
     }
 
     /// Get accumulated preprocessed modulation value for a parameter at a specific sample position.
@@ -387,6 +461,28 @@ impl ModulationMatrix {
             }
         }
 
+        // Accumulate modulation from all envelope follower and step sequencer slots
+        let unipolar_slot_outputs = self
+            .follower_slots
+            .iter()
+            .filter(|slot| slot.enabled)
+            .map(|slot| (&slot.targets, &slot.block_buffer))
+            .chain(
+                self.step_slots
+                    .iter()
+                    .filter(|slot| slot.enabled)
+                    .map(|slot| (&slot.targets, &slot.block_buffer)),
+            );
+        for (targets, block_buffer) in unipolar_slot_outputs {
+            for target in targets {
+                if target.parameter_id == parameter_id {
+                    let raw_value = block_buffer[sample_index];
+                    let mod_value = apply_unipolar(raw_value, target.bipolar);
+                    total += mod_value * target.amount;
+                }
+            }
+        }
+
         total
     }
 
@@ -405,6 +501,9 @@ impl ModulationMatrix {
         if let Some(slot) = &mut self.keytracking_slot {
             slot.processor.set_midi_note(note as f32);
         }
+        for slot in &mut self.step_slots {
+            slot.processor.reset();
+        }
     }
 
     /// Trigger note-off for all envelope sources.
@@ -489,6 +588,60 @@ impl ModulationMatrix {
         }
     }
 
+    /// Update attack time in seconds for a specific envelope follower slot.
+    pub fn update_follower_attack(&mut self, follower_index: usize, attack: f32) {
+        if let Some(slot) = self.follower_slots.get_mut(follower_index) {
+            slot.processor.set_attack(attack);
+        }
+    }
+
+    /// Update release time in seconds for a specific envelope follower slot.
+    pub fn update_follower_release(&mut self, follower_index: usize, release: f32) {
+        if let Some(slot) = self.follower_slots.get_mut(follower_index) {
+            slot.processor.set_release(release);
+        }
+    }
+
+    /// Update envelope follower target amount for a specific slot and parameter.
+    pub fn update_follower_target(
+        &mut self,
+        follower_index: usize,
+        parameter_id: FourCC,
+        amount: f32,
+        bipolar: bool,
+    ) {
+        if let Some(slot) = self.follower_slots.get_mut(follower_index) {
+            slot.update_target(parameter_id, amount, bipolar);
+        }
+    }
+
+    /// Update step rate for a specific step sequencer slot.
+    pub fn update_step_rate(&mut self, step_index: usize, rate: f64) {
+        if let Some(slot) = self.step_slots.get_mut(step_index) {
+            slot.processor.set_rate(rate);
+        }
+    }
+
+    /// Update a single step value for a specific step sequencer slot.
+    pub fn update_step_value(&mut self, step_index: usize, index: usize, value: f32) {
+        if let Some(slot) = self.step_slots.get_mut(step_index) {
+            slot.processor.set_step(index, value);
+        }
+    }
+
+    /// Update step sequencer target amount for a specific slot and parameter.
+    pub fn update_step_target(
+        &mut self,
+        step_index: usize,
+        parameter_id: FourCC,
+        amount: f32,
+        bipolar: bool,
+    ) {
+        if let Some(slot) = self.step_slots.get_mut(step_index) {
+            slot.update_target(parameter_id, amount, bipolar);
+        }
+    }
+
     /// Update velocity target amount for a specific parameter.
     pub fn update_velocity_target(&mut self, parameter_id: FourCC, amount: f32, bipolar: bool) {
         if let Some(slot) = &mut self.velocity_slot {
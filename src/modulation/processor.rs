@@ -314,6 +314,160 @@ impl ModulationProcessor for KeytrackingModulationProcessor {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Envelope follower modulation processor, which tracks the peak level of an audio input signal.
+///
+/// The input signal gets passed in blocks via [`Self::set_input`] before processing.
+///
+/// Output: unipolar [0.0, 1.0]
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollowerModulationProcessor {
+    sample_rate: u32,
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    envelope: f32,
+    input: [f32; MODULATION_PROCESSOR_BLOCK_SIZE],
+    input_len: usize,
+}
+
+impl EnvelopeFollowerModulationProcessor {
+    /// Create a new envelope follower with the given attack and release times in seconds.
+    pub fn new(sample_rate: u32, attack: f32, release: f32) -> Self {
+        Self {
+            sample_rate,
+            attack_coefficient: Self::coefficient(sample_rate, attack),
+            release_coefficient: Self::coefficient(sample_rate, release),
+            envelope: 0.0,
+            input: [0.0; MODULATION_PROCESSOR_BLOCK_SIZE],
+            input_len: 0,
+        }
+    }
+
+    /// Update attack time in seconds.
+    pub fn set_attack(&mut self, attack: f32) {
+        self.attack_coefficient = Self::coefficient(self.sample_rate, attack);
+    }
+
+    /// Update release time in seconds.
+    pub fn set_release(&mut self, release: f32) {
+        self.release_coefficient = Self::coefficient(self.sample_rate, release);
+    }
+
+    /// Set the interleaved input signal for the next processed block. Channels get downmixed
+    /// to their peak level. Frames exceeding the block size are ignored.
+    pub fn set_input(&mut self, input: &[f32], channel_count: usize) {
+        self.input_len = 0;
+        for (level, frame) in self.input.iter_mut().zip(input.chunks_exact(channel_count)) {
+            *level = frame
+                .iter()
+                .fold(0.0_f32, |max, sample| max.max(sample.abs()));
+            self.input_len += 1;
+        }
+    }
+
+    fn coefficient(sample_rate: u32, time: f32) -> f32 {
+        let samples = time * sample_rate as f32;
+        if samples > 1.0 {
+            (-1.0 / samples).exp()
+        } else {
+            0.0
+        }
+    }
+}
+
+impl ModulationProcessor for EnvelopeFollowerModulationProcessor {
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.input_len = 0;
+    }
+
+    fn is_active(&self) -> bool {
+        true // Followers are always active
+    }
+
+    fn process(&mut self, output: &mut [f32]) {
+        for (index, out) in output.iter_mut().enumerate() {
+            let level = if index < self.input_len {
+                self.input[index].min(1.0)
+            } else {
+                0.0
+            };
+            let coefficient = if level > self.envelope {
+                self.attack_coefficient
+            } else {
+                self.release_coefficient
+            };
+            self.envelope = level + coefficient * (self.envelope - level);
+            *out = self.envelope;
+        }
+        // consume input
+        self.input_len = 0;
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Step sequencer modulation processor, which cycles through a list of step values.
+///
+/// Output: unipolar [0.0, 1.0]
+#[derive(Debug, Clone)]
+pub struct StepSequencerModulationProcessor {
+    sample_rate: u32,
+    rate: f64,
+    steps: Vec<f32>,
+    step_index: usize,
+    step_phase: f64,
+}
+
+impl StepSequencerModulationProcessor {
+    /// Create a new step sequencer with the given rate in steps per second and step values.
+    pub fn new(sample_rate: u32, rate: f64, steps: Vec<f32>) -> Self {
+        Self {
+            sample_rate,
+            rate,
+            steps,
+            step_index: 0,
+            step_phase: 0.0,
+        }
+    }
+
+    /// Set rate in steps per second.
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+
+    /// Set the value of the step at the given index.
+    pub fn set_step(&mut self, index: usize, value: f32) {
+        if let Some(step) = self.steps.get_mut(index) {
+            *step = value.clamp(0.0, 1.0);
+        }
+    }
+}
+
+impl ModulationProcessor for StepSequencerModulationProcessor {
+    fn reset(&mut self) {
+        self.step_index = 0;
+        self.step_phase = 0.0;
+    }
+
+    fn is_active(&self) -> bool {
+        !self.steps.is_empty()
+    }
+
+    fn process(&mut self, output: &mut [f32]) {
+        let phase_increment = self.rate / self.sample_rate as f64;
+        for out in output {
+            *out = self.steps[self.step_index];
+            self.step_phase += phase_increment;
+            while self.step_phase >= 1.0 {
+                self.step_phase -= 1.0;
+                self.step_index = (self.step_index + 1) % self.steps.len();
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Routing from a modulation source to a target parameter.
 ///
 /// Specifies the parameter ID, modulation depth, and polarity transform.
@@ -339,3 +493,41 @@ impl ModulationProcessorTarget {
         }
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_sequencer() {
+        // 4 steps per second at 8 Hz: each step lasts 2 samples
+        let mut steps = StepSequencerModulationProcessor::new(8, 4.0, vec![1.0, 0.0, 0.5]);
+        let mut output = [0.0; 8];
+        steps.process(&mut output);
+        assert_eq!(output, [1.0, 1.0, 0.0, 0.0, 0.5, 0.5, 1.0, 1.0]);
+        steps.set_step(1, 2.0);
+        steps.reset();
+        steps.process(&mut output);
+        assert_eq!(output[2], 1.0);
+    }
+
+    #[test]
+    fn envelope_follower() {
+        let mut follower = EnvelopeFollowerModulationProcessor::new(1000, 0.0, 0.01);
+        let mut output = [0.0; 4];
+
+        // attacks instantly and tracks the peak of all channels
+        follower.set_input(&[0.5, -0.8, 0.2, 0.1, 0.0, 0.0, 1.5, 0.0], 2);
+        follower.process(&mut output);
+        assert_eq!(output[0], 0.8);
+        assert!(output[1] < 0.8 && output[1] > 0.2);
+        assert!(output[2] < output[1]);
+        assert_eq!(output[3], 1.0);
+
+        // releases without input
+        follower.process(&mut output);
+        assert!(output.windows(2).all(|w| w[1] < w[0]));
+    }
+}
@@ -9,8 +9,9 @@ use crate::{
     modulation::{
         matrix::{ModulationMatrix, ModulationMatrixSlot},
         processor::{
-            AhdsrModulationProcessor, KeytrackingModulationProcessor, LfoModulationProcessor,
-            VelocityModulationProcessor,
+            AhdsrModulationProcessor, EnvelopeFollowerModulationProcessor,
+            KeytrackingModulationProcessor, LfoModulationProcessor,
+            StepSequencerModulationProcessor, VelocityModulationProcessor,
         },
         ModulationConfig, ModulationSource, ModulationTarget,
     },
@@ -23,10 +24,12 @@ use crate::{
 /// Identifies which slot type and index a modulation source maps to.
 #[derive(Debug, Clone, Copy)]
 pub enum ModulationSlotType {
-    Lfo(usize),      // index into ModulationMatrix.lfo_slots
-    Envelope(usize), // index into ModulationMatrix.envelope_slots
-    Velocity,        // single velocity slot
-    Keytracking,     // single keytracking slot
+    Lfo(usize),              // index into ModulationMatrix.lfo_slots
+    Envelope(usize),         // index into ModulationMatrix.envelope_slots
+    Velocity,                // single velocity slot
+    Keytracking,             // single keytracking slot
+    EnvelopeFollower(usize), // index into ModulationMatrix.follower_slots
+    StepSequencer(usize),    // index into ModulationMatrix.step_slots
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -35,7 +38,7 @@ pub enum ModulationSlotType {
 ///
 /// Manages the modulation configuration and provides methods to create and update
 /// modulation matrices for each voice. Contains logic that is identical across all generators.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ModulationState {
     config: ModulationConfig,
     /// Maps source FourCC -> slot reference
@@ -53,6 +56,8 @@ impl ModulationState {
 
         let mut lfo_count = 0;
         let mut envelope_count = 0;
+        let mut follower_count = 0;
+        let mut step_count = 0;
 
         for source_config in &config.sources {
             let slot_type = match source_config {
@@ -68,6 +73,16 @@ impl ModulationState {
                 }
                 ModulationSource::Velocity { .. } => ModulationSlotType::Velocity,
                 ModulationSource::Keytracking { .. } => ModulationSlotType::Keytracking,
+                ModulationSource::EnvelopeFollower { .. } => {
+                    let follower = ModulationSlotType::EnvelopeFollower(follower_count);
+                    follower_count += 1;
+                    follower
+                }
+                ModulationSource::StepSequencer { .. } => {
+                    let steps = ModulationSlotType::StepSequencer(step_count);
+                    step_count += 1;
+                    steps
+                }
             };
             source_slot_map.insert(source_config.id(), slot_type);
         }
@@ -149,6 +164,30 @@ impl ModulationState {
                     let source = KeytrackingModulationProcessor::new(60.0);
                     matrix.set_keytracking_slot(ModulationMatrixSlot::new(source));
                 }
+                ModulationSource::EnvelopeFollower {
+                    attack_param,
+                    release_param,
+                    ..
+                } => {
+                    let source = EnvelopeFollowerModulationProcessor::new(
+                        sample_rate,
+                        attack_param.default_value(),
+                        release_param.default_value(),
+                    );
+                    matrix.add_follower_slot(ModulationMatrixSlot::new(source));
+                }
+                ModulationSource::StepSequencer {
+                    rate_param,
+                    step_params,
+                    ..
+                } => {
+                    let source = StepSequencerModulationProcessor::new(
+                        sample_rate,
+                        rate_param.default_value() as f64,
+                        step_params.iter().map(|p| p.default_value()).collect(),
+                    );
+                    matrix.add_step_slot(ModulationMatrixSlot::new(source));
+                }
             }
         }
 
@@ -160,12 +199,12 @@ impl ModulationState {
         self.source_parameter_ids.contains(&id)
     }
 
-    /// Get modulation source descriptors for the Generator trait.
+    /// Get modulation source descriptors for the Generator and Effect traits.
     pub fn sources(&self) -> Vec<ModulationSource> {
         self.config.sources.clone()
     }
 
-    /// Get modulatable parameter IDs for the Generator trait.
+    /// Get modulatable parameter IDs for the Generator and Effect traits.
     pub fn targets(&self) -> Vec<ModulationTarget> {
         self.config.targets.clone()
     }
@@ -214,6 +253,12 @@ impl ModulationState {
             ModulationSlotType::Keytracking => {
                 matrix.update_keytracking_target(target, amount, bipolar);
             }
+            ModulationSlotType::EnvelopeFollower(index) => {
+                matrix.update_follower_target(*index, target, amount, bipolar);
+            }
+            ModulationSlotType::StepSequencer(index) => {
+                matrix.update_step_target(*index, target, amount, bipolar);
+            }
         }
 
         Ok(())
@@ -6,11 +6,14 @@ compile_error!("wasm32 targets are incompatible with cpal-output. Use web-output
 
 #[cfg(feature = "cpal-output")]
 pub mod cpal;
+pub mod memory;
 #[cfg(feature = "wav-output")]
 pub mod wav;
 #[cfg(feature = "web-output")]
 pub mod web;
 
+use std::sync::mpsc::SyncSender;
+
 use super::source::Source;
 
 // -------------------------------------------------------------------------------------------------
@@ -28,6 +31,40 @@ pub type DefaultOutputDevice = web::WebOutput;
 
 // -------------------------------------------------------------------------------------------------
 
+/// Status events, which output devices send to report stream errors and device changes.
+///
+/// See [`OutputDevice::set_status_sender`] and
+/// [`Player::set_output_device_status_sender`](crate::Player::set_output_device_status_sender).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputDeviceStatusEvent {
+    /// Audio could not be delivered to the device in time (xrun), causing an audible glitch.
+    BufferUnderrun,
+    /// The device no longer is available, e.g. because it got unplugged. Playback stays silent
+    /// until the device got reconnected or a new device got set.
+    DeviceDisconnected,
+    /// The device's configuration, e.g. its sample rate or buffer size, got changed externally,
+    /// so the device's stream no longer is valid and needs to be reopened.
+    ConfigurationChanged,
+    /// The device's stream got reopened on the given device after it got disconnected or
+    /// reconfigured.
+    Reconnected {
+        /// Name of the device which now plays the stream.
+        device_name: String,
+    },
+    /// Reopening the device's stream failed. Playback stays silent until a new device got set.
+    ReconnectFailed {
+        /// Description of the error.
+        error: String,
+    },
+    /// Some other, backend specific stream error occurred.
+    StreamError {
+        /// Description of the error.
+        error: String,
+    },
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Platform-agnostic audio output device interface, abstracts platform-specific audio backends
 /// for [`Player`](crate::Player).
 pub trait OutputDevice: Send {
@@ -62,4 +99,12 @@ pub trait OutputDevice: Send {
 
     /// Releases the audio device and cleans up resources.
     fn close(&mut self);
+
+    /// Sets or removes a channel which receives [`OutputDeviceStatusEvent`]s, such as xruns and
+    /// disconnects. Events are sent without blocking: when the channel is full, they get dropped.
+    ///
+    /// Devices which don't report any status events don't need to implement this.
+    fn set_status_sender(&mut self, sender: Option<SyncSender<OutputDeviceStatusEvent>>) {
+        let _ = sender;
+    }
 }
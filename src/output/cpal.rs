@@ -1,7 +1,7 @@
 use std::{
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     thread::{self, JoinHandle},
     time::Instant,
@@ -18,10 +18,11 @@ use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
 
 use crate::{
     error::Error,
-    output::OutputDevice,
+    output::{OutputDevice, OutputDeviceStatusEvent},
     source::{empty::EmptySource, Source, SourceTime},
     utils::{
         buffer::clear_buffer,
+        dither::{DitherMode, Quantizer},
         smoothing::{apply_smoothed_gain, ExponentialSmoothedValue, SmoothedValue},
     },
 };
@@ -35,6 +36,11 @@ const PREFERRED_CHANNELS: cpal::ChannelCount = 2;
 
 // -------------------------------------------------------------------------------------------------
 
+/// Status event channel, which is shared with the stream's error handlers.
+type SharedStatusSender = Arc<Mutex<Option<SyncSender<OutputDeviceStatusEvent>>>>;
+
+// -------------------------------------------------------------------------------------------------
+
 /// Available audio backends for [`CpalOutput`].
 ///
 /// Represents different audio backends available on various platforms.
@@ -83,6 +89,10 @@ pub struct CpalOutputConfig {
     pub sample_rate: Option<u32>,
     /// Audio buffer size in frames. `None` uses the platform default buffer size.
     pub buffer_size: Option<u32>,
+    /// Reopen the stream on the driver's default device, when the device got disconnected or
+    /// reconfigured. The default device must support the stream's channel count and sample rate.
+    /// Disabled by default.
+    pub auto_reconnect: bool,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -96,6 +106,7 @@ pub struct CpalOutput {
     callback_sender: SyncSender<CallbackMessage>,
     stream_sender: SyncSender<StreamMessage>,
     stream_handle: StreamThreadHandle,
+    status_sender: SharedStatusSender,
 }
 
 impl CpalOutput {
@@ -138,6 +149,8 @@ impl CpalOutput {
         let playback_pos = Arc::new(AtomicU64::new(0));
         // Default volume
         let volume = 1.0;
+        // Status event channel, set via `set_status_sender`
+        let status_sender = Arc::new(Mutex::new(None));
 
         // Channel to send stream messages (pause/resume/close)
         const STREAM_MESSAGE_QUEUE_SIZE: usize = 32;
@@ -160,6 +173,13 @@ impl CpalOutput {
                     .unwrap_or(cpal::BufferSize::Default),
             };
             let sample_format = supported_stream_config.sample_format();
+            let context = StreamContext {
+                driver: config.driver,
+                auto_reconnect: config.auto_reconnect,
+                status_sender: Arc::clone(&status_sender),
+                stream_sender: stream_sender.clone(),
+                generation: 0,
+            };
             let stream = Stream::open(
                 device,
                 stream_config.clone(),
@@ -167,7 +187,7 @@ impl CpalOutput {
                 Arc::clone(&playback_pos),
                 volume,
                 callback_receiver,
-                stream_sender.clone(),
+                context,
             )?;
             Ok((stream, callback_sender, stream_config))
         };
@@ -208,6 +228,7 @@ impl CpalOutput {
             stream_sender,
             callback_sender,
             stream_handle,
+            status_sender,
         })
     }
 
@@ -301,7 +322,7 @@ impl CpalOutput {
         }
     }
 
-    fn open_host(driver: CpalOutputDeviceDriver) -> Result<cpal::Host, Error> {
+    pub(crate) fn open_host(driver: CpalOutputDeviceDriver) -> Result<cpal::Host, Error> {
         Ok(match driver {
             CpalOutputDeviceDriver::Default => cpal::default_host(),
             #[cfg(target_os = "windows")]
@@ -434,6 +455,13 @@ impl OutputDevice for CpalOutput {
             let _ = handle.join();
         }
     }
+
+    fn set_status_sender(&mut self, sender: Option<SyncSender<OutputDeviceStatusEvent>>) {
+        *self
+            .status_sender
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = sender;
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -449,6 +477,8 @@ struct StreamThreadHandle {
 enum StreamMessage {
     Pause,
     Resume,
+    /// Reopen the stream with the given generation, after its device got lost.
+    Reconnect(usize),
     Close,
 }
 
@@ -472,10 +502,25 @@ enum CallbackState {
 // -------------------------------------------------------------------------------------------------
 
 struct Stream {
-    stream: cpal::Stream,
+    stream: Option<cpal::Stream>,
     // keep device alive with the stream
     #[allow(dead_code)]
     device: cpal::Device,
+    config: cpal::StreamConfig,
+    callback: Arc<Mutex<StreamCallback>>,
+    context: StreamContext,
+    is_playing: bool,
+}
+
+/// Stream state which gets passed along to the stream's error handler.
+#[derive(Clone)]
+struct StreamContext {
+    driver: CpalOutputDeviceDriver,
+    auto_reconnect: bool,
+    status_sender: SharedStatusSender,
+    stream_sender: SyncSender<StreamMessage>,
+    /// Incremented with each reopened stream, to ignore reconnect requests of old streams.
+    generation: usize,
 }
 
 impl Stream {
@@ -486,10 +531,10 @@ impl Stream {
         playback_pos: Arc<AtomicU64>,
         volume: f32,
         callback_receiver: Receiver<CallbackMessage>,
-        stream_sender: SyncSender<StreamMessage>,
+        context: StreamContext,
     ) -> Result<Self, Error> {
-        let mut callback = StreamCallback {
-            stream_sender,
+        let callback = Arc::new(Mutex::new(StreamCallback {
+            stream_sender: context.stream_sender.clone(),
             callback_receiver,
             source: Box::new(EmptySource::new(
                 config.channels as usize,
@@ -503,112 +548,258 @@ impl Stream {
             )),
             state: CallbackState::Paused,
             volume: ExponentialSmoothedValue::new(volume, config.sample_rate),
-        };
+        }));
 
         log::info!("Opening output stream: {:?}", &config);
-        let stream = match sample_format {
+        let stream = Self::build_stream(&device, &config, sample_format, &callback, &context)?;
+
+        Ok(Self {
+            stream: Some(stream),
+            device,
+            config,
+            callback,
+            context,
+            is_playing: false,
+        })
+    }
+
+    fn process_messages(mut self, receiver: Receiver<StreamMessage>) {
+        while let Ok(msg) = receiver.recv() {
+            match msg {
+                StreamMessage::Pause => {
+                    log::debug!("Pausing audio output stream...");
+                    self.is_playing = false;
+                    if let Some(Err(err)) = self.stream.as_ref().map(|s| s.pause()) {
+                        log::error!("Failed to stop stream: {err}");
+                    }
+                }
+                StreamMessage::Resume => {
+                    log::debug!("Resuming audio output stream...");
+                    self.is_playing = true;
+                    if let Some(Err(err)) = self.stream.as_ref().map(|s| s.play()) {
+                        log::error!("Failed to start stream: {err}");
+                    }
+                }
+                StreamMessage::Reconnect(generation) => {
+                    if generation != self.context.generation {
+                        continue; // already reconnected
+                    }
+                    log::info!("Reopening audio output stream...");
+                    let event = match self.reconnect() {
+                        Ok(device_name) => {
+                            log::info!("Reopened audio output stream on device: {device_name}");
+                            OutputDeviceStatusEvent::Reconnected { device_name }
+                        }
+                        Err(err) => {
+                            log::error!("Failed to reopen audio output stream: {err}");
+                            OutputDeviceStatusEvent::ReconnectFailed {
+                                error: err.to_string(),
+                            }
+                        }
+                    };
+                    Self::send_status_event(&self.context.status_sender, event);
+                }
+                StreamMessage::Close => {
+                    log::debug!("Closing audio output stream...");
+                    if let Some(Err(err)) = self.stream.as_ref().map(|s| s.pause()) {
+                        log::error!("Failed to pause stream before stopping: {err}");
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Reopen the stream with the current stream config on the driver's default device.
+    /// Returns the new device's name.
+    fn reconnect(&mut self) -> Result<String, Error> {
+        // release the old stream first, so the device can be reopened
+        self.stream = None;
+        self.context.generation += 1;
+
+        let host = CpalOutput::open_host(self.context.driver)?;
+        let device = host
+            .default_output_device()
+            .ok_or(cpal::DefaultStreamConfigError::DeviceNotAvailable)?;
+        let device_name = device
+            .description()
+            .map(|description| description.to_string())
+            .unwrap_or_default();
+
+        // the stream's source can't change its specs, so the new device must match them
+        let supported_stream_config =
+            CpalOutput::select_stream_config(&device, Some(self.config.sample_rate))?;
+        if supported_stream_config.channels() != self.config.channels
+            || supported_stream_config.sample_rate() != self.config.sample_rate
+        {
+            return Err(Error::OutputDeviceError(
+                format!(
+                    "Device '{device_name}' does not support {} channels at {} Hz",
+                    self.config.channels, self.config.sample_rate
+                )
+                .into(),
+            ));
+        }
+
+        let sample_format = supported_stream_config.sample_format();
+        let stream = match Self::build_stream(
+            &device,
+            &self.config,
+            sample_format,
+            &self.callback,
+            &self.context,
+        ) {
+            Ok(stream) => stream,
+            Err(err) if self.config.buffer_size != cpal::BufferSize::Default => {
+                log::warn!(
+                    "Failed to reopen audio stream with fixed buffer size ({err}), \
+                     retrying with default buffer size..."
+                );
+                self.config.buffer_size = cpal::BufferSize::Default;
+                Self::build_stream(
+                    &device,
+                    &self.config,
+                    sample_format,
+                    &self.callback,
+                    &self.context,
+                )?
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if self.is_playing {
+            stream.play()?;
+        }
+        self.stream = Some(stream);
+        self.device = device;
+        Ok(device_name)
+    }
+
+    fn build_stream(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        callback: &Arc<Mutex<StreamCallback>>,
+        context: &StreamContext,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError> {
+        let callback = Arc::clone(callback);
+        match sample_format {
             cpal::SampleFormat::I8 => {
-                Self::build_output_stream::<i8, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                let mut quantizer = Self::quantizer(8, config);
+                Self::build_output_stream::<i8, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, Some(&mut quantizer))
                 })
             }
             cpal::SampleFormat::I16 => {
-                Self::build_output_stream::<i16, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                let mut quantizer = Self::quantizer(16, config);
+                Self::build_output_stream::<i16, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, Some(&mut quantizer))
                 })
             }
             cpal::SampleFormat::I32 => {
-                Self::build_output_stream::<i32, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                Self::build_output_stream::<i32, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, None)
                 })
             }
             cpal::SampleFormat::I64 => {
-                Self::build_output_stream::<i64, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                Self::build_output_stream::<i64, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, None)
                 })
             }
             cpal::SampleFormat::U8 => {
-                Self::build_output_stream::<u8, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                let mut quantizer = Self::quantizer(8, config);
+                Self::build_output_stream::<u8, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, Some(&mut quantizer))
                 })
             }
             cpal::SampleFormat::U16 => {
-                Self::build_output_stream::<u16, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                let mut quantizer = Self::quantizer(16, config);
+                Self::build_output_stream::<u16, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, Some(&mut quantizer))
                 })
             }
             cpal::SampleFormat::U32 => {
-                Self::build_output_stream::<u32, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                Self::build_output_stream::<u32, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, None)
                 })
             }
             cpal::SampleFormat::U64 => {
-                Self::build_output_stream::<u64, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                Self::build_output_stream::<u64, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, None)
                 })
             }
             cpal::SampleFormat::F32 => {
-                Self::build_output_stream::<f32, _>(&device, &config, move |output| {
-                    callback.write_samples_f32(output) // use specialized write function
+                Self::build_output_stream::<f32, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared_f32(&callback, output) // use specialized write function
                 })
             }
             cpal::SampleFormat::F64 => {
-                Self::build_output_stream::<f64, _>(&device, &config, move |output| {
-                    callback.write_samples(output)
+                Self::build_output_stream::<f64, _>(device, config, context, move |output| {
+                    StreamCallback::write_shared(&callback, output, None)
                 })
             }
             sample_format => panic!("Unsupported/unexpected sample format '{sample_format}'"),
-        }?;
-
-        Ok(Self { device, stream })
+        }
     }
 
-    fn process_messages(self, receiver: Receiver<StreamMessage>) {
-        while let Ok(msg) = receiver.recv() {
-            match msg {
-                StreamMessage::Pause => {
-                    log::debug!("Pausing audio output stream...");
-                    if let Err(err) = self.stream.pause() {
-                        log::error!("Failed to stop stream: {err}");
-                    }
-                }
-                StreamMessage::Resume => {
-                    log::debug!("Resuming audio output stream...");
-                    if let Err(err) = self.stream.play() {
-                        log::error!("Failed to start stream: {err}");
-                    }
-                }
-                StreamMessage::Close => {
-                    log::debug!("Closing audio output stream...");
-                    if let Err(err) = self.stream.pause() {
-                        log::error!("Failed to pause stream before stopping: {err}");
-                    }
-                    break;
-                }
-            }
-        }
+    // Quantizer for low resolution integer formats, which applies dither before the samples get
+    // converted to the target format.
+    fn quantizer(bit_depth: u32, config: &cpal::StreamConfig) -> Quantizer {
+        Quantizer::new(bit_depth, config.channels as usize, DitherMode::Tpdf)
     }
 
     fn build_output_stream<T, F>(
         device: &cpal::Device,
         config: &cpal::StreamConfig,
+        context: &StreamContext,
         mut writer: F,
     ) -> Result<cpal::Stream, cpal::BuildStreamError>
     where
         T: cpal::SizedSample,
         F: FnMut(&mut [T]) + Send + 'static,
     {
+        let context = context.clone();
         device.build_output_stream(
             config,
             move |output: &mut [T], _: &cpal::OutputCallbackInfo| {
                 writer(output);
             },
-            |err| {
-                log::error!("Audio output error: {err}");
-            },
+            move |err| Self::handle_stream_error(&context, err),
             None,
         )
     }
+
+    /// Report a stream error as status event and request a reconnect when the stream's device
+    /// got disconnected or reconfigured and auto-reconnects are enabled.
+    fn handle_stream_error(context: &StreamContext, err: cpal::StreamError) {
+        log::error!("Audio output error: {err}");
+        let event = match err {
+            cpal::StreamError::BufferUnderrun => OutputDeviceStatusEvent::BufferUnderrun,
+            cpal::StreamError::DeviceNotAvailable => OutputDeviceStatusEvent::DeviceDisconnected,
+            cpal::StreamError::StreamInvalidated => OutputDeviceStatusEvent::ConfigurationChanged,
+            cpal::StreamError::BackendSpecific { err } => OutputDeviceStatusEvent::StreamError {
+                error: err.to_string(),
+            },
+        };
+        let reconnect = matches!(
+            event,
+            OutputDeviceStatusEvent::DeviceDisconnected
+                | OutputDeviceStatusEvent::ConfigurationChanged
+        );
+        Self::send_status_event(&context.status_sender, event);
+        if reconnect && context.auto_reconnect {
+            let message = StreamMessage::Reconnect(context.generation);
+            let _ = context.stream_sender.try_send(message);
+        }
+    }
+
+    /// Send a status event without blocking. Events get dropped when no one is listening.
+    fn send_status_event(status_sender: &SharedStatusSender, event: OutputDeviceStatusEvent) {
+        if let Ok(sender) = status_sender.try_lock() {
+            if let Some(sender) = sender.as_ref() {
+                let _ = sender.try_send(event);
+            }
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -641,6 +832,27 @@ impl StreamCallback {
         }
     }
 
+    // Write into the given output, when the shared callback is not locked.
+    // NB: callbacks are shared with reopened streams only, so they never are locked in practice.
+    fn write_shared_f32(callback: &Mutex<Self>, output: &mut [f32]) {
+        if let Ok(mut callback) = callback.try_lock() {
+            callback.write_samples_f32(output);
+        } else {
+            clear_buffer(output);
+        }
+    }
+
+    fn write_shared<T>(callback: &Mutex<Self>, output: &mut [T], quantizer: Option<&mut Quantizer>)
+    where
+        T: cpal::SizedSample + cpal::FromSample<f32>,
+    {
+        if let Ok(mut callback) = callback.try_lock() {
+            callback.write_samples(output, quantizer);
+        } else {
+            output.fill(T::EQUILIBRIUM);
+        }
+    }
+
     fn write_samples_f32(&mut self, output: &mut [f32]) {
         // Handle messages
         self.process_messages();
@@ -650,7 +862,7 @@ impl StreamCallback {
         clear_buffer(&mut output[written..]);
     }
 
-    fn write_samples<T>(&mut self, output: &mut [T])
+    fn write_samples<T>(&mut self, output: &mut [T], quantizer: Option<&mut Quantizer>)
     where
         T: cpal::SizedSample + cpal::FromSample<f32>,
     {
@@ -661,6 +873,10 @@ impl StreamCallback {
         temp_buffer.resize(output.len(), 0.0);
         // Write into the f32 temp buffer
         let written = self.write_source(&mut temp_buffer);
+        // Dither when reducing the bit depth
+        if let Some(quantizer) = quantizer {
+            quantizer.process(&mut temp_buffer[..written]);
+        }
         // Convert from f32 to the target sample type
         for (o, i) in output.iter_mut().zip(temp_buffer.iter()).take(written) {
             *o = i.to_sample();
@@ -754,3 +970,90 @@ impl From<cpal::PauseStreamError> for Error {
         Error::OutputDeviceError(Box::new(err))
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream_context(
+        auto_reconnect: bool,
+    ) -> (
+        StreamContext,
+        Receiver<OutputDeviceStatusEvent>,
+        Receiver<StreamMessage>,
+    ) {
+        let (status_sender, status_receiver) = sync_channel(16);
+        let (stream_sender, stream_receiver) = sync_channel(16);
+        let context = StreamContext {
+            driver: CpalOutputDeviceDriver::default(),
+            auto_reconnect,
+            status_sender: Arc::new(Mutex::new(Some(status_sender))),
+            stream_sender,
+            generation: 1,
+        };
+        (context, status_receiver, stream_receiver)
+    }
+
+    #[test]
+    fn stream_error_status_events() {
+        let (context, status_receiver, stream_receiver) = stream_context(false);
+        let backend_error = cpal::BackendSpecificError {
+            description: "failed".to_string(),
+        };
+        let errors = [
+            (
+                cpal::StreamError::BufferUnderrun,
+                OutputDeviceStatusEvent::BufferUnderrun,
+            ),
+            (
+                cpal::StreamError::DeviceNotAvailable,
+                OutputDeviceStatusEvent::DeviceDisconnected,
+            ),
+            (
+                cpal::StreamError::StreamInvalidated,
+                OutputDeviceStatusEvent::ConfigurationChanged,
+            ),
+            (
+                cpal::StreamError::BackendSpecific {
+                    err: backend_error.clone(),
+                },
+                OutputDeviceStatusEvent::StreamError {
+                    error: backend_error.to_string(),
+                },
+            ),
+        ];
+        for (error, event) in errors {
+            Stream::handle_stream_error(&context, error);
+            assert_eq!(status_receiver.try_recv(), Ok(event));
+        }
+        // no reconnects without auto-reconnect
+        assert!(stream_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn stream_error_reconnects() {
+        let (context, status_receiver, stream_receiver) = stream_context(true);
+        // underruns and stream errors keep the stream
+        Stream::handle_stream_error(&context, cpal::StreamError::BufferUnderrun);
+        Stream::handle_stream_error(
+            &context,
+            cpal::StreamError::BackendSpecific {
+                err: cpal::BackendSpecificError {
+                    description: "failed".to_string(),
+                },
+            },
+        );
+        assert!(stream_receiver.try_recv().is_err());
+        // disconnects and invalidated streams reconnect the stream's generation
+        for error in [
+            cpal::StreamError::DeviceNotAvailable,
+            cpal::StreamError::StreamInvalidated,
+        ] {
+            Stream::handle_stream_error(&context, error);
+            assert!(stream_receiver.try_recv() == Ok(StreamMessage::Reconnect(1)));
+        }
+        assert_eq!(status_receiver.try_iter().count(), 4);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    output::OutputDevice,
+    source::{empty::EmptySource, Source, SourceTime},
+    utils::{
+        buffer::clear_buffer,
+        smoothing::{apply_smoothed_gain, ExponentialSmoothedValue, SmoothedValue},
+    },
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Max number of frames the memory stream renders from its source at once.
+const BLOCK_SIZE_FRAMES: usize = 512;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Audio output device without an audio thread on its own, which renders audio into memory
+/// when its host pulls it via [`MemoryOutput::pull`].
+///
+/// Use it to embed a player into another audio callback, e.g. a plugin host or game engine
+/// mixer, or to test exact sample output in unit tests. Audio gets rendered synchronously, as
+/// fast as possible, on the thread which pulls it. Source times are derived from the pulled
+/// sample frames only, so renderings don't depend on the wall clock and are reproducible.
+///
+/// Clones of a memory output share the same stream, so keep a clone to pull audio from, before
+/// passing the device to the player:
+///
+/// ```rust
+/// use phonic::{outputs::MemoryOutput, Player};
+///
+/// let output = MemoryOutput::new(44100, 2);
+/// let player = Player::new(output.clone(), None);
+/// // e.g. in the host's audio callback:
+/// let mut buffer = vec![0.0; 512 * 2];
+/// output.pull(&mut buffer);
+/// ```
+///
+/// Pulls never block: the player's control functions change the device's state lock-free, and
+/// pulls which happen while the player swaps the device's source render silence. Unlike
+/// `WavOutput`, memory outputs are running initially.
+///
+/// Offline players internally use a memory output too. See
+/// [`Player::new_offline`](crate::Player::new_offline).
+#[derive(Clone)]
+pub struct MemoryOutput {
+    channel_count: usize,
+    sample_rate: u32,
+    state: Arc<MemoryStreamState>,
+    stream: Arc<Mutex<MemoryStream>>,
+}
+
+impl MemoryOutput {
+    /// Create a new memory output device with the given signal specs.
+    ///
+    /// Panics when the sample rate or channel count is zero.
+    pub fn new(sample_rate: u32, channel_count: usize) -> Self {
+        assert!(sample_rate > 0, "Invalid sample rate");
+        assert!(channel_count > 0, "Invalid channel count");
+        let state = Arc::new(MemoryStreamState {
+            running: AtomicBool::new(true),
+            volume: AtomicU32::new(1.0_f32.to_bits()),
+            playback_pos: AtomicU64::new(0),
+        });
+        let stream = Arc::new(Mutex::new(MemoryStream {
+            channel_count,
+            sample_rate,
+            state: Arc::clone(&state),
+            source: Box::new(EmptySource::new(channel_count, sample_rate)),
+            smoothed_volume: ExponentialSmoothedValue::new(1.0, sample_rate),
+            start_instant: Instant::now(),
+        }));
+        Self {
+            channel_count,
+            sample_rate,
+            state,
+            stream,
+        }
+    }
+
+    /// Render the device's source into the given interleaved output buffer and advance the
+    /// playback position. Renders silence without advancing the position, when paused.
+    ///
+    /// The buffer's length must be a multiple of the device's channel count.
+    pub fn pull(&self, output: &mut [f32]) {
+        // NB: the stream only gets locked by the player while swapping sources
+        if let Ok(mut stream) = self.stream.try_lock() {
+            stream.render(output);
+        } else {
+            clear_buffer(output);
+            if self.state.running.load(Ordering::Relaxed) {
+                self.state
+                    .playback_pos
+                    .fetch_add(output.len() as u64, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl OutputDevice for MemoryOutput {
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn sample_position(&self) -> u64 {
+        self.state.playback_pos.load(Ordering::Relaxed)
+    }
+
+    fn volume(&self) -> f32 {
+        f32::from_bits(self.state.volume.load(Ordering::Relaxed))
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.state.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    fn is_suspended(&self) -> bool {
+        false
+    }
+
+    fn is_running(&self) -> bool {
+        self.state.running.load(Ordering::Relaxed)
+    }
+
+    fn pause(&mut self) {
+        self.state.running.store(false, Ordering::Relaxed);
+    }
+
+    fn resume(&mut self) {
+        self.state.running.store(true, Ordering::Relaxed);
+    }
+
+    fn play(&mut self, source: Box<dyn Source>) {
+        // ensure source has our sample rate and channel layout
+        assert_eq!(source.channel_count(), self.channel_count);
+        assert_eq!(source.sample_rate(), self.sample_rate);
+        // swap the source, but drop the old one after unlocking the stream
+        let _old_source = std::mem::replace(&mut self.stream.lock().unwrap().source, source);
+    }
+
+    fn stop(&mut self) {
+        let empty_source = EmptySource::new(self.channel_count, self.sample_rate);
+        self.play(Box::new(empty_source));
+    }
+
+    fn close(&mut self) {
+        self.state.running.store(false, Ordering::Relaxed);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Control state of a memory stream, which is shared lock-free with the device.
+struct MemoryStreamState {
+    running: AtomicBool,
+    volume: AtomicU32,
+    playback_pos: AtomicU64,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct MemoryStream {
+    channel_count: usize,
+    sample_rate: u32,
+    state: Arc<MemoryStreamState>,
+    source: Box<dyn Source>,
+    smoothed_volume: ExponentialSmoothedValue,
+    start_instant: Instant,
+}
+
+impl MemoryStream {
+    fn render(&mut self, output: &mut [f32]) {
+        debug_assert!(
+            output.len().is_multiple_of(self.channel_count),
+            "Expecting a whole number of frames in the output buffer"
+        );
+        if !self.state.running.load(Ordering::Relaxed) {
+            clear_buffer(output);
+            return;
+        }
+        let volume = f32::from_bits(self.state.volume.load(Ordering::Relaxed));
+        if volume != self.smoothed_volume.target() {
+            self.smoothed_volume.set_target(volume);
+        }
+        for block in output.chunks_mut(BLOCK_SIZE_FRAMES * self.channel_count) {
+            // Calculate source time from the playback position only
+            let playback_pos = self.state.playback_pos.load(Ordering::Relaxed);
+            let pos_in_frames = playback_pos / self.channel_count as u64;
+            let time = SourceTime {
+                pos_in_frames,
+                pos_instant: self.start_instant
+                    + Duration::from_secs_f64(pos_in_frames as f64 / self.sample_rate as f64),
+            };
+
+            // Write out as many samples as possible from the audio source and clear the rest
+            let written = self.source.write(block, &time);
+            clear_buffer(&mut block[written..]);
+
+            // Apply the global volume level
+            apply_smoothed_gain(block, &mut self.smoothed_volume);
+
+            self.state
+                .playback_pos
+                .fetch_add(block.len() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CounterSource {
+        value: f32,
+    }
+
+    impl Source for CounterSource {
+        fn channel_count(&self) -> usize {
+            2
+        }
+
+        fn sample_rate(&self) -> u32 {
+            1000
+        }
+
+        fn is_exhausted(&self) -> bool {
+            false
+        }
+
+        fn weight(&self) -> usize {
+            1
+        }
+
+        fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+            for (index, frame) in output.chunks_exact_mut(2).enumerate() {
+                let pos = (time.pos_in_frames + index as u64) as f32;
+                frame.copy_from_slice(&[self.value + pos, -pos]);
+            }
+            output.len()
+        }
+    }
+
+    #[test]
+    fn pull() {
+        let output = MemoryOutput::new(1000, 2);
+        let mut device: Box<dyn OutputDevice> = Box::new(output.clone());
+        device.play(Box::new(CounterSource { value: 0.5 }));
+
+        // renders source times from pulled frames
+        let mut buffer = [0.0; 6];
+        output.pull(&mut buffer);
+        assert_eq!(buffer, [0.5, 0.0, 1.5, -1.0, 2.5, -2.0]);
+        output.pull(&mut buffer[..2]);
+        assert_eq!(buffer[..2], [3.5, -3.0]);
+        assert_eq!(device.sample_position(), 8);
+
+        // renders silence without advancing when paused
+        device.pause();
+        output.pull(&mut buffer);
+        assert_eq!(buffer, [0.0; 6]);
+        assert_eq!(device.sample_position(), 8);
+        device.resume();
+
+        // renders silence after stopping
+        device.stop();
+        output.pull(&mut buffer);
+        assert_eq!(buffer, [0.0; 6]);
+        assert_eq!(device.sample_position(), 14);
+
+        // renders silence without blocking while the stream is locked
+        device.play(Box::new(CounterSource { value: 0.5 }));
+        {
+            let _lock = output.stream.lock().unwrap();
+            buffer.fill(1.0);
+            output.pull(&mut buffer);
+            assert_eq!(buffer, [0.0; 6]);
+            assert_eq!(device.sample_position(), 20);
+        }
+        output.pull(&mut buffer[..2]);
+        assert_eq!(buffer[..2], [10.5, -10.0]);
+    }
+}
@@ -11,7 +11,10 @@ use crate::{
     error::Error,
     output::OutputDevice,
     source::{empty::EmptySource, Source, SourceTime},
-    utils::smoothing::{apply_smoothed_gain, ExponentialSmoothedValue, SmoothedValue},
+    utils::{
+        dither::{DitherMode, Quantizer},
+        smoothing::{apply_smoothed_gain, ExponentialSmoothedValue, SmoothedValue},
+    },
 };
 
 use hound::{SampleFormat, WavSpec, WavWriter};
@@ -26,6 +29,112 @@ const BUFFER_SIZE_FRAMES: usize = 1024;
 
 // -------------------------------------------------------------------------------------------------
 
+/// Sample formats of written wav files.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WavSampleFormat {
+    /// 16 bit integer PCM.
+    Int16,
+    /// 24 bit integer PCM.
+    Int24,
+    /// 32 bit float. Written without any loss of precision, so no dithering is applied.
+    #[default]
+    Float32,
+}
+
+impl WavSampleFormat {
+    /// Bit depth of a single sample.
+    pub fn bits_per_sample(&self) -> u16 {
+        match self {
+            Self::Int16 => 16,
+            Self::Int24 => 24,
+            Self::Float32 => 32,
+        }
+    }
+
+    fn wav_spec(&self, sample_rate: u32, channel_count: usize) -> WavSpec {
+        WavSpec {
+            channels: channel_count as u16,
+            sample_rate,
+            bits_per_sample: self.bits_per_sample(),
+            sample_format: match self {
+                Self::Int16 | Self::Int24 => SampleFormat::Int,
+                Self::Float32 => SampleFormat::Float,
+            },
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Writes interleaved float samples into a wav file with the given sample format, applying
+/// dither when reducing the bit depth.
+pub(crate) struct WavFileWriter {
+    writer: WavWriter<BufWriter<File>>,
+    quantizer: Option<Quantizer>,
+    buffer: Vec<f32>,
+}
+
+impl WavFileWriter {
+    /// Create a new wav file at the given path.
+    pub fn create<P: AsRef<Path>>(
+        file_path: P,
+        sample_rate: u32,
+        channel_count: usize,
+        sample_format: WavSampleFormat,
+        dither: DitherMode,
+    ) -> Result<Self, Error> {
+        let spec = sample_format.wav_spec(sample_rate, channel_count);
+        let writer = WavWriter::create(file_path, spec)
+            .map_err(|err| Error::OutputDeviceError(Box::new(err)))?;
+        let quantizer = match sample_format {
+            WavSampleFormat::Int16 | WavSampleFormat::Int24 => Some(Quantizer::new(
+                spec.bits_per_sample as u32,
+                channel_count,
+                dither,
+            )),
+            WavSampleFormat::Float32 => None,
+        };
+        let buffer = vec![0.0; BUFFER_SIZE_FRAMES * channel_count];
+        Ok(Self {
+            writer,
+            quantizer,
+            buffer,
+        })
+    }
+
+    /// Write the given interleaved samples. The samples must contain whole frames.
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), Error> {
+        let map_err = |err| Error::OutputDeviceError(Box::new(err));
+        let Some(quantizer) = &mut self.quantizer else {
+            for sample in samples {
+                self.writer.write_sample(*sample).map_err(map_err)?;
+            }
+            return Ok(());
+        };
+        let scale = (1_u32 << (self.writer.spec().bits_per_sample - 1)) as f32;
+        for chunk in samples.chunks(self.buffer.len()) {
+            let buffer = &mut self.buffer[..chunk.len()];
+            buffer.copy_from_slice(chunk);
+            quantizer.process(buffer);
+            for sample in buffer.iter() {
+                self.writer
+                    .write_sample((sample * scale) as i32)
+                    .map_err(map_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the wav header and flush all pending samples.
+    pub fn finalize(self) -> Result<(), Error> {
+        self.writer
+            .finalize()
+            .map_err(|err| Error::OutputDeviceError(Box::new(err)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Audio output device, which writes audio into a wav file instead of playing it back.
 ///
 /// NOTE: Unlike the other output devices, the wav writer device is initially paused, so it
@@ -56,30 +165,49 @@ impl WavOutput {
     ///   produces any output (e.g. is stopped), the wav file will be closed automatically,
     ///   so the duration also can be endless to stop automatically.
     ///
-    /// Wav files contents are always saved as 32bit floats.
+    /// Wav files contents are saved as 32bit floats. See [`Self::open_with_format`] to write
+    /// integer PCM files.
     pub fn open_with_specs<P: AsRef<Path>>(
         file_path: P,
         sample_rate: u32,
         channel_count: usize,
         duration: Duration,
     ) -> Result<Self, Error> {
-        let spec = WavSpec {
-            channels: channel_count as u16,
+        Self::open_with_format(
+            file_path,
             sample_rate,
-            bits_per_sample: 32,
-            sample_format: SampleFormat::Float,
-        };
+            channel_count,
+            duration,
+            WavSampleFormat::Float32,
+            DitherMode::None,
+        )
+    }
 
-        let writer = WavWriter::create(file_path, spec)
-            .map_err(|e| Error::OutputDeviceError(Box::new(e)))?;
+    /// Create a new wav output device with the given parameters and sample format.
+    ///
+    /// * `sample_format`: Sample format of the wav file.
+    /// * `dither`: Dither mode which is applied when writing integer sample formats. Ignored
+    ///   for float formats.
+    ///
+    /// See [`Self::open_with_specs`] for descriptions of the other parameters.
+    pub fn open_with_format<P: AsRef<Path>>(
+        file_path: P,
+        sample_rate: u32,
+        channel_count: usize,
+        duration: Duration,
+        sample_format: WavSampleFormat,
+        dither: DitherMode,
+    ) -> Result<Self, Error> {
+        let writer =
+            WavFileWriter::create(file_path, sample_rate, channel_count, sample_format, dither)?;
 
         let stream = Arc::new(Mutex::new(WavStream {
             writer: Some(writer),
             channel_count,
             sample_rate,
-            source: Box::new(EmptySource::new(spec.channels as usize, spec.sample_rate)),
-            smoothed_volume: ExponentialSmoothedValue::new(1.0, spec.sample_rate),
-            buffer: vec![0.0; BUFFER_SIZE_FRAMES * spec.channels as usize],
+            source: Box::new(EmptySource::new(channel_count, sample_rate)),
+            smoothed_volume: ExponentialSmoothedValue::new(1.0, sample_rate),
+            buffer: vec![0.0; BUFFER_SIZE_FRAMES * channel_count],
             started: false,
             finished: false,
             playback_pos: 0,
@@ -194,7 +322,7 @@ impl OutputDevice for WavOutput {
 // -------------------------------------------------------------------------------------------------
 
 struct WavStream {
-    writer: Option<WavWriter<BufWriter<File>>>,
+    writer: Option<WavFileWriter>,
     channel_count: usize,
     sample_rate: u32,
     source: Box<dyn Source>,
@@ -238,10 +366,8 @@ impl WavStream {
 
         // Write to WAV file
         if let Some(ref mut writer) = self.writer {
-            for sample in &self.buffer[..written] {
-                if let Err(err) = writer.write_sample(*sample) {
-                    panic!("Failed to write sample to WAV file: {err}");
-                }
+            if let Err(err) = writer.write(&self.buffer[..written]) {
+                panic!("Failed to write samples to WAV file: {err}");
             }
         }
 
@@ -130,4 +130,7 @@ pub use boolean::{BooleanParameter, BooleanParameterValue};
 mod scaling;
 pub use scaling::ParameterScaling;
 
+mod automation;
+pub use automation::{AutomationLane, AutomationPoint, RampShape};
+
 pub mod formatters;
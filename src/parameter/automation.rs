@@ -0,0 +1,209 @@
+use crate::Error;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Curve shape of a parameter ramp or of an [`AutomationLane`] segment.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RampShape {
+    /// Constant rate of change.
+    #[default]
+    Linear,
+    /// Exponential curve: rising ramps start slow and accelerate, falling ramps start fast and
+    /// slow down, which sounds even for gains and frequencies.
+    Exponential,
+    /// Smooth cosine S-curve, which eases in and out.
+    SCurve,
+}
+
+impl RampShape {
+    /// Steepness of exponential ramps.
+    const EXPONENTIAL_CURVATURE: f32 = 4.0;
+
+    /// Interpolate between the given values at the given relative position in range `[0, 1]`.
+    pub fn interpolate(&self, from: f32, to: f32, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        let exponential = |position: f32| {
+            let k = Self::EXPONENTIAL_CURVATURE;
+            ((k * position).exp() - 1.0) / (k.exp() - 1.0)
+        };
+        let amount = match self {
+            Self::Linear => position,
+            Self::Exponential => {
+                if to >= from {
+                    exponential(position)
+                } else {
+                    1.0 - exponential(1.0 - position)
+                }
+            }
+            Self::SCurve => 0.5 - 0.5 * (std::f32::consts::PI * position).cos(),
+        };
+        from + (to - from) * amount
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A breakpoint in an [`AutomationLane`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutomationPoint {
+    /// Offset from the lane's start time in sample frames.
+    pub offset: u64,
+    /// Normalized parameter value in range `[0, 1]`.
+    pub value: f32,
+    /// Shape of the segment which leads from the previous point to this point.
+    pub shape: RampShape,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Breakpoint automation of a single parameter, which gets evaluated in the audio thread.
+///
+/// Curves get evaluated every 64 sample frames and at all breakpoints: breakpoint values get
+/// applied at their exact sample frame, values between breakpoints change in 64 frame steps.
+///
+/// Point offsets are relative to the lane's start time. The lane applies the first point's value
+/// at its offset, follows the curves between all points, then holds the last point's value.
+///
+/// Lanes get applied via `set_parameter_automation` in [`EffectHandle`](crate::EffectHandle)
+/// and [`GeneratorPlaybackHandle`](crate::GeneratorPlaybackHandle).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AutomationLane {
+    points: Vec<AutomationPoint>,
+}
+
+impl AutomationLane {
+    /// Create a new, empty lane.
+    pub fn new() -> Self {
+        Self { points: Vec::new() }
+    }
+
+    /// Create a lane which ramps from one normalized value to another one over the given
+    /// number of sample frames.
+    pub fn ramp(from: f32, to: f32, duration: u64, shape: RampShape) -> Self {
+        Self::new()
+            .with_point(0, from, RampShape::Linear)
+            .with_point(duration, to, shape)
+    }
+
+    /// Add a point at the given offset, replacing existing points at the same offset.
+    pub fn with_point(mut self, offset: u64, value: f32, shape: RampShape) -> Self {
+        self.add_point(offset, value, shape);
+        self
+    }
+
+    /// Add a point at the given offset, replacing existing points at the same offset.
+    pub fn add_point(&mut self, offset: u64, value: f32, shape: RampShape) {
+        let point = AutomationPoint {
+            offset,
+            value,
+            shape,
+        };
+        match self.points.binary_search_by_key(&offset, |p| p.offset) {
+            Ok(index) => self.points[index] = point,
+            Err(index) => self.points.insert(index, point),
+        }
+    }
+
+    /// All points, sorted by their offsets.
+    pub fn points(&self) -> &[AutomationPoint] {
+        &self.points
+    }
+
+    /// True when the lane has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Offset of the lane's first point in sample frames, or 0 when the lane is empty.
+    pub fn start(&self) -> u64 {
+        self.points.first().map_or(0, |p| p.offset)
+    }
+
+    /// Offset of the lane's last point in sample frames, or 0 when the lane is empty.
+    pub fn duration(&self) -> u64 {
+        self.points.last().map_or(0, |p| p.offset)
+    }
+
+    /// Value of the lane at the given offset. Returns the first point's value before the
+    /// lane starts and the last point's value after it ended, or `None` when the lane is empty.
+    pub fn value_at(&self, offset: u64) -> Option<f32> {
+        let next_index = self.points.partition_point(|p| p.offset <= offset);
+        if next_index == 0 {
+            return self.points.first().map(|p| p.value);
+        }
+        let previous = &self.points[next_index - 1];
+        let Some(next) = self.points.get(next_index) else {
+            return Some(previous.value);
+        };
+        let position = (offset - previous.offset) as f32 / (next.offset - previous.offset) as f32;
+        Some(next.shape.interpolate(previous.value, next.value, position))
+    }
+
+    /// Check that the lane has points and that all point values are normalized.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.points.is_empty() {
+            return Err(Error::ParameterError(
+                "Invalid automation: lane has no points".to_string(),
+            ));
+        }
+        if let Some(point) = self.points.iter().find(|p| !(0.0..=1.0).contains(&p.value)) {
+            return Err(Error::ParameterError(format!(
+                "Invalid automation: values should be normalized values, but is: '{}'",
+                point.value
+            )));
+        }
+        Ok(())
+    }
+
+    /// Number of sample frames from the given offset to the next point, or `None` when there
+    /// are no more points.
+    pub(crate) fn frames_until_next_point(&self, offset: u64) -> Option<u64> {
+        let next_index = self.points.partition_point(|p| p.offset <= offset);
+        self.points.get(next_index).map(|p| p.offset - offset)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramp_shapes() {
+        for shape in [RampShape::Linear, RampShape::Exponential, RampShape::SCurve] {
+            assert_eq!(shape.interpolate(0.2, 0.8, 0.0), 0.2);
+            assert!((shape.interpolate(0.2, 0.8, 1.0) - 0.8).abs() < 1e-6);
+            assert!((shape.interpolate(0.8, 0.2, 1.0) - 0.2).abs() < 1e-6);
+        }
+        assert_eq!(RampShape::Linear.interpolate(0.0, 1.0, 0.25), 0.25);
+        assert!(RampShape::Exponential.interpolate(0.0, 1.0, 0.5) < 0.5);
+        assert!(RampShape::Exponential.interpolate(1.0, 0.0, 0.5) < 0.5);
+        assert!(RampShape::SCurve.interpolate(0.0, 1.0, 0.25) < 0.25);
+        assert!((RampShape::SCurve.interpolate(0.0, 1.0, 0.5) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lane_values() {
+        assert_eq!(AutomationLane::new().value_at(0), None);
+
+        let lane = AutomationLane::new()
+            .with_point(300, 0.0, RampShape::Linear)
+            .with_point(100, 0.5, RampShape::SCurve)
+            .with_point(200, 1.0, RampShape::Linear)
+            .with_point(300, 0.2, RampShape::Linear);
+        assert_eq!(lane.points().len(), 3);
+        assert_eq!((lane.start(), lane.duration()), (100, 300));
+
+        assert_eq!(lane.value_at(0), Some(0.5));
+        assert_eq!(lane.value_at(100), Some(0.5));
+        assert_eq!(lane.value_at(150), Some(0.75));
+        assert_eq!(lane.value_at(200), Some(1.0));
+        assert!((lane.value_at(250).unwrap() - 0.6).abs() < 1e-6);
+        assert_eq!(lane.value_at(1000), Some(0.2));
+
+        assert_eq!(lane.frames_until_next_point(0), Some(100));
+        assert_eq!(lane.frames_until_next_point(150), Some(50));
+        assert_eq!(lane.frames_until_next_point(300), None);
+    }
+}
@@ -88,6 +88,9 @@ impl EnumParameter {
     }
 
     /// Create a raw, debug validated ParameterValueUpdate from an enum value for this parameter.
+    ///
+    /// The value is passed as string, so it can be tracked by the player without knowing the
+    /// enum type. [`EnumParameterValue`] converts it back to the enum value.
     #[must_use]
     pub fn value_update<E: PartialEq + ToString + Send + Sync + 'static>(
         &self,
@@ -100,7 +103,10 @@ impl EnumParameter {
             self.values,
             value.to_string()
         );
-        (self.id, ParameterValueUpdate::Raw(Arc::new(value)))
+        (
+            self.id,
+            ParameterValueUpdate::Raw(Arc::new(value.to_string())),
+        )
     }
 
     /// Create a raw, debug validated ParameterValueUpdate from an index for this parameter.
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
     sync::{
         atomic::{self, AtomicBool, AtomicUsize},
@@ -10,38 +10,56 @@ use std::{
     time::Duration,
 };
 
-use basedrop::{Collector, Handle, Owned};
+use basedrop::{Collector, Handle, Owned, Shared};
 use crossbeam_queue::ArrayQueue;
 use dashmap::DashMap;
 
 use crate::{
     effect::Effect,
     error::Error,
-    output::OutputDevice,
+    output::{memory::MemoryOutput, OutputDevice, OutputDeviceStatusEvent},
+    player::snapshot::{ParameterValueState, SharedParameterValueState},
     source::{
         amplified::AmplifiedSource,
         converted::ConvertedSource,
         file::FileSource,
         guarded::GuardedSource,
-        mapped::ChannelMappedSource,
-        measured::{CpuLoad, MeasuredSource, SharedCpuLoadState},
-        metered::{AudioLevel, MeteredSource, SharedAudioLevelState},
+        measured::{CpuLoad, CpuLoadState, MeasuredSource, SharedCpuLoadState},
+        metered::{
+            AudioLevel, MeteredSource, MeteringStates, SharedAudioLevelState, SharedSpectrumState,
+            SharedWaveformState, Spectrum, SpectrumConfig,
+        },
         mixed::{
-            EffectProcessor, MixedSource, MixerMessage, SubMixerProcessor, SubMixerThreadPool,
+            EffectProcessor, MixedSource, MixerMessage, MixerSendBuffer, MixerSendTarget,
+            SourceEffectChain, SourceMixerEventQueue, SubMixerProcessor, SubMixerThreadPool,
         },
         panned::PannedSource,
         playback::PlaybackMessageQueue,
-        resampled::ResamplingQuality,
+        resampled::{ResampledSource, ResamplingQuality},
+        routed::{RoutedSource, RoutedSourceMessage},
+        shared::{SharedSource, SharedSourceHandle},
         status::{PlaybackStatusContext, PlaybackStatusEvent},
         synth::SynthSource,
         Source,
     },
+    transport::{Beats, ScheduleTime, Transport},
     Generator,
 };
 
+#[cfg(feature = "wav-output")]
+use crate::{
+    output::wav::{WavFileWriter, WavSampleFormat},
+    utils::dither::DitherMode,
+};
+
 // -------------------------------------------------------------------------------------------------
 
 mod handles;
+mod snapshot;
+#[cfg(feature = "wav-output")]
+mod stems;
+#[cfg(test)]
+mod tests;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -57,12 +75,24 @@ pub type EffectId = usize;
 /// Unique ID for individual sounds played in a generator.
 pub type NotePlaybackId = usize;
 
+use handles::EffectEventQueue;
+
 // Playback handles for sources.
 pub use handles::{
     EffectHandle, FilePlaybackHandle, GeneratorPlaybackHandle, MixerHandle, SourcePlaybackHandle,
     SynthPlaybackHandle,
 };
 
+// Serializable graph snapshots.
+pub use snapshot::{
+    EffectSnapshot, GeneratorSnapshot, MixerSnapshot, ParameterSnapshot, PlayerSnapshot,
+    RestoredPlayerSnapshot, SendSnapshot,
+};
+
+// Offline stem rendering.
+#[cfg(feature = "wav-output")]
+pub use stems::RenderStem;
+
 /// A callback function to handle panics occurring within the player's main mixer.
 ///
 /// Will be called once only. The player is silent afterwards and should be shut down
@@ -72,6 +102,7 @@ pub type PanicHandler = crate::source::guarded::PanicHandler;
 // -------------------------------------------------------------------------------------------------
 
 /// How to move an effect within a mixer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum EffectMovement {
     /// Negative value shift the effect towards the start, positive ones towards the end.
     Direction(i32),
@@ -83,13 +114,76 @@ pub enum EffectMovement {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Where a mixer send taps the sending mixer's signal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixerSendMode {
+    /// Tap the signal after the mixer's effects and mute, but before the mixer's volume and
+    /// panning fader.
+    PreFader,
+    /// Tap the signal after the mixer's effects, mute, volume and panning.
+    #[default]
+    PostFader,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Where a stem taps a mixer's signal when rendering stems via `Player::render_stems_to_files`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StemMode {
+    /// Tap the mixer's summed and delay-compensated inputs, before the mixer's effects.
+    PreEffects,
+    /// Tap the mixer's output after its effects, mute, volume and panning: the signal which
+    /// the mixer feeds into its parent.
+    #[default]
+    PostEffects,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Where a sub-mixer's output goes to.
+///
+/// By default, sub-mixers get summed into their parent mixer. With multi-channel output devices,
+/// sub-mixers can also bypass their parents and play on dedicated device channels instead, e.g.
+/// to feed a cue output or separate stems. Channel indices are zero-based output device channels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixerOutput {
+    /// Mix into the parent mixer.
+    #[default]
+    Parent,
+    /// Play on the given output device channel and the channel after it.
+    /// Mono mixers get played on both channels.
+    Stereo(usize),
+    /// Play on the given output device channel. Multi-channel mixers get mixed down to mono.
+    Mono(usize),
+}
+
+impl MixerOutput {
+    /// The first output device channel and the number of channels the output plays on,
+    /// or `None` when mixing into the parent mixer.
+    pub fn device_channels(&self) -> Option<(usize, usize)> {
+        match *self {
+            Self::Parent => None,
+            Self::Stereo(channel) => Some((channel, 2)),
+            Self::Mono(channel) => Some((channel, 1)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Player internal info about a currently playing source.
 struct PlayingSource {
     is_playing: Arc<AtomicBool>,
     is_transient: bool,
     playback_message_queue: PlaybackMessageQueue,
     mixer_id: MixerId,
+    mixer_event_queue: SourceMixerEventQueue,
     source_name: String,
+    /// Set for generator sources only.
+    generator: Option<PlayingGenerator>,
 }
 
 impl Drop for PlayingSource {
@@ -100,22 +194,82 @@ impl Drop for PlayingSource {
     }
 }
 
+/// Player internal info about a playing generator.
+struct PlayingGenerator {
+    name: String,
+    parameters: SharedParameterValueState,
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Player internal info about an instantiated mixer.
-#[derive(Debug, Clone)]
-struct PlayerMixerInfo {
+#[derive(Clone)]
+pub(crate) struct PlayerMixerInfo {
     parent_id: MixerId,
     event_queue: Arc<ArrayQueue<MixerMessage>>,
+    /// Processing stage within the parent mixer, see [`Player::mixer_stages`].
+    stage: usize,
+    /// Effect ids in processing order.
+    effects: Vec<EffectId>,
+    /// Return mixer ids, modes and levels of all sends of the mixer.
+    sends: Vec<(MixerId, MixerSendMode, f32)>,
+    /// Where the mixer's output goes to.
+    output: MixerOutput,
+    /// Buffer the mixer writes its output into, when it plays on output device channels.
+    output_buffer: Option<Shared<MixerSendBuffer>>,
+    /// Fader values, as last set via the mixer's handle.
+    volume: f32,
+    panning: f32,
+    /// Mute and solo states, as set by the user.
+    muted: bool,
+    soloed: bool,
+    /// Resolved mute states, as last sent to the mixer, see [`Player::apply_mixer_mute_states`].
+    output_muted: bool,
+    sources_muted: bool,
+}
+
+impl PlayerMixerInfo {
+    fn new(parent_id: MixerId, event_queue: Arc<ArrayQueue<MixerMessage>>) -> Self {
+        Self {
+            parent_id,
+            event_queue,
+            stage: 0,
+            effects: Vec::new(),
+            sends: Vec::new(),
+            output: MixerOutput::Parent,
+            output_buffer: None,
+            volume: 1.0,
+            panning: 0.0,
+            muted: false,
+            soloed: false,
+            output_muted: false,
+            sources_muted: false,
+        }
+    }
 }
 
+/// Shared mixer infos, which are accessed by the player and mixer handles.
+pub(crate) type PlayerMixerInfos = Arc<DashMap<MixerId, PlayerMixerInfo>>;
+
+/// Serializes resolving and sending mixer mute states, which may happen from multiple threads
+/// via mixer handles, see [`Player::apply_mixer_mute_states`].
+pub(crate) type MuteStatesLock = Arc<Mutex<()>>;
+
+/// The main mixer with all its wrappers, as played by the output device.
+type MainMixerSource = RoutedSource<GuardedSource<MeasuredSource<MeteredSource<MixedSource>>>>;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Player internal info about an instantiated effect.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 struct PlayerEffectInfo {
     mixer_id: MixerId,
     effect_name: &'static str,
+    supports_sidechain: bool,
+    /// Mixer which feeds the effect's sidechain input, if any.
+    sidechain_mixer_id: Option<MixerId>,
+    /// Parameter values, as set via the effect's handle.
+    parameters: SharedParameterValueState,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -164,6 +318,26 @@ pub struct PlayerConfig {
     ///
     /// Default: `None` (avoid processing overhead)
     pub metering_interval: Option<Duration>,
+
+    /// FFT spectrum analysis settings for all mixers.
+    ///
+    /// `None` disables spectrum analysis entirely.
+    ///
+    /// Default: `None` (avoid processing overhead)
+    pub spectrum_analysis: Option<SpectrumConfig>,
+
+    /// Number of recent audio frames, which are recorded for waveform displays of all mixers.
+    ///
+    /// `None` disables waveform recording entirely.
+    ///
+    /// Default: `None` (avoid processing overhead)
+    pub waveform_history: Option<usize>,
+
+    /// Whether mixer audio level metering measures 4x oversampled true-peak levels and
+    /// detects overs on true-peak levels. Only applies when metering is enabled.
+    ///
+    /// Default: `false` (avoid processing overhead)
+    pub true_peak_metering: bool,
 }
 
 impl Default for PlayerConfig {
@@ -181,6 +355,9 @@ impl PlayerConfig {
             concurrent_worker_threads: None,
             measuring_interval: Some(Duration::from_millis(250)),
             metering_interval: None,
+            spectrum_analysis: None,
+            waveform_history: None,
+            true_peak_metering: false,
         }
     }
 
@@ -218,6 +395,29 @@ impl PlayerConfig {
         self
     }
 
+    /// Set the spectrum analysis settings for all mixers.
+    ///
+    /// Pass `None` to disable spectrum analysis entirely (with zero overhead).
+    pub fn spectrum_analysis(mut self, config: Option<SpectrumConfig>) -> Self {
+        self.spectrum_analysis = config;
+        self
+    }
+
+    /// Set the number of recent audio frames, which are recorded for waveform displays of
+    /// all mixers.
+    ///
+    /// Pass `None` to disable waveform recording entirely (with zero overhead).
+    pub fn waveform_history(mut self, frames: Option<usize>) -> Self {
+        self.waveform_history = frames;
+        self
+    }
+
+    /// Set if mixer audio level metering measures true-peak levels.
+    pub fn true_peak_metering(mut self, enabled: bool) -> Self {
+        self.true_peak_metering = enabled;
+        self
+    }
+
     /// Applied worker thread count, using system's available threads when
     /// `concurrent_worker_threads` is `None`.
     pub fn effective_concurrent_worker_threads(&self) -> usize {
@@ -250,6 +450,7 @@ impl PlayerConfig {
 pub struct Player {
     config: PlayerConfig,
     output_device: Box<dyn OutputDevice>,
+    output_device_status_sender: Option<SyncSender<OutputDeviceStatusEvent>>,
     playing_sources: Arc<DashMap<PlaybackId, PlayingSource>>,
     playback_status_running: Arc<AtomicBool>,
     playback_status_sender: SyncSender<PlaybackStatusEvent>,
@@ -257,12 +458,23 @@ pub struct Player {
     collector_handle: Handle,
     collector_running: Arc<AtomicBool>,
     collector_thread: Option<thread::JoinHandle<()>>,
-    mixers: DashMap<MixerId, PlayerMixerInfo>,
+    mixers: PlayerMixerInfos,
+    mute_states_lock: MuteStatesLock,
     effects: DashMap<EffectId, PlayerEffectInfo>,
+    main_mixer_source: SharedSourceHandle<MainMixerSource>,
+    main_mixer_channel_count: usize,
+    main_mixer_sample_rate: u32,
     main_mixer_measurement_state: Option<SharedCpuLoadState>,
-    main_mixer_metering_state: Option<SharedAudioLevelState>,
+    main_mixer_metering_states: MeteringStates,
+    output_latency: Arc<atomic::AtomicUsize>,
+    /// Latency of the main mixer's resampler in main mixer frames, see `set_output_device`.
+    output_resampler_latency: usize,
     main_mixer_panic_handler: Arc<Mutex<Option<PanicHandler>>>,
     main_mixer_dropped: Arc<atomic::AtomicBool>,
+    output_routing_queue: Arc<ArrayQueue<RoutedSourceMessage>>,
+    offline_output: Option<MemoryOutput>,
+    offline_buffer: Vec<f32>,
+    transport: Transport,
 }
 
 impl Player {
@@ -317,14 +529,13 @@ impl Player {
         ));
 
         // Create a mixer source and add it to the audio sink
-        let mut main_mixer = MixedSource::new(
-            if config.enforce_stereo_playback {
-                2
-            } else {
-                output_device.channel_count()
-            },
-            output_device.sample_rate(),
-        );
+        let main_mixer_channel_count = if config.enforce_stereo_playback {
+            2
+        } else {
+            output_device.channel_count()
+        };
+        let main_mixer_sample_rate = output_device.sample_rate();
+        let mut main_mixer = MixedSource::new(main_mixer_channel_count, main_mixer_sample_rate);
 
         // Create thread pool main mixer
         let thread_pool = (config.concurrent_processing
@@ -342,23 +553,27 @@ impl Player {
         main_mixer.set_thread_pool(thread_pool);
 
         let mixer_event_queue = main_mixer.message_queue();
+        let main_mixer_latency = main_mixer.latency_state();
 
         // Wrap main mixer in MeteredSource for audio level tracking
-        let metered_main_mixer = MeteredSource::new(main_mixer, config.metering_interval);
-        let main_mixer_metering_state = metered_main_mixer.state();
+        let metered_main_mixer = MeteredSource::new(
+            main_mixer,
+            config.metering_interval,
+            config.spectrum_analysis,
+            config.waveform_history,
+        )
+        .with_true_peak(config.true_peak_metering);
+        let main_mixer_metering_states = metered_main_mixer.states();
 
         // Wrap in MeasuredSource for CPU load tracking
         let measured_main_mixer =
             MeasuredSource::new(metered_main_mixer, config.measuring_interval);
         let main_mixer_measurement_state = measured_main_mixer.state();
 
-        let mixers = DashMap::new();
+        let mixers = Arc::new(DashMap::new());
         mixers.insert(
             Player::MAIN_MIXER_ID,
-            PlayerMixerInfo {
-                parent_id: Player::MAIN_MIXER_ID,
-                event_queue: mixer_event_queue,
-            },
+            PlayerMixerInfo::new(Player::MAIN_MIXER_ID, mixer_event_queue),
         );
         let effects = DashMap::new();
 
@@ -373,19 +588,27 @@ impl Player {
         )
         .with_drop_signal(Arc::clone(&main_mixer_dropped));
 
-        // Assign the wrapped main mixer as sink source
-        if config.enforce_stereo_playback && output_device.channel_count() != 2 {
-            // Map the main mixer's enforced stereo output to the output device's channel layout
-            let channel_mapped_source =
-                ChannelMappedSource::new(guarded_main_mixer, output_device.channel_count());
-            output_device.play(channel_mapped_source.into_box());
-        } else {
-            output_device.play(guarded_main_mixer.into_box());
-        }
+        // Map the main mixer's output to the output device's channel layout and add mixers
+        // which are routed to dedicated output channels, then assign it as sink source
+        let routed_main_mixer = RoutedSource::new(
+            guarded_main_mixer,
+            main_mixer_latency,
+            output_device.channel_count(),
+        );
+        let output_routing_queue = routed_main_mixer.message_queue();
+        let output_latency = routed_main_mixer.latency_state();
+
+        // Share the main mixer with the output device, so it can be moved to other devices
+        let (shared_main_mixer, main_mixer_source) = SharedSource::new(routed_main_mixer);
+        output_device.play(shared_main_mixer.into_box());
+
+        // Create the musical transport
+        let transport = Transport::new(output_device.sample_rate());
 
         Self {
             config,
             output_device,
+            output_device_status_sender: None,
             playing_sources,
             playback_status_running,
             playback_status_sender,
@@ -394,14 +617,69 @@ impl Player {
             collector_running,
             collector_thread,
             mixers,
+            mute_states_lock: MuteStatesLock::default(),
             effects,
             main_mixer_dropped,
             main_mixer_panic_handler,
+            main_mixer_source,
+            main_mixer_channel_count,
+            main_mixer_sample_rate,
             main_mixer_measurement_state,
-            main_mixer_metering_state,
+            main_mixer_metering_states,
+            output_latency,
+            output_resampler_latency: 0,
+            output_routing_queue,
+            offline_output: None,
+            offline_buffer: Vec::new(),
+            transport,
         }
     }
 
+    /// Create a new offline player, which renders audio synchronously and faster than realtime
+    /// via [`Self::render`] or [`Self::render_to_file`] instead of playing back audio on an
+    /// output device.
+    ///
+    /// Offline players have no device thread: set up the mixer graph and schedule sources and
+    /// events as usual, then render the desired amount of frames on the calling thread. Source
+    /// times only depend on the rendered frames, not on the wall clock, so renderings are
+    /// reproducible. Note that streamed file sources decode audio in a separate thread, so use
+    /// preloaded file sources for sample exact renderings. Effects with randomized states, such as
+    /// the [`ReverbEffect`](crate::effects::ReverbEffect)'s dither, will vary between renderings.
+    ///
+    /// See [Self::new] for a description of the `playback_status_sender` parameter.
+    pub fn new_offline<S: Into<Option<SyncSender<PlaybackStatusEvent>>>>(
+        sample_rate: u32,
+        channel_count: usize,
+        playback_status_sender: S,
+    ) -> Self {
+        Self::new_offline_with_config(
+            sample_rate,
+            channel_count,
+            playback_status_sender,
+            PlayerConfig::default(),
+        )
+    }
+
+    /// Create a new offline player with custom [`PlayerConfig`].
+    ///
+    /// When bit-exact renderings are required, e.g. for golden-file tests, disable concurrent
+    /// processing in the config: concurrently processed sub-mixers may get summed up in varying
+    /// orders, which results in tiny rounding differences.
+    ///
+    /// See [Self::new_offline] for descriptions of the other parameters.
+    pub fn new_offline_with_config<S: Into<Option<SyncSender<PlaybackStatusEvent>>>>(
+        sample_rate: u32,
+        channel_count: usize,
+        playback_status_sender: S,
+        config: PlayerConfig,
+    ) -> Self {
+        let offline_output = MemoryOutput::new(sample_rate, channel_count);
+        let mut player =
+            Self::new_with_config(offline_output.clone(), playback_status_sender, config);
+        player.offline_output = Some(offline_output);
+        player
+    }
+
     /// True when the output device is currently suspended,
     /// e.g. because the app which drives the audio stream is hidden.
     pub fn output_suspended(&self) -> bool {
@@ -409,26 +687,24 @@ impl Player {
     }
 
     /// Our main mixers sample rate.
+    ///
+    /// This is the sample rate of the output device the player got created with. When switching
+    /// to a device with another sample rate, the main mixer's output gets resampled.
     pub fn output_sample_rate(&self) -> u32 {
-        self.output_device.sample_rate()
+        self.main_mixer_sample_rate
     }
     /// Our main mixer's sample channel count.
     pub fn output_channel_count(&self) -> usize {
-        if self.config.enforce_stereo_playback {
-            2
-        } else {
-            self.output_device.channel_count()
-        }
+        self.main_mixer_channel_count
     }
 
-    /// Our actual playhead pos in sample frames
+    /// Our actual playhead pos in sample frames, at the main mixer's sample rate.
+    ///
+    /// This is the number of frames the main mixer rendered so far, which is the time base of
+    /// all scheduled events. It continues when switching output devices, so it may differ from
+    /// the current output device's own sample position.
     pub fn output_sample_frame_position(&self) -> u64 {
-        let channel_count = self.output_device.channel_count();
-        if channel_count > 0 {
-            self.output_device.sample_position() / channel_count as u64
-        } else {
-            0
-        }
+        self.main_mixer_source.position()
     }
 
     /// Our output's global volume factor
@@ -441,6 +717,147 @@ impl Player {
         self.output_device.set_volume(volume);
     }
 
+    /// Sets or removes a channel which receives [`OutputDeviceStatusEvent`]s from the output
+    /// device, such as xruns, disconnects and configuration changes, so applications can react
+    /// to them, e.g. by switching to another device via [`Self::set_output_device`].
+    ///
+    /// The channel stays connected when switching output devices. Note that not all output
+    /// devices report status events.
+    pub fn set_output_device_status_sender(
+        &mut self,
+        sender: Option<SyncSender<OutputDeviceStatusEvent>>,
+    ) {
+        self.output_device.set_status_sender(sender.clone());
+        self.output_device_status_sender = sender;
+    }
+
+    /// Switch playback to the given output device, e.g. when the user selected another audio
+    /// device, without interrupting the player's mixer graph.
+    ///
+    /// The running main mixer moves to the new device with all its sources, effects and
+    /// sub-mixers, so all mixer, effect and playback handles stay valid. Sample times continue
+    /// where they were. When the device's sample rate differs, the main mixer keeps running at
+    /// its original sample rate and its output gets resampled. When the device's channel count
+    /// differs, the main mix gets mapped to the device's channel layout and mixer outputs which
+    /// no longer fit into the device's channels get routed back into their parent mixers.
+    ///
+    /// The previous device gets closed. The new device takes over the previous device's volume
+    /// and running state. Offline players can no longer render after switching devices.
+    ///
+    /// When switching fails, the player keeps playing on its current device and all mixer
+    /// outputs stay unchanged.
+    pub fn set_output_device(
+        &mut self,
+        mut output_device: Box<dyn OutputDevice>,
+    ) -> Result<(), Error> {
+        let channel_count = output_device.channel_count();
+        let sample_rate = output_device.sample_rate();
+        if channel_count == 0 || sample_rate == 0 {
+            return Err(Error::ParameterError(format!(
+                "Invalid output device specs: {channel_count} channels at {sample_rate} Hz"
+            )));
+        }
+
+        // Validate everything which can fail, before changing routes
+        if !self.main_mixer_source.is_alive() {
+            return Err(Error::OutputDeviceError(
+                "The player's main mixer got dropped".into(),
+            ));
+        }
+        let unroutable_mixers: Vec<(MixerId, MixerOutput)> = self
+            .mixers
+            .iter()
+            .filter(|entry| {
+                entry
+                    .value()
+                    .output
+                    .device_channels()
+                    .is_some_and(|(first, count)| first + count > channel_count)
+            })
+            .map(|entry| (*entry.key(), entry.value().output))
+            .collect();
+        for (mixer_id, _) in &unroutable_mixers {
+            if self
+                .mixer_event_queue(self.mixer_parent_id(*mixer_id)?)?
+                .is_full()
+            {
+                return Err(Self::mixer_event_queue_error("set_output_device"));
+            }
+        }
+        let free_routing_slots =
+            self.output_routing_queue.capacity() - self.output_routing_queue.len();
+        if free_routing_slots < unroutable_mixers.len() {
+            return Err(Self::output_routing_queue_error("set_output_device"));
+        }
+
+        // Route mixers which don't fit into the new device's channels back into their parents.
+        // When this fails halfway, restore the routes which already got changed.
+        for (index, (mixer_id, _)) in unroutable_mixers.iter().enumerate() {
+            log::warn!("Mixer {mixer_id}'s output does not fit into the new output device");
+            if let Err(err) = self.set_mixer_output(*mixer_id, MixerOutput::Parent) {
+                for (mixer_id, previous_output) in &unroutable_mixers[..index] {
+                    if let Err(err) = self.set_mixer_output(*mixer_id, *previous_output) {
+                        log::error!("Failed to restore mixer {mixer_id}'s output: {err}");
+                    }
+                }
+                return Err(err);
+            }
+        }
+
+        // Detach the main mixer from the old device, then adapt it to the new device's layout
+        self.main_mixer_source.disconnect();
+        self.main_mixer_source
+            .with_source(|source| source.set_output_channels(channel_count));
+        let shared_main_mixer = self.main_mixer_source.connect().ok_or_else(|| {
+            Error::OutputDeviceError("The player's main mixer got dropped".into())
+        })?;
+        let main_mixer: Box<dyn Source> = if sample_rate != self.main_mixer_sample_rate {
+            let resampled_main_mixer = ResampledSource::new(
+                shared_main_mixer,
+                sample_rate,
+                ResamplingQuality::HighQuality,
+            );
+            // resampler latency is reported in device frames: convert to main mixer frames
+            self.output_resampler_latency = (resampled_main_mixer.latency() as u64
+                * self.main_mixer_sample_rate as u64)
+                .div_ceil(sample_rate as u64) as usize;
+            resampled_main_mixer.into_box()
+        } else {
+            self.output_resampler_latency = 0;
+            shared_main_mixer.into_box()
+        };
+
+        // Play the main mixer on the new device and close the old one
+        log::info!("Switching output device...");
+        let is_running = self.output_device.is_running();
+        output_device.set_volume(self.output_device.volume());
+        output_device.set_status_sender(self.output_device_status_sender.clone());
+        output_device.play(main_mixer);
+        if !is_running {
+            output_device.pause();
+        }
+        let mut old_output_device = std::mem::replace(&mut self.output_device, output_device);
+        old_output_device.stop();
+        old_output_device.close();
+        self.offline_output = None;
+
+        Ok(())
+    }
+
+    /// Get the total processing latency of the player's mixer graph in sample frames.
+    ///
+    /// This is the latency which latent effects, such as compressors with lookahead, add to the
+    /// main mixer's output. Parallel paths in the mixer graph get delay-compensated, so this is the
+    /// latency of the slowest path, including sub-mixers which are routed to dedicated output
+    /// channels. When the main mixer's output gets resampled to another output device's sample
+    /// rate, the resampler's latency is included too. The output device's buffer latency is not
+    /// included.
+    ///
+    /// The latency is reported in sample frames at the main mixer's sample rate.
+    pub fn output_latency(&self) -> usize {
+        self.output_latency.load(atomic::Ordering::Relaxed) + self.output_resampler_latency
+    }
+
     /// Get the current CPU load for the player's main mixer.
     ///
     /// Only available when CPU measurement is enabled in the player's [`PlayerConfig`].
@@ -462,7 +879,8 @@ impl Player {
     ///
     /// Only available when audio metering is enabled in the player's [`PlayerConfig`].
     pub fn audio_level(&self) -> Option<AudioLevel> {
-        self.main_mixer_metering_state
+        self.main_mixer_metering_states
+            .audio_level
             .as_ref()
             .and_then(|s| s.try_lock().ok())
             .map(|state| state.audio_level().clone())
@@ -472,7 +890,31 @@ impl Player {
     ///
     /// Only available when audio metering is enabled in the player's [`PlayerConfig`].
     pub fn audio_level_state(&self) -> Option<SharedAudioLevelState> {
-        self.main_mixer_metering_state.as_ref().map(Arc::clone)
+        self.main_mixer_metering_states.audio_level.clone()
+    }
+
+    /// Get the current magnitude spectrum of the player's main mixer.
+    ///
+    /// Only available when spectrum analysis is enabled in the player's [`PlayerConfig`].
+    pub fn spectrum(&self) -> Option<Spectrum> {
+        self.main_mixer_metering_states
+            .spectrum
+            .as_ref()
+            .map(|state| state.spectrum())
+    }
+
+    /// Get the shared, lock-free spectrum data for the player's main mixer.
+    ///
+    /// Only available when spectrum analysis is enabled in the player's [`PlayerConfig`].
+    pub fn spectrum_state(&self) -> Option<SharedSpectrumState> {
+        self.main_mixer_metering_states.spectrum.clone()
+    }
+
+    /// Get the shared, lock-free recent waveform history of the player's main mixer.
+    ///
+    /// Only available when waveform recording is enabled in the player's [`PlayerConfig`].
+    pub fn waveform_state(&self) -> Option<SharedWaveformState> {
+        self.main_mixer_metering_states.waveform.clone()
     }
 
     /// Sets or replaces a panic handler for the player's main mixer.
@@ -507,8 +949,107 @@ impl Player {
         self.output_device.pause();
     }
 
+    /// Access the player's musical [`Transport`], which converts [`Beats`](crate::Beats) and
+    /// [`Bars`](crate::Bars) positions to sample times in the player and playback handles.
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    /// Start the transport from its current position at the current output sample position.
+    pub fn start_transport(&mut self) {
+        self.transport.start(self.output_sample_frame_position());
+    }
+
+    /// Stop the transport at the current output sample position.
+    pub fn stop_transport(&mut self) {
+        self.transport.stop(self.output_sample_frame_position());
+    }
+
+    /// Move the transport to the given beat position at the current output sample position.
+    pub fn locate_transport(&mut self, position: Beats) {
+        self.transport
+            .locate(position, self.output_sample_frame_position());
+    }
+
+    /// True when this player got created via [`Self::new_offline`] and thus renders audio via
+    /// [`Self::render`] instead of playing it back on an output device.
+    pub fn is_offline(&self) -> bool {
+        self.offline_output.is_some()
+    }
+
+    /// Synchronously render the given number of sample frames on the calling thread.
+    ///
+    /// Returns the rendered interleaved output buffer, which has the player's output channel
+    /// layout. The buffer is reused by subsequent render calls. Rendering advances the player's
+    /// output position, so sources and events can be scheduled in between render calls.
+    ///
+    /// Only available for offline players, see [`Self::new_offline`].
+    pub fn render(&mut self, frames: usize) -> Result<&[f32], Error> {
+        let offline_output = self
+            .offline_output
+            .as_ref()
+            .ok_or_else(Self::offline_output_error)?;
+        let samples = frames * self.output_device.channel_count();
+        self.offline_buffer.resize(samples, 0.0);
+        offline_output.pull(&mut self.offline_buffer);
+        Ok(&self.offline_buffer)
+    }
+
+    /// Synchronously render the given number of sample frames into a new WAV file at the given
+    /// path. WAV file contents are saved as 32bit floats.
+    ///
+    /// Only available for offline players, see [`Self::new_offline`].
+    #[cfg(feature = "wav-output")]
+    pub fn render_to_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        file_path: P,
+        frames: usize,
+    ) -> Result<(), Error> {
+        self.render_to_file_with_format(
+            file_path,
+            frames,
+            WavSampleFormat::Float32,
+            DitherMode::None,
+        )
+    }
+
+    /// Synchronously render the given number of sample frames into a new WAV file at the given
+    /// path with the given sample format. When writing integer formats, samples get quantized
+    /// with the given dither mode.
+    ///
+    /// Only available for offline players, see [`Self::new_offline`].
+    #[cfg(feature = "wav-output")]
+    pub fn render_to_file_with_format<P: AsRef<std::path::Path>>(
+        &mut self,
+        file_path: P,
+        frames: usize,
+        sample_format: WavSampleFormat,
+        dither: DitherMode,
+    ) -> Result<(), Error> {
+        if self.offline_output.is_none() {
+            return Err(Self::offline_output_error());
+        }
+        let mut writer = WavFileWriter::create(
+            file_path,
+            self.output_device.sample_rate(),
+            self.output_device.channel_count(),
+            sample_format,
+            dither,
+        )?;
+
+        // Render and write in blocks to avoid allocating huge render buffers
+        const RENDER_BLOCK_FRAMES: usize = 4096;
+        let mut frames_left = frames;
+        while frames_left > 0 {
+            let block_frames = frames_left.min(RENDER_BLOCK_FRAMES);
+            writer.write(self.render(block_frames)?)?;
+            frames_left -= block_frames;
+        }
+        writer.finalize()
+    }
+
     /// Play a newly created or cloned file source.
-    pub fn play_file_source<F: FileSource, T: Into<Option<u64>>>(
+    pub fn play_file_source<F: FileSource, T: Into<ScheduleTime>>(
         &mut self,
         file_source: F,
         start_time: T,
@@ -516,10 +1057,34 @@ impl Player {
         self.play_file_source_with_context(file_source, start_time, None)
     }
     /// Play a newly created or cloned file source with the given playback status context.
-    pub fn play_file_source_with_context<F: FileSource, T: Into<Option<u64>>>(
+    pub fn play_file_source_with_context<F: FileSource, T: Into<ScheduleTime>>(
+        &mut self,
+        file_source: F,
+        start_time: T,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<FilePlaybackHandle, Error> {
+        self.play_file_source_with_effects_and_context(file_source, start_time, Vec::new(), context)
+    }
+    /// Play a newly created or cloned file source with the given insert effects.
+    ///
+    /// Insert effects process the source's output in the given order, right before the source
+    /// gets mixed. They can be automated via the returned handle's
+    /// [`effects`](FilePlaybackHandle::effects) and get dropped together with the source, after
+    /// their tails finished playing. Unlike mixer effects, insert effects can't use sidechains
+    /// and their latency is not compensated.
+    pub fn play_file_source_with_effects<F: FileSource, T: Into<ScheduleTime>>(
+        &mut self,
+        file_source: F,
+        start_time: T,
+        effects: Vec<Box<dyn Effect>>,
+    ) -> Result<FilePlaybackHandle, Error> {
+        self.play_file_source_with_effects_and_context(file_source, start_time, effects, None)
+    }
+    fn play_file_source_with_effects_and_context<F: FileSource, T: Into<ScheduleTime>>(
         &mut self,
         file_source: F,
         start_time: T,
+        effects: Vec<Box<dyn Effect>>,
         context: Option<PlaybackStatusContext>,
     ) -> Result<FilePlaybackHandle, Error> {
         // validate and get options
@@ -527,7 +1092,9 @@ impl Player {
         playback_options.validate()?;
         // validate and get target mixer
         let mixer_id = playback_options.target_mixer.unwrap_or(Self::MAIN_MIXER_ID);
-        let mixer_event_queue = self.mixer_event_queue(mixer_id)?;
+        let mixer_event_queue = SourceMixerEventQueue::new(self.mixer_event_queue(mixer_id)?);
+        // validate and get start time
+        let sample_time = self.transport.resolve(start_time)?.unwrap_or(0);
         // redirect source's playback status channel to us and set context
         let mut file_source = file_source;
         file_source.set_playback_status_sender(Some(self.playback_status_sender.clone()));
@@ -536,6 +1103,9 @@ impl Player {
         let playback_id = file_source.playback_id();
         let playback_message_queue = file_source.playback_message_queue();
         let source_name = format!("File: '{}'", file_source.file_name());
+        // create insert effects
+        let (effects, effect_handles) =
+            self.create_insert_effects(effects, playback_id, mixer_id, &mixer_event_queue)?;
         // convert file to mixer's rate and channel layout
         let converted_source = ConvertedSource::new(
             file_source,
@@ -571,18 +1141,20 @@ impl Player {
                 is_transient: true,
                 playback_message_queue: playback_message_queue.clone(),
                 mixer_id,
+                mixer_event_queue: mixer_event_queue.clone(),
                 source_name,
+                generator: None,
             },
         );
         // send the source to the mixer
         let source = Owned::new(&self.collector_handle, measured_source.into_box());
-        let sample_time = start_time.into().unwrap_or(0);
         if mixer_event_queue
             .push(MixerMessage::AddSource {
                 is_transient: true,
                 playback_id,
                 playback_message_queue: playback_message_queue.clone(),
                 source,
+                effects,
                 sample_time,
             })
             .is_err()
@@ -597,12 +1169,14 @@ impl Player {
                 playback_message_queue,
                 mixer_event_queue,
                 measurement_state,
+                effect_handles,
+                self.transport.clone(),
             ))
         }
     }
 
     /// Play a newly created or cloned synth source with the given playback options.
-    pub fn play_synth_source<S: SynthSource, T: Into<Option<u64>>>(
+    pub fn play_synth_source<S: SynthSource, T: Into<ScheduleTime>>(
         &mut self,
         synth_source: S,
         start_time: T,
@@ -611,10 +1185,34 @@ impl Player {
     }
     /// Play a newly created or cloned synth source with the given playback options and
     /// playback status context.
-    pub fn play_synth_source_with_context<S: SynthSource, T: Into<Option<u64>>>(
+    pub fn play_synth_source_with_context<S: SynthSource, T: Into<ScheduleTime>>(
+        &mut self,
+        synth_source: S,
+        start_time: T,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<SynthPlaybackHandle, Error> {
+        self.play_synth_source_with_effects_and_context(
+            synth_source,
+            start_time,
+            Vec::new(),
+            context,
+        )
+    }
+    /// Play a newly created or cloned synth source with the given playback options and insert
+    /// effects. See [Self::play_file_source_with_effects] for details about insert effects.
+    pub fn play_synth_source_with_effects<S: SynthSource, T: Into<ScheduleTime>>(
+        &mut self,
+        synth_source: S,
+        start_time: T,
+        effects: Vec<Box<dyn Effect>>,
+    ) -> Result<SynthPlaybackHandle, Error> {
+        self.play_synth_source_with_effects_and_context(synth_source, start_time, effects, None)
+    }
+    fn play_synth_source_with_effects_and_context<S: SynthSource, T: Into<ScheduleTime>>(
         &mut self,
         synth_source: S,
         start_time: T,
+        effects: Vec<Box<dyn Effect>>,
         context: Option<PlaybackStatusContext>,
     ) -> Result<SynthPlaybackHandle, Error> {
         // validate and get options
@@ -622,7 +1220,9 @@ impl Player {
         playback_options.validate()?;
         // validate and get target mixer
         let mixer_id = playback_options.target_mixer.unwrap_or(Self::MAIN_MIXER_ID);
-        let mixer_event_queue = self.mixer_event_queue(mixer_id)?;
+        let mixer_event_queue = SourceMixerEventQueue::new(self.mixer_event_queue(mixer_id)?);
+        // validate and get start time
+        let sample_time = self.transport.resolve(start_time)?.unwrap_or(0);
         // redirect source's playback status channel to us and set context
         let mut synth_source = synth_source;
         synth_source.set_playback_status_sender(Some(self.playback_status_sender.clone()));
@@ -631,6 +1231,9 @@ impl Player {
         let playback_id = synth_source.playback_id();
         let playback_message_queue = synth_source.playback_message_queue();
         let source_name = format!("Synth: '{}'", synth_source.synth_name());
+        // create insert effects
+        let (effects, effect_handles) =
+            self.create_insert_effects(effects, playback_id, mixer_id, &mixer_event_queue)?;
         // convert synth to mixer's rate and channel layout
         let converted_source = ConvertedSource::new(
             synth_source,
@@ -666,18 +1269,20 @@ impl Player {
                 is_transient: true,
                 playback_message_queue: playback_message_queue.clone(),
                 mixer_id,
+                mixer_event_queue: mixer_event_queue.clone(),
                 source_name,
+                generator: None,
             },
         );
         // send the source to the mixer
         let source = Owned::new(&self.collector_handle, measured_source.into_box());
-        let sample_time = start_time.into().unwrap_or(0);
         if mixer_event_queue
             .push(MixerMessage::AddSource {
                 is_transient: true,
                 playback_id,
                 playback_message_queue: playback_message_queue.clone(),
                 source,
+                effects,
                 sample_time,
             })
             .is_err()
@@ -692,6 +1297,8 @@ impl Player {
                 playback_message_queue,
                 mixer_event_queue,
                 measurement_state,
+                effect_handles,
+                self.transport.clone(),
             ))
         }
     }
@@ -705,17 +1312,28 @@ impl Player {
     ///
     /// Note that boxed `dyn Generator` can be passed here as well as there's a generator impl
     /// defined for `Box<dyn Generator>` in the Generator trait definition.
-    pub fn play_generator<G: Generator + 'static, T: Into<Option<u64>>>(
+    pub fn play_generator<G: Generator + 'static, T: Into<ScheduleTime>>(
         &mut self,
         generator: G,
         start_time: T,
+    ) -> Result<GeneratorPlaybackHandle, Error> {
+        self.play_generator_with_effects(generator, start_time, Vec::new())
+    }
+
+    /// Play a generator source with the given options and insert effects.
+    /// See [Self::play_generator] and [Self::play_file_source_with_effects] for details.
+    pub fn play_generator_with_effects<G: Generator + 'static, T: Into<ScheduleTime>>(
+        &mut self,
+        generator: G,
+        start_time: T,
+        effects: Vec<Box<dyn Effect>>,
     ) -> Result<GeneratorPlaybackHandle, Error> {
         let is_transient = true;
         let mixer_id = generator
             .playback_options()
             .target_mixer
             .unwrap_or(Self::MAIN_MIXER_ID);
-        self.add_or_play_generator(generator, is_transient, mixer_id, start_time)
+        self.add_or_play_generator(generator, is_transient, mixer_id, start_time, effects)
     }
 
     /// Add a generator source with the given options. *Added* generators will not be removed
@@ -731,6 +1349,17 @@ impl Player {
         &mut self,
         generator: G,
         mixer_id: M,
+    ) -> Result<GeneratorPlaybackHandle, Error> {
+        self.add_generator_with_effects(generator, mixer_id, Vec::new())
+    }
+
+    /// Add a generator source with the given options and insert effects.
+    /// See [Self::add_generator] and [Self::play_file_source_with_effects] for details.
+    pub fn add_generator_with_effects<G: Generator + 'static, M: Into<Option<MixerId>>>(
+        &mut self,
+        generator: G,
+        mixer_id: M,
+        effects: Vec<Box<dyn Effect>>,
     ) -> Result<GeneratorPlaybackHandle, Error> {
         let is_transient = false;
         let mixer_id = mixer_id.into().unwrap_or(Self::MAIN_MIXER_ID);
@@ -739,7 +1368,7 @@ impl Player {
                 log::warn!("Ignoring target mixer id from playback options when adding instead of playing a generator");
             }
         }
-        self.add_or_play_generator(generator, is_transient, mixer_id, None)
+        self.add_or_play_generator(generator, is_transient, mixer_id, None, effects)
     }
 
     /// Remove a generator which was added via [Self::add_generator].
@@ -753,8 +1382,8 @@ impl Player {
                     "Expected a non transient generator here, which was added via 'add_generator'"
                 );
                 // Send the remove message to parent
-                if self
-                    .mixer_event_queue(playing_source.mixer_id)?
+                if playing_source
+                    .mixer_event_queue
                     .push(MixerMessage::RemoveSource { playback_id })
                     .is_err()
                 {
@@ -768,12 +1397,87 @@ impl Player {
         Ok(())
     }
 
+    /// Move a playing file, synth or generator source to another mixer without stopping it.
+    ///
+    /// The source gets faded out in its current mixer and faded in again in the new mixer with a
+    /// short declicking fade. Pending scheduled events of the source move along with it, and the
+    /// source's playback handles control the source in its new mixer after the move.
+    pub fn move_source(&mut self, playback_id: PlaybackId, mixer_id: MixerId) -> Result<(), Error> {
+        if !self.playing_sources.contains_key(&playback_id) {
+            return Err(Error::SourceNotPlaying);
+        }
+        self.move_playing_source(playback_id, mixer_id, "move_source")
+    }
+
+    /// Move a generator, which got added via [Self::add_generator] or played via
+    /// [Self::play_generator], to another mixer without stopping it.
+    ///
+    /// See [Self::move_source] for details.
+    pub fn move_generator(
+        &mut self,
+        playback_id: PlaybackId,
+        mixer_id: MixerId,
+    ) -> Result<(), Error> {
+        let is_generator = self
+            .playing_sources
+            .get(&playback_id)
+            .is_some_and(|source| source.generator.is_some());
+        if !is_generator {
+            return Err(Error::GeneratorNotFoundError(playback_id));
+        }
+        self.move_playing_source(playback_id, mixer_id, "move_generator")
+    }
+
+    fn move_playing_source(
+        &mut self,
+        playback_id: PlaybackId,
+        mixer_id: MixerId,
+        event_name: &str,
+    ) -> Result<(), Error> {
+        let target_mixer_queue = self.mixer_event_queue(mixer_id)?;
+        let Some(mut playing_source) = self.playing_sources.get_mut(&playback_id) else {
+            return Err(Error::SourceNotPlaying);
+        };
+        if playing_source.mixer_id == mixer_id {
+            return Ok(());
+        }
+        // NB: the source's mixer queue always leads to the mixer which currently owns the source,
+        // even when a previous move is still in progress.
+        let source_mixer_queue = playing_source.mixer_event_queue.clone();
+        if source_mixer_queue
+            .push(MixerMessage::MoveSource {
+                playback_id,
+                target_mixer_queue,
+                source_mixer_queue: source_mixer_queue.clone(),
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error(event_name));
+        }
+        playing_source.mixer_id = mixer_id;
+        Ok(())
+    }
+
     /// Add a new mixer to an existing mixer.
     /// Use `None` as mixer id to add it to the main mixer.
     pub fn add_mixer<M: Into<Option<MixerId>>>(
         &mut self,
         parent_mixer_id: M,
     ) -> Result<MixerHandle, Error> {
+        self.add_mixer_with_output(parent_mixer_id, MixerOutput::Parent)
+    }
+
+    /// Add a new mixer to an existing mixer, which plays its output on the given output device
+    /// channels instead of mixing it into the parent mixer, e.g. to feed a cue output or to
+    /// play stems on separate outputs. Use `None` as mixer id to add it to the main mixer.
+    ///
+    /// See [`Self::set_mixer_output`] for more info about output assignments.
+    pub fn add_mixer_with_output<M: Into<Option<MixerId>>>(
+        &mut self,
+        parent_mixer_id: M,
+        output: MixerOutput,
+    ) -> Result<MixerHandle, Error> {
+        self.validate_mixer_output(output)?;
         let parent_mixer_id = parent_mixer_id.into().unwrap_or(Self::MAIN_MIXER_ID);
         let parent_mixer_event_queue = self.mixer_event_queue(parent_mixer_id)?;
 
@@ -782,19 +1486,49 @@ impl Player {
         let mixer_id = Self::unique_mixer_id();
 
         // Wrap in MeteredSource for audio level tracking
-        let metered_mixer = MeteredSource::new(mixer, self.config.metering_interval);
-        let metering_state = metered_mixer.state();
+        let metered_mixer = MeteredSource::new(
+            mixer,
+            self.config.metering_interval,
+            self.config.spectrum_analysis,
+            self.config.waveform_history,
+        )
+        .with_true_peak(self.config.true_peak_metering);
+        let metering_states = metered_mixer.states();
 
         // Wrap in MeasuredSource for CPU load tracking
         let measured_mixer = MeasuredSource::new(metered_mixer, self.config.measuring_interval);
         let measurement_state = measured_mixer.state();
 
         // Wrap into an owned processor
-        let mixer_processor = Owned::new(
+        let mut mixer_processor = Owned::new(
             &self.collector_handle,
             SubMixerProcessor::new(Box::new(measured_mixer)),
         );
 
+        // Connect the output route of mixers which play on output device channels first
+        let output_buffer = if let Some((first_channel, channel_count)) = output.device_channels() {
+            let buffer = Shared::new(
+                &self.collector_handle,
+                MixerSendBuffer::new(self.output_channel_count(), self.output_sample_rate()),
+            );
+            if self
+                .output_routing_queue
+                .push(RoutedSourceMessage::AddRoute {
+                    mixer_id,
+                    first_channel,
+                    channel_count,
+                    buffer: Shared::clone(&buffer),
+                })
+                .is_err()
+            {
+                return Err(Self::output_routing_queue_error("add_mixer"));
+            }
+            mixer_processor.set_direct_output(Some(Shared::clone(&buffer)));
+            Some(buffer)
+        } else {
+            None
+        };
+
         // Send message to parent mixer
         if parent_mixer_event_queue
             .push(MixerMessage::AddMixer {
@@ -803,20 +1537,28 @@ impl Player {
             })
             .is_err()
         {
+            // Disconnect the output route again
+            if output_buffer.is_some() {
+                self.undo_output_route(mixer_id, MixerOutput::Parent, None);
+            }
             Err(Self::mixer_event_queue_error("add_mixer"))
         } else {
-            self.mixers.insert(
-                mixer_id,
-                PlayerMixerInfo {
-                    parent_id: parent_mixer_id,
-                    event_queue: mixer_queue,
-                },
-            );
+            let mut mixer_info = PlayerMixerInfo::new(parent_mixer_id, Arc::clone(&mixer_queue));
+            mixer_info.output = output;
+            mixer_info.output_buffer = output_buffer;
+            self.mixers.insert(mixer_id, mixer_info);
+
+            // Mute the new mixer when other mixers are soloed
+            Self::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, 0)?;
 
             Ok(MixerHandle::new(
                 mixer_id,
+                mixer_queue,
+                Arc::clone(&self.mixers),
+                Arc::clone(&self.mute_states_lock),
                 measurement_state,
-                metering_state,
+                metering_states,
+                self.transport.clone(),
             ))
         }
     }
@@ -857,15 +1599,383 @@ impl Player {
                 .collect();
 
             for effect_id in effects_to_remove {
+                if self.effect_sidechain_mixer_id(effect_id)?.is_some() {
+                    self.set_effect_sidechain(effect_id, None)?;
+                }
                 self.effects.remove(&effect_id);
             }
 
+            // Disconnect all effect sidechains which are fed by this mixer
+            let keyed_effects: Vec<EffectId> = self
+                .effects
+                .iter()
+                .filter(|entry| entry.value().sidechain_mixer_id == Some(mixer_id))
+                .map(|entry| *entry.key())
+                .collect();
+            for effect_id in keyed_effects {
+                self.set_effect_sidechain(effect_id, None)?;
+            }
+
+            // Remove all sends from and to this mixer
+            let sending_mixers: Vec<MixerId> = self
+                .mixers
+                .iter()
+                .filter(|entry| entry.value().sends.iter().any(|(id, ..)| *id == mixer_id))
+                .map(|entry| *entry.key())
+                .collect();
+            for sending_mixer_id in sending_mixers {
+                self.remove_send(sending_mixer_id, mixer_id)?;
+            }
+            let return_mixers: Vec<MixerId> = self
+                .mixers
+                .get(&mixer_id)
+                .map(|info| info.sends.iter().map(|(id, ..)| *id).collect())
+                .unwrap_or_default();
+            for return_mixer_id in return_mixers {
+                self.remove_send(mixer_id, return_mixer_id)?;
+            }
+
+            // Disconnect the mixer's output route
+            if self.mixer_output(mixer_id)? != MixerOutput::Parent
+                && self
+                    .output_routing_queue
+                    .push(RoutedSourceMessage::RemoveRoute { mixer_id })
+                    .is_err()
+            {
+                return Err(Self::output_routing_queue_error("remove_mixer"));
+            }
+
             // Remove the mixer from tracking maps
             self.mixers.remove(&mixer_id);
-            Ok(())
+
+            // Update solo states, in case the removed mixer was soloed
+            Self::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, 0)
+        }
+    }
+
+    /// Move a mixer with all its sources, effects and sub-mixers to another parent mixer.
+    /// Use `None` as parent mixer id to move it to the main mixer.
+    ///
+    /// Like moved sources, the mixer gets faded out in its old parent and faded in again in its
+    /// new parent with a short declicking fade. Depending on the processing order of the parents,
+    /// the fades may be up to one audio buffer apart.
+    ///
+    /// Mixers can't be moved into themselves or into one of their own sub-mixers, and moves must
+    /// not break existing sends or effect sidechains, else a `MixerRoutingError` is returned.
+    pub fn move_mixer<M: Into<Option<MixerId>>>(
+        &mut self,
+        mixer_id: MixerId,
+        parent_mixer_id: M,
+    ) -> Result<(), Error> {
+        // Can't move the main mixer
+        if mixer_id == Self::MAIN_MIXER_ID {
+            return Err(Error::ParameterError(
+                "Cannot move the main mixer".to_string(),
+            ));
+        }
+
+        let parent_mixer_id = parent_mixer_id.into().unwrap_or(Self::MAIN_MIXER_ID);
+        let old_parent_mixer_id = self.mixer_parent_id(mixer_id)?;
+        let old_parent_mixer_event_queue = self.mixer_event_queue(old_parent_mixer_id)?;
+        let parent_mixer_event_queue = self.mixer_event_queue(parent_mixer_id)?;
+        if parent_mixer_id == old_parent_mixer_id {
+            return Ok(());
+        }
+
+        // Avoid cycles in the mixer tree
+        if self.mixer_path(parent_mixer_id).contains(&mixer_id) {
+            return Err(Error::MixerRoutingError(format!(
+                "Mixer {mixer_id} can't be moved into itself or into its own sub-mixer \
+                 {parent_mixer_id}"
+            )));
+        }
+
+        // Validate sends and sidechains with the new parent
+        let set_parent_id = |parent_id| {
+            if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+                info.parent_id = parent_id;
+            }
+        };
+        set_parent_id(parent_mixer_id);
+        let stages = match self.mixer_stages(&self.mixer_dependencies()) {
+            Ok(stages) => stages,
+            Err(err) => {
+                set_parent_id(old_parent_mixer_id);
+                return Err(err);
+            }
+        };
+        let stage = stages.get(&mixer_id).copied().unwrap_or(0);
+
+        // Announce the mixer in the new parent, then hand it over from the old parent
+        let handover = Shared::new(&self.collector_handle, ArrayQueue::new(1));
+        if parent_mixer_event_queue
+            .push(MixerMessage::AttachMixer {
+                mixer_id,
+                stage,
+                handover: Shared::clone(&handover),
+            })
+            .is_err()
+        {
+            set_parent_id(old_parent_mixer_id);
+            return Err(Self::mixer_event_queue_error("move_mixer"));
+        }
+        if old_parent_mixer_event_queue
+            .push(MixerMessage::DetachMixer { mixer_id, handover })
+            .is_err()
+        {
+            // the new parent drops the pending mixer when removing it
+            let _ = parent_mixer_event_queue.push(MixerMessage::RemoveMixer { mixer_id });
+            set_parent_id(old_parent_mixer_id);
+            return Err(Self::mixer_event_queue_error("move_mixer"));
+        }
+        if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+            info.stage = stage;
+        }
+
+        // Update stages of all other mixers
+        self.apply_mixer_stages(stages)?;
+
+        // Update solo states, which depend on the mixer tree
+        Self::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, 0)
+    }
+
+    /// Get the output assignment of the given mixer.
+    pub fn mixer_output(&self, mixer_id: MixerId) -> Result<MixerOutput, Error> {
+        self.mixers
+            .get(&mixer_id)
+            .map(|entry| entry.value().output)
+            .ok_or(Error::MixerNotFoundError(mixer_id))
+    }
+
+    /// Change where the given sub-mixer's output goes to.
+    ///
+    /// Sub-mixers which are assigned to output device channels get processed within their parent
+    /// mixer as usual, but their output bypasses the parent mixer and all mixers above it. It
+    /// gets added to the given channels of the output device, after the main mix got mapped to the
+    /// device's channel layout. Routed outputs get delay-compensated against the main mix.
+    ///
+    /// Channels must exist in the output device, else a `ParameterError` is returned. The main
+    /// mixer always plays on the device's channels, so it can't be reassigned.
+    pub fn set_mixer_output(
+        &mut self,
+        mixer_id: MixerId,
+        output: MixerOutput,
+    ) -> Result<(), Error> {
+        if mixer_id == Self::MAIN_MIXER_ID {
+            return Err(Error::ParameterError(
+                "Cannot change the main mixer's output".to_string(),
+            ));
+        }
+        self.validate_mixer_output(output)?;
+        let parent_mixer_event_queue = self.mixer_event_queue(self.mixer_parent_id(mixer_id)?)?;
+        let (previous_output, previous_buffer) = self
+            .mixers
+            .get(&mixer_id)
+            .map(|info| (info.output, info.output_buffer.clone()))
+            .ok_or(Error::MixerNotFoundError(mixer_id))?;
+        if previous_output == output {
+            return Ok(());
+        }
+
+        let output_buffer = if let Some((first_channel, channel_count)) = output.device_channels() {
+            // Connect the output route first, then start writing into it. Mixers which already
+            // play on output device channels keep writing into their existing buffer.
+            let buffer = previous_buffer.clone().unwrap_or_else(|| {
+                Shared::new(
+                    &self.collector_handle,
+                    MixerSendBuffer::new(self.output_channel_count(), self.output_sample_rate()),
+                )
+            });
+            if self
+                .output_routing_queue
+                .push(RoutedSourceMessage::AddRoute {
+                    mixer_id,
+                    first_channel,
+                    channel_count,
+                    buffer: Shared::clone(&buffer),
+                })
+                .is_err()
+            {
+                return Err(Self::output_routing_queue_error("set_mixer_output"));
+            }
+            if previous_buffer.is_none()
+                && parent_mixer_event_queue
+                    .push(MixerMessage::SetMixerDirectOutput {
+                        mixer_id,
+                        buffer: Some(Shared::clone(&buffer)),
+                    })
+                    .is_err()
+            {
+                self.undo_output_route(mixer_id, previous_output, previous_buffer);
+                return Err(Self::mixer_event_queue_error("set_mixer_output"));
+            }
+            Some(buffer)
+        } else {
+            // Disconnect the output route, then mix into the parent again
+            if self
+                .output_routing_queue
+                .push(RoutedSourceMessage::RemoveRoute { mixer_id })
+                .is_err()
+            {
+                return Err(Self::output_routing_queue_error("set_mixer_output"));
+            }
+            if parent_mixer_event_queue
+                .push(MixerMessage::SetMixerDirectOutput {
+                    mixer_id,
+                    buffer: None,
+                })
+                .is_err()
+            {
+                self.undo_output_route(mixer_id, previous_output, previous_buffer);
+                return Err(Self::mixer_event_queue_error("set_mixer_output"));
+            }
+            None
+        };
+        if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+            info.output = output;
+            info.output_buffer = output_buffer;
+        }
+        Ok(())
+    }
+
+    /// Restore the given previous output route of a mixer, after changing its output failed.
+    fn undo_output_route(
+        &self,
+        mixer_id: MixerId,
+        output: MixerOutput,
+        buffer: Option<Shared<MixerSendBuffer>>,
+    ) {
+        let message = match (output.device_channels(), buffer) {
+            (Some((first_channel, channel_count)), Some(buffer)) => RoutedSourceMessage::AddRoute {
+                mixer_id,
+                first_channel,
+                channel_count,
+                buffer,
+            },
+            _ => RoutedSourceMessage::RemoveRoute { mixer_id },
+        };
+        if self.output_routing_queue.push(message).is_err() {
+            log::warn!("Failed to restore the output route of mixer {mixer_id}");
         }
     }
 
+    /// Add a send from the given mixer to a return mixer, which may be located anywhere in
+    /// the mixer graph. The sending mixer's signal gets added with the given send level to
+    /// the return mixer's input, so e.g. a single reverb effect can be shared among mixers.
+    ///
+    /// Sends must not create feedback loops: a mixer can't send to itself or to one of its
+    /// own sub-mixers, and sends must not form cycles, else a `MixerRoutingError` is returned.
+    ///
+    /// Use [`MixerHandle::set_send_level`] to automate the send level.
+    pub fn add_send(
+        &mut self,
+        mixer_id: MixerId,
+        return_mixer_id: MixerId,
+        level: f32,
+        mode: MixerSendMode,
+    ) -> Result<(), Error> {
+        if level < 0.0 {
+            return Err(Error::ParameterError(format!(
+                "Invalid send level: {level}, must be >= 0"
+            )));
+        }
+        let mixer_event_queue = self.mixer_event_queue(mixer_id)?;
+        let return_mixer_event_queue = self.mixer_event_queue(return_mixer_id)?;
+        if mixer_id == return_mixer_id {
+            return Err(Error::MixerRoutingError(format!(
+                "Mixer {mixer_id} can't send to itself"
+            )));
+        }
+        if self.mixer_sends(mixer_id)?.contains(&return_mixer_id) {
+            return Err(Error::MixerRoutingError(format!(
+                "Mixer {mixer_id} already sends to mixer {return_mixer_id}"
+            )));
+        }
+
+        // Validate the new routing
+        let mut dependencies = self.mixer_dependencies();
+        dependencies.push((mixer_id, return_mixer_id));
+        let stages = self.mixer_stages(&dependencies)?;
+
+        // Connect the return mixer first, then start sending
+        let buffer = Shared::new(
+            &self.collector_handle,
+            MixerSendBuffer::new(self.output_channel_count(), self.output_sample_rate()),
+        );
+        if return_mixer_event_queue
+            .push(MixerMessage::AddSendInput {
+                source_mixer_id: mixer_id,
+                buffer: Shared::clone(&buffer),
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error("add_send"));
+        }
+        if mixer_event_queue
+            .push(MixerMessage::AddSend {
+                target: MixerSendTarget::Mixer(return_mixer_id),
+                mode,
+                level,
+                buffer,
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error("add_send"));
+        }
+        if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+            info.sends.push((return_mixer_id, mode, level));
+        }
+
+        self.apply_mixer_stages(stages)?;
+
+        // Update solo states of return mixers
+        Self::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, 0)
+    }
+
+    /// Remove a send from the given mixer to the given return mixer.
+    pub fn remove_send(
+        &mut self,
+        mixer_id: MixerId,
+        return_mixer_id: MixerId,
+    ) -> Result<(), Error> {
+        if !self.mixer_sends(mixer_id)?.contains(&return_mixer_id) {
+            return Err(Error::MixerRoutingError(format!(
+                "Mixer {mixer_id} does not send to mixer {return_mixer_id}"
+            )));
+        }
+
+        // Stop sending, then disconnect the return mixer
+        if self
+            .mixer_event_queue(mixer_id)?
+            .push(MixerMessage::RemoveSend {
+                target: MixerSendTarget::Mixer(return_mixer_id),
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error("remove_send"));
+        }
+        if let Ok(return_mixer_event_queue) = self.mixer_event_queue(return_mixer_id) {
+            if return_mixer_event_queue
+                .push(MixerMessage::RemoveSendInput {
+                    source_mixer_id: mixer_id,
+                })
+                .is_err()
+            {
+                return Err(Self::mixer_event_queue_error("remove_send"));
+            }
+        }
+        if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+            info.sends.retain(|(id, ..)| *id != return_mixer_id);
+        }
+
+        // Removing dependencies never creates cycles, but may relax processing stages
+        let stages = self.mixer_stages(&self.mixer_dependencies())?;
+        self.apply_mixer_stages(stages)?;
+
+        // Update solo states of return mixers
+        Self::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, 0)
+    }
+
     /// Remove all sub-mixers from the given mixer.
     /// Use `None` as mixer_id to remove all sub-mixers from the main mixer.
     pub fn remove_all_mixers<M: Into<Option<MixerId>>>(
@@ -904,10 +2014,16 @@ impl Player {
 
         let mut effect = effect.into_box();
         let effect_name = effect.name();
+        let supports_sidechain = effect.supports_sidechain();
+        let parameters = ParameterValueState::new_shared(&effect.parameters());
         effect.initialize(self.output_sample_rate(), channel_count, max_frames)?;
 
         // Wrap into a processor
-        let effect_processor = Owned::new(&self.collector_handle, EffectProcessor::new(effect));
+        let measurement_state = self.new_effect_measurement_state();
+        let effect_processor = Owned::new(
+            &self.collector_handle,
+            EffectProcessor::new(effect, measurement_state.clone()),
+        );
 
         let effect_id = Self::unique_effect_id();
         if mixer_event_queue
@@ -924,8 +2040,14 @@ impl Player {
                 PlayerEffectInfo {
                     mixer_id,
                     effect_name,
+                    supports_sidechain,
+                    sidechain_mixer_id: None,
+                    parameters: Arc::clone(&parameters),
                 },
             );
+            if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+                info.effects.push(effect_id);
+            }
 
             // Create and return handle
             Ok(EffectHandle::new(
@@ -934,7 +2056,10 @@ impl Player {
                 effect_name,
                 mixer_event_queue,
                 self.collector_handle.clone(),
-            ))
+                parameters,
+                self.transport.clone(),
+            )
+            .with_measurement_state(measurement_state))
         }
     }
 
@@ -969,12 +2094,31 @@ impl Player {
         {
             Err(Self::mixer_event_queue_error("move_effect"))
         } else {
+            // Mirror the mixer's effect order
+            if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+                if let Some(current_pos) = info.effects.iter().position(|id| *id == effect_id) {
+                    info.effects.remove(current_pos);
+                    let new_pos = match movement {
+                        EffectMovement::Direction(offset) => {
+                            let target = current_pos as i32 + offset;
+                            target.clamp(0, info.effects.len() as i32) as usize
+                        }
+                        EffectMovement::Start => 0,
+                        EffectMovement::End => info.effects.len(),
+                    };
+                    info.effects.insert(new_pos, effect_id);
+                }
+            }
             Ok(())
         }
     }
 
     /// Remove an effect from the given mixer.
     pub fn remove_effect(&mut self, effect_id: EffectId) -> Result<(), Error> {
+        // Disconnect the effect's sidechain source
+        if self.effect_sidechain_mixer_id(effect_id)?.is_some() {
+            self.set_effect_sidechain(effect_id, None)?;
+        }
         // Send the remove message
         if self
             .effect_mixer_event_queue(effect_id)?
@@ -983,12 +2127,118 @@ impl Player {
         {
             Err(Self::mixer_event_queue_error("remove_effect"))
         } else {
-            // Remove from tracking map
-            self.effects.remove(&effect_id);
+            // Remove from tracking maps
+            if let Some((_, effect_info)) = self.effects.remove(&effect_id) {
+                if let Some(mut info) = self.mixers.get_mut(&effect_info.mixer_id) {
+                    info.effects.retain(|id| *id != effect_id);
+                }
+            }
             Ok(())
         }
     }
 
+    /// Set or remove the sidechain input of an effect which supports sidechains, such as the
+    /// [`CompressorEffect`](crate::effects::CompressorEffect) or
+    /// [`GateEffect`](crate::effects::GateEffect).
+    ///
+    /// The given mixer's output then gets passed as secondary input to the effect, e.g. to duck
+    /// a music mixer with a voice mixer. Use `None` as sidechain mixer id to remove the sidechain.
+    ///
+    /// Like sends, sidechains must not create feedback loops: the sidechain mixer can't be the
+    /// effect's own mixer or one of the mixer's parents, else a `MixerRoutingError` is returned.
+    pub fn set_effect_sidechain<M: Into<Option<MixerId>>>(
+        &mut self,
+        effect_id: EffectId,
+        sidechain_mixer_id: M,
+    ) -> Result<(), Error> {
+        let sidechain_mixer_id = sidechain_mixer_id.into();
+        let effect_info = self
+            .effects
+            .get(&effect_id)
+            .ok_or(Error::EffectNotFoundError(effect_id))?
+            .value()
+            .clone();
+        if sidechain_mixer_id.is_some() && !effect_info.supports_sidechain {
+            return Err(Error::ParameterError(format!(
+                "Effect '{}' does not support sidechain inputs",
+                effect_info.effect_name
+            )));
+        }
+        if sidechain_mixer_id == Some(effect_info.mixer_id) {
+            return Err(Error::MixerRoutingError(format!(
+                "Mixer {} can't feed the sidechain of its own effect {effect_id}",
+                effect_info.mixer_id
+            )));
+        }
+        let effect_mixer_event_queue = self.mixer_event_queue(effect_info.mixer_id)?;
+
+        // Validate the new routing
+        let mut dependencies = self.mixer_dependencies();
+        if let Some(old_sidechain_mixer_id) = effect_info.sidechain_mixer_id {
+            let old_dependency = (old_sidechain_mixer_id, effect_info.mixer_id);
+            if let Some(pos) = dependencies.iter().position(|dep| *dep == old_dependency) {
+                dependencies.remove(pos);
+            }
+        }
+        if let Some(sidechain_mixer_id) = sidechain_mixer_id {
+            self.mixer_event_queue(sidechain_mixer_id)?;
+            dependencies.push((sidechain_mixer_id, effect_info.mixer_id));
+        }
+        let stages = self.mixer_stages(&dependencies)?;
+
+        // Stop sending from the old sidechain mixer
+        if let Some(old_sidechain_mixer_id) = effect_info.sidechain_mixer_id {
+            if let Ok(old_sidechain_mixer_event_queue) =
+                self.mixer_event_queue(old_sidechain_mixer_id)
+            {
+                if old_sidechain_mixer_event_queue
+                    .push(MixerMessage::RemoveSend {
+                        target: MixerSendTarget::EffectSidechain(effect_id),
+                    })
+                    .is_err()
+                {
+                    return Err(Self::mixer_event_queue_error("set_effect_sidechain"));
+                }
+            }
+        }
+
+        // Connect the effect first, then start sending from the new sidechain mixer
+        let buffer = sidechain_mixer_id.map(|_| {
+            Shared::new(
+                &self.collector_handle,
+                MixerSendBuffer::new(self.output_channel_count(), self.output_sample_rate()),
+            )
+        });
+        if effect_mixer_event_queue
+            .push(MixerMessage::SetEffectSidechain {
+                effect_id,
+                buffer: buffer.clone(),
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error("set_effect_sidechain"));
+        }
+        if let (Some(sidechain_mixer_id), Some(buffer)) = (sidechain_mixer_id, buffer) {
+            if self
+                .mixer_event_queue(sidechain_mixer_id)?
+                .push(MixerMessage::AddSend {
+                    target: MixerSendTarget::EffectSidechain(effect_id),
+                    mode: MixerSendMode::PostFader,
+                    level: 1.0,
+                    buffer,
+                })
+                .is_err()
+            {
+                return Err(Self::mixer_event_queue_error("set_effect_sidechain"));
+            }
+        }
+        if let Some(mut info) = self.effects.get_mut(&effect_id) {
+            info.sidechain_mixer_id = sidechain_mixer_id;
+        }
+
+        self.apply_mixer_stages(stages)
+    }
+
     /// Remove all effects from the given mixer.
     /// Use `None` as mixer_id to remove all effects from the main mixer.
     pub fn remove_all_effects<M: Into<Option<MixerId>>>(
@@ -1045,18 +2295,21 @@ impl Player {
         Ok(())
     }
 
-    fn add_or_play_generator<G: Generator + 'static, T: Into<Option<u64>>>(
+    fn add_or_play_generator<G: Generator + 'static, T: Into<ScheduleTime>>(
         &mut self,
         generator: G,
         is_transient: bool,
         mixer_id: MixerId,
         start_time: T,
+        effects: Vec<Box<dyn Effect>>,
     ) -> Result<GeneratorPlaybackHandle, Error> {
         // validate and get options
         let playback_options = *generator.playback_options();
         playback_options.validate()?;
         // validate and get target mixer
-        let mixer_event_queue = self.mixer_event_queue(mixer_id)?;
+        let mixer_event_queue = SourceMixerEventQueue::new(self.mixer_event_queue(mixer_id)?);
+        // validate and get start time
+        let sample_time = self.transport.resolve(start_time)?.unwrap_or(0);
         // set generator's transient flag
         let mut generator = generator;
         generator.set_is_transient(is_transient);
@@ -1065,7 +2318,13 @@ impl Player {
         // get source in playback id and message channel
         let playback_id = generator.playback_id();
         let playback_message_queue = generator.playback_message_queue();
-        let source_name = format!("Generator '{}'", generator.generator_name());
+        let generator_name = generator.generator_name();
+        let source_name = format!("Generator '{generator_name}'");
+        // track parameter values for snapshots
+        let parameters = ParameterValueState::new_shared(&generator.parameters());
+        // create insert effects
+        let (effects, effect_handles) =
+            self.create_insert_effects(effects, playback_id, mixer_id, &mixer_event_queue)?;
         // convert generator to mixer's rate and channel layout
         let converted_source = ConvertedSource::new(
             generator,
@@ -1101,18 +2360,23 @@ impl Player {
                 is_transient,
                 playback_message_queue: playback_message_queue.clone(),
                 mixer_id,
+                mixer_event_queue: mixer_event_queue.clone(),
                 source_name,
+                generator: Some(PlayingGenerator {
+                    name: generator_name,
+                    parameters: Arc::clone(&parameters),
+                }),
             },
         );
         // send the source to the mixer
         let source = Owned::new(&self.collector_handle, measured_source.into_box());
-        let sample_time = start_time.into().unwrap_or(0);
         if mixer_event_queue
             .push(MixerMessage::AddSource {
                 is_transient,
                 playback_id,
                 playback_message_queue: playback_message_queue.clone(),
                 source,
+                effects,
                 sample_time,
             })
             .is_err()
@@ -1128,10 +2392,63 @@ impl Player {
                 mixer_event_queue,
                 self.collector_handle.clone(),
                 measurement_state,
+                effect_handles,
+                parameters,
+                self.transport.clone(),
             ))
         }
     }
 
+    /// Initialize insert effects of a source, which is about to be played.
+    fn create_insert_effects(
+        &self,
+        insert_effects: Vec<Box<dyn Effect>>,
+        playback_id: PlaybackId,
+        mixer_id: MixerId,
+        mixer_event_queue: &SourceMixerEventQueue,
+    ) -> Result<(Option<Owned<SourceEffectChain>>, Vec<EffectHandle>), Error> {
+        if insert_effects.is_empty() {
+            return Ok((None, Vec::new()));
+        }
+
+        let channel_count = self.output_channel_count();
+        // Insert effects process the source in the mixer's temp buffer of size:
+        let max_frames = MixedSource::MAX_MIX_BUFFER_SAMPLES / channel_count;
+
+        let mut effects = Vec::with_capacity(insert_effects.len());
+        let mut effect_handles = Vec::with_capacity(insert_effects.len());
+        for mut effect in insert_effects {
+            let effect_name = effect.name();
+            let parameters = ParameterValueState::new_shared(&effect.parameters());
+            effect.initialize(self.output_sample_rate(), channel_count, max_frames)?;
+
+            let effect_id = Self::unique_effect_id();
+            let measurement_state = self.new_effect_measurement_state();
+            effects.push((
+                effect_id,
+                EffectProcessor::new(effect, measurement_state.clone()),
+            ));
+            effect_handles.push(
+                EffectHandle::new(
+                    effect_id,
+                    mixer_id,
+                    effect_name,
+                    EffectEventQueue::Source {
+                        playback_id,
+                        mixer_event_queue: mixer_event_queue.clone(),
+                    },
+                    self.collector_handle.clone(),
+                    parameters,
+                    self.transport.clone(),
+                )
+                .with_measurement_state(measurement_state),
+            );
+        }
+
+        let effects = Owned::new(&self.collector_handle, SourceEffectChain::new(effects));
+        Ok((Some(effects), effect_handles))
+    }
+
     fn handle_playback_events(
         playback_sender: Option<SyncSender<PlaybackStatusEvent>>,
         playing_sources: Arc<DashMap<PlaybackId, PlayingSource>>,
@@ -1211,16 +2528,35 @@ impl Player {
         Error::SendError("Mixer queue is full".to_string())
     }
 
+    fn output_routing_queue_error(event_name: &str) -> Error {
+        log::warn!("Output routing queue is full. Failed to send a {event_name} event.");
+
+        Error::SendError("Output routing queue is full".to_string())
+    }
+
+    fn validate_mixer_output(&self, output: MixerOutput) -> Result<(), Error> {
+        let device_channel_count = self.output_device.channel_count();
+        match output.device_channels() {
+            Some((first_channel, channel_count))
+                if first_channel + channel_count > device_channel_count =>
+            {
+                Err(Error::ParameterError(format!(
+                    "Invalid mixer output {output:?}: the output device has {device_channel_count} channels"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn offline_output_error() -> Error {
+        Error::OutputDeviceError("Rendering is only supported by offline players".into())
+    }
+
     fn effect_mixer_event_queue(
         &self,
         effect_id: EffectId,
     ) -> Result<Arc<ArrayQueue<MixerMessage>>, Error> {
-        let effect_info = *self
-            .effects
-            .get(&effect_id)
-            .ok_or(Error::EffectNotFoundError(effect_id))?
-            .value();
-        self.mixer_event_queue(effect_info.mixer_id)
+        self.mixer_event_queue(self.effect_parent_mixer_id(effect_id)?)
     }
 
     fn mixer_parent_id(&self, mixer_id: MixerId) -> Result<MixerId, Error> {
@@ -1244,6 +2580,261 @@ impl Player {
             .collect()
     }
 
+    fn mixer_sends(&self, mixer_id: MixerId) -> Result<Vec<MixerId>, Error> {
+        self.mixers
+            .get(&mixer_id)
+            .map(|entry| entry.value().sends.iter().map(|(id, ..)| *id).collect())
+            .ok_or(Error::MixerNotFoundError(mixer_id))
+    }
+
+    /// Path from the given mixer up to the main mixer, including the mixer and main mixer.
+    fn mixer_path(&self, mixer_id: MixerId) -> Vec<MixerId> {
+        let mut path = vec![mixer_id];
+        let mut current_id = mixer_id;
+        while current_id != Self::MAIN_MIXER_ID {
+            match self.mixer_parent_id(current_id) {
+                Ok(parent_id) if !path.contains(&parent_id) => {
+                    path.push(parent_id);
+                    current_id = parent_id;
+                }
+                _ => break,
+            }
+        }
+        path
+    }
+
+    /// All (source, target) mixer pairs, where the target mixer consumes the source mixer's
+    /// output in addition to the regular parent/child routing, via sends or effect sidechains.
+    fn mixer_dependencies(&self) -> Vec<(MixerId, MixerId)> {
+        let mut dependencies = Vec::new();
+        for entry in self.mixers.iter() {
+            for (target_id, ..) in &entry.value().sends {
+                dependencies.push((*entry.key(), *target_id));
+            }
+        }
+        for entry in self.effects.iter() {
+            let effect_info = entry.value();
+            if let Some(sidechain_mixer_id) = effect_info.sidechain_mixer_id {
+                dependencies.push((sidechain_mixer_id, effect_info.mixer_id));
+            }
+        }
+        dependencies
+    }
+
+    /// Calculate the processing stages of all mixers within their parents, so that mixers
+    /// which feed other mixers are processed before their receivers.
+    ///
+    /// Mixers are processed depth first, so a dependency gets resolved at the dependency's common
+    /// ancestor: the ancestor's sub-mixer which contains the source must run before the one
+    /// which contains the target. Targets which are ancestors of their sources are fine as is,
+    /// as mixers process their sub-mixers before reading inputs from other mixers.
+    ///
+    /// Returns an error when a dependency can't be resolved: when a mixer depends on its own
+    /// sub-mixer's output or when dependencies form a cycle.
+    fn mixer_stages(
+        &self,
+        dependencies: &[(MixerId, MixerId)],
+    ) -> Result<HashMap<MixerId, usize>, Error> {
+        // Collect required (before, after) sub-mixer orderings per common ancestor
+        let mut orderings: HashMap<MixerId, Vec<(MixerId, MixerId)>> = HashMap::new();
+        for (source_id, target_id) in dependencies {
+            let source_path = self.mixer_path(*source_id);
+            let target_path = self.mixer_path(*target_id);
+            if source_path.contains(target_id) {
+                // target is an ancestor of the source
+                continue;
+            }
+            if target_path.contains(source_id) {
+                return Err(Error::MixerRoutingError(format!(
+                    "Mixer {target_id} is a sub-mixer of mixer {source_id} and thus \
+                     can't receive its output"
+                )));
+            }
+            let source_index = source_path
+                .iter()
+                .position(|id| target_path.contains(id))
+                .expect("Expecting mixers to share the main mixer as ancestor");
+            let ancestor_id = source_path[source_index];
+            let target_index = target_path
+                .iter()
+                .position(|id| *id == ancestor_id)
+                .expect("Expecting a common ancestor");
+            orderings.entry(ancestor_id).or_default().push((
+                source_path[source_index - 1], //
+                target_path[target_index - 1],
+            ));
+        }
+
+        // Resolve stages per ancestor, using a longest path topological sort
+        let mut stages = HashMap::new();
+        for (ancestor_id, ordering) in orderings {
+            let sub_mixers = self.sub_mixers_of(ancestor_id);
+            let mut ancestor_stages: HashMap<MixerId, usize> =
+                sub_mixers.iter().map(|id| (*id, 0)).collect();
+            let mut resolved = HashSet::new();
+            while resolved.len() < sub_mixers.len() {
+                // find a sub-mixer which has no unresolved predecessors
+                let next = sub_mixers.iter().find(|id| {
+                    !resolved.contains(*id)
+                        && ordering
+                            .iter()
+                            .all(|(before, after)| after != *id || resolved.contains(before))
+                });
+                let Some(next_id) = next.copied() else {
+                    return Err(Error::MixerRoutingError(
+                        "Mixer sends would create a feedback loop".to_string(),
+                    ));
+                };
+                let stage = ancestor_stages[&next_id];
+                for (_, after) in ordering.iter().filter(|(before, _)| *before == next_id) {
+                    let after_stage = ancestor_stages.entry(*after).or_default();
+                    *after_stage = (*after_stage).max(stage + 1);
+                }
+                resolved.insert(next_id);
+            }
+            stages.extend(ancestor_stages);
+        }
+        Ok(stages)
+    }
+
+    /// Apply changed mixer stages, as calculated by `mixer_stages`, to all mixers.
+    fn apply_mixer_stages(&self, stages: HashMap<MixerId, usize>) -> Result<(), Error> {
+        let changed_stages: Vec<(MixerId, MixerId, usize)> = self
+            .mixers
+            .iter()
+            .filter_map(|entry| {
+                let (mixer_id, info) = (*entry.key(), entry.value());
+                let stage = stages.get(&mixer_id).copied().unwrap_or(0);
+                if mixer_id != Self::MAIN_MIXER_ID && info.stage != stage {
+                    Some((mixer_id, info.parent_id, stage))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        for (mixer_id, parent_id, stage) in changed_stages {
+            if self
+                .mixer_event_queue(parent_id)?
+                .push(MixerMessage::SetMixerStage { mixer_id, stage })
+                .is_err()
+            {
+                return Err(Self::mixer_event_queue_error("set_mixer_stage"));
+            }
+            if let Some(mut info) = self.mixers.get_mut(&mixer_id) {
+                info.stage = stage;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the mute and solo states of all mixers and send changed mute states to the
+    /// mixers at the given sample time.
+    ///
+    /// Solo works like on a mixing console: when any mixer is soloed, all mixers which are not
+    /// soloed get muted, except for the soloed mixers' sub-mixers and return mixers which are fed
+    /// by soloed mixers. Parents of soloed mixers pass through the soloed mixer's output, but
+    /// mute their own sources.
+    ///
+    /// Mixer handles call this from other threads, so resolving and sending the states is
+    /// serialized via the given lock: else a concurrent call could send outdated states last.
+    fn apply_mixer_mute_states(
+        mixers: &DashMap<MixerId, PlayerMixerInfo>,
+        mute_states_lock: &Mutex<()>,
+        sample_time: u64,
+    ) -> Result<(), Error> {
+        let _guard = mute_states_lock
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+
+        // Take a snapshot of the mixer graph
+        let parents: HashMap<MixerId, MixerId> = mixers
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().parent_id))
+            .collect();
+        let path = |mixer_id: MixerId| {
+            let mut path = vec![mixer_id];
+            let mut current_id = mixer_id;
+            while let Some(parent_id) = parents.get(&current_id).copied() {
+                if current_id == Self::MAIN_MIXER_ID || path.contains(&parent_id) {
+                    break;
+                }
+                path.push(parent_id);
+                current_id = parent_id;
+            }
+            path
+        };
+
+        // Collect all mixers which are audible because of solos
+        let mut soloed: HashSet<MixerId> = mixers
+            .iter()
+            .filter(|entry| entry.value().soloed)
+            .map(|entry| *entry.key())
+            .collect();
+        let any_soloed = !soloed.is_empty();
+        if any_soloed {
+            // add return mixers of soloed mixers, until there are no more new ones
+            loop {
+                let returns: Vec<MixerId> = mixers
+                    .iter()
+                    .filter(|entry| path(*entry.key()).iter().any(|id| soloed.contains(id)))
+                    .flat_map(|entry| {
+                        entry
+                            .value()
+                            .sends
+                            .iter()
+                            .map(|(id, ..)| *id)
+                            .collect::<Vec<_>>()
+                    })
+                    .filter(|id| !soloed.contains(id))
+                    .collect();
+                if returns.is_empty() {
+                    break;
+                }
+                soloed.extend(returns);
+            }
+        }
+        let mut solo_paths = HashSet::new();
+        for mixer_id in &soloed {
+            solo_paths.extend(path(*mixer_id).into_iter().skip(1));
+        }
+
+        // Resolve mute states
+        let changed_states: Vec<(MixerId, bool, bool)> = mixers
+            .iter()
+            .filter_map(|entry| {
+                let (mixer_id, info) = (*entry.key(), entry.value());
+                let mixer_path = path(mixer_id);
+                let is_soloed = mixer_path.iter().any(|id| soloed.contains(id));
+                let is_solo_path = !is_soloed && solo_paths.contains(&mixer_id);
+                let output_muted = info.muted || (any_soloed && !is_soloed && !is_solo_path);
+                let sources_muted = is_solo_path;
+                if info.output_muted != output_muted || info.sources_muted != sources_muted {
+                    Some((mixer_id, output_muted, sources_muted))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        for (mixer_id, output_muted, sources_muted) in changed_states {
+            if let Some(mut info) = mixers.get_mut(&mixer_id) {
+                if info
+                    .event_queue
+                    .push(MixerMessage::SetMixerMuteState {
+                        muted: output_muted,
+                        sources_muted,
+                        sample_time,
+                    })
+                    .is_err()
+                {
+                    return Err(Self::mixer_event_queue_error("set_mixer_mute_state"));
+                }
+                info.output_muted = output_muted;
+                info.sources_muted = sources_muted;
+            }
+        }
+        Ok(())
+    }
+
     fn effect_parent_mixer_id(&self, effect_id: EffectId) -> Result<MixerId, Error> {
         self.effects
             .get(&effect_id)
@@ -1251,6 +2842,13 @@ impl Player {
             .ok_or(Error::EffectNotFoundError(effect_id))
     }
 
+    fn effect_sidechain_mixer_id(&self, effect_id: EffectId) -> Result<Option<MixerId>, Error> {
+        self.effects
+            .get(&effect_id)
+            .map(|entry| entry.value().sidechain_mixer_id)
+            .ok_or(Error::EffectNotFoundError(effect_id))
+    }
+
     fn effects_of(&self, mixer_id: MixerId) -> Vec<EffectId> {
         self.effects
             .iter()
@@ -1275,6 +2873,17 @@ impl Player {
         Self::unique_id()
     }
 
+    /// Create a new CPU load measurement state for an effect processor, when measuring is
+    /// enabled in the player's config.
+    fn new_effect_measurement_state(&self) -> Option<SharedCpuLoadState> {
+        self.config.measuring_interval.map(|interval| {
+            Arc::new(Mutex::new(CpuLoadState::new(
+                interval,
+                self.output_sample_rate(),
+            )))
+        })
+    }
+
     fn unique_effect_id() -> EffectId {
         // ensure mixer and effect id's don't clash
         Self::unique_id()
@@ -1401,10 +3010,38 @@ impl Player {
             for effect_entry in effects_on_mixer {
                 let effect_id = effect_entry.key();
                 let effect_info = effect_entry.value();
+                if let Some(sidechain_mixer_id) = effect_info.sidechain_mixer_id {
+                    writeln!(
+                        f,
+                        "{}- {} (ID: {}, Sidechain: Mixer {})",
+                        item_indent, effect_info.effect_name, effect_id, sidechain_mixer_id
+                    )?;
+                } else {
+                    writeln!(
+                        f,
+                        "{}- {} (ID: {})",
+                        item_indent, effect_info.effect_name, effect_id
+                    )?;
+                }
+            }
+        }
+
+        // Sends
+        let mut sends = self
+            .mixers
+            .get(&mixer_id)
+            .map(|info| info.sends.clone())
+            .unwrap_or_default();
+        sends.sort_by_key(|(id, ..)| *id);
+
+        if !sends.is_empty() {
+            writeln!(f, "{}> Sends:", child_indent)?;
+            let item_indent = "  ".repeat(indent_level + 2);
+            for (return_mixer_id, mode, _) in sends {
                 writeln!(
                     f,
-                    "{}- {} (ID: {})",
-                    item_indent, effect_info.effect_name, effect_id
+                    "{}- Mixer (ID: {}, {:?})",
+                    item_indent, return_mixer_id, mode
                 )?;
             }
         }
@@ -15,3 +15,5 @@ pub use generator::GeneratorPlaybackHandle;
 pub use mixer::MixerHandle;
 pub use source::SourcePlaybackHandle;
 pub use synth::SynthPlaybackHandle;
+
+pub(crate) use effect::EffectEventQueue;
@@ -6,14 +6,50 @@ use four_cc::FourCC;
 use crate::{
     effect::EffectMessage,
     error::Error,
-    parameter::ParameterValueUpdate,
-    player::{EffectId, MixerId},
-    source::mixed::MixerMessage,
+    parameter::{AutomationLane, ParameterValueUpdate, RampShape},
+    player::{snapshot::SharedParameterValueState, EffectId, MixerId, PlaybackId},
+    source::{
+        measured::{CpuLoad, SharedCpuLoadState},
+        mixed::{MixerMessage, SourceMixerEventQueue},
+    },
+    transport::{ScheduleTime, Transport},
 };
 use basedrop::{Handle, Owned};
 
 // -------------------------------------------------------------------------------------------------
 
+/// Event queue of the mixer which runs an effect.
+#[derive(Clone)]
+pub(crate) enum EffectEventQueue {
+    /// An effect in a mixer's effect chain.
+    Mixer(Arc<ArrayQueue<MixerMessage>>),
+    /// An insert effect of a playing source, which may move to other mixers.
+    Source {
+        playback_id: PlaybackId,
+        mixer_event_queue: SourceMixerEventQueue,
+    },
+}
+
+impl EffectEventQueue {
+    #[allow(clippy::result_large_err)]
+    fn push(&self, message: MixerMessage) -> Result<(), MixerMessage> {
+        match self {
+            Self::Mixer(queue) => queue.push(message),
+            Self::Source {
+                mixer_event_queue, ..
+            } => mixer_event_queue.push(message),
+        }
+    }
+}
+
+impl From<Arc<ArrayQueue<MixerMessage>>> for EffectEventQueue {
+    fn from(queue: Arc<ArrayQueue<MixerMessage>>) -> Self {
+        Self::Mixer(queue)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Automate [`Effect`](crate::Effect) parameters or send messages.
 ///
 /// Handles are `Send` and `Sync` so they can be sent across threads.
@@ -22,49 +58,94 @@ pub struct EffectHandle {
     effect_id: EffectId,
     mixer_id: MixerId,
     effect_name: &'static str,
-    mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+    mixer_event_queue: EffectEventQueue,
     collector_handle: Handle,
+    parameter_state: SharedParameterValueState,
+    measurement_state: Option<SharedCpuLoadState>,
+    transport: Transport,
 }
 
 impl EffectHandle {
-    pub(crate) fn new(
+    pub(crate) fn new<Q: Into<EffectEventQueue>>(
         effect_id: EffectId,
         mixer_id: MixerId,
         effect_name: &'static str,
-        mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+        mixer_event_queue: Q,
         collector_handle: Handle,
+        parameter_state: SharedParameterValueState,
+        transport: Transport,
     ) -> Self {
         Self {
             effect_id,
             mixer_id,
             effect_name,
-            mixer_event_queue,
+            mixer_event_queue: mixer_event_queue.into(),
             collector_handle,
+            parameter_state,
+            measurement_state: None,
+            transport,
         }
     }
 
+    /// Set the effect processor's CPU load measurement state.
+    pub(crate) fn with_measurement_state(
+        mut self,
+        measurement_state: Option<SharedCpuLoadState>,
+    ) -> Self {
+        self.measurement_state = measurement_state;
+        self
+    }
+
     /// Get the effect ID.
     pub fn id(&self) -> EffectId {
         self.effect_id
     }
 
     /// Get the mixer ID this effect belongs to.
+    ///
+    /// For insert effects of sources, this is the mixer the source initially got played on.
     pub fn mixer_id(&self) -> MixerId {
         self.mixer_id
     }
 
+    /// Get the playback ID of the source this effect belongs to, when this is an insert effect
+    /// of a file, synth or generator source.
+    pub fn playback_id(&self) -> Option<PlaybackId> {
+        match self.mixer_event_queue {
+            EffectEventQueue::Mixer(_) => None,
+            EffectEventQueue::Source { playback_id, .. } => Some(playback_id),
+        }
+    }
+
     /// Get the effect's name.
     pub fn effect_name(&self) -> &'static str {
         self.effect_name
     }
 
+    /// Get the CPU load of the effect's processing.
+    ///
+    /// Only available when CPU measurement is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
+    pub fn cpu_load(&self) -> Option<CpuLoad> {
+        self.measurement_state
+            .as_ref()
+            .and_then(|s| s.try_lock().ok())
+            .map(|state| state.cpu_load())
+    }
+
+    /// Get the CPU load data for this effect.
+    ///
+    /// Only available when CPU measurement is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
+    pub fn cpu_load_state(&self) -> Option<SharedCpuLoadState> {
+        self.measurement_state.as_ref().map(Arc::clone)
+    }
+
     /// Set a parameter's value via the given raw or normalized value update definition
     /// at a specific sample time or immediately.
     ///
     /// Note: Value update (id, value) tuples can be created safely via `value_update` functions
     /// in [FloatParameter](crate::parameters::FloatParameter), [IntegerParameter](crate::parameters::IntegerParameter),
     /// [EnumParameter](crate::parameters::EnumParameter) and [BooleanParameter](crate::parameters::BooleanParameter).
-    pub fn set_parameter<T: Into<Option<u64>>>(
+    pub fn set_parameter<T: Into<ScheduleTime>>(
         &self,
         (parameter_id, update): (FourCC, ParameterValueUpdate),
         sample_time: T,
@@ -76,8 +157,8 @@ impl EffectHandle {
                 )));
             }
         }
-        let sample_time = sample_time.into().unwrap_or(0);
-        let value = Owned::new(&self.collector_handle, update);
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        let value = Owned::new(&self.collector_handle, update.clone());
         if self
             .mixer_event_queue
             .push(MixerMessage::ProcessEffectParameterUpdate {
@@ -90,6 +171,9 @@ impl EffectHandle {
         {
             Err(Self::mixer_event_queue_error("set_parameter"))
         } else {
+            if let Ok(mut state) = self.parameter_state.lock() {
+                state.apply_update(parameter_id, &update);
+            }
             Ok(())
         }
     }
@@ -100,30 +184,176 @@ impl EffectHandle {
     /// Note: Value update (id, value) tuples can be created safely via `value_update` functions
     /// in [FloatParameter](crate::parameters::FloatParameter), [IntegerParameter](crate::parameters::IntegerParameter),
     /// [EnumParameter](crate::parameters::EnumParameter) and [BooleanParameter](crate::parameters::BooleanParameter).
-    pub fn set_parameters<T: Into<Option<u64>>>(
+    pub fn set_parameters<T: Into<ScheduleTime>>(
         &self,
         values: Vec<(FourCC, ParameterValueUpdate)>,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into().unwrap_or(0);
-        let values = Owned::new(&self.collector_handle, values);
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        let owned_values = Owned::new(&self.collector_handle, values.clone());
         if self
             .mixer_event_queue
             .push(MixerMessage::ProcessEffectParameterUpdates {
                 effect_id: self.effect_id,
-                values,
+                values: owned_values,
                 sample_time,
             })
             .is_err()
         {
             Err(Self::mixer_event_queue_error("set_parameter"))
+        } else {
+            if let Ok(mut state) = self.parameter_state.lock() {
+                for (parameter_id, update) in &values {
+                    state.apply_update(*parameter_id, update);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Ramp a parameter from its current value to the given normalized value over the given
+    /// number of sample frames, with the given curve shape, starting at a specific sample time
+    /// or immediately.
+    ///
+    /// Ramps start from the parameter's last value which got set via this handle. See
+    /// [`Self::set_parameter_automation`] on how ramps interact with other value changes.
+    pub fn set_parameter_ramp<T: Into<ScheduleTime>>(
+        &self,
+        parameter_id: FourCC,
+        value: f32,
+        duration: u64,
+        shape: RampShape,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        let current_value = self
+            .parameter_state
+            .lock()
+            .ok()
+            .and_then(|state| state.value(parameter_id))
+            .ok_or_else(|| Error::ParameterError(format!("Unknown parameter: '{parameter_id}'")))?;
+        let lane = AutomationLane::ramp(current_value, value, duration, shape);
+        self.set_parameter_automation(parameter_id, lane, sample_time)
+    }
+
+    /// Automate a parameter with the given breakpoint lane, starting at a specific sample time
+    /// or immediately. The mixer evaluates the lane in the audio thread, at all breakpoints and
+    /// in steps of 64 sample frames in between, see [`AutomationLane`].
+    ///
+    /// A new ramp or automation replaces a running ramp or automation of the same parameter.
+    /// Parameter updates via [`Self::set_parameter`] or [`Self::set_parameters`] cancel them.
+    ///
+    /// The handle's parameter state, which following ramps start from, takes over the lane's last
+    /// value right away, even when the lane starts in the future. To ramp from a value which is
+    /// scheduled at a later time, pass an explicit lane to this function instead.
+    pub fn set_parameter_automation<T: Into<ScheduleTime>>(
+        &self,
+        parameter_id: FourCC,
+        lane: AutomationLane,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        lane.validate()?;
+        let is_known_parameter = self
+            .parameter_state
+            .lock()
+            .is_ok_and(|state| state.value(parameter_id).is_some());
+        if !is_known_parameter {
+            return Err(Error::ParameterError(format!(
+                "Unknown parameter: '{parameter_id}'"
+            )));
+        }
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        let last_value = lane.points().last().map(|p| p.value);
+        let lane = Owned::new(&self.collector_handle, lane);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::AutomateEffectParameter {
+                effect_id: self.effect_id,
+                parameter_id,
+                lane,
+                sample_time,
+            })
+            .is_err()
+        {
+            Err(Self::mixer_event_queue_error("set_parameter_automation"))
+        } else {
+            if let (Ok(mut state), Some(value)) = (self.parameter_state.lock(), last_value) {
+                state.apply_update(parameter_id, &ParameterValueUpdate::Normalized(value));
+            }
+            Ok(())
+        }
+    }
+
+    /// Set or update a modulation routing at the given sample time or immediately.
+    ///
+    /// # Arguments
+    /// * `source` - Modulation source ID (e.g., FilterEffect::MOD_SOURCE_LFO)
+    /// * `target` - Target parameter ID (e.g., FilterEffect::CUTOFF.id())
+    /// * `amount` - Modulation amount (-1.0..=1.0)
+    /// * `bipolar` - If true, transforms unipolar sources (0.0-1.0) to bipolar (-1.0..1.0)
+    ///   centered at 0.5.
+    /// * `sample_time` - When to apply (None = immediate, Some = scheduled)
+    ///
+    /// Invalid sources or targets are logged as errors in the audio thread. Use
+    /// [`Effect::modulation_sources`](crate::Effect::modulation_sources) and
+    /// [`Effect::modulation_targets`](crate::Effect::modulation_targets) to query valid ones.
+    pub fn set_modulation<T: Into<ScheduleTime>>(
+        &self,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        if !(-1.0..=1.0).contains(&amount) {
+            return Err(Error::ParameterError(format!(
+                "Modulation amount must be in range -1.0..=1.0 but is {amount}"
+            )));
+        }
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::SetEffectModulation {
+                effect_id: self.effect_id,
+                source,
+                target,
+                amount,
+                bipolar,
+                sample_time,
+            })
+            .is_err()
+        {
+            Err(Self::mixer_event_queue_error("set_modulation"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Remove a modulation routing at the given sample time or immediately.
+    pub fn clear_modulation<T: Into<ScheduleTime>>(
+        &self,
+        source: FourCC,
+        target: FourCC,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::ClearEffectModulation {
+                effect_id: self.effect_id,
+                source,
+                target,
+                sample_time,
+            })
+            .is_err()
+        {
+            Err(Self::mixer_event_queue_error("clear_modulation"))
         } else {
             Ok(())
         }
     }
 
     /// Send a custom message to the effect at a specific sample time or immediately.
-    pub fn send_message<M: EffectMessage + 'static, T: Into<Option<u64>>>(
+    pub fn send_message<M: EffectMessage + 'static, T: Into<ScheduleTime>>(
         &self,
         message: M,
         sample_time: T,
@@ -142,7 +372,7 @@ impl EffectHandle {
             &self.collector_handle,
             Box::new(message) as Box<dyn EffectMessage>,
         );
-        let sample_time = sample_time.into().unwrap_or(0);
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
 
         if self
             .mixer_event_queue
@@ -159,6 +389,10 @@ impl EffectHandle {
         }
     }
 
+    pub(crate) fn parameter_state(&self) -> &SharedParameterValueState {
+        &self.parameter_state
+    }
+
     fn mixer_event_queue_error(event_name: &str) -> Error {
         log::warn!("Mixer's event queue is full. Failed to send a {event_name} event.");
         log::warn!("Increase the mixer event queue to prevent this from happening...");
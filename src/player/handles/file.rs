@@ -3,8 +3,6 @@ use std::sync::{
     Arc,
 };
 
-use crossbeam_queue::ArrayQueue;
-
 use crate::{
     error::Error,
     player::PlaybackId,
@@ -12,13 +10,16 @@ use crate::{
         amplified::AmplifiedSourceMessage,
         file::FilePlaybackMessage,
         measured::{CpuLoad, SharedCpuLoadState},
-        mixed::MixerMessage,
+        mixed::{MixerMessage, SourceMixerEventQueue},
         panned::PannedSourceMessage,
         playback::PlaybackMessageQueue,
     },
+    transport::{ScheduleTime, Transport},
 };
 use std::time::Duration;
 
+use super::EffectHandle;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Query and change runtime playback properties of a playing [`FileSource`](crate::FileSource).
@@ -32,8 +33,10 @@ pub struct FilePlaybackHandle {
     is_playing: Arc<AtomicBool>,
     playback_id: PlaybackId,
     playback_message_queue: PlaybackMessageQueue,
-    mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+    mixer_event_queue: SourceMixerEventQueue,
     measurement_state: Option<SharedCpuLoadState>,
+    effects: Vec<EffectHandle>,
+    transport: Transport,
 }
 
 impl FilePlaybackHandle {
@@ -41,8 +44,10 @@ impl FilePlaybackHandle {
         is_playing: Arc<AtomicBool>,
         playback_id: PlaybackId,
         playback_message_queue: crate::source::playback::PlaybackMessageQueue,
-        mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+        mixer_event_queue: SourceMixerEventQueue,
         measurement_state: Option<SharedCpuLoadState>,
+        effects: Vec<EffectHandle>,
+        transport: Transport,
     ) -> Self {
         Self {
             is_playing,
@@ -50,6 +55,8 @@ impl FilePlaybackHandle {
             playback_message_queue,
             mixer_event_queue,
             measurement_state,
+            effects,
+            transport,
         }
     }
 
@@ -58,6 +65,12 @@ impl FilePlaybackHandle {
         self.playback_id
     }
 
+    /// Handles of the source's insert effects, in processing order.
+    /// See [`Player::play_file_source_with_effects`](crate::Player::play_file_source_with_effects).
+    pub fn effects(&self) -> &[EffectHandle] {
+        &self.effects
+    }
+
     /// Check if this source is still playing.
     pub fn is_playing(&self) -> bool {
         self.is_playing.load(Ordering::Relaxed)
@@ -74,12 +87,12 @@ impl FilePlaybackHandle {
     }
 
     /// Stop this source at the given sample time or immediately.
-    pub fn stop<T: Into<Option<u64>>>(&self, stop_time: T) -> Result<(), Error> {
+    pub fn stop<T: Into<ScheduleTime>>(&self, stop_time: T) -> Result<(), Error> {
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
 
-        let stop_time = stop_time.into();
+        let stop_time = self.transport.resolve(stop_time)?;
         if let Some(sample_time) = stop_time {
             // Schedule stop with mixer. Force push stop commands to avoid hanging notes...
             if self
@@ -108,7 +121,7 @@ impl FilePlaybackHandle {
     }
 
     /// Change playback position of the source at a specific sample time or immediately.
-    pub fn seek<T: Into<Option<u64>>>(
+    pub fn seek<T: Into<ScheduleTime>>(
         &self,
         position: Duration,
         sample_time: T,
@@ -117,7 +130,7 @@ impl FilePlaybackHandle {
             return Err(Error::SourceNotPlaying);
         }
 
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if let Some(sample_time) = sample_time {
             // Schedule with mixer
             if self
@@ -147,7 +160,7 @@ impl FilePlaybackHandle {
 
     /// Set file source's speed at a given sample time in future or immediately,
     /// with the given optional glide rate in semitones per second.
-    pub fn set_speed<T: Into<Option<u64>>>(
+    pub fn set_speed<T: Into<ScheduleTime>>(
         &self,
         speed: f64,
         glide: Option<f32>,
@@ -157,7 +170,7 @@ impl FilePlaybackHandle {
             return Err(Error::SourceNotPlaying);
         }
 
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if let Some(sample_time) = sample_time {
             // Schedule with mixer
             if self
@@ -190,7 +203,7 @@ impl FilePlaybackHandle {
     }
 
     /// Set source's volume at a given sample time in future or immediately.
-    pub fn set_volume<T: Into<Option<u64>>>(
+    pub fn set_volume<T: Into<ScheduleTime>>(
         &self,
         volume: f32,
         sample_time: T,
@@ -199,7 +212,7 @@ impl FilePlaybackHandle {
             return Err(Error::SourceNotPlaying);
         }
 
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if let Some(sample_time) = sample_time {
             // Schedule with mixer
             if self
@@ -229,7 +242,7 @@ impl FilePlaybackHandle {
     }
 
     /// Set source's panning at a given sample time in future or immediately.
-    pub fn set_panning<T: Into<Option<u64>>>(
+    pub fn set_panning<T: Into<ScheduleTime>>(
         &self,
         panning: f32,
         sample_time: T,
@@ -238,7 +251,7 @@ impl FilePlaybackHandle {
             return Err(Error::SourceNotPlaying);
         }
 
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if let Some(sample_time) = sample_time {
             // Schedule with mixer
             if self
@@ -4,7 +4,6 @@ use std::sync::{
 };
 
 use basedrop::{Handle, Owned};
-use crossbeam_queue::ArrayQueue;
 use four_cc::FourCC;
 
 use crate::{
@@ -12,18 +11,21 @@ use crate::{
     generator::{
         unique_note_id, GeneratorMessage, GeneratorPlaybackEvent, GeneratorPlaybackMessage,
     },
-    parameter::ParameterValueUpdate,
-    player::PlaybackId,
+    parameter::{AutomationLane, ParameterValueUpdate, RampShape},
+    player::{snapshot::SharedParameterValueState, PlaybackId},
     source::{
         amplified::AmplifiedSourceMessage,
         measured::{CpuLoad, SharedCpuLoadState},
-        mixed::MixerMessage,
+        mixed::{MixerMessage, SourceMixerEventQueue},
         panned::PannedSourceMessage,
         playback::PlaybackMessageQueue,
     },
+    transport::{ScheduleTime, Transport},
     NotePlaybackId, PlaybackStatusContext,
 };
 
+use super::EffectHandle;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Query and change runtime playback properties of a playing [`Generator`](crate::Generator).
@@ -34,19 +36,26 @@ pub struct GeneratorPlaybackHandle {
     is_playing: Arc<AtomicBool>,
     playback_id: PlaybackId,
     playback_message_queue: PlaybackMessageQueue,
-    mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+    mixer_event_queue: SourceMixerEventQueue,
     collector_handle: Handle,
     measurement_state: Option<SharedCpuLoadState>,
+    effects: Vec<EffectHandle>,
+    parameter_state: SharedParameterValueState,
+    transport: Transport,
 }
 
 impl GeneratorPlaybackHandle {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         is_playing: Arc<AtomicBool>,
         playback_id: PlaybackId,
         playback_message_queue: PlaybackMessageQueue,
-        mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+        mixer_event_queue: SourceMixerEventQueue,
         collector_handle: Handle,
         measurement_state: Option<SharedCpuLoadState>,
+        effects: Vec<EffectHandle>,
+        parameter_state: SharedParameterValueState,
+        transport: Transport,
     ) -> Self {
         Self {
             is_playing,
@@ -55,6 +64,9 @@ impl GeneratorPlaybackHandle {
             mixer_event_queue,
             collector_handle,
             measurement_state,
+            effects,
+            parameter_state,
+            transport,
         }
     }
 
@@ -63,6 +75,12 @@ impl GeneratorPlaybackHandle {
         self.playback_id
     }
 
+    /// Handles of the source's insert effects, in processing order.
+    /// See [`Player::play_generator_with_effects`](crate::Player::play_generator_with_effects).
+    pub fn effects(&self) -> &[EffectHandle] {
+        &self.effects
+    }
+
     /// Check if this source is still playing.
     pub fn is_playing(&self) -> bool {
         self.is_playing.load(Ordering::Relaxed)
@@ -79,8 +97,8 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Stop this source at the given sample time or immediately.
-    pub fn stop<T: Into<Option<u64>>>(&self, stop_time: T) -> Result<(), Error> {
-        let stop_time = stop_time.into();
+    pub fn stop<T: Into<ScheduleTime>>(&self, stop_time: T) -> Result<(), Error> {
+        let stop_time = self.transport.resolve(stop_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -117,12 +135,12 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Set source's volume at a given sample time in future or immediately.
-    pub fn set_volume<T: Into<Option<u64>>>(
+    pub fn set_volume<T: Into<ScheduleTime>>(
         &self,
         volume: f32,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -157,12 +175,12 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Set source's panning at a given sample time in future or immediately.
-    pub fn set_panning<T: Into<Option<u64>>>(
+    pub fn set_panning<T: Into<ScheduleTime>>(
         &self,
         panning: f32,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -197,7 +215,7 @@ impl GeneratorPlaybackHandle {
 
     /// Trigger a note on event at the given sample time or immediately.
     /// Returns the note playback ID that can be used to control this specific note instance.
-    pub fn note_on<T: Into<Option<u64>>>(
+    pub fn note_on<T: Into<ScheduleTime>>(
         &self,
         note: u8,
         volume: Option<f32>,
@@ -211,7 +229,7 @@ impl GeneratorPlaybackHandle {
     /// Trigger a note on event at the given sample time or immediately and pass along the given
     /// playback context to the playback status channel.
     /// Returns the note playback ID that can be used to control this specific note instance.
-    pub fn note_on_with_context<T: Into<Option<u64>>>(
+    pub fn note_on_with_context<T: Into<ScheduleTime>>(
         &self,
         note: u8,
         volume: Option<f32>,
@@ -219,7 +237,7 @@ impl GeneratorPlaybackHandle {
         context: Option<PlaybackStatusContext>,
         sample_time: T,
     ) -> Result<NotePlaybackId, Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -239,12 +257,12 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Trigger a note off event for a specific note instance at the given sample time or immediately.
-    pub fn note_off<T: Into<Option<u64>>>(
+    pub fn note_off<T: Into<ScheduleTime>>(
         &self,
         note_id: NotePlaybackId,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -256,14 +274,14 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Set playback speed (pitch) for a specific note instance at the given sample time or immediately.
-    pub fn set_note_speed<T: Into<Option<u64>>>(
+    pub fn set_note_speed<T: Into<ScheduleTime>>(
         &self,
         note_id: NotePlaybackId,
         speed: f64,
         glide: Option<f32>,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -280,8 +298,8 @@ impl GeneratorPlaybackHandle {
 
     /// Trigger note off for all currently playing notes immediately or at the given sample time.
     /// This is useful for panic/reset scenarios.
-    pub fn all_notes_off<T: Into<Option<u64>>>(&self, sample_time: T) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+    pub fn all_notes_off<T: Into<ScheduleTime>>(&self, sample_time: T) -> Result<(), Error> {
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -293,13 +311,13 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Set volume for a specific note instance at the given sample time or immediately.
-    pub fn set_note_volume<T: Into<Option<u64>>>(
+    pub fn set_note_volume<T: Into<ScheduleTime>>(
         &self,
         note_id: NotePlaybackId,
         volume: f32,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -311,13 +329,13 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Set panning for a specific note instance at the given sample time or immediately.
-    pub fn set_note_panning<T: Into<Option<u64>>>(
+    pub fn set_note_panning<T: Into<ScheduleTime>>(
         &self,
         note_id: NotePlaybackId,
         panning: f32,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -334,7 +352,7 @@ impl GeneratorPlaybackHandle {
     /// Note: Value update (id, value) tuples can be created safely via `value_update` functions
     /// in [FloatParameter](crate::parameters::FloatParameter), [IntegerParameter](crate::parameters::IntegerParameter),
     /// [EnumParameter](crate::parameters::EnumParameter) and [BooleanParameter](crate::parameters::BooleanParameter).
-    pub fn set_parameter<T: Into<Option<u64>>>(
+    pub fn set_parameter<T: Into<ScheduleTime>>(
         &self,
         (parameter_id, update): (FourCC, ParameterValueUpdate),
         sample_time: T,
@@ -346,14 +364,18 @@ impl GeneratorPlaybackHandle {
                 )));
             }
         }
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         let id = parameter_id;
-        let value = Owned::new(&self.collector_handle, update);
+        let value = Owned::new(&self.collector_handle, update.clone());
         self.send_playback_event(
             sample_time,
             GeneratorPlaybackEvent::SetParameter { id, value },
             "set_parameter",
-        )
+        )?;
+        if let Ok(mut state) = self.parameter_state.lock() {
+            state.apply_update(parameter_id, &update);
+        }
+        Ok(())
     }
 
     /// Set multiple parameter values via the given raw or normalized value update definition
@@ -362,18 +384,99 @@ impl GeneratorPlaybackHandle {
     /// Note: Value update (id, value) tuples can be created safely via `value_update` functions
     /// in [FloatParameter](crate::parameters::FloatParameter), [IntegerParameter](crate::parameters::IntegerParameter),
     /// [EnumParameter](crate::parameters::EnumParameter) and [BooleanParameter](crate::parameters::BooleanParameter).
-    pub fn set_parameters<T: Into<Option<u64>>>(
+    pub fn set_parameters<T: Into<ScheduleTime>>(
         &self,
         values: Vec<(FourCC, ParameterValueUpdate)>,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
-        let values = Owned::new(&self.collector_handle, values);
+        let sample_time = self.transport.resolve(sample_time)?;
+        let owned_values = Owned::new(&self.collector_handle, values.clone());
         self.send_playback_event(
             sample_time,
-            GeneratorPlaybackEvent::SetParameters { values },
+            GeneratorPlaybackEvent::SetParameters {
+                values: owned_values,
+            },
             "set_parameters",
-        )
+        )?;
+        if let Ok(mut state) = self.parameter_state.lock() {
+            for (parameter_id, update) in &values {
+                state.apply_update(*parameter_id, update);
+            }
+        }
+        Ok(())
+    }
+
+    /// Ramp a parameter from its current value to the given normalized value over the given
+    /// number of sample frames, with the given curve shape, starting at a specific sample time
+    /// or immediately.
+    ///
+    /// Ramps start from the parameter's last value which got set via this handle. See
+    /// [`Self::set_parameter_automation`] on how ramps interact with other value changes.
+    pub fn set_parameter_ramp<T: Into<ScheduleTime>>(
+        &self,
+        parameter_id: FourCC,
+        value: f32,
+        duration: u64,
+        shape: RampShape,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        let current_value = self
+            .parameter_state
+            .lock()
+            .ok()
+            .and_then(|state| state.value(parameter_id))
+            .ok_or_else(|| Error::ParameterError(format!("Unknown parameter: '{parameter_id}'")))?;
+        let lane = AutomationLane::ramp(current_value, value, duration, shape);
+        self.set_parameter_automation(parameter_id, lane, sample_time)
+    }
+
+    /// Automate a parameter with the given breakpoint lane, starting at a specific sample time
+    /// or immediately. The mixer evaluates the lane in the audio thread, at all breakpoints and
+    /// in steps of 64 sample frames in between, see [`AutomationLane`].
+    ///
+    /// A new ramp or automation replaces a running ramp or automation of the same parameter.
+    /// Parameter updates via [`Self::set_parameter`] or [`Self::set_parameters`] cancel them,
+    /// when they are scheduled at a sample time. Immediate updates get overridden by running
+    /// automations.
+    ///
+    /// The handle's parameter state, which following ramps start from, takes over the lane's last
+    /// value right away, even when the lane starts in the future. To ramp from a value which is
+    /// scheduled at a later time, pass an explicit lane to this function instead.
+    pub fn set_parameter_automation<T: Into<ScheduleTime>>(
+        &self,
+        parameter_id: FourCC,
+        lane: AutomationLane,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        lane.validate()?;
+        let is_known_parameter = self
+            .parameter_state
+            .lock()
+            .is_ok_and(|state| state.value(parameter_id).is_some());
+        if !is_known_parameter {
+            return Err(Error::ParameterError(format!(
+                "Unknown parameter: '{parameter_id}'"
+            )));
+        }
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        let last_value = lane.points().last().map(|p| p.value);
+        let lane = Owned::new(&self.collector_handle, lane);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::AutomateGeneratorParameter {
+                playback_id: self.playback_id,
+                parameter_id,
+                lane,
+                sample_time,
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error("set_parameter_automation"));
+        }
+        if let (Ok(mut state), Some(value)) = (self.parameter_state.lock(), last_value) {
+            state.apply_update(parameter_id, &ParameterValueUpdate::Normalized(value));
+        }
+        Ok(())
     }
 
     /// Set or update a modulation routing at the given sample time or immediately.
@@ -386,7 +489,7 @@ impl GeneratorPlaybackHandle {
     ///   centered at 0.5. Use for sources like keytracking when you want
     ///   middle values to be neutral (no modulation).
     /// * `sample_time` - When to apply (None = immediate, Some = scheduled)
-    pub fn set_modulation<T: Into<Option<u64>>>(
+    pub fn set_modulation<T: Into<ScheduleTime>>(
         &self,
         source: FourCC,
         target: FourCC,
@@ -394,7 +497,7 @@ impl GeneratorPlaybackHandle {
         bipolar: bool,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -412,13 +515,13 @@ impl GeneratorPlaybackHandle {
     }
 
     /// Remove a modulation routing at the given sample time or immediately.
-    pub fn clear_modulation<T: Into<Option<u64>>>(
+    pub fn clear_modulation<T: Into<ScheduleTime>>(
         &self,
         source: FourCC,
         target: FourCC,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -434,12 +537,12 @@ impl GeneratorPlaybackHandle {
     ///
     /// Use this for state changes that cannot be expressed as a simple parameter update
     /// (e.g. loop point overrides, sample buffer swaps).
-    pub fn send_message<M: GeneratorMessage + 'static, T: Into<Option<u64>>>(
+    pub fn send_message<M: GeneratorMessage + 'static, T: Into<ScheduleTime>>(
         &self,
         message: M,
         sample_time: T,
     ) -> Result<(), Error> {
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
@@ -452,6 +555,10 @@ impl GeneratorPlaybackHandle {
         )
     }
 
+    pub(crate) fn parameter_state(&self) -> &SharedParameterValueState {
+        &self.parameter_state
+    }
+
     fn send_playback_event(
         &self,
         sample_time: Option<u64>,
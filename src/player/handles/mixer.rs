@@ -1,35 +1,56 @@
 use std::sync::Arc;
 
+use crossbeam_queue::ArrayQueue;
+
 use crate::{
-    player::MixerId,
+    error::Error,
+    player::{MixerId, MuteStatesLock, Player, PlayerMixerInfo, PlayerMixerInfos},
     source::{
         measured::{CpuLoad, SharedCpuLoadState},
-        metered::{AudioLevel, SharedAudioLevelState},
+        metered::{
+            AudioLevel, MeteringStates, SharedAudioLevelState, SharedSpectrumState,
+            SharedWaveformState, Spectrum,
+        },
+        mixed::MixerMessage,
     },
+    transport::{ScheduleTime, Transport},
 };
 
 // -------------------------------------------------------------------------------------------------
 
-/// A handle to a mixer, which allows querying runtime properties.
+/// A handle to a mixer, which allows querying runtime properties and controlling the mixer's
+/// volume, panning, mute and solo states and sends.
 ///
 /// Handles are `Send` and `Sync` so they can be sent across threads.
 #[derive(Clone)]
 pub struct MixerHandle {
     mixer_id: MixerId,
+    mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+    mixers: PlayerMixerInfos,
+    mute_states_lock: MuteStatesLock,
     measurement_state: Option<SharedCpuLoadState>,
-    metering_state: Option<SharedAudioLevelState>,
+    metering_states: MeteringStates,
+    transport: Transport,
 }
 
 impl MixerHandle {
     pub(crate) fn new(
         mixer_id: MixerId,
+        mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+        mixers: PlayerMixerInfos,
+        mute_states_lock: MuteStatesLock,
         measurement_state: Option<SharedCpuLoadState>,
-        metering_state: Option<SharedAudioLevelState>,
+        metering_states: MeteringStates,
+        transport: Transport,
     ) -> Self {
         Self {
             mixer_id,
+            mixer_event_queue,
+            mixers,
+            mute_states_lock,
             measurement_state,
-            metering_state,
+            metering_states,
+            transport,
         }
     }
 
@@ -38,6 +59,141 @@ impl MixerHandle {
         self.mixer_id
     }
 
+    /// Change the mixer's output volume at a specific sample time or immediately.
+    /// The volume is a linear gain factor, where 1.0 is unity gain.
+    pub fn set_volume<T: Into<ScheduleTime>>(
+        &self,
+        volume: f32,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        if volume < 0.0 {
+            return Err(Error::ParameterError(format!(
+                "Invalid mixer volume: {volume}, must be >= 0"
+            )));
+        }
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::SetMixerVolume {
+                volume,
+                sample_time,
+            })
+            .is_err()
+        {
+            Err(Self::mixer_event_queue_error("set_volume"))
+        } else {
+            self.update_mixer_info(|info| info.volume = volume)
+        }
+    }
+
+    /// Change the mixer's output panning at a specific sample time or immediately.
+    /// The panning value must be in range -1.0 (left) to 1.0 (right), where 0.0 is center.
+    pub fn set_panning<T: Into<ScheduleTime>>(
+        &self,
+        panning: f32,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        if !(-1.0..=1.0).contains(&panning) {
+            return Err(Error::ParameterError(format!(
+                "Invalid mixer panning: {panning}, must be in range -1..=1"
+            )));
+        }
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::SetMixerPanning {
+                panning,
+                sample_time,
+            })
+            .is_err()
+        {
+            Err(Self::mixer_event_queue_error("set_panning"))
+        } else {
+            self.update_mixer_info(|info| info.panning = panning)
+        }
+    }
+
+    /// Returns true when the mixer got muted via [`Self::set_muted`].
+    pub fn is_muted(&self) -> bool {
+        self.mixers
+            .get(&self.mixer_id)
+            .is_some_and(|info| info.muted)
+    }
+
+    /// Mute or unmute the mixer's output at a specific sample time or immediately.
+    ///
+    /// Muting is declicked and also silences the mixer's pre-fader sends.
+    /// Mute and solo changes cancel all mute and solo changes which are scheduled later on.
+    pub fn set_muted<T: Into<ScheduleTime>>(
+        &self,
+        muted: bool,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        self.update_mixer_info(|info| info.muted = muted)?;
+        Player::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, sample_time)
+    }
+
+    /// Returns true when the mixer got soloed via [`Self::set_solo`].
+    pub fn is_soloed(&self) -> bool {
+        self.mixers
+            .get(&self.mixer_id)
+            .is_some_and(|info| info.soloed)
+    }
+
+    /// Solo or unsolo the mixer at a specific sample time or immediately.
+    ///
+    /// Solo works across the whole mixer tree like on a mixing console: when any mixer is soloed,
+    /// all other mixers get muted, except for the sub-mixers of soloed mixers and return mixers
+    /// which receive sends from soloed mixers. Parent mixers of soloed mixers pass through the
+    /// soloed mixer's output, but mute their own sources. Multiple mixers can be soloed at once.
+    pub fn set_solo<T: Into<ScheduleTime>>(
+        &self,
+        soloed: bool,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        self.update_mixer_info(|info| info.soloed = soloed)?;
+        Player::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, sample_time)
+    }
+
+    /// Change the level of an existing send to the given return mixer at a specific sample time
+    /// or immediately. Sends are added via [`Player::add_send`](crate::Player::add_send).
+    pub fn set_send_level<T: Into<ScheduleTime>>(
+        &self,
+        return_mixer_id: MixerId,
+        level: f32,
+        sample_time: T,
+    ) -> Result<(), Error> {
+        if level < 0.0 {
+            return Err(Error::ParameterError(format!(
+                "Invalid send level: {level}, must be >= 0"
+            )));
+        }
+        let sample_time = self.transport.resolve(sample_time)?.unwrap_or(0);
+        if self
+            .mixer_event_queue
+            .push(MixerMessage::SetSendLevel {
+                target_mixer_id: return_mixer_id,
+                level,
+                sample_time,
+            })
+            .is_err()
+        {
+            Err(Self::mixer_event_queue_error("set_send_level"))
+        } else {
+            self.update_mixer_info(|info| {
+                if let Some(send) = info
+                    .sends
+                    .iter_mut()
+                    .find(|(id, ..)| *id == return_mixer_id)
+                {
+                    send.2 = level;
+                }
+            })
+        }
+    }
+
     /// Get the CPU load data for this mixer.
     ///
     /// Only available when CPU measurement was enabled in the playback options
@@ -60,7 +216,8 @@ impl MixerHandle {
     ///
     /// Only available when audio metering is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
     pub fn audio_level(&self) -> Option<AudioLevel> {
-        self.metering_state
+        self.metering_states
+            .audio_level
             .as_ref()
             .and_then(|s| s.try_lock().ok())
             .map(|state| state.audio_level().clone())
@@ -70,6 +227,49 @@ impl MixerHandle {
     ///
     /// Only available when audio metering is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
     pub fn audio_level_state(&self) -> Option<SharedAudioLevelState> {
-        self.metering_state.as_ref().map(Arc::clone)
+        self.metering_states.audio_level.clone()
+    }
+
+    /// Get the current magnitude spectrum for this mixer.
+    ///
+    /// Only available when spectrum analysis is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
+    pub fn spectrum(&self) -> Option<Spectrum> {
+        self.metering_states
+            .spectrum
+            .as_ref()
+            .map(|state| state.spectrum())
+    }
+
+    /// Get the shared, lock-free spectrum state for this mixer, if spectrum analysis is enabled.
+    /// Use [`SpectrumState::read_magnitudes`](crate::SpectrumState::read_magnitudes) to read
+    /// magnitudes without allocating.
+    ///
+    /// Only available when spectrum analysis is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
+    pub fn spectrum_state(&self) -> Option<SharedSpectrumState> {
+        self.metering_states.spectrum.clone()
+    }
+
+    /// Get the shared, lock-free recent waveform history for this mixer, if waveform recording
+    /// is enabled. Use [`WaveformState::read`](crate::WaveformState::read) to read free running
+    /// or triggered waveforms without allocating.
+    ///
+    /// Only available when waveform recording is enabled in the player's [`PlayerConfig`](crate::PlayerConfig).
+    pub fn waveform_state(&self) -> Option<SharedWaveformState> {
+        self.metering_states.waveform.clone()
+    }
+
+    fn update_mixer_info<F: FnOnce(&mut PlayerMixerInfo)>(&self, update: F) -> Result<(), Error> {
+        let mut info = self
+            .mixers
+            .get_mut(&self.mixer_id)
+            .ok_or(Error::MixerNotFoundError(self.mixer_id))?;
+        update(&mut info);
+        Ok(())
+    }
+
+    fn mixer_event_queue_error(event_name: &str) -> Error {
+        log::warn!("Mixer's event queue is full. Failed to send a {event_name} event.");
+        log::warn!("Increase the mixer event queue to prevent this from happening...");
+        Error::SendError("Mixer queue is full".to_string())
     }
 }
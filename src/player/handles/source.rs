@@ -1,6 +1,6 @@
-use super::{FilePlaybackHandle, GeneratorPlaybackHandle, SynthPlaybackHandle};
+use super::{EffectHandle, FilePlaybackHandle, GeneratorPlaybackHandle, SynthPlaybackHandle};
 
-use crate::{error::Error, source::measured::CpuLoad};
+use crate::{error::Error, source::measured::CpuLoad, transport::ScheduleTime};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -34,7 +34,16 @@ impl SourcePlaybackHandle {
         }
     }
 
-    pub fn stop<T: Into<Option<u64>>>(&self, stop_time: T) -> Result<(), Error> {
+    /// Handles of the source's insert effects, in processing order.
+    pub fn effects(&self) -> &[EffectHandle] {
+        match self {
+            SourcePlaybackHandle::File(handle) => handle.effects(),
+            SourcePlaybackHandle::Synth(handle) => handle.effects(),
+            SourcePlaybackHandle::Generator(handle) => handle.effects(),
+        }
+    }
+
+    pub fn stop<T: Into<ScheduleTime>>(&self, stop_time: T) -> Result<(), Error> {
         match self {
             SourcePlaybackHandle::File(handle) => handle.stop(stop_time),
             SourcePlaybackHandle::Synth(handle) => handle.stop(stop_time),
@@ -3,21 +3,22 @@ use std::sync::{
     Arc,
 };
 
-use crossbeam_queue::ArrayQueue;
-
 use crate::{
     error::Error,
     player::PlaybackId,
     source::{
         amplified::AmplifiedSourceMessage,
         measured::{CpuLoad, SharedCpuLoadState},
-        mixed::MixerMessage,
+        mixed::{MixerMessage, SourceMixerEventQueue},
         panned::PannedSourceMessage,
         playback::PlaybackMessageQueue,
     },
+    transport::{ScheduleTime, Transport},
     SynthPlaybackMessage,
 };
 
+use super::EffectHandle;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Query and change runtime playback properties of a playing [`SynthSource`](crate::SynthSource).
@@ -31,8 +32,10 @@ pub struct SynthPlaybackHandle {
     is_playing: Arc<AtomicBool>,
     playback_id: PlaybackId,
     playback_message_queue: PlaybackMessageQueue,
-    mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+    mixer_event_queue: SourceMixerEventQueue,
     measurement_state: Option<SharedCpuLoadState>,
+    effects: Vec<EffectHandle>,
+    transport: Transport,
 }
 
 impl SynthPlaybackHandle {
@@ -40,8 +43,10 @@ impl SynthPlaybackHandle {
         is_playing: Arc<AtomicBool>,
         playback_id: PlaybackId,
         playback_message_queue: PlaybackMessageQueue,
-        mixer_event_queue: Arc<ArrayQueue<MixerMessage>>,
+        mixer_event_queue: SourceMixerEventQueue,
         measurement_state: Option<SharedCpuLoadState>,
+        effects: Vec<EffectHandle>,
+        transport: Transport,
     ) -> Self {
         Self {
             is_playing,
@@ -49,6 +54,8 @@ impl SynthPlaybackHandle {
             playback_message_queue,
             mixer_event_queue,
             measurement_state,
+            effects,
+            transport,
         }
     }
 
@@ -57,6 +64,12 @@ impl SynthPlaybackHandle {
         self.playback_id
     }
 
+    /// Handles of the source's insert effects, in processing order.
+    /// See [`Player::play_synth_source_with_effects`](crate::Player::play_synth_source_with_effects).
+    pub fn effects(&self) -> &[EffectHandle] {
+        &self.effects
+    }
+
     /// Check if this source is still playing.
     pub fn is_playing(&self) -> bool {
         self.is_playing.load(Ordering::Relaxed)
@@ -73,12 +86,12 @@ impl SynthPlaybackHandle {
     }
 
     /// Stop this source at the given sample time or immediately.
-    pub fn stop<T: Into<Option<u64>>>(&self, stop_time: T) -> Result<(), Error> {
+    pub fn stop<T: Into<ScheduleTime>>(&self, stop_time: T) -> Result<(), Error> {
         if !self.is_playing() {
             return Err(Error::SourceNotPlaying);
         }
 
-        let stop_time = stop_time.into();
+        let stop_time = self.transport.resolve(stop_time)?;
         if let Some(sample_time) = stop_time {
             // Schedule stop with mixer. Force push stop commands to avoid hanging notes...
             if self
@@ -107,7 +120,7 @@ impl SynthPlaybackHandle {
     }
 
     /// Set source's volume at a given sample time in future or immediately.
-    pub fn set_volume<T: Into<Option<u64>>>(
+    pub fn set_volume<T: Into<ScheduleTime>>(
         &self,
         volume: f32,
         sample_time: T,
@@ -116,7 +129,7 @@ impl SynthPlaybackHandle {
             return Err(Error::SourceNotPlaying);
         }
 
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if let Some(sample_time) = sample_time {
             // Schedule with mixer
             if self
@@ -146,7 +159,7 @@ impl SynthPlaybackHandle {
     }
 
     /// Set source's panning at a given sample time in future or immediately.
-    pub fn set_panning<T: Into<Option<u64>>>(
+    pub fn set_panning<T: Into<ScheduleTime>>(
         &self,
         panning: f32,
         sample_time: T,
@@ -155,7 +168,7 @@ impl SynthPlaybackHandle {
             return Err(Error::SourceNotPlaying);
         }
 
-        let sample_time = sample_time.into();
+        let sample_time = self.transport.resolve(sample_time)?;
         if let Some(sample_time) = sample_time {
             // Schedule with mixer
             if self
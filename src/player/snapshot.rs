@@ -0,0 +1,472 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use four_cc::FourCC;
+
+use crate::{
+    effect::Effect,
+    error::Error,
+    parameter::{BooleanParameter, EnumParameter, FloatParameter, IntegerParameter},
+    player::{
+        EffectHandle, EffectId, GeneratorPlaybackHandle, MixerHandle, MixerId, MixerOutput,
+        MixerSendMode, PlaybackId, Player,
+    },
+    Generator, Parameter, ParameterValueUpdate,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// A plain data snapshot of a [`Player`]'s processing graph: its mixers, their effect chains,
+/// sends and sidechains, and all added generators, including their current parameter values.
+///
+/// Create snapshots via [`Player::snapshot`] and restore them via [`Player::restore_snapshot`].
+/// With the `serde` feature enabled, snapshots are serializable and can be converted from and to
+/// JSON via `to_json` and `from_json`.
+///
+/// Parameter values are tracked by the player, starting from the parameter's default values,
+/// and get updated with all values which are set via [`EffectHandle`]s and
+/// [`GeneratorPlaybackHandle`]s. Changes which an effect or generator applies on its own, e.g.
+/// via custom messages, are not part of the snapshot.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerSnapshot {
+    /// All mixers, starting with the main mixer. Parent mixers are listed before their children.
+    pub mixers: Vec<MixerSnapshot>,
+    /// All generators which got added via [`Player::add_generator`].
+    pub generators: Vec<GeneratorSnapshot>,
+}
+
+/// Snapshot of a single mixer in a [`PlayerSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MixerSnapshot {
+    /// The mixer's id at the time the snapshot was taken.
+    pub id: MixerId,
+    /// The parent mixer's id or `None` for the main mixer.
+    pub parent_id: Option<MixerId>,
+    /// Where the mixer's output goes to, as set via [`Player::set_mixer_output`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub output: MixerOutput,
+    /// Linear output volume.
+    pub volume: f32,
+    /// Output panning in range -1..=1.
+    pub panning: f32,
+    /// Mute state, as set via [`MixerHandle::set_muted`].
+    pub muted: bool,
+    /// Solo state, as set via [`MixerHandle::set_solo`].
+    pub soloed: bool,
+    /// The mixer's sends to return mixers.
+    pub sends: Vec<SendSnapshot>,
+    /// The mixer's effects in processing order.
+    pub effects: Vec<EffectSnapshot>,
+}
+
+/// Snapshot of a mixer send in a [`MixerSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SendSnapshot {
+    /// Id of the return mixer.
+    pub return_mixer_id: MixerId,
+    /// Linear send level.
+    pub level: f32,
+    /// Where the send taps the mixer's signal.
+    pub mode: MixerSendMode,
+}
+
+/// Snapshot of an effect in a [`MixerSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectSnapshot {
+    /// The effect's id at the time the snapshot was taken.
+    pub id: EffectId,
+    /// The effect's [name](Effect::name), which is used to recreate the effect.
+    pub name: String,
+    /// Id of the mixer which feeds the effect's sidechain input, if any.
+    pub sidechain_mixer_id: Option<MixerId>,
+    /// Normalized parameter values.
+    pub parameters: Vec<ParameterSnapshot>,
+}
+
+/// Snapshot of an added generator in a [`PlayerSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeneratorSnapshot {
+    /// The generator's playback id at the time the snapshot was taken.
+    pub playback_id: PlaybackId,
+    /// The generator's [name](Generator::generator_name), which is used to recreate it.
+    pub name: String,
+    /// Id of the mixer the generator is playing on.
+    pub mixer_id: MixerId,
+    /// Normalized parameter values.
+    pub parameters: Vec<ParameterSnapshot>,
+}
+
+/// Snapshot of a single effect or generator parameter value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParameterSnapshot {
+    /// The parameter's id.
+    pub id: FourCC,
+    /// The parameter's **normalized** value.
+    pub value: f32,
+}
+
+#[cfg(feature = "serde")]
+impl PlayerSnapshot {
+    /// Serialize the snapshot into a pretty printed JSON string.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|err| Error::SerializationError(Box::new(err)))
+    }
+
+    /// Deserialize a snapshot from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|err| Error::SerializationError(Box::new(err)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Handles to the mixers, effects and generators, which got created when restoring a
+/// [`PlayerSnapshot`] via [`Player::restore_snapshot`].
+///
+/// Handles are keyed by the ids from the snapshot, as restored objects get new ids.
+#[derive(Clone, Default)]
+pub struct RestoredPlayerSnapshot {
+    /// Mixer handles by snapshot mixer id. This includes the main mixer.
+    pub mixers: HashMap<MixerId, MixerHandle>,
+    /// Effect handles by snapshot effect id.
+    pub effects: HashMap<EffectId, EffectHandle>,
+    /// Generator handles by snapshot playback id.
+    pub generators: HashMap<PlaybackId, GeneratorPlaybackHandle>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Tracks normalized parameter values of an effect or generator, as set via its handle.
+#[derive(Debug)]
+pub(crate) struct ParameterValueState {
+    values: Vec<(Box<dyn Parameter>, f32)>,
+}
+
+/// Shared parameter value state, which is accessed by the player and effect/generator handles.
+pub(crate) type SharedParameterValueState = Arc<Mutex<ParameterValueState>>;
+
+impl ParameterValueState {
+    /// Create a new state with the default values of the given parameters.
+    pub fn new(parameters: &[&dyn Parameter]) -> Self {
+        let values = parameters
+            .iter()
+            .map(|parameter| (parameter.dyn_clone(), parameter.default_value()))
+            .collect();
+        Self { values }
+    }
+
+    /// Create a new shared state with the default values of the given parameters.
+    pub fn new_shared(parameters: &[&dyn Parameter]) -> SharedParameterValueState {
+        Arc::new(Mutex::new(Self::new(parameters)))
+    }
+
+    /// Returns true when the state contains a parameter with the given id.
+    pub fn contains(&self, id: FourCC) -> bool {
+        self.values
+            .iter()
+            .any(|(parameter, _)| parameter.id() == id)
+    }
+
+    /// Current normalized value of the parameter with the given id.
+    pub fn value(&self, id: FourCC) -> Option<f32> {
+        self.values
+            .iter()
+            .find(|(parameter, _)| parameter.id() == id)
+            .map(|(_, value)| *value)
+    }
+
+    /// Apply a raw or normalized value update.
+    pub fn apply_update(&mut self, id: FourCC, update: &ParameterValueUpdate) {
+        if let Some((parameter, value)) = self
+            .values
+            .iter_mut()
+            .find(|(parameter, _)| parameter.id() == id)
+        {
+            match update {
+                ParameterValueUpdate::Normalized(normalized) => {
+                    *value = normalized.clamp(0.0, 1.0);
+                }
+                ParameterValueUpdate::Raw(raw) => {
+                    if let Some(normalized) = Self::normalize_raw_value(parameter.as_ref(), raw) {
+                        *value = normalized;
+                    } else {
+                        log::warn!("Can't track raw value type of parameter '{id}'");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convert the given snapshot values to normalized value updates. Values of parameters which
+    /// are not part of this state get skipped.
+    pub fn restorable_values(
+        &self,
+        parameters: &[ParameterSnapshot],
+    ) -> Vec<(FourCC, ParameterValueUpdate)> {
+        parameters
+            .iter()
+            .filter_map(|parameter| {
+                if self.contains(parameter.id) {
+                    let value = parameter.value.clamp(0.0, 1.0);
+                    Some((parameter.id, ParameterValueUpdate::Normalized(value)))
+                } else {
+                    log::warn!("Ignoring unknown parameter '{}' in snapshot", parameter.id);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Current normalized parameter values.
+    pub fn snapshot(&self) -> Vec<ParameterSnapshot> {
+        self.values
+            .iter()
+            .map(|(parameter, value)| ParameterSnapshot {
+                id: parameter.id(),
+                value: *value,
+            })
+            .collect()
+    }
+
+    fn normalize_raw_value(
+        parameter: &dyn Parameter,
+        raw: &Arc<dyn Any + Send + Sync>,
+    ) -> Option<f32> {
+        let parameter = parameter as &dyn Any;
+        if let Some(float_param) = parameter.downcast_ref::<FloatParameter>() {
+            let value = if let Some(value) = raw.downcast_ref::<f32>() {
+                *value
+            } else {
+                *raw.downcast_ref::<f64>()? as f32
+            };
+            Some(float_param.normalize_value(float_param.clamp_value(value)))
+        } else if let Some(integer_param) = parameter.downcast_ref::<IntegerParameter>() {
+            let value = if let Some(value) = raw.downcast_ref::<i32>() {
+                *value
+            } else if let Some(value) = raw.downcast_ref::<i64>() {
+                *value as i32
+            } else if let Some(value) = raw.downcast_ref::<u32>() {
+                *value as i32
+            } else {
+                *raw.downcast_ref::<u64>()? as i32
+            };
+            Some(integer_param.normalize_value(integer_param.clamp_value(value)))
+        } else if let Some(enum_param) = parameter.downcast_ref::<EnumParameter>() {
+            let value_count = enum_param.values().len();
+            if let Some(index) = raw.downcast_ref::<usize>() {
+                Some((*index).min(value_count - 1) as f32 / (value_count - 1).max(1) as f32)
+            } else {
+                Some(enum_param.normalize_value(raw.downcast_ref::<String>()?))
+            }
+        } else if let Some(boolean_param) = parameter.downcast_ref::<BooleanParameter>() {
+            Some(boolean_param.normalize_value(*raw.downcast_ref::<bool>()?))
+        } else {
+            None
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Player {
+    /// Capture the player's current mixer graph, effect chains and added generators as plain,
+    /// optionally serializable data. See [`PlayerSnapshot`] for details.
+    ///
+    /// Transient sources, such as played files, synths or played generators, are not included.
+    pub fn snapshot(&self) -> PlayerSnapshot {
+        let mut mixers = Vec::new();
+        let mut pending_mixer_ids = vec![Self::MAIN_MIXER_ID];
+        while let Some(mixer_id) = pending_mixer_ids.pop() {
+            let mut sub_mixer_ids = self.sub_mixers_of(mixer_id);
+            sub_mixer_ids.sort_unstable_by(|a, b| b.cmp(a));
+            pending_mixer_ids.extend(sub_mixer_ids);
+            if let Some(snapshot) = self.mixer_snapshot(mixer_id) {
+                mixers.push(snapshot);
+            }
+        }
+
+        let mut generators: Vec<GeneratorSnapshot> = self
+            .playing_sources
+            .iter()
+            .filter(|entry| !entry.value().is_transient)
+            .filter_map(|entry| {
+                let generator = entry.value().generator.as_ref()?;
+                Some(GeneratorSnapshot {
+                    playback_id: *entry.key(),
+                    name: generator.name.clone(),
+                    mixer_id: entry.value().mixer_id,
+                    parameters: generator.parameters.lock().ok()?.snapshot(),
+                })
+            })
+            .collect();
+        generators.sort_by_key(|generator| generator.playback_id);
+
+        PlayerSnapshot { mixers, generators }
+    }
+
+    /// Recreate the mixers, effects and generators of the given snapshot.
+    ///
+    /// Effects and generators are created via the given factory functions from their snapshot's
+    /// names, then their snapshot parameter values get applied. Mixers and effects are added to
+    /// the player's existing graph: the snapshot's main mixer effects are appended to the player's
+    /// main mixer effects, so usually snapshots should be restored into a newly created player,
+    /// or a player which got cleared via [`Self::remove_all_mixers`] and
+    /// [`Self::remove_all_effects`] first.
+    ///
+    /// Restored objects get new ids. Returns handles to all restored objects, keyed by the ids
+    /// from the snapshot.
+    pub fn restore_snapshot<EF, GF>(
+        &mut self,
+        snapshot: &PlayerSnapshot,
+        mut create_effect: EF,
+        mut create_generator: GF,
+    ) -> Result<RestoredPlayerSnapshot, Error>
+    where
+        EF: FnMut(&EffectSnapshot) -> Result<Box<dyn Effect>, Error>,
+        GF: FnMut(&GeneratorSnapshot) -> Result<Box<dyn Generator>, Error>,
+    {
+        let mut restored = RestoredPlayerSnapshot::default();
+
+        // Create mixers: parents are listed before their children
+        let mut mixer_ids = HashMap::new();
+        for mixer in &snapshot.mixers {
+            let mixer_handle = match mixer.parent_id {
+                None => self.main_mixer_handle()?,
+                Some(parent_id) => {
+                    let new_parent_id = *mixer_ids
+                        .get(&parent_id)
+                        .ok_or(Error::MixerNotFoundError(parent_id))?;
+                    self.add_mixer_with_output(new_parent_id, mixer.output)?
+                }
+            };
+            mixer_handle.set_volume(mixer.volume, None)?;
+            mixer_handle.set_panning(mixer.panning, None)?;
+            mixer_ids.insert(mixer.id, mixer_handle.id());
+            restored.mixers.insert(mixer.id, mixer_handle);
+        }
+        let new_mixer_id = |mixer_id: MixerId| {
+            mixer_ids
+                .get(&mixer_id)
+                .copied()
+                .ok_or(Error::MixerNotFoundError(mixer_id))
+        };
+
+        // Create effect chains, sends and sidechains
+        for mixer in &snapshot.mixers {
+            for effect in &mixer.effects {
+                let effect_handle =
+                    self.add_effect(create_effect(effect)?, new_mixer_id(mixer.id)?)?;
+                let values = effect_handle
+                    .parameter_state()
+                    .lock()
+                    .map(|state| state.restorable_values(&effect.parameters))
+                    .unwrap_or_default();
+                effect_handle.set_parameters(values, None)?;
+                restored.effects.insert(effect.id, effect_handle);
+            }
+        }
+        for mixer in &snapshot.mixers {
+            for send in &mixer.sends {
+                self.add_send(
+                    new_mixer_id(mixer.id)?,
+                    new_mixer_id(send.return_mixer_id)?,
+                    send.level,
+                    send.mode,
+                )?;
+            }
+            for effect in &mixer.effects {
+                if let Some(sidechain_mixer_id) = effect.sidechain_mixer_id {
+                    let effect_id = restored.effects[&effect.id].id();
+                    self.set_effect_sidechain(effect_id, new_mixer_id(sidechain_mixer_id)?)?;
+                }
+            }
+        }
+
+        // Add generators
+        for generator in &snapshot.generators {
+            let generator_handle = self.add_generator(
+                create_generator(generator)?,
+                new_mixer_id(generator.mixer_id)?,
+            )?;
+            let values = generator_handle
+                .parameter_state()
+                .lock()
+                .map(|state| state.restorable_values(&generator.parameters))
+                .unwrap_or_default();
+            generator_handle.set_parameters(values, None)?;
+            restored
+                .generators
+                .insert(generator.playback_id, generator_handle);
+        }
+
+        // Apply mute and solo states, once all mixers and sends are present
+        for mixer in &snapshot.mixers {
+            if let Some(mut info) = self.mixers.get_mut(&new_mixer_id(mixer.id)?) {
+                info.muted = mixer.muted;
+                info.soloed = mixer.soloed;
+            }
+        }
+        Self::apply_mixer_mute_states(&self.mixers, &self.mute_states_lock, 0)?;
+
+        Ok(restored)
+    }
+
+    fn main_mixer_handle(&self) -> Result<MixerHandle, Error> {
+        Ok(MixerHandle::new(
+            Self::MAIN_MIXER_ID,
+            self.mixer_event_queue(Self::MAIN_MIXER_ID)?,
+            Arc::clone(&self.mixers),
+            Arc::clone(&self.mute_states_lock),
+            self.main_mixer_measurement_state.clone(),
+            self.main_mixer_metering_states.clone(),
+            self.transport.clone(),
+        ))
+    }
+
+    fn mixer_snapshot(&self, mixer_id: MixerId) -> Option<MixerSnapshot> {
+        let info = self.mixers.get(&mixer_id)?.clone();
+        let effects = info
+            .effects
+            .iter()
+            .filter_map(|effect_id| {
+                let effect_info = self.effects.get(effect_id)?;
+                let parameters = effect_info.parameters.lock().ok()?.snapshot();
+                Some(EffectSnapshot {
+                    id: *effect_id,
+                    name: effect_info.effect_name.to_string(),
+                    sidechain_mixer_id: effect_info.sidechain_mixer_id,
+                    parameters,
+                })
+            })
+            .collect();
+        let sends = info
+            .sends
+            .iter()
+            .map(|(return_mixer_id, mode, level)| SendSnapshot {
+                return_mixer_id: *return_mixer_id,
+                level: *level,
+                mode: *mode,
+            })
+            .collect();
+        Some(MixerSnapshot {
+            id: mixer_id,
+            parent_id: (mixer_id != Self::MAIN_MIXER_ID).then_some(info.parent_id),
+            output: info.output,
+            volume: info.volume,
+            panning: info.panning,
+            muted: info.muted,
+            soloed: info.soloed,
+            sends,
+            effects,
+        })
+    }
+}
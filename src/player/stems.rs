@@ -0,0 +1,199 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic,
+    time::Instant,
+};
+
+use basedrop::Shared;
+
+use crate::{
+    error::Error,
+    output::wav::{WavFileWriter, WavSampleFormat},
+    player::{MixerId, Player, StemMode},
+    source::{
+        mixed::{MixedSource, MixerMessage, MixerSendBuffer},
+        SourceTime,
+    },
+    utils::{buffer::clear_buffer, dither::DitherMode},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// A mixer's signal, which gets rendered into its own WAV file along with the player's master
+/// output via [`Player::render_stems_to_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderStem {
+    /// The mixer to tap. The main mixer can be tapped too.
+    pub mixer_id: MixerId,
+    /// Where to tap the mixer's signal.
+    pub mode: StemMode,
+    /// Target WAV file path.
+    pub file_path: PathBuf,
+}
+
+impl RenderStem {
+    /// Create a new stem, which taps the given mixer's output after its effects.
+    pub fn new<P: AsRef<Path>>(mixer_id: MixerId, file_path: P) -> Self {
+        Self {
+            mixer_id,
+            mode: StemMode::default(),
+            file_path: file_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Tap the mixer's signal at the given point.
+    pub fn mode(mut self, mode: StemMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A connected stem tap and the file it gets written to.
+struct StemWriter {
+    tap_id: usize,
+    mixer_id: MixerId,
+    buffer: Shared<MixerSendBuffer>,
+    writer: WavFileWriter,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Player {
+    /// Synchronously render the given number of sample frames of the master output and the
+    /// given stems into separate WAV files in a single pass.
+    ///
+    /// Stems tap their mixer's signal within the mixer graph, so they also contain effect tails
+    /// and sends from other mixers, and get delay-compensated to stay sample-aligned with the
+    /// master output. The master file has the output device's channel layout, stems have the
+    /// main mixer's channel layout, see [`Self::output_channel_count`]. All files use the
+    /// player's output sample rate. Integer sample formats get quantized with the given dither
+    /// mode.
+    ///
+    /// Only available for offline players, see [`Self::new_offline`].
+    pub fn render_stems_to_files<P: AsRef<Path>>(
+        &mut self,
+        master_file_path: P,
+        stems: &[RenderStem],
+        frames: usize,
+        sample_format: WavSampleFormat,
+        dither: DitherMode,
+    ) -> Result<(), Error> {
+        if self.offline_output.is_none() {
+            return Err(Self::offline_output_error());
+        }
+        let master_channel_count = self.output_device.channel_count();
+        let channel_count = self.output_channel_count();
+        let sample_rate = self.output_sample_rate();
+        let create_writer = |file_path: &Path, channel_count: usize| {
+            WavFileWriter::create(file_path, sample_rate, channel_count, sample_format, dither)
+        };
+
+        // Create all files and connect stem taps
+        let mut master_writer = create_writer(master_file_path.as_ref(), master_channel_count)?;
+        let mut stem_writers = Vec::with_capacity(stems.len());
+        for stem in stems {
+            let stem_writer = create_writer(&stem.file_path, channel_count)
+                .and_then(|writer| self.add_stem_tap(stem.mixer_id, stem.mode, writer));
+            match stem_writer {
+                Ok(stem_writer) => stem_writers.push(stem_writer),
+                Err(err) => {
+                    self.remove_stem_taps(&stem_writers);
+                    return Err(err);
+                }
+            }
+        }
+
+        // Render and write in blocks, which fit into the stem tap buffers
+        let result = self.render_stems(&mut master_writer, &mut stem_writers, frames);
+        self.remove_stem_taps(&stem_writers);
+        result?;
+
+        master_writer.finalize()?;
+        for stem_writer in stem_writers {
+            stem_writer.writer.finalize()?;
+        }
+        Ok(())
+    }
+
+    fn add_stem_tap(
+        &self,
+        mixer_id: MixerId,
+        mode: StemMode,
+        writer: WavFileWriter,
+    ) -> Result<StemWriter, Error> {
+        let mixer_event_queue = self.mixer_event_queue(mixer_id)?;
+        let tap_id = Self::unique_id();
+        let buffer = Shared::new(
+            &self.collector_handle,
+            MixerSendBuffer::new(self.output_channel_count(), self.output_sample_rate()),
+        );
+        if mixer_event_queue
+            .push(MixerMessage::AddStemTap {
+                tap_id,
+                mode,
+                buffer: Shared::clone(&buffer),
+            })
+            .is_err()
+        {
+            return Err(Self::mixer_event_queue_error("add_stem_tap"));
+        }
+        Ok(StemWriter {
+            tap_id,
+            mixer_id,
+            buffer,
+            writer,
+        })
+    }
+
+    fn remove_stem_taps(&self, stem_writers: &[StemWriter]) {
+        for stem_writer in stem_writers {
+            if let Ok(mixer_event_queue) = self.mixer_event_queue(stem_writer.mixer_id) {
+                if mixer_event_queue
+                    .push(MixerMessage::RemoveStemTap {
+                        tap_id: stem_writer.tap_id,
+                    })
+                    .is_err()
+                {
+                    log::warn!(
+                        "Failed to remove stem tap from mixer {}",
+                        stem_writer.mixer_id
+                    );
+                }
+            }
+        }
+    }
+
+    fn render_stems(
+        &mut self,
+        master_writer: &mut WavFileWriter,
+        stem_writers: &mut [StemWriter],
+        frames: usize,
+    ) -> Result<(), Error> {
+        let channel_count = self.output_channel_count();
+        let render_block_frames = MixedSource::MAX_MIX_BUFFER_SAMPLES / channel_count;
+        let mut stem_buffer = vec![0.0; render_block_frames * channel_count];
+        let mut frames_left = frames;
+        while frames_left > 0 {
+            let block_frames = frames_left.min(render_block_frames);
+            let time = SourceTime {
+                pos_in_frames: self.main_mixer_source.position(),
+                pos_instant: Instant::now(),
+            };
+            master_writer.write(self.render(block_frames)?)?;
+
+            // read stems delay-compensated to the master output's latency
+            let latency = self.output_latency.load(atomic::Ordering::Relaxed);
+            let stem_buffer = &mut stem_buffer[..block_frames * channel_count];
+            for stem_writer in stem_writers.iter_mut() {
+                clear_buffer(stem_buffer);
+                let delay_frames = latency.saturating_sub(stem_writer.buffer.latency());
+                stem_writer.buffer.read(stem_buffer, &time, delay_frames);
+                stem_writer.writer.write(stem_buffer)?;
+            }
+            frames_left -= block_frames;
+        }
+        Ok(())
+    }
+}
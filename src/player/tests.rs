@@ -0,0 +1,1557 @@
+use std::time::Duration;
+
+use four_cc::FourCC;
+
+use crate::{
+    effects::{ChorusEffect, CompressorEffect, DelayEffect, FilterEffect, GateEffect},
+    parameters::{FloatParameter, FloatParameterValue},
+    sources::{SynthSourceGenerator, SynthSourceImpl},
+    utils::panning_factors,
+    AutomationLane, Effect, EffectTime, Error, InputDevice, MixerId, MixerOutput, MixerSendMode,
+    Parameter, ParameterValueUpdate, Player, PlayerConfig, RampShape, SynthPlaybackHandle,
+    SynthPlaybackOptions,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+pub(crate) const SAMPLE_RATE: u32 = 48000;
+
+/// Number of frames after which all smoothed mixer values reached their targets.
+pub(crate) const SETTLE_FRAMES: usize = 8192;
+
+/// Synth generator which endlessly plays a constant stereo signal.
+pub(crate) struct ConstantGenerator {
+    value: f32,
+}
+
+impl SynthSourceGenerator for ConstantGenerator {
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+
+    fn channel_count(&self) -> usize {
+        2
+    }
+
+    fn generate(&mut self, output: &mut [f32]) -> usize {
+        output.fill(self.value);
+        output.len()
+    }
+}
+
+/// Synth generator which endlessly plays a stereo sawtooth with the given period.
+pub(crate) struct SawGenerator {
+    period: usize,
+    position: usize,
+}
+
+impl SynthSourceGenerator for SawGenerator {
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+
+    fn channel_count(&self) -> usize {
+        2
+    }
+
+    fn generate(&mut self, output: &mut [f32]) -> usize {
+        for frame in output.chunks_exact_mut(2) {
+            let value = 2.0 * self.position as f32 / self.period as f32 - 1.0;
+            frame.fill(value);
+            self.position = (self.position + 1) % self.period;
+        }
+        output.len()
+    }
+}
+
+/// Synth generator which endlessly plays the given constant value on each channel.
+pub(crate) struct ChannelsGenerator {
+    values: Vec<f32>,
+}
+
+impl SynthSourceGenerator for ChannelsGenerator {
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+
+    fn channel_count(&self) -> usize {
+        self.values.len()
+    }
+
+    fn generate(&mut self, output: &mut [f32]) -> usize {
+        for frame in output.chunks_exact_mut(self.values.len()) {
+            frame.copy_from_slice(&self.values);
+        }
+        output.len()
+    }
+}
+
+/// Effect which delays its input by a fixed number of frames and reports it as latency.
+pub(crate) struct LatencyEffect {
+    latency: usize,
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl LatencyEffect {
+    pub fn new(latency: usize) -> Self {
+        Self {
+            latency,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl Effect for LatencyEffect {
+    fn name(&self) -> &'static str {
+        "LatencyEffect"
+    }
+
+    fn weight(&self) -> usize {
+        1
+    }
+
+    fn parameters(&self) -> Vec<&dyn Parameter> {
+        vec![]
+    }
+
+    fn initialize(
+        &mut self,
+        _sample_rate: u32,
+        channel_count: usize,
+        _max_frames: usize,
+    ) -> Result<(), Error> {
+        self.buffer = vec![0.0; self.latency * channel_count];
+        Ok(())
+    }
+
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        for sample in output.iter_mut() {
+            std::mem::swap(sample, &mut self.buffer[self.position]);
+            self.position = (self.position + 1) % self.buffer.len();
+        }
+    }
+
+    fn process_tail(&self) -> Option<usize> {
+        Some(self.latency)
+    }
+
+    fn latency(&self) -> usize {
+        self.latency
+    }
+
+    fn process_parameter_update(
+        &mut self,
+        id: FourCC,
+        _value: &ParameterValueUpdate,
+    ) -> Result<(), Error> {
+        Err(Error::ParameterError(format!("Unknown parameter: '{id}'")))
+    }
+}
+
+/// Effect which subtracts its sidechain signal from its input.
+pub(crate) struct SidechainDifferenceEffect;
+
+impl Effect for SidechainDifferenceEffect {
+    fn name(&self) -> &'static str {
+        "SidechainDifferenceEffect"
+    }
+
+    fn weight(&self) -> usize {
+        1
+    }
+
+    fn parameters(&self) -> Vec<&dyn Parameter> {
+        vec![]
+    }
+
+    fn initialize(
+        &mut self,
+        _sample_rate: u32,
+        _channel_count: usize,
+        _max_frames: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn process(&mut self, _output: &mut [f32], _time: &EffectTime) {}
+
+    fn supports_sidechain(&self) -> bool {
+        true
+    }
+
+    fn process_with_sidechain(
+        &mut self,
+        output: &mut [f32],
+        sidechain: &[f32],
+        _time: &EffectTime,
+    ) {
+        for (sample, key) in output.iter_mut().zip(sidechain) {
+            *sample -= key;
+        }
+    }
+
+    fn process_tail(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn process_parameter_update(
+        &mut self,
+        id: FourCC,
+        _value: &ParameterValueUpdate,
+    ) -> Result<(), Error> {
+        Err(Error::ParameterError(format!("Unknown parameter: '{id}'")))
+    }
+}
+
+/// Effect which replaces its input with its unsmoothed, normalized level parameter value.
+pub(crate) struct LevelEffect {
+    level: FloatParameterValue,
+}
+
+impl LevelEffect {
+    pub const LEVEL: FloatParameter =
+        FloatParameter::new(FourCC(*b"levl"), "Level", 0.0..=1.0, 1.0);
+
+    pub fn new() -> Self {
+        Self {
+            level: FloatParameterValue::from_description(Self::LEVEL),
+        }
+    }
+}
+
+impl Effect for LevelEffect {
+    fn name(&self) -> &'static str {
+        "LevelEffect"
+    }
+
+    fn weight(&self) -> usize {
+        1
+    }
+
+    fn parameters(&self) -> Vec<&dyn Parameter> {
+        vec![self.level.description()]
+    }
+
+    fn initialize(
+        &mut self,
+        _sample_rate: u32,
+        _channel_count: usize,
+        _max_frames: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        output.fill(self.level.value());
+    }
+
+    fn process_tail(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn process_parameter_update(
+        &mut self,
+        id: FourCC,
+        value: &ParameterValueUpdate,
+    ) -> Result<(), Error> {
+        if id == Self::LEVEL.id() {
+            self.level.apply_update(value);
+            Ok(())
+        } else {
+            Err(Error::ParameterError(format!("Unknown parameter: '{id}'")))
+        }
+    }
+}
+
+/// Create a new offline stereo player, which processes mixers sequentially, so renderings
+/// are bit-exact.
+pub(crate) fn new_player() -> Player {
+    new_player_with_channels(2)
+}
+
+/// Create a new offline player with the given output channel count.
+pub(crate) fn new_player_with_channels(channel_count: usize) -> Player {
+    let config = PlayerConfig::default()
+        .concurrent_processing(false)
+        .measuring_interval(None);
+    Player::new_offline_with_config(SAMPLE_RATE, channel_count, None, config)
+}
+
+/// Play a constant signal on the given mixer.
+pub(crate) fn play_constant(
+    player: &mut Player,
+    value: f32,
+    mixer_id: Option<MixerId>,
+) -> SynthPlaybackHandle {
+    let mut options = SynthPlaybackOptions::default().playback_pos_emit_disabled();
+    options.target_mixer = mixer_id;
+    let source = SynthSourceImpl::new(
+        "Constant",
+        ConstantGenerator { value },
+        options,
+        SAMPLE_RATE,
+    )
+    .expect("Failed to create synth source");
+    player
+        .play_synth_source(source, None)
+        .expect("Failed to play synth source")
+}
+
+/// Render the given number of frames and return a copy of the rendered buffer.
+pub(crate) fn render(player: &mut Player, frames: usize) -> Vec<f32> {
+    player.render(frames).expect("Failed to render").to_vec()
+}
+
+/// Assert that the given samples, which follow the given previous sample, contain no steps.
+pub(crate) fn assert_declicked(previous_sample: f32, samples: &[f32]) {
+    let mut previous_sample = previous_sample;
+    for (index, sample) in samples.iter().enumerate() {
+        assert!(
+            (sample - previous_sample).abs() < 0.01,
+            "Sample {index} steps from {previous_sample} to {sample}"
+        );
+        previous_sample = *sample;
+    }
+}
+
+/// Assert that all given samples have the expected value.
+pub(crate) fn assert_samples(samples: &[f32], expected: f32) {
+    for (index, sample) in samples.iter().enumerate() {
+        assert!(
+            (sample - expected).abs() < 1e-5,
+            "Sample {index} is {sample}, expected {expected}"
+        );
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn send_levels() {
+    let mut player = new_player();
+    let return_mixer = player.add_mixer(None).unwrap();
+    let send_mixer = player.add_mixer(None).unwrap();
+    play_constant(&mut player, 0.25, Some(send_mixer.id()));
+    player
+        .add_send(
+            send_mixer.id(),
+            return_mixer.id(),
+            0.5,
+            MixerSendMode::PostFader,
+        )
+        .unwrap();
+
+    // return mixer adds the send in the same processing cycle
+    assert_samples(&render(&mut player, 256), 0.25 + 0.125);
+
+    // send levels are smoothed, automatable
+    send_mixer
+        .set_send_level(return_mixer.id(), 1.0, None)
+        .unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25 + 0.25);
+
+    // removed sends no longer feed the return mixer
+    player
+        .remove_send(send_mixer.id(), return_mixer.id())
+        .unwrap();
+    assert_samples(&render(&mut player, 256), 0.25);
+}
+
+#[test]
+fn send_taps() {
+    let mut player = new_player();
+    let pre_return_mixer = player.add_mixer(None).unwrap();
+    let post_return_mixer = player.add_mixer(None).unwrap();
+    let send_mixer = player.add_mixer(None).unwrap();
+    play_constant(&mut player, 0.25, Some(send_mixer.id()));
+    player
+        .add_send(
+            send_mixer.id(),
+            pre_return_mixer.id(),
+            1.0,
+            MixerSendMode::PreFader,
+        )
+        .unwrap();
+    player
+        .add_send(
+            send_mixer.id(),
+            post_return_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader,
+        )
+        .unwrap();
+    assert_samples(&render(&mut player, 256), 3.0 * 0.25);
+
+    // pre-fader sends ignore the fader
+    send_mixer.set_volume(0.0, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25);
+
+    // mute silences pre-fader sends too
+    send_mixer.set_muted(true, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.0);
+}
+
+#[test]
+fn send_stages() {
+    let mut player = new_player();
+    // chain of return mixers, which were added in reverse processing order
+    let last_mixer = player.add_mixer(None).unwrap();
+    let middle_mixer = player.add_mixer(None).unwrap();
+    let first_mixer = player.add_mixer(None).unwrap();
+    play_constant(&mut player, 0.25, Some(first_mixer.id()));
+    player
+        .add_send(
+            middle_mixer.id(),
+            last_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader,
+        )
+        .unwrap();
+    player
+        .add_send(
+            first_mixer.id(),
+            middle_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader,
+        )
+        .unwrap();
+
+    // all sends arrive in the same processing cycle, without a block delay
+    assert_samples(&render(&mut player, 256), 3.0 * 0.25);
+
+    // feedback loops get rejected
+    assert!(player
+        .add_send(
+            last_mixer.id(),
+            first_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader
+        )
+        .is_err());
+    assert!(player
+        .add_send(
+            first_mixer.id(),
+            first_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader
+        )
+        .is_err());
+    assert_samples(&render(&mut player, 256), 3.0 * 0.25);
+}
+
+#[test]
+fn gate_sidechain() {
+    let mut player = new_player();
+    let key_mixer = player.add_mixer(None).unwrap();
+    let music_mixer = player.add_mixer(None).unwrap();
+    play_constant(&mut player, 0.5, Some(music_mixer.id()));
+    let gate = player
+        .add_effect(
+            GateEffect::with_parameters(-30.0, 0.001, 0.0, 0.01, -60.0),
+            music_mixer.id(),
+        )
+        .unwrap();
+    player
+        .set_effect_sidechain(gate.id(), key_mixer.id())
+        .unwrap();
+
+    // the loud music signal does not open the gate, as long as the key is silent
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.0);
+
+    // a key above the threshold opens the gate (key mixer gets mixed into the main mixer too)
+    play_constant(&mut player, 0.25, Some(key_mixer.id()));
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5 + 0.25);
+}
+
+#[test]
+fn compressor_sidechain() {
+    let mut player = new_player();
+    let key_mixer = player.add_mixer(None).unwrap();
+    let music_mixer = player.add_mixer(None).unwrap();
+    play_constant(&mut player, 0.5, Some(music_mixer.id()));
+    let compressor = player
+        .add_effect(
+            CompressorEffect::with_compressor_parameters(-12.0, 8.0, 0.0, 0.001, 0.1, 0.0, 0.001),
+            music_mixer.id(),
+        )
+        .unwrap();
+    player
+        .set_effect_sidechain(compressor.id(), key_mixer.id())
+        .unwrap();
+
+    // the music is above the threshold, but the silent key does not trigger compression
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5);
+
+    // a 0 dB key ducks the music by 12 dB * (1 - 1/8)
+    play_constant(&mut player, 1.0, Some(key_mixer.id()));
+    render(&mut player, SETTLE_FRAMES);
+    let ducked = 0.5 * 10.0_f32.powf(-12.0 * (1.0 - 1.0 / 8.0) / 20.0);
+    let output = render(&mut player, 256);
+    for sample in output {
+        assert!(
+            (sample - (1.0 + ducked)).abs() < 1e-3,
+            "Sample is {sample}, expected {}",
+            1.0 + ducked
+        );
+    }
+}
+
+#[test]
+fn sidechain_latency_compensation() {
+    const LATENCY: usize = 100;
+
+    let mut player = new_player();
+    let key_mixer = player.add_mixer(None).unwrap();
+    let music_mixer = player.add_mixer(None).unwrap();
+    let latent_mixer = player.add_mixer(music_mixer.id()).unwrap();
+    player
+        .add_effect(LatencyEffect::new(LATENCY), latent_mixer.id())
+        .unwrap();
+    let difference = player
+        .add_effect(SidechainDifferenceEffect, music_mixer.id())
+        .unwrap();
+    player
+        .set_effect_sidechain(difference.id(), key_mixer.id())
+        .unwrap();
+
+    // start the latent music path and the key at the same time
+    play_constant(&mut player, 0.5, Some(latent_mixer.id()));
+    play_constant(&mut player, 0.5, Some(key_mixer.id()));
+
+    // the key gets delayed to the music path, so the difference effect cancels both, and the key
+    // mixer's main output gets delayed to the music mixer's output
+    let output = render(&mut player, 1024);
+    assert_samples(&output[..LATENCY * 2], 0.0);
+    assert_samples(&output[LATENCY * 2..], 0.5);
+}
+
+#[test]
+fn offline_rendering_is_deterministic() {
+    fn render_graph(block_size: usize) -> Vec<f32> {
+        let mut player = new_player();
+        let fx_mixer = player.add_mixer(None).unwrap();
+        player
+            .add_effect(ChorusEffect::new(), fx_mixer.id())
+            .unwrap();
+        player
+            .add_effect(DelayEffect::new(), fx_mixer.id())
+            .unwrap();
+        player
+            .add_effect(FilterEffect::new(), fx_mixer.id())
+            .unwrap();
+        for (period, start_time) in [(100, 0), (151, 1000), (217, 3333)] {
+            let options = SynthPlaybackOptions::default()
+                .playback_pos_emit_disabled()
+                .target_mixer(fx_mixer.id())
+                .start_at_time(start_time);
+            let source = SynthSourceImpl::new(
+                "Saw",
+                SawGenerator {
+                    period,
+                    position: 0,
+                },
+                options,
+                SAMPLE_RATE,
+            )
+            .unwrap();
+            let handle = player.play_synth_source(source, None).unwrap();
+            handle.stop(start_time + 5000).unwrap();
+        }
+        let mut output = Vec::new();
+        for _ in 0..16384 / block_size {
+            output.extend_from_slice(&render(&mut player, block_size));
+        }
+        output
+    }
+
+    // renderings with the same render calls are bit-exact
+    let output = render_graph(512);
+    assert!(output.iter().any(|sample| sample.abs() > 0.01));
+    assert_eq!(output, render_graph(512));
+    let output = render_graph(64);
+    assert_eq!(output, render_graph(64));
+}
+
+#[test]
+#[cfg(feature = "wav-output")]
+fn render_stems() {
+    use crate::{
+        outputs::{DitherMode, WavSampleFormat},
+        RenderStem, StemMode,
+    };
+
+    fn read_wav(file_path: &std::path::Path, channel_count: u16) -> Vec<f32> {
+        let reader = hound::WavReader::open(file_path).unwrap();
+        assert_eq!(reader.spec().channels, channel_count);
+        reader.into_samples::<f32>().map(Result::unwrap).collect()
+    }
+
+    const LATENCY: usize = 100;
+    const FRAMES: usize = 20000;
+
+    // render a 4 channel master with a stereo main mixer and a delayed sub-mixer
+    let mut player = new_player_with_channels(4);
+    let stem_mixer = player.add_mixer(None).unwrap();
+    player
+        .add_effect(LatencyEffect::new(LATENCY), stem_mixer.id())
+        .unwrap();
+    let mut options = SynthPlaybackOptions::default().playback_pos_emit_disabled();
+    options.target_mixer = Some(stem_mixer.id());
+    let generator = SawGenerator {
+        period: 64,
+        position: 0,
+    };
+    let source = SynthSourceImpl::new("Saw", generator, options, SAMPLE_RATE).unwrap();
+    player.play_synth_source(source, None).unwrap();
+    play_constant(&mut player, 0.25, None);
+    render(&mut player, SETTLE_FRAMES);
+
+    let temp_dir = std::env::temp_dir().join(format!("phonic-stems-{}", std::process::id()));
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let master_path = temp_dir.join("master.wav");
+    let stems = [
+        RenderStem::new(Player::MAIN_MIXER_ID, temp_dir.join("main.wav")),
+        RenderStem::new(stem_mixer.id(), temp_dir.join("post.wav")),
+        RenderStem::new(stem_mixer.id(), temp_dir.join("pre.wav")).mode(StemMode::PreEffects),
+    ];
+    player
+        .render_stems_to_files(
+            &master_path,
+            &stems,
+            FRAMES,
+            WavSampleFormat::Float32,
+            DitherMode::None,
+        )
+        .unwrap();
+
+    let master = read_wav(&master_path, 4);
+    let main = read_wav(&stems[0].file_path, 2);
+    let post = read_wav(&stems[1].file_path, 2);
+    let pre = read_wav(&stems[2].file_path, 2);
+    std::fs::remove_dir_all(&temp_dir).unwrap();
+    assert_eq!(master.len(), FRAMES * 4);
+    assert!(post.iter().any(|sample| sample.abs() > 0.5));
+
+    // stems are sample-aligned with the master. Pre-effect stems have no history to
+    // compensate the delay with in the first frames.
+    for (frame, master_frame) in master.chunks_exact(4).enumerate() {
+        let main_frame = &main[frame * 2..frame * 2 + 2];
+        let post_frame = &post[frame * 2..frame * 2 + 2];
+        let pre_frame = &pre[frame * 2..frame * 2 + 2];
+        assert_eq!(main_frame, &master_frame[..2], "Main stem frame {frame}");
+        assert_samples(&master_frame[2..], 0.0);
+        for (post, master) in post_frame.iter().zip(&master_frame[..2]) {
+            assert!(
+                (post + 0.25 - master).abs() < 1e-5,
+                "Post-effect stem frame {frame} is {post}, master {master}"
+            );
+        }
+        if frame >= LATENCY {
+            assert_eq!(pre_frame, post_frame, "Pre-effect stem frame {frame}");
+        } else {
+            assert_samples(pre_frame, 0.0);
+        }
+    }
+}
+
+#[test]
+fn mixer_volume_and_panning() {
+    let mut player = new_player();
+    let mixer = player.add_mixer(None).unwrap();
+    play_constant(&mut player, 0.5, Some(mixer.id()));
+    assert_samples(&render(&mut player, 256), 0.5);
+
+    mixer.set_volume(0.5, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25);
+
+    mixer.set_panning(-0.5, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    let (pan_l, pan_r) = panning_factors(-0.5);
+    let output = render(&mut player, 256);
+    for frame in output.chunks_exact(2) {
+        assert_samples(&frame[..1], 0.25 * pan_l);
+        assert_samples(&frame[1..], 0.25 * pan_r);
+    }
+
+    // scheduled changes apply at the given sample time
+    let position = player.output_sample_frame_position();
+    mixer
+        .set_volume(1.0, position + 4 * SETTLE_FRAMES as u64)
+        .unwrap();
+    mixer
+        .set_panning(0.0, position + 4 * SETTLE_FRAMES as u64)
+        .unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    let output = render(&mut player, 256);
+    for frame in output.chunks_exact(2) {
+        assert_samples(&frame[..1], 0.25 * pan_l);
+        assert_samples(&frame[1..], 0.25 * pan_r);
+    }
+    render(&mut player, 4 * SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5);
+
+    // invalid values get rejected
+    assert!(mixer.set_volume(-1.0, None).is_err());
+    assert!(mixer.set_panning(2.0, None).is_err());
+}
+
+#[test]
+fn mixer_mute() {
+    let mut player = new_player();
+    let parent_mixer = player.add_mixer(None).unwrap();
+    let child_mixer = player.add_mixer(parent_mixer.id()).unwrap();
+    play_constant(&mut player, 0.25, Some(parent_mixer.id()));
+    play_constant(&mut player, 0.5, Some(child_mixer.id()));
+    assert_samples(&render(&mut player, 256), 0.75);
+
+    child_mixer.set_muted(true, None).unwrap();
+    assert!(child_mixer.is_muted());
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25);
+
+    // muting a parent mutes its sub-mixers too
+    parent_mixer.set_muted(true, None).unwrap();
+    child_mixer.set_muted(false, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.0);
+
+    parent_mixer.set_muted(false, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.75);
+
+    // immediate mute changes cancel mute changes which are scheduled later on
+    let mute_time = player.output_sample_frame_position() + 1024;
+    parent_mixer.set_muted(true, mute_time).unwrap();
+    parent_mixer.set_muted(false, None).unwrap();
+    assert!(!parent_mixer.is_muted());
+    render(&mut player, 1024 + SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.75);
+}
+
+#[test]
+fn mixer_solo() {
+    let mut player = new_player();
+    let return_mixer = player.add_mixer(None).unwrap();
+    let other_mixer = player.add_mixer(None).unwrap();
+    let parent_mixer = player.add_mixer(None).unwrap();
+    let child_mixer = player.add_mixer(parent_mixer.id()).unwrap();
+    play_constant(&mut player, 0.5, None);
+    play_constant(&mut player, 0.25, Some(other_mixer.id()));
+    play_constant(&mut player, 0.125, Some(parent_mixer.id()));
+    play_constant(&mut player, 0.0625, Some(child_mixer.id()));
+    player
+        .add_send(
+            child_mixer.id(),
+            return_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader,
+        )
+        .unwrap();
+    assert_samples(&render(&mut player, 256), 0.5 + 0.25 + 0.125 + 2.0 * 0.0625);
+
+    // soloing a sub-mixer mutes all other mixers, except the solo mixer's return mixer.
+    // the parent passes through the soloed mixer, but mutes its own sources.
+    child_mixer.set_solo(true, None).unwrap();
+    assert!(child_mixer.is_soloed());
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 2.0 * 0.0625);
+
+    // soloing a parent makes its sub-mixers audible
+    child_mixer.set_solo(false, None).unwrap();
+    parent_mixer.set_solo(true, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.125 + 2.0 * 0.0625);
+
+    // multiple mixers can be soloed at once
+    other_mixer.set_solo(true, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25 + 0.125 + 2.0 * 0.0625);
+
+    // muted soloed mixers stay muted
+    other_mixer.set_muted(true, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.125 + 2.0 * 0.0625);
+
+    other_mixer.set_muted(false, None).unwrap();
+    other_mixer.set_solo(false, None).unwrap();
+    parent_mixer.set_solo(false, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5 + 0.25 + 0.125 + 2.0 * 0.0625);
+}
+
+#[test]
+fn mixer_mute_from_multiple_threads() {
+    let mut player = new_player();
+    let mixers = (0..8)
+        .map(|_| player.add_mixer(None).unwrap())
+        .collect::<Vec<_>>();
+    for mixer in &mixers {
+        play_constant(&mut player, 0.125, Some(mixer.id()));
+    }
+
+    // toggle mute and solo states concurrently: the last resolved states must win
+    std::thread::scope(|scope| {
+        for (index, mixer) in mixers.iter().enumerate() {
+            scope.spawn(move || {
+                for _ in 0..50 {
+                    mixer.set_solo(true, None).unwrap();
+                    mixer.set_muted(true, None).unwrap();
+                    mixer.set_solo(false, None).unwrap();
+                    mixer.set_muted(index % 2 == 0, None).unwrap();
+                }
+            });
+        }
+    });
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 4.0 * 0.125);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_round_trip() {
+    use crate::{
+        effects::{FilterEffectType, GainEffect, GainEffectDcFilterMode},
+        generators::EmptyGenerator,
+        EffectSnapshot, Generator, GeneratorSnapshot, PlayerSnapshot,
+    };
+
+    let mut player = new_player();
+    let bus_mixer = player.add_mixer(None).unwrap();
+    let child_mixer = player.add_mixer(bus_mixer.id()).unwrap();
+    let return_mixer = player.add_mixer(None).unwrap();
+    let key_mixer = player.add_mixer(None).unwrap();
+    bus_mixer.set_volume(0.5, None).unwrap();
+    bus_mixer.set_panning(-0.25, None).unwrap();
+    key_mixer.set_muted(true, None).unwrap();
+    player
+        .add_send(
+            child_mixer.id(),
+            return_mixer.id(),
+            0.5,
+            MixerSendMode::PreFader,
+        )
+        .unwrap();
+    let gain = player
+        .add_effect(GainEffect::new(), bus_mixer.id())
+        .unwrap();
+    gain.set_parameters(
+        vec![
+            GainEffect::GAIN.value_update(0.5),
+            GainEffect::DC_FILTER.value_update(GainEffectDcFilterMode::Fast),
+        ],
+        None,
+    )
+    .unwrap();
+    let filter = player
+        .add_effect(FilterEffect::new(), return_mixer.id())
+        .unwrap();
+    filter
+        .set_parameter(
+            FilterEffect::TYPE.value_update(FilterEffectType::Bandstop),
+            None,
+        )
+        .unwrap();
+    let compressor = player
+        .add_effect(CompressorEffect::new_compressor(), child_mixer.id())
+        .unwrap();
+    player
+        .set_effect_sidechain(compressor.id(), key_mixer.id())
+        .unwrap();
+    player
+        .add_generator(EmptyGenerator::new(2, SAMPLE_RATE), child_mixer.id())
+        .unwrap();
+
+    // snapshots track parameter values, including enum values
+    let snapshot = player.snapshot();
+    let filter_snapshot = snapshot
+        .mixers
+        .iter()
+        .flat_map(|mixer| &mixer.effects)
+        .find(|effect| effect.id == filter.id())
+        .unwrap();
+    let filter_type = filter_snapshot
+        .parameters
+        .iter()
+        .find(|parameter| parameter.id == FilterEffect::TYPE.id())
+        .unwrap();
+    assert_eq!(filter_type.value, 2.0 / 3.0);
+
+    // snapshots survive a JSON round-trip
+    let json = snapshot.to_json().unwrap();
+    let json_snapshot = PlayerSnapshot::from_json(&json).unwrap();
+    assert_eq!(json_snapshot, snapshot);
+
+    // restoring a snapshot recreates the same graph with new ids
+    let mut restored_player = new_player();
+    let restored = restored_player
+        .restore_snapshot(
+            &json_snapshot,
+            |effect: &EffectSnapshot| match effect.name.as_str() {
+                GainEffect::EFFECT_NAME => Ok(GainEffect::new().into_box()),
+                FilterEffect::EFFECT_NAME => Ok(FilterEffect::new().into_box()),
+                CompressorEffect::EFFECT_NAME => Ok(CompressorEffect::new_compressor().into_box()),
+                name => Err(Error::ParameterError(format!("Unknown effect: {name}"))),
+            },
+            |_generator: &GeneratorSnapshot| {
+                Ok(EmptyGenerator::new(2, SAMPLE_RATE).into_box() as Box<dyn Generator>)
+            },
+        )
+        .unwrap();
+    let mut expected_snapshot = snapshot.clone();
+    for mixer in &mut expected_snapshot.mixers {
+        mixer.id = restored.mixers[&mixer.id].id();
+        mixer.parent_id = mixer
+            .parent_id
+            .map(|parent_id| restored.mixers[&parent_id].id());
+        for send in &mut mixer.sends {
+            send.return_mixer_id = restored.mixers[&send.return_mixer_id].id();
+        }
+        for effect in &mut mixer.effects {
+            effect.id = restored.effects[&effect.id].id();
+            effect.sidechain_mixer_id = effect
+                .sidechain_mixer_id
+                .map(|mixer_id| restored.mixers[&mixer_id].id());
+        }
+    }
+    for generator in &mut expected_snapshot.generators {
+        generator.playback_id = restored.generators[&generator.playback_id].id();
+        generator.mixer_id = restored.mixers[&generator.mixer_id].id();
+    }
+    assert_eq!(restored_player.snapshot(), expected_snapshot);
+
+    // both graphs sound the same
+    for (mixer_id, value) in [
+        (bus_mixer.id(), 0.5),
+        (child_mixer.id(), 0.25),
+        (return_mixer.id(), 0.125),
+        (key_mixer.id(), 1.0),
+    ] {
+        play_constant(&mut player, value, Some(mixer_id));
+        play_constant(
+            &mut restored_player,
+            value,
+            Some(restored.mixers[&mixer_id].id()),
+        );
+    }
+    let output = render(&mut player, SETTLE_FRAMES);
+    assert!(output.iter().any(|sample| sample.abs() > 0.01));
+    // restored parameters are denormalized from the snapshot, so allow rounding errors
+    let restored_output = render(&mut restored_player, SETTLE_FRAMES);
+    for (index, (sample, restored_sample)) in output.iter().zip(restored_output).enumerate() {
+        assert!(
+            (sample - restored_sample).abs() < 1e-5,
+            "Sample {index} is {restored_sample}, expected {sample}"
+        );
+    }
+}
+
+#[test]
+fn move_source() {
+    let mut player = new_player();
+    let source_mixer = player.add_mixer(None).unwrap();
+    let target_mixer = player.add_mixer(None).unwrap();
+    source_mixer.set_volume(0.5, None).unwrap();
+    target_mixer.set_volume(0.25, None).unwrap();
+    let source = play_constant(&mut player, 1.0, Some(source_mixer.id()));
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5);
+
+    // scheduled source events move along with the source
+    let volume_time = player.output_sample_frame_position() + 2 * SETTLE_FRAMES as u64;
+    source.set_volume(0.5, volume_time).unwrap();
+    player.move_source(source.id(), target_mixer.id()).unwrap();
+
+    // the source gets declicked while moving
+    let output = render(&mut player, 1024);
+    assert_declicked(0.5, &output);
+    render(&mut player, SETTLE_FRAMES - 1024);
+    assert_samples(&render(&mut player, 256), 0.25);
+    assert!(source.is_playing());
+
+    render(&mut player, 2 * SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25 * 0.5);
+
+    // moving to the same mixer is a no-op, moving to missing mixers fails
+    player.move_source(source.id(), target_mixer.id()).unwrap();
+    assert_samples(&render(&mut player, 256), 0.25 * 0.5);
+    assert!(player.move_source(source.id(), 12345).is_err());
+}
+
+#[test]
+fn move_generator() {
+    use crate::generators::EmptyGenerator;
+
+    let mut player = new_player();
+    let source_mixer = player.add_mixer(None).unwrap();
+    let target_mixer = player.add_mixer(None).unwrap();
+    let generator = player
+        .add_generator(EmptyGenerator::new(2, SAMPLE_RATE), source_mixer.id())
+        .unwrap();
+    let source = play_constant(&mut player, 1.0, Some(source_mixer.id()));
+
+    player
+        .move_generator(generator.id(), target_mixer.id())
+        .unwrap();
+    render(&mut player, 1024);
+    assert!(generator.is_playing());
+
+    // moved generators get removed from their new mixer
+    let snapshot = player.snapshot();
+    let generator_snapshot = &snapshot.generators[0];
+    assert_eq!(generator_snapshot.mixer_id, target_mixer.id());
+    player.remove_generator(generator.id()).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert!(!generator.is_playing());
+    assert!(player
+        .move_generator(generator.id(), source_mixer.id())
+        .is_err());
+
+    // only generators can be moved via move_generator
+    assert!(player
+        .move_generator(source.id(), target_mixer.id())
+        .is_err());
+}
+
+#[test]
+fn move_mixer() {
+    let mut player = new_player();
+    let old_parent_mixer = player.add_mixer(None).unwrap();
+    let new_parent_mixer = player.add_mixer(None).unwrap();
+    let mixer = player.add_mixer(old_parent_mixer.id()).unwrap();
+    let sub_mixer = player.add_mixer(mixer.id()).unwrap();
+    old_parent_mixer.set_volume(0.5, None).unwrap();
+    new_parent_mixer.set_volume(0.25, None).unwrap();
+    play_constant(&mut player, 1.0, Some(sub_mixer.id()));
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5);
+
+    // the mixer moves with its sub-mixers and sources, declicked
+    player
+        .move_mixer(mixer.id(), new_parent_mixer.id())
+        .unwrap();
+    let output = render(&mut player, 1024);
+    assert_declicked(0.5, &output);
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25);
+
+    // moving it back to its old parent works too
+    player
+        .move_mixer(mixer.id(), old_parent_mixer.id())
+        .unwrap();
+    assert_declicked(0.25, &render(&mut player, 1024));
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.5);
+
+    // and moving it to the main mixer
+    player.move_mixer(mixer.id(), None).unwrap();
+    assert_declicked(0.5, &render(&mut player, 1024));
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 1.0);
+}
+
+#[test]
+fn move_mixer_routing_errors() {
+    let mut player = new_player();
+    let mixer = player.add_mixer(None).unwrap();
+    let sub_mixer = player.add_mixer(mixer.id()).unwrap();
+    let send_mixer = player.add_mixer(mixer.id()).unwrap();
+    let return_mixer = player.add_mixer(mixer.id()).unwrap();
+    play_constant(&mut player, 0.5, Some(sub_mixer.id()));
+    player
+        .add_send(
+            send_mixer.id(),
+            return_mixer.id(),
+            1.0,
+            MixerSendMode::PostFader,
+        )
+        .unwrap();
+
+    // mixers can't be moved into themselves or their own sub-mixers
+    assert!(matches!(
+        player.move_mixer(mixer.id(), mixer.id()),
+        Err(Error::MixerRoutingError(_))
+    ));
+    assert!(matches!(
+        player.move_mixer(mixer.id(), sub_mixer.id()),
+        Err(Error::MixerRoutingError(_))
+    ));
+
+    // moves must not break sends: the return mixer can't be moved below its sender
+    assert!(matches!(
+        player.move_mixer(return_mixer.id(), send_mixer.id()),
+        Err(Error::MixerRoutingError(_))
+    ));
+
+    // the main mixer can't be moved, missing mixers fail
+    assert!(player.move_mixer(0, mixer.id()).is_err());
+    assert!(player.move_mixer(12345, None).is_err());
+
+    // rejected moves leave the mixer tree untouched
+    render(&mut player, 1024);
+    assert_samples(&render(&mut player, 256), 0.5);
+    assert_eq!(
+        player.snapshot().mixers[1].parent_id,
+        Some(0),
+        "Expected the mixer to stay on the main mixer"
+    );
+}
+
+#[test]
+fn insert_effects() {
+    use crate::effects::{GainEffect, GainEffectDcFilterMode};
+
+    let mut player = new_player();
+    let source = SynthSourceImpl::new(
+        "Constant",
+        ConstantGenerator { value: 0.5 },
+        SynthPlaybackOptions::default().playback_pos_emit_disabled(),
+        SAMPLE_RATE,
+    )
+    .unwrap();
+    let gain = GainEffect::with_parameters(0.0, GainEffectDcFilterMode::Off);
+    let latency = LatencyEffect::new(100);
+    let source = player
+        .play_synth_source_with_effects(source, None, vec![gain.into_box(), latency.into_box()])
+        .unwrap();
+    assert_eq!(source.effects().len(), 2);
+    assert!(source
+        .effects()
+        .iter()
+        .all(|effect| effect.playback_id() == Some(source.id())));
+
+    // insert effects process the source in the given order
+    let output = render(&mut player, 256);
+    assert_samples(&output[..2 * 100], 0.0);
+    assert_samples(&output[2 * 100..], 0.5);
+
+    // and get automated via the source's playback handle
+    source.effects()[0]
+        .set_parameter(GainEffect::GAIN.value_update(0.5), None)
+        .unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_samples(&render(&mut player, 256), 0.25);
+}
+
+#[test]
+fn parameter_automation_breakpoints() {
+    const START: usize = 10000;
+
+    let lane = AutomationLane::new()
+        .with_point(0, 0.0, RampShape::Linear)
+        .with_point(100, 1.0, RampShape::Linear)
+        .with_point(1000, 0.25, RampShape::Linear);
+    let assert_automated = |output: &[f32]| {
+        let frames = |range: std::ops::Range<usize>| {
+            &output[2 * (START + range.start)..2 * (START + range.end)]
+        };
+        assert_samples(&output[..2 * START], 1.0);
+        // values between breakpoints change in 64 frame steps
+        assert_samples(frames(0..64), 0.0);
+        assert_samples(frames(64..100), 0.64);
+        // breakpoints land on their exact sample frames
+        assert_samples(frames(100..164), 1.0);
+        assert!(frames(990..1000).iter().all(|sample| *sample > 0.26));
+        assert_samples(frames(1000..2048), 0.25);
+    };
+
+    // mixer effects
+    let mut player = new_player();
+    play_constant(&mut player, 0.5, None);
+    let effect = player.add_effect(LevelEffect::new(), None).unwrap();
+    assert!(effect
+        .set_parameter_automation(FourCC(*b"none"), lane.clone(), START as u64)
+        .is_err());
+    effect
+        .set_parameter_automation(LevelEffect::LEVEL.id(), lane.clone(), START as u64)
+        .unwrap();
+    assert_automated(&render(&mut player, START + 2048));
+
+    // insert effects of sources
+    let mut player = new_player();
+    let source = SynthSourceImpl::new(
+        "Constant",
+        ConstantGenerator { value: 0.5 },
+        SynthPlaybackOptions::default().playback_pos_emit_disabled(),
+        SAMPLE_RATE,
+    )
+    .unwrap();
+    let source = player
+        .play_synth_source_with_effects(source, None, vec![LevelEffect::new().into_box()])
+        .unwrap();
+    source.effects()[0]
+        .set_parameter_automation(LevelEffect::LEVEL.id(), lane, START as u64)
+        .unwrap();
+    assert_automated(&render(&mut player, START + 2048));
+}
+
+#[test]
+fn insert_effect_tails() {
+    // returns the number of non-silent frames after stopping a source with the given effects
+    fn frames_after_stop(effects: Vec<Box<dyn Effect>>) -> usize {
+        let mut player = new_player();
+        let mut options = SynthPlaybackOptions::default().playback_pos_emit_disabled();
+        options.fade_out_duration = None;
+        let source = SynthSourceImpl::new(
+            "Constant",
+            ConstantGenerator { value: 1.0 },
+            options,
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        let source = player
+            .play_synth_source_with_effects(source, None, effects)
+            .unwrap();
+        render(&mut player, 2048);
+        source.stop(None).unwrap();
+        let output = render(&mut player, 8192);
+        let frames = output.iter().take_while(|sample| **sample == 1.0).count() / 2;
+        assert_samples(&output[2 * frames..], 0.0);
+        frames
+    }
+
+    // the source gets kept alive until the effect tails finished playing
+    let frames_without_effects = frames_after_stop(vec![]);
+    let frames_with_effects = frames_after_stop(vec![LatencyEffect::new(1000).into_box()]);
+    assert_eq!(frames_with_effects, frames_without_effects + 1000);
+}
+
+#[test]
+fn mixer_output_routing() {
+    let mut player = new_player_with_channels(4);
+    play_constant(&mut player, 0.25, None);
+    let mixer_count = player.snapshot().mixers.len();
+    let routed_mixer = player
+        .add_mixer_with_output(None, MixerOutput::Stereo(2))
+        .unwrap();
+    play_constant(&mut player, 0.5, Some(routed_mixer.id()));
+    render(&mut player, SETTLE_FRAMES);
+
+    // routed mixers bypass the main mix and play on their own output channels
+    let assert_frames = |output: &[f32], expected: [f32; 4]| {
+        for frame in output.chunks_exact(4) {
+            for (sample, expected) in frame.iter().zip(expected) {
+                assert!(
+                    (sample - expected).abs() < 1e-5,
+                    "{frame:?} != {expected:?}"
+                );
+            }
+        }
+    };
+    assert_frames(&render(&mut player, 256), [0.25, 0.25, 0.5, 0.5]);
+
+    // stereo mixers get mixed down on mono outputs
+    player
+        .set_mixer_output(routed_mixer.id(), MixerOutput::Mono(3))
+        .unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_frames(&render(&mut player, 256), [0.25, 0.25, 0.0, 0.5]);
+
+    // and get mixed into their parent again
+    player
+        .set_mixer_output(routed_mixer.id(), MixerOutput::Parent)
+        .unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    assert_frames(&render(&mut player, 256), [0.75, 0.75, 0.0, 0.0]);
+
+    // outputs must exist in the output device and the main mixer can't be rerouted
+    assert!(player
+        .set_mixer_output(routed_mixer.id(), MixerOutput::Stereo(3))
+        .is_err());
+    assert!(player
+        .set_mixer_output(Player::MAIN_MIXER_ID, MixerOutput::Stereo(0))
+        .is_err());
+    assert!(player
+        .add_mixer_with_output(None, MixerOutput::Mono(4))
+        .is_err());
+    assert_eq!(player.snapshot().mixers.len(), mixer_count + 1);
+}
+
+#[test]
+fn mixer_output_delay_compensation() {
+    let mut player = new_player_with_channels(4);
+    let routed_mixer = player
+        .add_mixer_with_output(None, MixerOutput::Stereo(2))
+        .unwrap();
+    player
+        .add_effect(LatencyEffect::new(100), routed_mixer.id())
+        .unwrap();
+    for mixer_id in [None, Some(routed_mixer.id())] {
+        let mut options = SynthPlaybackOptions::default().playback_pos_emit_disabled();
+        options.target_mixer = mixer_id;
+        let generator = SawGenerator {
+            period: 64,
+            position: 0,
+        };
+        let source = SynthSourceImpl::new("Saw", generator, options, SAMPLE_RATE).unwrap();
+        player.play_synth_source(source, None).unwrap();
+    }
+    render(&mut player, SETTLE_FRAMES);
+    assert_eq!(player.output_latency(), 100);
+
+    // the main mix gets delayed to the routed mixer's latency
+    let output = render(&mut player, 1024);
+    assert!(output.iter().any(|sample| sample.abs() > 0.5));
+    for frame in output.chunks_exact(4) {
+        assert!(
+            (frame[0] - frame[2]).abs() < 1e-5,
+            "Main mix {} and routed output {} are not aligned",
+            frame[0],
+            frame[2]
+        );
+    }
+}
+
+#[test]
+fn mixer_output_channel_mapping() {
+    let config = PlayerConfig::default()
+        .concurrent_processing(false)
+        .measuring_interval(None)
+        .enforce_stereo_playback(false);
+    let mut player = Player::new_offline_with_config(SAMPLE_RATE, 4, None, config);
+    let routed_mixer = player
+        .add_mixer_with_output(None, MixerOutput::Mono(3))
+        .unwrap();
+    let mut options = SynthPlaybackOptions::default().playback_pos_emit_disabled();
+    options.target_mixer = Some(routed_mixer.id());
+    let generator = ChannelsGenerator {
+        values: vec![0.1, 0.2, 0.3, 0.4],
+    };
+    let source = SynthSourceImpl::new("Channels", generator, options, SAMPLE_RATE).unwrap();
+    player.play_synth_source(source, None).unwrap();
+    render(&mut player, SETTLE_FRAMES);
+
+    // mono outputs mix down all channels of multi-channel mixers
+    let output = render(&mut player, 256);
+    for frame in output.chunks_exact(4) {
+        assert_samples(&frame[..3], 0.0);
+        assert_samples(&frame[3..], 0.25);
+    }
+
+    // stereo outputs play the mixer's first two channels
+    player
+        .set_mixer_output(routed_mixer.id(), MixerOutput::Stereo(1))
+        .unwrap();
+    render(&mut player, SETTLE_FRAMES);
+    let output = render(&mut player, 256);
+    for frame in output.chunks_exact(4) {
+        assert_samples(&frame[..1], 0.0);
+        assert_samples(&frame[1..2], 0.1);
+        assert_samples(&frame[2..3], 0.2);
+        assert_samples(&frame[3..], 0.0);
+    }
+}
+
+#[test]
+fn set_output_device() {
+    use crate::{outputs::MemoryOutput, source::routed::RoutedSourceMessage};
+
+    let config = PlayerConfig::default()
+        .concurrent_processing(false)
+        .measuring_interval(None);
+    let output = MemoryOutput::new(SAMPLE_RATE, 2);
+    let mut player = Player::new_with_config(output.clone(), None, config);
+    let source = play_constant(&mut player, 0.5, None);
+    let mut buffer = vec![0.0; 2 * SETTLE_FRAMES];
+    output.pull(&mut buffer);
+    output.pull(&mut buffer[..2 * 256]);
+    assert_samples(&buffer[..2 * 256], 0.5);
+    let position = player.output_sample_frame_position();
+    assert_eq!(position, SETTLE_FRAMES as u64 + 256);
+    assert_eq!(player.output_latency(), 0);
+
+    // switch to a device with another sample rate and channel layout
+    let resampled_output = MemoryOutput::new(44100, 4);
+    player
+        .set_output_device(Box::new(resampled_output.clone()))
+        .unwrap();
+    assert_eq!(player.output_sample_rate(), SAMPLE_RATE);
+    assert!(player.output_latency() > 0);
+
+    // the old device no longer plays the main mixer
+    output.pull(&mut buffer[..2 * 256]);
+    assert_samples(&buffer[..2 * 256], 0.0);
+
+    // the main mixer continues on the new device, resampled and mapped to its channels
+    let assert_frames = |output: &[f32], expected: [f32; 4]| {
+        for frame in output.chunks_exact(4) {
+            for (sample, expected) in frame.iter().zip(expected) {
+                assert!(
+                    (sample - expected).abs() < 1e-3,
+                    "{frame:?} != {expected:?}"
+                );
+            }
+        }
+    };
+    let mut buffer = vec![0.0; 4 * SETTLE_FRAMES];
+    resampled_output.pull(&mut buffer);
+    resampled_output.pull(&mut buffer[..4 * 256]);
+    assert_frames(&buffer[..4 * 256], [0.5, 0.5, 0.0, 0.0]);
+    assert!(player.output_sample_frame_position() > position + SETTLE_FRAMES as u64);
+
+    // handles and routings stay valid
+    source.set_volume(0.5, None).unwrap();
+    let routed_mixer = player
+        .add_mixer_with_output(None, MixerOutput::Stereo(2))
+        .unwrap();
+    play_constant(&mut player, 0.125, Some(routed_mixer.id()));
+    resampled_output.pull(&mut buffer);
+    resampled_output.pull(&mut buffer[..4 * 256]);
+    assert_frames(&buffer[..4 * 256], [0.25, 0.25, 0.125, 0.125]);
+
+    // mixer outputs which don't fit into the next device get mixed into their parents again
+    let stereo_output = MemoryOutput::new(SAMPLE_RATE, 2);
+    player
+        .set_output_device(Box::new(stereo_output.clone()))
+        .unwrap();
+    assert_eq!(
+        player.mixer_output(routed_mixer.id()).unwrap(),
+        MixerOutput::Parent
+    );
+    assert_eq!(player.output_latency(), 0);
+    let mut buffer = vec![0.0; 2 * SETTLE_FRAMES];
+    stereo_output.pull(&mut buffer);
+    stereo_output.pull(&mut buffer[..2 * 256]);
+    assert_samples(&buffer[..2 * 256], 0.375);
+
+    // failed device switches keep the current device and all mixer outputs
+    let quad_output = MemoryOutput::new(SAMPLE_RATE, 4);
+    player
+        .set_output_device(Box::new(quad_output.clone()))
+        .unwrap();
+    player
+        .set_mixer_output(routed_mixer.id(), MixerOutput::Stereo(2))
+        .unwrap();
+    let mono_mixer = player
+        .add_mixer_with_output(None, MixerOutput::Mono(3))
+        .unwrap();
+    // leave room for one of the two route changes only
+    while player
+        .output_routing_queue
+        .push(RoutedSourceMessage::RemoveRoute {
+            mixer_id: Player::MAIN_MIXER_ID,
+        })
+        .is_ok()
+    {}
+    player.output_routing_queue.pop();
+    assert!(player
+        .set_output_device(Box::new(MemoryOutput::new(SAMPLE_RATE, 2)))
+        .is_err());
+    assert_eq!(player.output_device.channel_count(), 4);
+    assert_eq!(
+        player.mixer_output(routed_mixer.id()).unwrap(),
+        MixerOutput::Stereo(2)
+    );
+    assert_eq!(
+        player.mixer_output(mono_mixer.id()).unwrap(),
+        MixerOutput::Mono(3)
+    );
+}
+
+#[test]
+#[cfg(feature = "wav-input")]
+fn play_wav_input() {
+    use std::time::Instant;
+
+    use crate::{inputs::WavInput, outputs::MemoryOutput};
+
+    // write a mono file with a short constant signal
+    const INPUT_FRAMES: usize = SAMPLE_RATE as usize / 4;
+    let file_path = std::env::temp_dir().join(format!("phonic-input-{}.wav", std::process::id()));
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&file_path, spec).unwrap();
+    for _ in 0..INPUT_FRAMES {
+        writer.write_sample(0.5_f32).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let config = PlayerConfig::default()
+        .concurrent_processing(false)
+        .measuring_interval(None);
+    let output = MemoryOutput::new(SAMPLE_RATE, 2);
+    let mut player = Player::new_with_config(output.clone(), None, config);
+    let mut input = WavInput::open(&file_path).unwrap();
+    std::fs::remove_file(&file_path).unwrap();
+    assert_eq!(input.channel_count(), 1);
+    let source = player
+        .play_input(
+            &mut input,
+            SynthPlaybackOptions::default().playback_pos_emit_disabled(),
+        )
+        .unwrap();
+
+    // pull the output in real-time, as the input device streams the file in real-time
+    let mut buffer = vec![0.0; 2 * 256];
+    let mut captured_frames = 0;
+    let start = Instant::now();
+    while source.is_playing() && start.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(5));
+        output.pull(&mut buffer);
+        // the mono input plays on both output channels
+        captured_frames += buffer
+            .chunks_exact(2)
+            .filter(|frame| frame.iter().all(|sample| (sample - 0.5).abs() < 1e-5))
+            .count();
+    }
+
+    // the captured file came through and the input finished playing at the file's end
+    assert!(
+        captured_frames > INPUT_FRAMES / 2,
+        "Captured {captured_frames} frames"
+    );
+    assert!(!source.is_playing());
+    assert!(input.sample_position() >= INPUT_FRAMES as u64);
+}
+
+#[test]
+fn effect_cpu_load() {
+    const MEASURING_INTERVAL: Duration = Duration::from_millis(10);
+
+    // no measurements without a measuring interval
+    let mut player = new_player();
+    let effect = player.add_effect(FilterEffect::new(), None).unwrap();
+    assert!(effect.cpu_load().is_none());
+    assert!(effect.cpu_load_state().is_none());
+
+    let config = PlayerConfig::default()
+        .concurrent_processing(false)
+        .measuring_interval(Some(MEASURING_INTERVAL));
+    let mut player = Player::new_offline_with_config(SAMPLE_RATE, 2, None, config);
+    let active_effect = player.add_effect(FilterEffect::new(), None).unwrap();
+    let idle_mixer = player.add_mixer(None).unwrap();
+    let idle_effect = player
+        .add_effect(FilterEffect::new(), idle_mixer.id())
+        .unwrap();
+    play_constant(&mut player, 0.5, None);
+    render(&mut player, SAMPLE_RATE as usize);
+
+    // publish measurements of two full intervals after all effect tails finished
+    for _ in 0..2 {
+        std::thread::sleep(MEASURING_INTERVAL);
+        render(&mut player, 512);
+    }
+
+    // active effects report their load, bypassed effects are idle
+    let active_load = active_effect.cpu_load().unwrap();
+    assert!(active_load.average > 0.0, "{active_load:?}");
+    assert!(active_load.peak >= active_load.average, "{active_load:?}");
+    let idle_load = idle_effect.cpu_load().unwrap();
+    assert_eq!(idle_load.average, 0.0, "{idle_load:?}");
+    assert_eq!(idle_load.peak, 0.0, "{idle_load:?}");
+}
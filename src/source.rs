@@ -13,12 +13,15 @@ pub mod converted;
 pub mod empty;
 pub mod file;
 pub mod guarded;
+pub mod input;
 pub mod mapped;
 pub mod measured;
 pub mod metered;
 pub mod mixed;
 pub mod panned;
 pub mod resampled;
+pub(crate) mod routed;
+pub(crate) mod shared;
 pub mod synth;
 
 // -------------------------------------------------------------------------------------------------
@@ -0,0 +1,354 @@
+//! Live audio input source.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::SyncSender,
+        Arc,
+    },
+    time::Duration,
+};
+
+use crossbeam_queue::ArrayQueue;
+use rb::{Consumer, RbConsumer, RbInspector, SpscRb, RB};
+
+use super::synth::{
+    common::{SynthSourceGenerator, SynthSourceImpl},
+    SynthPlaybackMessage, SynthPlaybackOptions, SynthSource,
+};
+use crate::{
+    input::{InputDevice, InputStreamWriter},
+    source::{Source, SourceTime},
+    utils::{buffer::clear_buffer, time::SampleTimeClock},
+    Error, PlaybackId, PlaybackStatusContext, PlaybackStatusEvent, Player, SynthPlaybackHandle,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Default ring buffer safety margin of input sources.
+const DEFAULT_LATENCY: Duration = Duration::from_millis(10);
+/// Additional ring buffer space, which absorbs bursts from input devices.
+const BUFFER_HEADROOM: Duration = Duration::from_millis(500);
+/// Time window in which the ring buffer's fill level gets watched to detect clock drifts.
+const DRIFT_WINDOW: Duration = Duration::from_millis(500);
+
+// -------------------------------------------------------------------------------------------------
+
+/// A synth generator which reads audio captured by an [`InputDevice`] from a ring buffer.
+///
+/// Input and output devices run on different clocks, so the ring buffer's fill level slowly
+/// drifts away. The reader watches the lowest fill level in a time window and keeps it at the
+/// configured latency: it drops or repeats single sample frames to compensate small drifts and
+/// skips frames when the input ran far ahead. When the buffer runs empty, it outputs silence
+/// and buffers up the configured latency again.
+struct InputStreamReader {
+    buffer: Arc<SpscRb<f32>>,
+    consumer: Consumer<f32>,
+    channel_count: usize,
+    closed: Arc<AtomicBool>,
+    latency_samples: usize,
+    buffering: bool,
+    drift_window_samples: usize,
+    drift_window_pos: usize,
+    drift_window_min_fill: usize,
+    slip_frames: isize,
+    is_exhausted: bool,
+}
+
+impl InputStreamReader {
+    fn new(channel_count: usize, sample_rate: u32, latency: Duration) -> (Self, InputStreamWriter) {
+        assert!(channel_count > 0, "Invalid channel count");
+        let duration_to_samples = |duration: Duration| {
+            SampleTimeClock::duration_to_sample_time(duration, sample_rate) as usize * channel_count
+        };
+        let latency_samples = duration_to_samples(latency).max(channel_count);
+        let buffer = Arc::new(SpscRb::new(
+            latency_samples + duration_to_samples(BUFFER_HEADROOM),
+        ));
+        let consumer = buffer.consumer();
+        let closed = Arc::new(AtomicBool::new(false));
+        let writer =
+            InputStreamWriter::new(buffer.clone(), channel_count, sample_rate, closed.clone());
+        let reader = Self {
+            buffer,
+            consumer,
+            channel_count,
+            closed,
+            latency_samples,
+            buffering: true,
+            drift_window_samples: duration_to_samples(DRIFT_WINDOW),
+            drift_window_pos: 0,
+            drift_window_min_fill: usize::MAX,
+            slip_frames: 0,
+            is_exhausted: false,
+        };
+        (reader, writer)
+    }
+
+    fn reset_drift_window(&mut self) {
+        self.drift_window_pos = 0;
+        self.drift_window_min_fill = usize::MAX;
+        self.slip_frames = 0;
+    }
+
+    fn update_drift_window(&mut self, samples: usize) {
+        self.drift_window_min_fill = self.drift_window_min_fill.min(self.buffer.count());
+        self.drift_window_pos += samples;
+        if self.drift_window_pos < self.drift_window_samples {
+            return;
+        }
+        let channel_count = self.channel_count;
+        let latency_frames = (self.latency_samples / channel_count) as isize;
+        let deviation = (self.drift_window_min_fill / channel_count) as isize - latency_frames;
+        self.reset_drift_window();
+        let tolerance = (latency_frames / 4).max(2);
+        let max_slip_frames = (self.drift_window_samples / channel_count / 1000) as isize;
+        if deviation > max_slip_frames {
+            // input ran far ahead (e.g. after output stalls): skip to the latency at once
+            let _ = self.consumer.skip(deviation as usize * channel_count);
+        } else if deviation.abs() > tolerance {
+            // compensate clock drifts gradually
+            self.slip_frames = deviation;
+        }
+    }
+}
+
+impl SynthSourceGenerator for InputStreamReader {
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.is_exhausted
+    }
+
+    fn generate(&mut self, output: &mut [f32]) -> usize {
+        if self.is_exhausted {
+            return 0;
+        }
+        let channel_count = self.channel_count;
+        let closed = self.closed.load(Ordering::Relaxed);
+
+        // buffer up latency before (re)starting to read
+        if self.buffering {
+            if closed || self.buffer.count() >= self.latency_samples + output.len() {
+                self.buffering = false;
+                self.reset_drift_window();
+            } else {
+                clear_buffer(output);
+                return output.len();
+            }
+        }
+
+        // drop or repeat a single frame to compensate clock drifts
+        let mut read_len = output.len();
+        if self.slip_frames > 0 {
+            if self.consumer.skip(channel_count).is_ok() {
+                self.slip_frames -= 1;
+            }
+        } else if self.slip_frames < 0 && output.len() >= 2 * channel_count {
+            read_len -= channel_count;
+            self.slip_frames += 1;
+        }
+
+        let read = self.consumer.read(&mut output[..read_len]).unwrap_or(0);
+        if read < read_len {
+            clear_buffer(&mut output[read..]);
+            if closed {
+                // writer is gone and all pending samples are read
+                self.is_exhausted = true;
+                return read;
+            }
+            // underrun: input clock is too slow or the device stalled
+            self.buffering = true;
+            return output.len();
+        }
+        if read_len < output.len() {
+            output.copy_within(read_len - channel_count..read_len, read_len);
+        }
+
+        self.update_drift_window(output.len());
+        output.len()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A [`SynthSource`] which streams audio captured by an [`InputDevice`], e.g. a microphone or
+/// line-in, so it can be monitored and processed in the player's mixers.
+///
+/// Captured audio is passed via a ring buffer, which keeps the input and output clocks aligned
+/// with the given latency as safety margin. The source plays until it gets stopped or until the
+/// input device stops capturing into it.
+pub struct InputSource(SynthSourceImpl<InputStreamReader>);
+
+impl InputSource {
+    /// Create a new input source, which captures audio from the given device with a default
+    /// latency of 10ms. Usually created via [`Player::play_input`].
+    ///
+    /// The device starts capturing into the new source, replacing any previous one.
+    pub fn new(
+        input_device: &mut dyn InputDevice,
+        options: SynthPlaybackOptions,
+    ) -> Result<Self, Error> {
+        Self::new_with_latency(input_device, DEFAULT_LATENCY, options)
+    }
+
+    /// Create a new input source with a custom ring buffer latency. Higher latencies avoid
+    /// dropouts with jittery input devices.
+    pub fn new_with_latency(
+        input_device: &mut dyn InputDevice,
+        latency: Duration,
+        options: SynthPlaybackOptions,
+    ) -> Result<Self, Error> {
+        let channel_count = input_device.channel_count();
+        let sample_rate = input_device.sample_rate();
+        if channel_count == 0 || sample_rate == 0 {
+            return Err(Error::ParameterError(format!(
+                "Invalid input device specs: {channel_count} channels at {sample_rate} Hz"
+            )));
+        }
+        let (reader, writer) = InputStreamReader::new(channel_count, sample_rate, latency);
+        let source = SynthSourceImpl::new("Input", reader, options, sample_rate)?;
+        input_device.capture(writer);
+        Ok(Self(source))
+    }
+}
+
+impl SynthSource for InputSource {
+    fn synth_name(&self) -> String {
+        self.0.synth_name()
+    }
+
+    fn playback_id(&self) -> PlaybackId {
+        self.0.playback_id()
+    }
+
+    fn playback_options(&self) -> &SynthPlaybackOptions {
+        self.0.playback_options()
+    }
+
+    fn playback_message_queue(&self) -> Arc<ArrayQueue<SynthPlaybackMessage>> {
+        self.0.playback_message_queue()
+    }
+
+    fn playback_status_sender(&self) -> Option<SyncSender<PlaybackStatusEvent>> {
+        self.0.playback_status_sender()
+    }
+
+    fn set_playback_status_sender(&mut self, sender: Option<SyncSender<PlaybackStatusEvent>>) {
+        self.0.set_playback_status_sender(sender)
+    }
+
+    fn playback_status_context(&self) -> Option<PlaybackStatusContext> {
+        self.0.playback_status_context()
+    }
+
+    fn set_playback_status_context(&mut self, context: Option<PlaybackStatusContext>) {
+        self.0.set_playback_status_context(context)
+    }
+}
+
+impl Source for InputSource {
+    fn channel_count(&self) -> usize {
+        self.0.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.0.sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.0.is_exhausted()
+    }
+
+    fn weight(&self) -> usize {
+        self.0.weight()
+    }
+
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        self.0.write(output, time)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Player {
+    /// Play audio captured by the given input device with the given options, e.g. to monitor
+    /// a microphone through a mixer's effects. See [`SynthPlaybackOptions`] for more info about
+    /// available options.
+    ///
+    /// Captured audio gets converted to the player's output channel layout and sample rate.
+    /// The input plays until it gets stopped via the returned handle, or until the device stops
+    /// capturing.
+    pub fn play_input(
+        &mut self,
+        input_device: &mut dyn InputDevice,
+        options: SynthPlaybackOptions,
+    ) -> Result<SynthPlaybackHandle, Error> {
+        self.play_input_with_context(input_device, options, None)
+    }
+
+    /// Play audio captured by the given input device with the given options and a custom
+    /// playback status context.
+    pub fn play_input_with_context(
+        &mut self,
+        input_device: &mut dyn InputDevice,
+        options: SynthPlaybackOptions,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<SynthPlaybackHandle, Error> {
+        // create input source
+        let start_time = options.start_time;
+        let source = InputSource::new(input_device, options)?;
+        // and play it
+        self.play_synth_source_with_context(source, start_time, context)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_alignment() {
+        let sample_rate = 1000;
+        let (mut reader, mut writer) =
+            InputStreamReader::new(1, sample_rate, Duration::from_millis(10));
+        let mut output = [1.0; 5];
+
+        // buffers up latency before reading
+        writer.write(&[0.5; 12]);
+        assert_eq!(reader.generate(&mut output), 5);
+        assert_eq!(output, [0.0; 5]);
+        writer.write(&[0.5; 3]);
+        assert_eq!(reader.generate(&mut output), 5);
+        assert_eq!(output, [0.5; 5]);
+
+        // pads underruns with silence and buffers up again
+        for _ in 0..2 {
+            assert_eq!(reader.generate(&mut output), 5);
+            assert_eq!(output, [0.5; 5]);
+        }
+        assert_eq!(reader.generate(&mut output), 5);
+        assert_eq!(output, [0.0; 5]);
+        assert!(reader.buffering);
+
+        // skips to the latency when the input runs far ahead
+        writer.write(&[0.25; 500]);
+        for _ in 0..(sample_rate as usize / 2 / output.len()) {
+            writer.write(&[0.25; 5]);
+            assert_eq!(reader.generate(&mut output), 5);
+            assert_eq!(output, [0.25; 5]);
+        }
+        assert_eq!(reader.buffer.count(), 10);
+
+        // finishes after reading all samples when the writer got dropped
+        drop(writer);
+        assert_eq!(reader.generate(&mut output), 5);
+        assert_eq!(reader.generate(&mut output), 5);
+        assert_eq!(reader.generate(&mut output), 0);
+        assert!(reader.is_exhausted());
+    }
+}
@@ -5,6 +5,11 @@ use std::{
 };
 
 use super::{Source, SourceTime};
+use loudness::LoudnessMeter;
+use spectrum::SpectrumAnalyzer;
+use stereo::StereoMeter;
+use true_peak::TruePeakMeter;
+use waveform::WaveformRecorder;
 
 use crate::utils::{
     buffer::InterleavedBuffer,
@@ -13,6 +18,84 @@ use crate::utils::{
 
 // -------------------------------------------------------------------------------------------------
 
+mod loudness;
+mod spectrum;
+mod stereo;
+mod true_peak;
+mod waveform;
+
+pub use spectrum::{SharedSpectrumState, Spectrum, SpectrumConfig, SpectrumState, SpectrumWindow};
+pub use waveform::{SharedWaveformState, WaveformState, WaveformTrigger};
+
+// -------------------------------------------------------------------------------------------------
+
+/// EBU R128 loudness metrics of a source, measured as K-weighted loudness according to
+/// ITU-R BS.1770.
+///
+/// Loudness values are in LUFS and are `f32::NEG_INFINITY` until enough audio got measured or
+/// when the signal is silent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Momentary loudness of the last 400ms.
+    pub momentary: f32,
+    /// Short-term loudness of the last 3 seconds.
+    pub short_term: f32,
+    /// Gated integrated loudness since the meter got created or reset.
+    pub integrated: f32,
+    /// Loudness range (LRA) in LU since the meter got created or reset, see EBU Tech 3342.
+    pub range: f32,
+}
+
+impl Default for Loudness {
+    fn default() -> Self {
+        Self {
+            momentary: f32::NEG_INFINITY,
+            short_term: f32::NEG_INFINITY,
+            integrated: f32::NEG_INFINITY,
+            range: 0.0,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Stereo image metrics of the first two channels of a source, e.g. for correlation meters
+/// and goniometers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StereoImage {
+    /// Phase correlation coefficient of the left and right channel in range `[-1, 1]`:
+    /// 1 = mono, 0 = uncorrelated, -1 = out of phase. 0 for silence.
+    pub correlation: f32,
+    /// RMS level of the mid signal `(L + R) / 2` (linear).
+    pub mid_rms: f32,
+    /// RMS level of the side signal `(L - R) / 2` (linear).
+    pub side_rms: f32,
+    /// Decimated goniometer points as `(x, y)` pairs, oldest first, with x = `(R - L) / 2` and
+    /// y = `(L + R) / 2`, so mono signals are vertical lines and left-only signals lean to the
+    /// left. Holds up to [`Self::MAX_GONIOMETER_POINTS`] points of the last update interval.
+    pub goniometer: Vec<(f32, f32)>,
+}
+
+impl StereoImage {
+    /// Max number of goniometer points per update.
+    pub const MAX_GONIOMETER_POINTS: usize = 512;
+
+    /// Mid/side energy balance in range `[-1, 1]`: 1 = mid only (mono), 0 = equal mid and
+    /// side energy, -1 = side only (out of phase). 0 for silence.
+    pub fn mid_side_balance(&self) -> f32 {
+        let mid_energy = self.mid_rms * self.mid_rms;
+        let side_energy = self.side_rms * self.side_rms;
+        let energy = mid_energy + side_energy;
+        if energy > 0.0 {
+            (mid_energy - side_energy) / energy
+        } else {
+            0.0
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Audio level metrics of a source.
 #[derive(Debug, Clone, Default)]
 pub struct AudioLevel {
@@ -20,6 +103,21 @@ pub struct AudioLevel {
     pub peak: Vec<f32>,
     /// Per-channel RMS level (linear).
     pub rms: Vec<f32>,
+    /// Per-channel 4x oversampled true-peak amplitude (linear), see ITU-R BS.1770.
+    /// Empty when true-peak metering is disabled.
+    pub true_peak: Vec<f32>,
+    /// Per-channel clip indicators: set when a channel exceeded 0 dBFS (0 dBTP when true-peak
+    /// metering is enabled). Clip indicators hold until they get reset via
+    /// [`AudioLevelState::reset_clip_indicators`].
+    pub clipped: Vec<bool>,
+    /// Per-channel number of over events: each run of consecutive samples above 0 dBFS
+    /// (0 dBTP when true-peak metering is enabled) counts as one over. Counts accumulate until
+    /// they get reset via [`AudioLevelState::reset_clip_indicators`].
+    pub overs: Vec<u64>,
+    /// EBU R128 loudness of all channels.
+    pub loudness: Loudness,
+    /// Stereo image of the first two channels. `None` for mono sources.
+    pub stereo: Option<StereoImage>,
 }
 
 impl AudioLevel {
@@ -38,6 +136,22 @@ impl AudioLevel {
             .unwrap_or(f32::NEG_INFINITY)
     }
 
+    /// True-peak level in dBTP for the given channel. Returns `f32::NEG_INFINITY` for silence
+    /// or when true-peak metering is disabled.
+    pub fn true_peak_db(&self, channel: usize) -> f32 {
+        self.true_peak
+            .get(channel)
+            .copied()
+            .map(|p| {
+                if p > 0.0 {
+                    20.0 * p.log10()
+                } else {
+                    f32::NEG_INFINITY
+                }
+            })
+            .unwrap_or(f32::NEG_INFINITY)
+    }
+
     /// RMS level in dBFS for the given channel. Returns `f32::NEG_INFINITY` for silence.
     pub fn rms_db(&self, channel: usize) -> f32 {
         self.rms
@@ -60,6 +174,19 @@ impl Display for AudioLevel {
             .map(|ch| format!("{:.1}", self.peak_db(ch)))
             .collect();
         write!(f, "Peak: [{}] dBFS", peak_strings.join(", "))?;
+        if !self.true_peak.is_empty() {
+            let true_peak_strings: Vec<String> = (0..self.true_peak.len())
+                .map(|ch| format!("{:.1}", self.true_peak_db(ch)))
+                .collect();
+            write!(f, ", True Peak: [{}] dBTP", true_peak_strings.join(", "))?;
+        }
+        if self.clipped.iter().any(|clipped| *clipped) {
+            write!(f, ", Clipped")?;
+        }
+        write!(f, ", Loudness: {:.1} LUFS", self.loudness.short_term)?;
+        if let Some(stereo) = &self.stereo {
+            write!(f, ", Correlation: {:.2}", stereo.correlation)?;
+        }
         Ok(())
     }
 }
@@ -77,6 +204,7 @@ pub struct AudioLevelState {
     peak_hold: Vec<f32>,
     sum_square: Vec<f64>,
     collected_frames: u64,
+    loudness_reset: bool,
     update_interval: SampleTime,
     update_interval_clock: SampleTimeClock,
     audio_level: AudioLevel,
@@ -84,16 +212,28 @@ pub struct AudioLevelState {
 
 impl AudioLevelState {
     pub fn new(update_interval: Duration, channel_count: usize, sample_rate: u32) -> Self {
+        let update_interval =
+            SampleTimeClock::duration_to_sample_time(update_interval, sample_rate);
+        let stereo = (channel_count >= 2).then(|| StereoImage {
+            goniometer: Vec::with_capacity(StereoImage::MAX_GONIOMETER_POINTS),
+            ..StereoImage::default()
+        });
         Self {
             channel_count,
             peak_hold: vec![0.0; channel_count],
             sum_square: vec![0.0; channel_count],
             collected_frames: 0,
-            update_interval: SampleTimeClock::duration_to_sample_time(update_interval, sample_rate),
+            loudness_reset: false,
+            update_interval,
             update_interval_clock: SampleTimeClock::new(sample_rate),
             audio_level: AudioLevel {
                 peak: vec![0.0; channel_count],
                 rms: vec![0.0; channel_count],
+                true_peak: Vec::new(),
+                clipped: vec![false; channel_count],
+                overs: vec![0; channel_count],
+                loudness: Loudness::default(),
+                stereo,
             },
         }
     }
@@ -103,8 +243,52 @@ impl AudioLevelState {
         &self.audio_level
     }
 
-    /// Update audio level
+    /// Reset loudness measurements, e.g. to start measuring the integrated loudness and
+    /// loudness range of a new program. Measurements restart with the next recorded audio.
+    pub fn reset_loudness(&mut self) {
+        self.loudness_reset = true;
+        self.audio_level.loudness = Loudness::default();
+    }
+
+    /// Reset clip indicators and over counts, e.g. when the user clicks on a clip indicator.
+    pub fn reset_clip_indicators(&mut self) {
+        self.audio_level.clipped.fill(false);
+        self.audio_level.overs.fill(0);
+    }
+
+    /// Enable or disable publishing true-peak levels. Allocates, so this should not be called
+    /// in real-time threads.
+    pub(crate) fn set_true_peak_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.audio_level.true_peak = vec![0.0; self.channel_count];
+        } else {
+            self.audio_level.true_peak = Vec::new();
+        }
+    }
+
+    /// Update audio level. Loudness, stereo image, true-peak levels and overs get measured by
+    /// the metered source which owns the state only, so they stay unchanged here.
     pub fn record(&mut self, output: &[f32], time: &SourceTime) {
+        self.record_with_meters(output, time, None);
+    }
+
+    /// Update audio level and publish the given meters' measurements.
+    ///
+    /// The meters get fed by the caller, outside of the state's lock, so their measurements
+    /// continue while the state is locked by others.
+    pub(crate) fn record_with_meters(
+        &mut self,
+        output: &[f32],
+        time: &SourceTime,
+        mut meters: Option<&mut LevelMeters>,
+    ) {
+        if let Some(meters) = meters.as_deref_mut() {
+            if self.loudness_reset {
+                meters.loudness_meter.reset();
+                self.loudness_reset = false;
+            }
+        }
+
         let channel_count = self.channel_count;
         if channel_count == 0 || output.is_empty() {
             return;
@@ -121,6 +305,17 @@ impl AudioLevelState {
             }
         }
 
+        // Publish overs, which got detected since the last record, right away.
+        if let Some(meters) = meters.as_deref_mut() {
+            for (channel, overs) in meters.overs.iter_mut().enumerate() {
+                if *overs > 0 {
+                    self.audio_level.clipped[channel] = true;
+                    self.audio_level.overs[channel] += *overs;
+                    *overs = 0;
+                }
+            }
+        }
+
         // Publish results at the configured interval.
         self.collected_frames += (output.len() / channel_count) as u64;
 
@@ -133,6 +328,25 @@ impl AudioLevelState {
                     0.0
                 };
             }
+            if let Some(meters) = meters {
+                if meters.true_peak_meter.is_some() {
+                    for (true_peak, hold) in self
+                        .audio_level
+                        .true_peak
+                        .iter_mut()
+                        .zip(&mut meters.true_peak_hold)
+                    {
+                        *true_peak = *hold;
+                        *hold = 0.0;
+                    }
+                }
+                self.audio_level.loudness = meters.loudness_meter.loudness();
+                if let (Some(stereo_meter), Some(stereo)) =
+                    (&mut meters.stereo_meter, &mut self.audio_level.stereo)
+                {
+                    stereo_meter.publish(stereo);
+                }
+            }
 
             self.update_interval_clock.reset(time.pos_in_frames);
             self.collected_frames = 0;
@@ -144,34 +358,152 @@ impl AudioLevelState {
 
 // -------------------------------------------------------------------------------------------------
 
-/// A source wrapper that measures the audio output level of an inner source.
+/// Meters of a `MeteredSource`, which get fed by the source outside of the audio level state's
+/// lock, so they don't skip audio while the state is locked by others. Their measurements get
+/// published via [`AudioLevelState::record_with_meters`].
+pub(crate) struct LevelMeters {
+    channel_count: usize,
+    loudness_meter: LoudnessMeter,
+    stereo_meter: Option<StereoMeter>,
+    true_peak_meter: Option<TruePeakMeter>,
+    true_peak_hold: Vec<f32>,
+    in_over: Vec<bool>,
+    // overs which got detected since they got published the last time
+    overs: Vec<u64>,
+}
+
+impl LevelMeters {
+    pub fn new(update_interval: Duration, channel_count: usize, sample_rate: u32) -> Self {
+        let update_interval =
+            SampleTimeClock::duration_to_sample_time(update_interval, sample_rate);
+        // decimate goniometer points, so one update interval fills all points
+        let goniometer_decimation = (update_interval as usize)
+            .div_ceil(StereoImage::MAX_GONIOMETER_POINTS)
+            .max(1);
+        let loudness_meter = LoudnessMeter::new(channel_count, sample_rate);
+        let stereo_meter =
+            (channel_count >= 2).then(|| StereoMeter::new(channel_count, goniometer_decimation));
+        Self {
+            channel_count,
+            loudness_meter,
+            stereo_meter,
+            true_peak_meter: None,
+            true_peak_hold: vec![0.0; channel_count],
+            in_over: vec![false; channel_count],
+            overs: vec![0; channel_count],
+        }
+    }
+
+    /// Enable or disable true-peak metering and detect overs on true-peak instead of
+    /// sample-peak levels. Allocates, so this should not be called in real-time threads.
+    pub fn set_true_peak_enabled(&mut self, enabled: bool) {
+        self.true_peak_meter = enabled.then(|| TruePeakMeter::new(self.channel_count));
+        self.true_peak_hold.fill(0.0);
+    }
+
+    /// Feed the given interleaved output into all meters and detect overs.
+    pub fn process(&mut self, output: &[f32]) {
+        self.loudness_meter.process(output);
+        if let Some(stereo_meter) = &mut self.stereo_meter {
+            stereo_meter.process(output);
+        }
+        for frame in output.frames(self.channel_count) {
+            for (channel, &sample) in frame.enumerate() {
+                let level = if let Some(true_peak_meter) = &mut self.true_peak_meter {
+                    let true_peak = true_peak_meter.process(channel, sample);
+                    if true_peak > self.true_peak_hold[channel] {
+                        self.true_peak_hold[channel] = true_peak;
+                    }
+                    true_peak
+                } else {
+                    sample.abs()
+                };
+                if level > 1.0 {
+                    if !self.in_over[channel] {
+                        self.in_over[channel] = true;
+                        self.overs[channel] += 1;
+                    }
+                } else {
+                    self.in_over[channel] = false;
+                }
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Thread-safe handles to the metering states of a `MeteredSource`. States are `None` when the
+/// corresponding measurement is disabled.
+#[derive(Clone, Default)]
+pub(crate) struct MeteringStates {
+    pub audio_level: Option<SharedAudioLevelState>,
+    pub spectrum: Option<SharedSpectrumState>,
+    pub waveform: Option<SharedWaveformState>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A source wrapper that measures the audio output level and, optionally, the spectrum and
+/// recent waveform of an inner source.
 ///
-/// When no `update_interval` is provided, the wrapper is a transparent pass-through.
+/// When no `update_interval`, `spectrum_config` and `waveform_frames` is provided, the wrapper
+/// is a transparent pass-through.
 pub struct MeteredSource<S: Source> {
     source: S,
     state: Option<SharedAudioLevelState>,
+    meters: Option<LevelMeters>,
+    spectrum_analyzer: Option<SpectrumAnalyzer>,
+    waveform_recorder: Option<WaveformRecorder>,
 }
 
 impl<S: Source> MeteredSource<S> {
-    /// Wraps a source to measure its audio output levels.
-    /// Pass `None`as update_interval to disable metering entirely.
-    pub fn new(source: S, update_interval: Option<Duration>) -> Self {
-        if let Some(update_interval) = update_interval {
-            let channel_count = source.channel_count();
-            Self {
-                state: Some(Arc::new(Mutex::new(AudioLevelState::new(
-                    update_interval,
-                    channel_count,
-                    source.sample_rate(),
-                )))),
-                source,
-            }
-        } else {
-            Self {
-                source,
-                state: None,
-            }
+    /// Wraps a source to measure its audio output levels, spectrum and waveform.
+    /// Pass `None`as update_interval to disable level metering, `None` as spectrum_config
+    /// to disable spectrum analysis and `None` as waveform_frames to disable waveform recording.
+    pub fn new(
+        source: S,
+        update_interval: Option<Duration>,
+        spectrum_config: Option<SpectrumConfig>,
+        waveform_frames: Option<usize>,
+    ) -> Self {
+        let channel_count = source.channel_count();
+        let sample_rate = source.sample_rate();
+        let state = update_interval.map(|update_interval| {
+            Arc::new(Mutex::new(AudioLevelState::new(
+                update_interval,
+                channel_count,
+                sample_rate,
+            )))
+        });
+        let meters = update_interval
+            .map(|update_interval| LevelMeters::new(update_interval, channel_count, sample_rate));
+        let spectrum_analyzer =
+            spectrum_config.map(|config| SpectrumAnalyzer::new(config, channel_count, sample_rate));
+        let waveform_recorder =
+            waveform_frames.map(|frames| WaveformRecorder::new(frames, channel_count, sample_rate));
+        Self {
+            source,
+            state,
+            meters,
+            spectrum_analyzer,
+            waveform_recorder,
+        }
+    }
+
+    /// Enable oversampled true-peak metering and detect overs on true-peak instead of sample-peak
+    /// levels. Has no effect when level metering is disabled.
+    pub fn with_true_peak(mut self, enabled: bool) -> Self {
+        if let Some(meters) = &mut self.meters {
+            meters.set_true_peak_enabled(enabled);
         }
+        if let Some(state) = &self.state {
+            state
+                .lock()
+                .expect("Failed to lock metering state")
+                .set_true_peak_enabled(enabled);
+        }
+        self
     }
 
     /// Returns a reference to the wrapped source.
@@ -181,9 +513,19 @@ impl<S: Source> MeteredSource<S> {
         &self.source
     }
 
-    /// Returns a thread-safe handle to the metering state, when metering is enabled.
-    pub(crate) fn state(&self) -> Option<SharedAudioLevelState> {
-        self.state.clone()
+    /// Returns thread-safe handles to all enabled metering states.
+    pub(crate) fn states(&self) -> MeteringStates {
+        MeteringStates {
+            audio_level: self.state.clone(),
+            spectrum: self
+                .spectrum_analyzer
+                .as_ref()
+                .map(|analyzer| analyzer.state()),
+            waveform: self
+                .waveform_recorder
+                .as_ref()
+                .map(|recorder| recorder.state()),
+        }
     }
 }
 
@@ -208,11 +550,211 @@ impl<S: Source> Source for MeteredSource<S> {
         let written = self.source.write(output, time);
         if written > 0 {
             if let Some(state) = &self.state {
+                // NB: feed meters outside of the lock, so no audio gets skipped
+                if let Some(meters) = &mut self.meters {
+                    meters.process(&output[..written]);
+                }
                 if let Ok(mut state) = state.try_lock() {
-                    state.record(&output[..written], time);
+                    state.record_with_meters(&output[..written], time, self.meters.as_mut());
                 }
             }
+            if let Some(spectrum_analyzer) = &mut self.spectrum_analyzer {
+                spectrum_analyzer.process(&output[..written]);
+            }
+            if let Some(waveform_recorder) = &mut self.waveform_recorder {
+                waveform_recorder.process(&output[..written]);
+            }
         }
         written
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Endless stereo 1 kHz sine at -23 dBFS.
+    struct SineSource {
+        position: u64,
+    }
+
+    impl Source for SineSource {
+        fn channel_count(&self) -> usize {
+            2
+        }
+
+        fn sample_rate(&self) -> u32 {
+            48000
+        }
+
+        fn is_exhausted(&self) -> bool {
+            false
+        }
+
+        fn weight(&self) -> usize {
+            1
+        }
+
+        fn write(&mut self, output: &mut [f32], _time: &SourceTime) -> usize {
+            let amplitude = 10.0_f32.powf(-23.0 / 20.0);
+            for frame in output.chunks_exact_mut(2) {
+                let phase = 2.0 * std::f32::consts::PI * 1000.0 * self.position as f32 / 48000.0;
+                frame.fill(amplitude * phase.sin());
+                self.position += 1;
+            }
+            output.len()
+        }
+    }
+
+    /// Endless stereo signal with constant left and right channel values.
+    struct ConstantSource {
+        values: [f32; 2],
+    }
+
+    impl Source for ConstantSource {
+        fn channel_count(&self) -> usize {
+            2
+        }
+
+        fn sample_rate(&self) -> u32 {
+            48000
+        }
+
+        fn is_exhausted(&self) -> bool {
+            false
+        }
+
+        fn weight(&self) -> usize {
+            1
+        }
+
+        fn write(&mut self, output: &mut [f32], _time: &SourceTime) -> usize {
+            for frame in output.chunks_exact_mut(2) {
+                frame.copy_from_slice(&self.values);
+            }
+            output.len()
+        }
+    }
+
+    #[test]
+    fn loudness_outside_of_lock() {
+        let mut source = MeteredSource::new(
+            SineSource { position: 0 },
+            Some(Duration::from_millis(100)),
+            None,
+            None,
+        );
+        let state = source.states().audio_level.unwrap();
+        let mut buffer = vec![0.0; 480 * 2];
+        let mut pos_in_frames = 0;
+        let mut write = |source: &mut MeteredSource<SineSource>, frames: u64| {
+            for _ in 0..frames / 480 {
+                let time = SourceTime {
+                    pos_in_frames,
+                    pos_instant: Instant::now(),
+                };
+                source.write(&mut buffer, &time);
+                pos_in_frames += 480;
+            }
+        };
+
+        // keeps measuring loudness while the state is locked
+        {
+            let _lock = state.lock().unwrap();
+            write(&mut source, 48000 * 5);
+        }
+        write(&mut source, 48000);
+        let loudness = state.lock().unwrap().audio_level().loudness;
+        assert!((loudness.short_term + 23.0).abs() < 0.1, "{loudness:?}");
+        assert!((loudness.integrated + 23.0).abs() < 0.1, "{loudness:?}");
+
+        // restarts measurements after resets
+        state.lock().unwrap().reset_loudness();
+        assert_eq!(
+            state.lock().unwrap().audio_level().loudness,
+            Loudness::default()
+        );
+        write(&mut source, 48000);
+        let loudness = state.lock().unwrap().audio_level().loudness;
+        assert_eq!(loudness.short_term, f32::NEG_INFINITY);
+        assert!((loudness.momentary + 23.0).abs() < 0.1, "{loudness:?}");
+    }
+
+    #[test]
+    fn stereo_image_outside_of_lock() {
+        let mut source = MeteredSource::new(
+            ConstantSource { values: [0.5, 0.5] },
+            Some(Duration::from_millis(100)),
+            None,
+            None,
+        );
+        let state = source.states().audio_level.unwrap();
+        let mut buffer = vec![0.0; 480 * 2];
+        let mut pos_in_frames = 0;
+        let mut write = |source: &mut MeteredSource<ConstantSource>, frames: u64| {
+            for _ in 0..frames / 480 {
+                let time = SourceTime {
+                    pos_in_frames,
+                    pos_instant: Instant::now(),
+                };
+                source.write(&mut buffer, &time);
+                pos_in_frames += 480;
+            }
+        };
+
+        // keeps measuring the correlated signal while the state is locked
+        {
+            let _lock = state.lock().unwrap();
+            write(&mut source, 4800 * 4);
+        }
+        source.source.values = [0.5, -0.5];
+        write(&mut source, 480);
+        let stereo = state.lock().unwrap().audio_level().stereo.clone().unwrap();
+        assert!(stereo.correlation > 0.5, "{stereo:?}");
+        assert!(stereo.mid_rms > stereo.side_rms, "{stereo:?}");
+    }
+
+    #[test]
+    fn overs_outside_of_lock() {
+        let mut source = MeteredSource::new(
+            ConstantSource { values: [0.5, 0.5] },
+            Some(Duration::from_millis(100)),
+            None,
+            None,
+        )
+        .with_true_peak(true);
+        let state = source.states().audio_level.unwrap();
+        let mut buffer = vec![0.0; 480 * 2];
+        let mut pos_in_frames = 0;
+        let mut write = |source: &mut MeteredSource<ConstantSource>, values: [f32; 2]| {
+            source.source.values = values;
+            let time = SourceTime {
+                pos_in_frames,
+                pos_instant: Instant::now(),
+            };
+            source.write(&mut buffer, &time);
+            pos_in_frames += 480;
+        };
+
+        // keeps detecting overs while the state is locked
+        {
+            let _lock = state.lock().unwrap();
+            write(&mut source, [1.5, 0.5]);
+            write(&mut source, [0.5, 0.5]);
+            write(&mut source, [1.5, 0.5]);
+            for _ in 0..10 {
+                write(&mut source, [0.5, 0.5]);
+            }
+        }
+        write(&mut source, [0.5, 0.5]);
+        let audio_level = state.lock().unwrap().audio_level().clone();
+        assert_eq!(audio_level.overs, [2, 0]);
+        assert_eq!(audio_level.clipped, [true, false]);
+        assert!(audio_level.true_peak[0] > 1.4, "{audio_level:?}");
+        assert!(audio_level.true_peak[1] < 1.0, "{audio_level:?}");
+    }
+}
@@ -0,0 +1,364 @@
+use std::f64::consts::PI;
+
+use super::Loudness;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Loudness offset of the K-weighted mean square in LUFS, see ITU-R BS.1770.
+const LOUDNESS_OFFSET: f64 = -0.691;
+
+/// Absolute gate for integrated loudness and loudness range in LUFS.
+const ABSOLUTE_GATE: f64 = -70.0;
+/// Relative gate for integrated loudness in LU.
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+/// Relative gate for loudness range in LU.
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+
+/// Number of 100ms sub-blocks in a momentary (400ms) and short-term (3s) measurement window.
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+
+/// Loudness histogram range and resolution, which is used to gate blocks without storing them.
+const HISTOGRAM_MIN: f64 = ABSOLUTE_GATE;
+const HISTOGRAM_MAX: f64 = 10.0;
+const HISTOGRAM_STEP: f64 = 0.1;
+const HISTOGRAM_BINS: usize = ((HISTOGRAM_MAX - HISTOGRAM_MIN) / HISTOGRAM_STEP) as usize;
+
+// -------------------------------------------------------------------------------------------------
+
+fn energy_to_loudness(energy: f64) -> f64 {
+    if energy > 0.0 {
+        LOUDNESS_OFFSET + 10.0 * energy.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+fn loudness_to_energy(loudness: f64) -> f64 {
+    10.0_f64.powf((loudness - LOUDNESS_OFFSET) / 10.0)
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Direct form I biquad coefficients.
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+/// K-weighting filter: a high shelf which models the acoustic effects of the head, followed
+/// by the revised low-frequency B-weighting high-pass, see ITU-R BS.1770.
+///
+/// Coefficients get derived from the filters' analog prototypes, so they match the
+/// standard's 48 kHz coefficients and work with any other sample rate as well.
+#[derive(Debug, Clone, Copy)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f64;
+
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10.0_f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        };
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        };
+
+        Self { shelf, high_pass }
+    }
+}
+
+/// Per channel K-weighting filter state.
+#[derive(Debug, Default, Clone, Copy)]
+struct KWeightingState {
+    shelf: [f64; 4],
+    high_pass: [f64; 4],
+}
+
+impl KWeightingState {
+    #[inline]
+    fn process_biquad(biquad: &Biquad, state: &mut [f64; 4], input: f64) -> f64 {
+        let [x1, x2, y1, y2] = *state;
+        let output = biquad.b[0] * input + biquad.b[1] * x1 + biquad.b[2] * x2
+            - biquad.a[0] * y1
+            - biquad.a[1] * y2;
+        *state = [input, x1, output, y1];
+        output
+    }
+
+    #[inline]
+    fn process(&mut self, filter: &KWeighting, input: f64) -> f64 {
+        let shelved = Self::process_biquad(&filter.shelf, &mut self.shelf, input);
+        Self::process_biquad(&filter.high_pass, &mut self.high_pass, shelved)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Histogram of gating block loudness values, which allows gating an unlimited number of
+/// blocks with a fixed amount of memory.
+#[derive(Debug, Clone)]
+struct LoudnessHistogram {
+    bins: Vec<u32>,
+    bin_energies: Vec<f64>,
+}
+
+impl LoudnessHistogram {
+    fn new() -> Self {
+        let bins = vec![0; HISTOGRAM_BINS];
+        let bin_energies = (0..HISTOGRAM_BINS)
+            .map(|bin| loudness_to_energy(Self::bin_loudness(bin)))
+            .collect();
+        Self { bins, bin_energies }
+    }
+
+    fn bin_loudness(bin: usize) -> f64 {
+        HISTOGRAM_MIN + (bin as f64 + 0.5) * HISTOGRAM_STEP
+    }
+
+    fn add(&mut self, loudness: f64) {
+        // blocks below the absolute gate never count
+        if loudness >= ABSOLUTE_GATE {
+            let bin = ((loudness - HISTOGRAM_MIN) / HISTOGRAM_STEP) as usize;
+            self.bins[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.bins.fill(0);
+    }
+
+    // Index of the first bin, which passes the relative gate, and the gated block count.
+    fn relative_gate(&self, relative_gate: f64) -> Option<(usize, u64)> {
+        let (count, energy) = self.bins.iter().zip(&self.bin_energies).fold(
+            (0_u64, 0.0_f64),
+            |(count, energy), (&bin_count, bin_energy)| {
+                (
+                    count + bin_count as u64,
+                    energy + bin_energy * bin_count as f64,
+                )
+            },
+        );
+        if count == 0 {
+            return None;
+        }
+        let gate = energy_to_loudness(energy / count as f64) + relative_gate;
+        let first_bin = (((gate - HISTOGRAM_MIN) / HISTOGRAM_STEP).ceil().max(0.0) as usize)
+            .min(HISTOGRAM_BINS - 1);
+        let gated_count = self.bins[first_bin..]
+            .iter()
+            .map(|count| *count as u64)
+            .sum::<u64>();
+        Some((first_bin, gated_count))
+    }
+
+    /// Integrated loudness of all blocks which pass the absolute and relative gate.
+    fn integrated(&self) -> f64 {
+        let Some((first_bin, count)) = self.relative_gate(INTEGRATED_RELATIVE_GATE) else {
+            return f64::NEG_INFINITY;
+        };
+        if count == 0 {
+            return f64::NEG_INFINITY;
+        }
+        let energy = (first_bin..HISTOGRAM_BINS)
+            .map(|bin| self.bin_energies[bin] * self.bins[bin] as f64)
+            .sum::<f64>();
+        energy_to_loudness(energy / count as f64)
+    }
+
+    /// Loudness range: the difference between the 10th and 95th percentile of all blocks which
+    /// pass the absolute and relative gate, see EBU Tech 3342.
+    fn range(&self) -> f64 {
+        let Some((first_bin, count)) = self.relative_gate(RANGE_RELATIVE_GATE) else {
+            return 0.0;
+        };
+        if count == 0 {
+            return 0.0;
+        }
+        let percentile = |percentile: f64| {
+            let target = ((count - 1) as f64 * percentile).round() as u64;
+            let mut seen = 0_u64;
+            for bin in first_bin..HISTOGRAM_BINS {
+                seen += self.bins[bin] as u64;
+                if seen > target {
+                    return Self::bin_loudness(bin);
+                }
+            }
+            Self::bin_loudness(HISTOGRAM_BINS - 1)
+        };
+        percentile(0.95) - percentile(0.10)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// EBU R128 loudness meter, which measures K-weighted momentary, short-term and integrated
+/// loudness and the loudness range of an interleaved signal.
+///
+/// Signals get analyzed in 100ms sub-blocks: momentary loudness uses a sliding 400ms window,
+/// short-term loudness a sliding 3s window. Integrated loudness gates 400ms blocks with 75%
+/// overlap, loudness range gates the short-term values, which are measured every 100ms.
+#[derive(Debug, Clone)]
+pub(crate) struct LoudnessMeter {
+    channel_weights: Vec<f64>,
+    filter: KWeighting,
+    filter_states: Vec<KWeightingState>,
+    sub_block_frames: usize,
+    sub_block_pos: usize,
+    sub_block_energy: f64,
+    sub_blocks: [f64; SHORT_TERM_SUB_BLOCKS],
+    sub_block_count: usize,
+    integrated_histogram: LoudnessHistogram,
+    range_histogram: LoudnessHistogram,
+}
+
+impl LoudnessMeter {
+    pub fn new(channel_count: usize, sample_rate: u32) -> Self {
+        // Surround channels of 5.1 layouts get boosted, LFE channels ignored.
+        let channel_weights = if channel_count == 6 {
+            vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41]
+        } else {
+            vec![1.0; channel_count]
+        };
+        Self {
+            channel_weights,
+            filter: KWeighting::new(sample_rate),
+            filter_states: vec![KWeightingState::default(); channel_count],
+            sub_block_frames: (sample_rate as usize / 10).max(1),
+            sub_block_pos: 0,
+            sub_block_energy: 0.0,
+            sub_blocks: [0.0; SHORT_TERM_SUB_BLOCKS],
+            sub_block_count: 0,
+            integrated_histogram: LoudnessHistogram::new(),
+            range_histogram: LoudnessHistogram::new(),
+        }
+    }
+
+    /// Reset all measurements, e.g. to start measuring the integrated loudness of a new program.
+    pub fn reset(&mut self) {
+        self.filter_states.fill(KWeightingState::default());
+        self.sub_block_pos = 0;
+        self.sub_block_energy = 0.0;
+        self.sub_block_count = 0;
+        self.integrated_histogram.clear();
+        self.range_histogram.clear();
+    }
+
+    /// Analyze the given interleaved signal.
+    pub fn process(&mut self, input: &[f32]) {
+        let channel_count = self.channel_weights.len();
+        for frame in input.chunks_exact(channel_count) {
+            for ((sample, weight), state) in frame
+                .iter()
+                .zip(&self.channel_weights)
+                .zip(&mut self.filter_states)
+            {
+                let filtered = state.process(&self.filter, *sample as f64);
+                self.sub_block_energy += weight * filtered * filtered;
+            }
+            self.sub_block_pos += 1;
+            if self.sub_block_pos == self.sub_block_frames {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    /// The current loudness measurements.
+    pub fn loudness(&self) -> Loudness {
+        Loudness {
+            momentary: self.window_loudness(MOMENTARY_SUB_BLOCKS) as f32,
+            short_term: self.window_loudness(SHORT_TERM_SUB_BLOCKS) as f32,
+            integrated: self.integrated_histogram.integrated() as f32,
+            range: self.range_histogram.range() as f32,
+        }
+    }
+
+    fn finish_sub_block(&mut self) {
+        let energy = self.sub_block_energy / self.sub_block_frames as f64;
+        self.sub_blocks[self.sub_block_count % SHORT_TERM_SUB_BLOCKS] = energy;
+        self.sub_block_count += 1;
+        self.sub_block_pos = 0;
+        self.sub_block_energy = 0.0;
+
+        let momentary = self.window_loudness(MOMENTARY_SUB_BLOCKS);
+        if momentary.is_finite() {
+            self.integrated_histogram.add(momentary);
+        }
+        let short_term = self.window_loudness(SHORT_TERM_SUB_BLOCKS);
+        if short_term.is_finite() {
+            self.range_histogram.add(short_term);
+        }
+    }
+
+    // Loudness of the last given number of sub-blocks or -inf when not enough sub-blocks got
+    // measured yet.
+    fn window_loudness(&self, sub_blocks: usize) -> f64 {
+        if self.sub_block_count < sub_blocks {
+            return f64::NEG_INFINITY;
+        }
+        let energy = (self.sub_block_count - sub_blocks..self.sub_block_count)
+            .map(|index| self.sub_blocks[index % SHORT_TERM_SUB_BLOCKS])
+            .sum::<f64>();
+        energy_to_loudness(energy / sub_blocks as f64)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loudness() {
+        // EBU Tech 3341: a stereo 1 kHz sine at -23 dBFS reads -23 LUFS
+        let sample_rate = 48000;
+        let amplitude = 10.0_f32.powf(-23.0 / 20.0);
+        let signal = (0..sample_rate as usize * 10)
+            .flat_map(|frame| {
+                let value = amplitude
+                    * (2.0 * std::f32::consts::PI * 1000.0 * frame as f32 / 48000.0).sin();
+                [value, value]
+            })
+            .collect::<Vec<_>>();
+        let mut meter = LoudnessMeter::new(2, sample_rate);
+        assert_eq!(meter.loudness().momentary, f32::NEG_INFINITY);
+        meter.process(&signal);
+        let loudness = meter.loudness();
+        assert!((loudness.momentary + 23.0).abs() < 0.1, "{loudness:?}");
+        assert!((loudness.short_term + 23.0).abs() < 0.1, "{loudness:?}");
+        assert!((loudness.integrated + 23.0).abs() < 0.1, "{loudness:?}");
+        assert!(loudness.range < 0.2, "{loudness:?}");
+
+        // a 20 dB quieter second half widens the loudness range
+        let quiet_signal = signal.iter().map(|s| s * 0.1).collect::<Vec<_>>();
+        meter.process(&quiet_signal);
+        let loudness = meter.loudness();
+        assert!((loudness.momentary + 43.0).abs() < 0.1, "{loudness:?}");
+        assert!((loudness.range - 20.0).abs() < 0.5, "{loudness:?}");
+
+        // resets all measurements
+        meter.reset();
+        assert_eq!(meter.loudness().integrated, f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    f64::consts::PI,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::utils::dsp::fft::Fft;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Window functions, which are applied to signals before analyzing them, to reduce spectral
+/// leakage.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SpectrumWindow {
+    /// No windowing: best frequency resolution, but high spectral leakage.
+    Rectangular,
+    /// Hann window: a good general purpose compromise.
+    #[default]
+    Hann,
+    /// Blackman window: lower leakage than Hann, with slightly wider peaks.
+    Blackman,
+    /// 4-term Blackman-Harris window: very low leakage, with wide peaks.
+    BlackmanHarris,
+}
+
+impl SpectrumWindow {
+    /// Window coefficient at the given index within a window of the given size.
+    fn coefficient(&self, index: usize, size: usize) -> f32 {
+        let phase = 2.0 * PI * index as f64 / size as f64;
+        let value = match self {
+            Self::Rectangular => 1.0,
+            Self::Hann => 0.5 - 0.5 * phase.cos(),
+            Self::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+            Self::BlackmanHarris => {
+                0.35875 - 0.48829 * phase.cos() + 0.14128 * (2.0 * phase).cos()
+                    - 0.01168 * (3.0 * phase).cos()
+            }
+        };
+        value as f32
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Spectrum analysis settings. See [`PlayerConfig`](crate::PlayerConfig) on how to enable
+/// spectrum analysis for mixers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpectrumConfig {
+    /// FFT size in sample frames. A power of two in range `[64, 32768]`. Larger sizes give a
+    /// finer frequency resolution but react slower.
+    pub fft_size: usize,
+    /// Window function, which is applied before analyzing the signal.
+    pub window: SpectrumWindow,
+    /// Smoothing of magnitudes across analysis frames in range `[0, 1)`: 0 disables
+    /// smoothing, higher values let magnitudes fall back slower.
+    pub smoothing: f32,
+}
+
+impl Default for SpectrumConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpectrumConfig {
+    /// Min and max FFT sizes.
+    pub const MIN_FFT_SIZE: usize = 64;
+    pub const MAX_FFT_SIZE: usize = 32768;
+
+    /// Create a new default spectrum configuration.
+    pub fn new() -> Self {
+        Self {
+            fft_size: 2048,
+            window: SpectrumWindow::Hann,
+            smoothing: 0.8,
+        }
+    }
+
+    /// Set the FFT size. Gets rounded up to the next power of two and clamped to the valid range.
+    pub fn fft_size(mut self, fft_size: usize) -> Self {
+        self.fft_size = fft_size
+            .next_power_of_two()
+            .clamp(Self::MIN_FFT_SIZE, Self::MAX_FFT_SIZE);
+        self
+    }
+
+    /// Set the window function.
+    pub fn window(mut self, window: SpectrumWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the magnitude smoothing. Gets clamped to the valid range.
+    pub fn smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing.clamp(0.0, 0.99);
+        self
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A copy of the magnitude spectrum of a source.
+#[derive(Debug, Clone, Default)]
+pub struct Spectrum {
+    /// Linear magnitudes of all FFT bins, from DC to Nyquist. A full scale sine wave has a
+    /// magnitude of 1.0 (0 dBFS) in its bin, a DC offset or Nyquist signal its amplitude.
+    pub magnitudes: Vec<f32>,
+    /// Sample rate of the analyzed signal.
+    pub sample_rate: u32,
+    /// FFT size of the analysis.
+    pub fft_size: usize,
+}
+
+impl Spectrum {
+    /// Center frequency of the given bin in Hz.
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate as f32 / self.fft_size as f32
+    }
+
+    /// Magnitude of the given bin in dBFS. Returns `f32::NEG_INFINITY` for silence or invalid
+    /// bins.
+    pub fn magnitude_db(&self, bin: usize) -> f32 {
+        self.magnitudes
+            .get(bin)
+            .copied()
+            .map(|m| {
+                if m > 0.0 {
+                    20.0 * m.log10()
+                } else {
+                    f32::NEG_INFINITY
+                }
+            })
+            .unwrap_or(f32::NEG_INFINITY)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A thread-safe handle to a [`SpectrumState`].
+pub type SharedSpectrumState = Arc<SpectrumState>;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Spectrum analysis results, shared between a `MeteredSource` and its handles.
+///
+/// Magnitudes are stored in atomics, so they can be read at any time without locking and
+/// without blocking the audio thread. Bins are updated one by one, so a read may mix up bins of
+/// two subsequent analysis frames.
+pub struct SpectrumState {
+    sample_rate: u32,
+    fft_size: usize,
+    magnitudes: Vec<AtomicU32>,
+    update_count: AtomicU64,
+}
+
+impl SpectrumState {
+    fn new(fft_size: usize, sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            fft_size,
+            magnitudes: (0..fft_size / 2 + 1).map(|_| AtomicU32::new(0)).collect(),
+            update_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Sample rate of the analyzed signal.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// FFT size of the analysis.
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Number of magnitude bins, from DC to Nyquist.
+    pub fn bin_count(&self) -> usize {
+        self.magnitudes.len()
+    }
+
+    /// Number of analysis frames which got published so far. Can be used to check if new
+    /// magnitudes are available.
+    pub fn update_count(&self) -> u64 {
+        self.update_count.load(Ordering::Acquire)
+    }
+
+    /// Copy linear magnitudes into the given buffer without allocating. Returns the number of
+    /// copied bins.
+    pub fn read_magnitudes(&self, output: &mut [f32]) -> usize {
+        for (o, magnitude) in output.iter_mut().zip(&self.magnitudes) {
+            *o = f32::from_bits(magnitude.load(Ordering::Relaxed));
+        }
+        output.len().min(self.magnitudes.len())
+    }
+
+    /// A copy of the current magnitude spectrum.
+    pub fn spectrum(&self) -> Spectrum {
+        let mut magnitudes = vec![0.0; self.bin_count()];
+        self.read_magnitudes(&mut magnitudes);
+        Spectrum {
+            magnitudes,
+            sample_rate: self.sample_rate,
+            fft_size: self.fft_size,
+        }
+    }
+
+    fn publish(&self, magnitudes: &[f32]) {
+        for (magnitude, value) in self.magnitudes.iter().zip(magnitudes) {
+            magnitude.store(value.to_bits(), Ordering::Relaxed);
+        }
+        self.update_count.fetch_add(1, Ordering::Release);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Analyzes the mono downmix of an interleaved signal with a windowed FFT.
+///
+/// Analysis frames overlap by 50%: a new frame gets analyzed and published every
+/// `fft_size / 2` sample frames.
+pub(crate) struct SpectrumAnalyzer {
+    state: SharedSpectrumState,
+    channel_count: usize,
+    fft: Fft,
+    window: Vec<f32>,
+    magnitude_scale: f32,
+    smoothing: f32,
+    input: Vec<f32>,
+    input_pos: usize,
+    hop_counter: usize,
+    real: Vec<f32>,
+    imag: Vec<f32>,
+    magnitudes: Vec<f32>,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(config: SpectrumConfig, channel_count: usize, sample_rate: u32) -> Self {
+        let config = config.fft_size(config.fft_size).smoothing(config.smoothing);
+        let fft_size = config.fft_size;
+        let window = (0..fft_size)
+            .map(|index| config.window.coefficient(index, fft_size))
+            .collect::<Vec<_>>();
+        // scale magnitudes, so a DC offset reads its amplitude, see also `analyze`
+        let magnitude_scale = 1.0 / window.iter().sum::<f32>();
+        let state = Arc::new(SpectrumState::new(fft_size, sample_rate));
+        Self {
+            magnitudes: vec![0.0; state.bin_count()],
+            state,
+            channel_count,
+            fft: Fft::new(fft_size),
+            window,
+            magnitude_scale,
+            smoothing: config.smoothing,
+            input: vec![0.0; fft_size],
+            input_pos: 0,
+            hop_counter: 0,
+            real: vec![0.0; fft_size],
+            imag: vec![0.0; fft_size],
+        }
+    }
+
+    /// Shared analysis results.
+    pub fn state(&self) -> SharedSpectrumState {
+        Arc::clone(&self.state)
+    }
+
+    /// Analyze the given interleaved signal.
+    pub fn process(&mut self, input: &[f32]) {
+        let fft_size = self.fft.size();
+        let channel_scale = 1.0 / self.channel_count as f32;
+        for frame in input.chunks_exact(self.channel_count) {
+            self.input[self.input_pos] = frame.iter().sum::<f32>() * channel_scale;
+            self.input_pos = (self.input_pos + 1) % fft_size;
+            self.hop_counter += 1;
+            if self.hop_counter >= fft_size / 2 {
+                self.hop_counter = 0;
+                self.analyze();
+            }
+        }
+    }
+
+    fn analyze(&mut self) {
+        // apply window to the last fft_size frames, oldest frame first
+        let (newest, oldest) = self.input.split_at(self.input_pos);
+        for ((real, input), window) in self
+            .real
+            .iter_mut()
+            .zip(oldest.iter().chain(newest))
+            .zip(&self.window)
+        {
+            *real = input * window;
+        }
+        self.imag.fill(0.0);
+        self.fft.process(&mut self.real, &mut self.imag);
+
+        let smoothing = self.smoothing;
+        let nyquist_bin = self.magnitudes.len() - 1;
+        for (bin, ((magnitude, real), imag)) in self
+            .magnitudes
+            .iter_mut()
+            .zip(&self.real)
+            .zip(&self.imag)
+            .enumerate()
+        {
+            // bins between DC and Nyquist also contain their negative frequency's half, so a
+            // full scale sine reads 1.0 too
+            let scale = if bin == 0 || bin == nyquist_bin {
+                self.magnitude_scale
+            } else {
+                2.0 * self.magnitude_scale
+            };
+            let value = (real * real + imag * imag).sqrt() * scale;
+            *magnitude = smoothing * *magnitude + (1.0 - smoothing) * value;
+        }
+        self.state.publish(&self.magnitudes);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sine_spectrum() {
+        let sample_rate = 48000;
+        let config = SpectrumConfig::new().fft_size(1024).smoothing(0.0);
+        let mut analyzer = SpectrumAnalyzer::new(config, 2, sample_rate);
+        let state = analyzer.state();
+        assert_eq!(state.bin_count(), 513);
+        assert_eq!(state.update_count(), 0);
+
+        // a full scale sine, centered on bin 64
+        let frequency = 64.0 * sample_rate as f32 / 1024.0;
+        let signal = (0..4096)
+            .flat_map(|frame| {
+                let phase = 2.0 * std::f32::consts::PI * frequency * frame as f32;
+                let value = (phase / sample_rate as f32).sin();
+                [value, value]
+            })
+            .collect::<Vec<_>>();
+        analyzer.process(&signal);
+        assert_eq!(state.update_count(), 8);
+
+        let spectrum = state.spectrum();
+        assert_eq!(spectrum.bin_frequency(64), frequency);
+        assert!(spectrum.magnitude_db(64).abs() < 0.1);
+        assert!(spectrum.magnitude_db(32) < -100.0);
+        assert!(spectrum.magnitude_db(256) < -100.0);
+    }
+
+    #[test]
+    fn dc_and_nyquist_spectrum() {
+        let config = SpectrumConfig::new().fft_size(1024).smoothing(0.0);
+        let mut analyzer = SpectrumAnalyzer::new(config, 1, 48000);
+        let state = analyzer.state();
+
+        // a DC offset plus a signal at Nyquist, both at half scale
+        let signal = (0..4096)
+            .map(|frame| if frame % 2 == 0 { 1.0 } else { 0.0 })
+            .collect::<Vec<_>>();
+        analyzer.process(&signal);
+
+        let spectrum = state.spectrum();
+        assert!((spectrum.magnitudes[0] - 0.5).abs() < 1e-3);
+        assert!((spectrum.magnitudes[512] - 0.5).abs() < 1e-3);
+        assert!(spectrum.magnitude_db(256) < -100.0);
+    }
+}
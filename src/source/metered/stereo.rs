@@ -0,0 +1,159 @@
+use super::StereoImage;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Measures phase correlation, mid/side levels and goniometer points of the first two channels
+/// of an interleaved signal.
+pub(crate) struct StereoMeter {
+    channel_count: usize,
+    sum_left_right: f64,
+    sum_left_square: f64,
+    sum_right_square: f64,
+    sum_mid_square: f64,
+    sum_side_square: f64,
+    collected_frames: u64,
+    decimation: usize,
+    decimation_counter: usize,
+    points: Vec<(f32, f32)>,
+    points_pos: usize,
+    points_len: usize,
+}
+
+impl StereoMeter {
+    /// Create a new meter, which keeps one goniometer point every `decimation` frames.
+    pub fn new(channel_count: usize, decimation: usize) -> Self {
+        debug_assert!(channel_count >= 2, "Expecting at least two channels");
+        Self {
+            channel_count,
+            sum_left_right: 0.0,
+            sum_left_square: 0.0,
+            sum_right_square: 0.0,
+            sum_mid_square: 0.0,
+            sum_side_square: 0.0,
+            collected_frames: 0,
+            decimation: decimation.max(1),
+            decimation_counter: 0,
+            points: vec![(0.0, 0.0); StereoImage::MAX_GONIOMETER_POINTS],
+            points_pos: 0,
+            points_len: 0,
+        }
+    }
+
+    /// Accumulate the given interleaved signal.
+    pub fn process(&mut self, input: &[f32]) {
+        for frame in input.chunks_exact(self.channel_count) {
+            let (left, right) = (frame[0], frame[1]);
+            let mid = (left + right) * 0.5;
+            let side = (left - right) * 0.5;
+            self.sum_left_right += left as f64 * right as f64;
+            self.sum_left_square += left as f64 * left as f64;
+            self.sum_right_square += right as f64 * right as f64;
+            self.sum_mid_square += mid as f64 * mid as f64;
+            self.sum_side_square += side as f64 * side as f64;
+            self.collected_frames += 1;
+
+            self.decimation_counter += 1;
+            if self.decimation_counter >= self.decimation {
+                self.decimation_counter = 0;
+                // rotate by 45 degrees, so left-only signals lean to the left
+                self.points[self.points_pos] = (-side, mid);
+                self.points_pos = (self.points_pos + 1) % self.points.len();
+                self.points_len = (self.points_len + 1).min(self.points.len());
+            }
+        }
+    }
+
+    /// Write the accumulated metrics into the given image and restart accumulating.
+    pub fn publish(&mut self, image: &mut StereoImage) {
+        let denominator = (self.sum_left_square * self.sum_right_square).sqrt();
+        image.correlation = if denominator > 1e-12 {
+            (self.sum_left_right / denominator).clamp(-1.0, 1.0) as f32
+        } else {
+            0.0
+        };
+        let frames = self.collected_frames.max(1) as f64;
+        image.mid_rms = (self.sum_mid_square / frames).sqrt() as f32;
+        image.side_rms = (self.sum_side_square / frames).sqrt() as f32;
+
+        // copy points oldest first. The image's vec is preallocated, so this won't allocate.
+        image.goniometer.clear();
+        if self.points_len < self.points.len() {
+            image
+                .goniometer
+                .extend_from_slice(&self.points[..self.points_len]);
+        } else {
+            image
+                .goniometer
+                .extend_from_slice(&self.points[self.points_pos..]);
+            image
+                .goniometer
+                .extend_from_slice(&self.points[..self.points_pos]);
+        }
+
+        self.sum_left_right = 0.0;
+        self.sum_left_square = 0.0;
+        self.sum_right_square = 0.0;
+        self.sum_mid_square = 0.0;
+        self.sum_side_square = 0.0;
+        self.collected_frames = 0;
+        self.points_pos = 0;
+        self.points_len = 0;
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stereo_image() {
+        let sine = (0..4000)
+            .map(|index| (index as f32 * 0.05).sin() * 0.5)
+            .collect::<Vec<_>>();
+        let stereo_image = |left: &dyn Fn(f32) -> f32, right: &dyn Fn(f32) -> f32| {
+            let input = sine
+                .iter()
+                .flat_map(|value| [left(*value), right(*value)])
+                .collect::<Vec<_>>();
+            let mut meter = StereoMeter::new(2, 4);
+            let mut image = StereoImage::default();
+            meter.process(&input);
+            meter.publish(&mut image);
+            image
+        };
+
+        // mono
+        let image = stereo_image(&|v| v, &|v| v);
+        assert!((image.correlation - 1.0).abs() < 1e-4);
+        assert!((image.mid_side_balance() - 1.0).abs() < 1e-4);
+        assert_eq!(image.goniometer.len(), 512);
+        assert!(image.goniometer.iter().all(|(x, _)| *x == 0.0));
+
+        // out of phase
+        let image = stereo_image(&|v| v, &|v| -v);
+        assert!((image.correlation + 1.0).abs() < 1e-4);
+        assert!((image.mid_side_balance() + 1.0).abs() < 1e-4);
+
+        // left only
+        let image = stereo_image(&|v| v, &|_| 0.0);
+        assert_eq!(image.correlation, 0.0);
+        assert!(image.mid_side_balance().abs() < 1e-4);
+        assert!(image.goniometer.iter().all(|(x, y)| *x == -*y));
+
+        // points are ordered oldest first
+        let mut meter = StereoMeter::new(2, 1);
+        let mut image = StereoImage::default();
+        let ramp = (0..600)
+            .flat_map(|index| [index as f32, index as f32])
+            .collect::<Vec<_>>();
+        meter.process(&ramp);
+        meter.publish(&mut image);
+        assert_eq!(image.goniometer.first(), Some(&(0.0, 88.0)));
+        assert_eq!(image.goniometer.last(), Some(&(0.0, 599.0)));
+        meter.process(&ramp[..20]);
+        meter.publish(&mut image);
+        assert_eq!(image.goniometer.len(), 10);
+    }
+}
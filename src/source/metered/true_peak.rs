@@ -0,0 +1,104 @@
+use std::f64::consts::PI;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Oversampling factor and number of filter taps per polyphase branch, see ITU-R BS.1770
+/// Annex 2, which uses a 48 tap filter for 4x oversampling.
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Estimates inter-sample (true) peaks by 4x oversampling signals with a polyphase
+/// windowed-sinc interpolation filter.
+pub(crate) struct TruePeakMeter {
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    // per channel history of the last input samples, doubled to avoid wrapping in the FIR
+    history: Vec<[f32; 2 * TAPS_PER_PHASE]>,
+    history_pos: Vec<usize>,
+}
+
+impl TruePeakMeter {
+    pub fn new(channel_count: usize) -> Self {
+        // Blackman windowed sinc low-pass at the input's Nyquist frequency
+        let length = OVERSAMPLING * TAPS_PER_PHASE;
+        let center = (length - 1) as f64 / 2.0;
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+        for (phase, taps) in phases.iter_mut().enumerate() {
+            for (tap, coefficient) in taps.iter_mut().enumerate() {
+                let n = (tap * OVERSAMPLING + phase) as f64;
+                let x = (n - center) / OVERSAMPLING as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let w = 2.0 * PI * (n + 0.5) / length as f64;
+                let window = 0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+                *coefficient = (sinc * window) as f32;
+            }
+            // normalize each branch to unity DC gain
+            let sum = taps.iter().sum::<f32>();
+            taps.iter_mut().for_each(|coefficient| *coefficient /= sum);
+        }
+        Self {
+            phases,
+            history: vec![[0.0; 2 * TAPS_PER_PHASE]; channel_count],
+            history_pos: vec![0; channel_count],
+        }
+    }
+
+    /// Feed the next sample of the given channel and return the absolute true peak level
+    /// between the previous and the given sample, including the sample itself.
+    #[inline]
+    pub fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        let history = &mut self.history[channel];
+        let pos = &mut self.history_pos[channel];
+        *pos = (*pos + TAPS_PER_PHASE - 1) % TAPS_PER_PHASE;
+        history[*pos] = sample;
+        history[*pos + TAPS_PER_PHASE] = sample;
+        let window = &history[*pos..*pos + TAPS_PER_PHASE];
+        let mut peak = sample.abs();
+        for taps in &self.phases {
+            let value = taps
+                .iter()
+                .zip(window)
+                .map(|(coefficient, sample)| coefficient * sample)
+                .sum::<f32>();
+            peak = peak.max(value.abs());
+        }
+        peak
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn true_peak() {
+        // a full scale sine at fs/4, sampled at +/-45 degrees, has a sample peak of -3 dB
+        let mut meter = TruePeakMeter::new(1);
+        let mut sample_peak = 0.0_f32;
+        let mut true_peak = 0.0_f32;
+        for index in 0..1000 {
+            let phase = std::f32::consts::FRAC_PI_2 * index as f32 + std::f32::consts::FRAC_PI_4;
+            let sample = phase.sin();
+            sample_peak = sample_peak.max(sample.abs());
+            true_peak = true_peak.max(meter.process(0, sample));
+        }
+        let to_db = |value: f32| 20.0 * value.log10();
+        assert!((to_db(sample_peak) + 3.01).abs() < 0.01);
+        assert!(to_db(true_peak).abs() < 0.5, "{}", to_db(true_peak));
+
+        // DC passes with unity gain
+        let mut meter = TruePeakMeter::new(2);
+        let mut peak = 0.0;
+        for _ in 0..100 {
+            peak = meter.process(1, 0.5);
+        }
+        assert!((peak - 0.5).abs() < 1e-4);
+    }
+}
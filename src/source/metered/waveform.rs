@@ -0,0 +1,241 @@
+use std::sync::{
+    atomic::{AtomicU32, AtomicU64, Ordering},
+    Arc,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Trigger modes, which align waveforms read from a [`WaveformState`], so periodic signals
+/// show up as a stable picture in oscilloscope displays.
+///
+/// Triggers are detected on the mono downmix of all channels. When no trigger point can be
+/// found in the recorded history, the most recent frames are read instead.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum WaveformTrigger {
+    /// No alignment: read the most recent frames.
+    #[default]
+    Free,
+    /// Align to the last frame where the signal rises above the given level.
+    /// Use a level of `0.0` for zero-crossing alignment.
+    RisingEdge(f32),
+    /// Align to the last frame where the signal falls below the given level.
+    FallingEdge(f32),
+}
+
+impl WaveformTrigger {
+    /// Rising zero-crossing trigger.
+    pub const ZERO_CROSSING: Self = Self::RisingEdge(0.0);
+
+    fn triggers(&self, previous: f32, current: f32) -> bool {
+        match *self {
+            Self::Free => true,
+            Self::RisingEdge(level) => previous < level && current >= level,
+            Self::FallingEdge(level) => previous > level && current <= level,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A thread-safe handle to a [`WaveformState`].
+pub type SharedWaveformState = Arc<WaveformState>;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Fixed-size history of the most recent interleaved audio frames of a source, shared between
+/// a `MeteredSource` and its handles, e.g. to draw live waveforms.
+///
+/// Samples are stored in atomics, so the history can be read at any time without locking and
+/// without blocking the audio thread. The ring buffer holds twice the configured history
+/// length, so triggered reads can search for trigger points in older frames, and reads only
+/// get torn when the audio thread writes more than a history length while reading.
+pub struct WaveformState {
+    channel_count: usize,
+    sample_rate: u32,
+    frame_count: usize,
+    capacity: usize,
+    samples: Vec<AtomicU32>,
+    write_position: AtomicU64,
+}
+
+impl WaveformState {
+    fn new(frame_count: usize, channel_count: usize, sample_rate: u32) -> Self {
+        let frame_count = frame_count.max(1);
+        let capacity = 2 * frame_count;
+        Self {
+            channel_count,
+            sample_rate,
+            frame_count,
+            capacity,
+            samples: (0..capacity * channel_count)
+                .map(|_| AtomicU32::new(0))
+                .collect(),
+            write_position: AtomicU64::new(0),
+        }
+    }
+
+    /// Channel count of the recorded frames.
+    pub fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    /// Sample rate of the recorded frames.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Max number of frames which can be read at once.
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Total number of frames which got recorded so far. Can be used to check if new frames
+    /// are available.
+    pub fn write_position(&self) -> u64 {
+        self.write_position.load(Ordering::Acquire)
+    }
+
+    /// Copy recent interleaved frames into the given buffer without allocating, aligned with
+    /// the given trigger. Frames which have not been recorded yet read as silence.
+    ///
+    /// Reads up to [`Self::frame_count`] frames and returns the number of read frames.
+    pub fn read(&self, output: &mut [f32], trigger: WaveformTrigger) -> usize {
+        let channel_count = self.channel_count;
+        let frames = (output.len() / channel_count).min(self.frame_count);
+        let write_position = self.write_position() as i64;
+        let latest_start = write_position - frames as i64;
+        let start = match trigger {
+            WaveformTrigger::Free => latest_start,
+            _ => self
+                .find_trigger(trigger, latest_start, write_position)
+                .unwrap_or(latest_start),
+        };
+        for (frame, position) in output[..frames * channel_count]
+            .chunks_exact_mut(channel_count)
+            .zip(start..)
+        {
+            let offset = self.sample_offset(position);
+            for (sample, value) in frame.iter_mut().zip(&self.samples[offset..]) {
+                *sample = f32::from_bits(value.load(Ordering::Relaxed));
+            }
+        }
+        frames
+    }
+
+    /// Search backwards for the latest trigger position, which still has enough recorded
+    /// frames in front of it.
+    fn find_trigger(
+        &self,
+        trigger: WaveformTrigger,
+        latest_start: i64,
+        write_position: i64,
+    ) -> Option<i64> {
+        let oldest_start = (write_position - self.capacity as i64 + 1).max(1);
+        let mut current = self.mono_sample(latest_start);
+        for position in (oldest_start..=latest_start).rev() {
+            let previous = self.mono_sample(position - 1);
+            if trigger.triggers(previous, current) {
+                return Some(position);
+            }
+            current = previous;
+        }
+        None
+    }
+
+    fn mono_sample(&self, position: i64) -> f32 {
+        let offset = self.sample_offset(position);
+        self.samples[offset..offset + self.channel_count]
+            .iter()
+            .map(|value| f32::from_bits(value.load(Ordering::Relaxed)))
+            .sum::<f32>()
+            / self.channel_count as f32
+    }
+
+    #[inline]
+    fn sample_offset(&self, position: i64) -> usize {
+        position.rem_euclid(self.capacity as i64) as usize * self.channel_count
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Records interleaved frames into a [`WaveformState`].
+pub(crate) struct WaveformRecorder {
+    state: SharedWaveformState,
+}
+
+impl WaveformRecorder {
+    pub fn new(frame_count: usize, channel_count: usize, sample_rate: u32) -> Self {
+        Self {
+            state: Arc::new(WaveformState::new(frame_count, channel_count, sample_rate)),
+        }
+    }
+
+    /// Shared recorded history.
+    pub fn state(&self) -> SharedWaveformState {
+        Arc::clone(&self.state)
+    }
+
+    /// Record the given interleaved frames.
+    pub fn process(&mut self, input: &[f32]) {
+        let state = &self.state;
+        let channel_count = state.channel_count;
+        let mut position = state.write_position.load(Ordering::Relaxed) as i64;
+        for frame in input.chunks_exact(channel_count) {
+            let offset = state.sample_offset(position);
+            for (value, sample) in state.samples[offset..].iter().zip(frame) {
+                value.store(sample.to_bits(), Ordering::Relaxed);
+            }
+            position += 1;
+        }
+        state
+            .write_position
+            .store(position as u64, Ordering::Release);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_history() {
+        let mut recorder = WaveformRecorder::new(8, 2, 44100);
+        let state = recorder.state();
+
+        // unrecorded frames read as silence
+        let mut output = [1.0; 8];
+        assert_eq!(state.read(&mut output, WaveformTrigger::Free), 4);
+        assert_eq!(output, [0.0; 8]);
+
+        // reads the most recent frames
+        let ramp = (0..40).map(|index| index as f32).collect::<Vec<_>>();
+        recorder.process(&ramp);
+        assert_eq!(state.write_position(), 20);
+        assert_eq!(state.read(&mut output, WaveformTrigger::Free), 4);
+        assert_eq!(output, [32.0, 33.0, 34.0, 35.0, 36.0, 37.0, 38.0, 39.0]);
+
+        // reads at most frame_count frames
+        let mut output = [0.0; 32];
+        assert_eq!(state.read(&mut output, WaveformTrigger::Free), 8);
+        assert_eq!(output[0], 24.0);
+
+        // aligns to the latest trigger point with enough frames in front of it
+        let square = (0..16)
+            .flat_map(|index| {
+                let value = if index % 8 < 4 { 1.0 } else { -1.0 };
+                [value, value]
+            })
+            .collect::<Vec<_>>();
+        recorder.process(&square);
+        let mut output = [0.0; 4];
+        state.read(&mut output, WaveformTrigger::ZERO_CROSSING);
+        assert_eq!(output, [1.0, 1.0, 1.0, 1.0]);
+        state.read(&mut output, WaveformTrigger::FallingEdge(0.0));
+        assert_eq!(output, [-1.0, -1.0, -1.0, -1.0]);
+        state.read(&mut output, WaveformTrigger::RisingEdge(2.0));
+        assert_eq!(output, [-1.0, -1.0, -1.0, -1.0]);
+    }
+}
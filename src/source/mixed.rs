@@ -1,14 +1,22 @@
-use std::{collections::VecDeque, sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock, RwLockWriteGuard,
+    },
+    time::Duration,
+};
 
-use basedrop::Owned;
+use basedrop::{Owned, Shared};
 use crossbeam_queue::ArrayQueue;
 use four_cc::FourCC;
 
 use crate::{
     effect::EffectMessage,
     generator::GeneratorPlaybackMessage,
-    parameter::ParameterValueUpdate,
-    player::{EffectId, EffectMovement},
+    parameter::{AutomationLane, ParameterValueUpdate},
+    player::{EffectId, EffectMovement, MixerSendMode, StemMode},
     source::{
         amplified::AmplifiedSourceMessage, file::FilePlaybackMessage, panned::PannedSourceMessage,
         playback::PlaybackMessageQueue, Source, SourceTime,
@@ -16,16 +24,27 @@ use crate::{
     utils::{
         buffer::{add_buffers, clear_buffer},
         event::{Event, EventProcessor},
+        smoothing::{
+            apply_smoothed_gain, apply_smoothed_panning, ExponentialSmoothedValue,
+            LinearSmoothedValue, SmoothedValue,
+        },
     },
     GeneratorPlaybackEvent, MixerId, PlaybackId,
 };
 
 // -------------------------------------------------------------------------------------------------
 
+mod automation;
 mod effect;
+mod latency;
+mod send;
 mod submixer;
 
-pub(crate) use effect::EffectProcessor;
+use automation::{AutomationTarget, ParameterAutomation};
+
+pub(crate) use effect::{EffectProcessor, SourceEffectChain};
+pub(crate) use latency::LatencyCompensator;
+pub(crate) use send::{MixerSend, MixerSendBuffer, MixerSendTarget};
 pub(crate) use submixer::{SubMixerProcessingResult, SubMixerProcessor, SubMixerThreadPool};
 
 // -------------------------------------------------------------------------------------------------
@@ -39,6 +58,106 @@ pub(crate) struct PlayingSource {
     source: Owned<Box<dyn Source>>,
     start_time: u64,
     stop_time: Option<u64>,
+    /// Declicking fade, when the source moves in from or out to another mixer.
+    fade_gain: Option<LinearSmoothedValue>,
+    /// Set when the source moves to another mixer, after it faded out.
+    pending_move: Option<PendingSourceMove>,
+    /// Optional insert effects, which process the source's output.
+    effects: Option<Owned<SourceEffectChain>>,
+}
+
+impl PlayingSource {
+    /// True when the given event applies to the source or to one of its insert effects.
+    fn owns_event(&self, event: &MixerEvent) -> bool {
+        if let Some(playback_id) = event.playback_id() {
+            playback_id == self.playback_id
+        } else if let (Some(effect_id), Some(effects)) = (event.effect_id(), &self.effects) {
+            effects.contains(effect_id)
+        } else {
+            false
+        }
+    }
+
+    /// True when the given automation applies to the source or to one of its insert effects.
+    fn owns_automation(&self, automation: &ParameterAutomation) -> bool {
+        match automation.target() {
+            AutomationTarget::Generator(playback_id) => playback_id == self.playback_id,
+            AutomationTarget::Effect(effect_id) => self
+                .effects
+                .as_ref()
+                .is_some_and(|effects| effects.contains(effect_id)),
+        }
+    }
+}
+
+/// Mixer internal struct to keep track of a source which moves to another mixer.
+struct PendingSourceMove {
+    target_mixer_queue: Arc<ArrayQueue<MixerMessage>>,
+    source_mixer_queue: SourceMixerEventQueue,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Mixer event queue of a playing source, which gets redirected to another mixer's event queue
+/// when the source moves to another mixer.
+///
+/// Used by playback handles and the player to schedule source events in the mixer which
+/// currently plays the source.
+#[derive(Clone)]
+pub(crate) struct SourceMixerEventQueue(Arc<RwLock<Arc<ArrayQueue<MixerMessage>>>>);
+
+impl SourceMixerEventQueue {
+    pub fn new(mixer_event_queue: Arc<ArrayQueue<MixerMessage>>) -> Self {
+        Self(Arc::new(RwLock::new(mixer_event_queue)))
+    }
+
+    /// Push a message into the current mixer's event queue. See [`ArrayQueue::push`].
+    #[allow(clippy::result_large_err)]
+    pub fn push(&self, message: MixerMessage) -> Result<(), MixerMessage> {
+        // NB: push while holding the lock, so messages won't get lost when the source moves
+        let queue = self.0.read().unwrap_or_else(|err| err.into_inner());
+        queue.push(message)
+    }
+
+    /// Force push a message into the current mixer's event queue. See [`ArrayQueue::force_push`].
+    pub fn force_push(&self, message: MixerMessage) -> Option<MixerMessage> {
+        let queue = self.0.read().unwrap_or_else(|err| err.into_inner());
+        queue.force_push(message)
+    }
+
+    /// Try to lock the queue for redirecting it, without blocking.
+    /// Used in the mixer's real-time thread when a source moved to another mixer.
+    fn try_lock_for_redirect(&self) -> Option<RwLockWriteGuard<'_, Arc<ArrayQueue<MixerMessage>>>> {
+        self.0.try_write().ok()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Single slot queue, which hands over a sub-mixer processor from its old to its new parent
+/// mixer, when a sub-mixer moves to another parent mixer.
+pub(crate) type SubMixerHandover = Shared<ArrayQueue<Owned<SubMixerProcessor>>>;
+
+/// Mixer internal struct to keep track of a sub-mixer which is moving in from another mixer.
+struct PendingSubMixer {
+    mixer_id: MixerId,
+    stage: usize,
+    handover: SubMixerHandover,
+    /// Set when the sub-mixer moved on to yet another mixer before it arrived here.
+    forward_handover: Option<SubMixerHandover>,
+    /// Set when the sub-mixer's direct output changed before it arrived here.
+    direct_output: Option<Option<Shared<MixerSendBuffer>>>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Mixer internal struct to keep track of a stem tap, which copies the mixer's signal into a
+/// buffer while rendering stems.
+struct StemTap {
+    tap_id: usize,
+    mode: StemMode,
+    gain: ExponentialSmoothedValue,
+    buffer: Shared<MixerSendBuffer>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -73,6 +192,32 @@ pub(crate) enum MixerEvent {
         event: GeneratorPlaybackEvent,
         sample_time: u64,
     },
+    AutomateGeneratorParameter {
+        playback_id: PlaybackId,
+        parameter_id: FourCC,
+        lane: Owned<AutomationLane>,
+        sample_time: u64,
+    },
+    // Fader
+    SetMixerVolume {
+        volume: f32,
+        sample_time: u64,
+    },
+    SetMixerPanning {
+        panning: f32,
+        sample_time: u64,
+    },
+    SetMixerMuteState {
+        muted: bool,
+        sources_muted: bool,
+        sample_time: u64,
+    },
+    // Sends
+    SetSendLevel {
+        target_mixer_id: MixerId,
+        level: f32,
+        sample_time: u64,
+    },
     // Effects
     ProcessEffectMessage {
         effect_id: EffectId,
@@ -90,6 +235,54 @@ pub(crate) enum MixerEvent {
         values: Owned<Vec<(FourCC, ParameterValueUpdate)>>,
         sample_time: u64,
     },
+    AutomateEffectParameter {
+        effect_id: EffectId,
+        parameter_id: FourCC,
+        lane: Owned<AutomationLane>,
+        sample_time: u64,
+    },
+    SetEffectModulation {
+        effect_id: EffectId,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+        sample_time: u64,
+    },
+    ClearEffectModulation {
+        effect_id: EffectId,
+        source: FourCC,
+        target: FourCC,
+        sample_time: u64,
+    },
+}
+
+impl MixerEvent {
+    /// Playback id of the source the event applies to, if any.
+    fn playback_id(&self) -> Option<PlaybackId> {
+        match self {
+            Self::SeekSource { playback_id, .. }
+            | Self::SetSourceSpeed { playback_id, .. }
+            | Self::SetSourceVolume { playback_id, .. }
+            | Self::SetSourcePanning { playback_id, .. }
+            | Self::TriggerGeneratorEvent { playback_id, .. }
+            | Self::AutomateGeneratorParameter { playback_id, .. } => Some(*playback_id),
+            _ => None,
+        }
+    }
+
+    /// Id of the effect the event applies to, if any.
+    fn effect_id(&self) -> Option<EffectId> {
+        match self {
+            Self::ProcessEffectMessage { effect_id, .. }
+            | Self::ProcessEffectParameterUpdate { effect_id, .. }
+            | Self::ProcessEffectParameterUpdates { effect_id, .. }
+            | Self::AutomateEffectParameter { effect_id, .. }
+            | Self::SetEffectModulation { effect_id, .. }
+            | Self::ClearEffectModulation { effect_id, .. } => Some(*effect_id),
+            _ => None,
+        }
+    }
 }
 
 impl Event for MixerEvent {
@@ -100,9 +293,17 @@ impl Event for MixerEvent {
             Self::SetSourceVolume { sample_time, .. } => *sample_time,
             Self::SetSourcePanning { sample_time, .. } => *sample_time,
             Self::TriggerGeneratorEvent { sample_time, .. } => *sample_time,
+            Self::AutomateGeneratorParameter { sample_time, .. } => *sample_time,
+            Self::SetMixerVolume { sample_time, .. } => *sample_time,
+            Self::SetMixerPanning { sample_time, .. } => *sample_time,
+            Self::SetMixerMuteState { sample_time, .. } => *sample_time,
+            Self::SetSendLevel { sample_time, .. } => *sample_time,
             Self::ProcessEffectMessage { sample_time, .. } => *sample_time,
             Self::ProcessEffectParameterUpdate { sample_time, .. } => *sample_time,
             Self::ProcessEffectParameterUpdates { sample_time, .. } => *sample_time,
+            Self::AutomateEffectParameter { sample_time, .. } => *sample_time,
+            Self::SetEffectModulation { sample_time, .. } => *sample_time,
+            Self::ClearEffectModulation { sample_time, .. } => *sample_time,
         }
     }
 }
@@ -119,6 +320,7 @@ pub(crate) enum MixerMessage {
         playback_id: PlaybackId,
         playback_message_queue: PlaybackMessageQueue,
         source: Owned<Box<dyn Source>>,
+        effects: Option<Owned<SourceEffectChain>>,
         sample_time: u64,
     },
     SetSourceSpeed {
@@ -149,12 +351,29 @@ pub(crate) enum MixerMessage {
     RemoveSource {
         playback_id: PlaybackId,
     },
+    MoveSource {
+        playback_id: PlaybackId,
+        target_mixer_queue: Arc<ArrayQueue<MixerMessage>>,
+        source_mixer_queue: SourceMixerEventQueue,
+    },
+    AddMovedSource {
+        source: PlayingSource,
+    },
+    AddMovedSourceEvent {
+        event: MixerEvent,
+    },
     // Generators
     TriggerGeneratorEvent {
         playback_id: PlaybackId,
         event: GeneratorPlaybackEvent,
         sample_time: u64,
     },
+    AutomateGeneratorParameter {
+        playback_id: PlaybackId,
+        parameter_id: FourCC,
+        lane: Owned<AutomationLane>,
+        sample_time: u64,
+    },
     // Mixers
     AddMixer {
         mixer_id: MixerId,
@@ -163,6 +382,75 @@ pub(crate) enum MixerMessage {
     RemoveMixer {
         mixer_id: MixerId,
     },
+    // Hand over a sub-mixer to a new parent mixer
+    DetachMixer {
+        mixer_id: MixerId,
+        handover: SubMixerHandover,
+    },
+    // Receive a sub-mixer from its old parent mixer
+    AttachMixer {
+        mixer_id: MixerId,
+        stage: usize,
+        handover: SubMixerHandover,
+    },
+    SetMixerStage {
+        mixer_id: MixerId,
+        stage: usize,
+    },
+    // Route a sub-mixer's output into the given buffer instead of mixing it into this mixer
+    SetMixerDirectOutput {
+        mixer_id: MixerId,
+        buffer: Option<Shared<MixerSendBuffer>>,
+    },
+    // Fader
+    SetMixerVolume {
+        volume: f32,
+        sample_time: u64,
+    },
+    SetMixerPanning {
+        panning: f32,
+        sample_time: u64,
+    },
+    SetMixerMuteState {
+        // mute the mixer's output
+        muted: bool,
+        // mute the mixer's own sources only, e.g. when a sub-mixer is soloed
+        sources_muted: bool,
+        sample_time: u64,
+    },
+    // Sends
+    AddSend {
+        target: MixerSendTarget,
+        mode: MixerSendMode,
+        level: f32,
+        buffer: Shared<MixerSendBuffer>,
+    },
+    RemoveSend {
+        target: MixerSendTarget,
+    },
+    SetSendLevel {
+        target_mixer_id: MixerId,
+        level: f32,
+        sample_time: u64,
+    },
+    AddSendInput {
+        source_mixer_id: MixerId,
+        buffer: Shared<MixerSendBuffer>,
+    },
+    RemoveSendInput {
+        source_mixer_id: MixerId,
+    },
+    // Stems
+    #[cfg_attr(not(feature = "wav-output"), allow(dead_code))]
+    AddStemTap {
+        tap_id: usize,
+        mode: StemMode,
+        buffer: Shared<MixerSendBuffer>,
+    },
+    #[cfg_attr(not(feature = "wav-output"), allow(dead_code))]
+    RemoveStemTap {
+        tap_id: usize,
+    },
     // Effects
     AddEffect {
         effect_id: EffectId,
@@ -175,6 +463,10 @@ pub(crate) enum MixerMessage {
     RemoveEffect {
         effect_id: EffectId,
     },
+    SetEffectSidechain {
+        effect_id: EffectId,
+        buffer: Option<Shared<MixerSendBuffer>>,
+    },
     ProcessEffectMessage {
         effect_id: EffectId,
         message: Owned<Box<dyn EffectMessage>>,
@@ -191,18 +483,52 @@ pub(crate) enum MixerMessage {
         values: Owned<Vec<(FourCC, ParameterValueUpdate)>>,
         sample_time: u64,
     },
+    AutomateEffectParameter {
+        effect_id: EffectId,
+        parameter_id: FourCC,
+        lane: Owned<AutomationLane>,
+        sample_time: u64,
+    },
+    SetEffectModulation {
+        effect_id: EffectId,
+        source: FourCC,
+        target: FourCC,
+        amount: f32,
+        bipolar: bool,
+        sample_time: u64,
+    },
+    ClearEffectModulation {
+        effect_id: EffectId,
+        source: FourCC,
+        target: FourCC,
+        sample_time: u64,
+    },
 }
 
 // -------------------------------------------------------------------------------------------------
 
 /// A [`Source`] which converts and mixes other sources together.
+///
+/// Latencies of sub-mixers, sends and effects get compensated: all mixer inputs get delayed to
+/// match the input with the highest latency before they are summed, so they stay sample-aligned.
 pub struct MixedSource {
     playing_sources: VecDeque<PlayingSource>,
     mixers: Vec<(MixerId, Owned<SubMixerProcessor>)>,
+    pending_mixers: Vec<PendingSubMixer>,
     effects: Vec<(EffectId, Owned<EffectProcessor>)>,
     effects_bypassed: bool,
+    sends: Vec<MixerSend>,
+    send_inputs: Vec<(MixerId, Shared<MixerSendBuffer>)>,
+    stem_taps: Vec<StemTap>,
+    volume: ExponentialSmoothedValue,
+    panning: ExponentialSmoothedValue,
+    mute_gain: ExponentialSmoothedValue,
+    sources_gain: ExponentialSmoothedValue,
+    sources_latency_compensator: LatencyCompensator,
+    latency: Arc<AtomicUsize>,
     message_queue: Arc<ArrayQueue<MixerMessage>>,
     events: VecDeque<MixerEvent>,
+    automations: Vec<ParameterAutomation>,
     channel_count: usize,
     sample_rate: u32,
     mix_buffer: Vec<f32>,
@@ -215,6 +541,10 @@ impl MixedSource {
     /// Sources and Effects will never requested to produce more samples than this const.
     pub const MAX_MIX_BUFFER_SAMPLES: usize = 8 * 1024;
 
+    /// Duration of the declicking fade-out and fade-in in seconds, when a source or sub-mixer
+    /// moves from one mixer to another one.
+    const MOVE_FADE_TIME: f32 = 0.005;
+
     /// Create a new mixer source with the given signal specs.
     pub fn new(channel_count: usize, sample_rate: u32) -> Self {
         // prealloc playing source, sub mixer and effect lists
@@ -222,9 +552,25 @@ impl MixedSource {
         let playing_sources = VecDeque::with_capacity(PLAYING_EVENTS_CAPACITY);
         const MIXERS_CAPACITY: usize = 16;
         let mixers = Vec::with_capacity(MIXERS_CAPACITY);
+        let pending_mixers = Vec::with_capacity(MIXERS_CAPACITY);
         const EFFECTS_CAPACITY: usize = 16;
         let effects = Vec::with_capacity(EFFECTS_CAPACITY);
         let effects_bypassed = true;
+        const SENDS_CAPACITY: usize = 16;
+        let sends = Vec::with_capacity(SENDS_CAPACITY);
+        let send_inputs = Vec::with_capacity(SENDS_CAPACITY);
+        const STEM_TAPS_CAPACITY: usize = 16;
+        let stem_taps = Vec::with_capacity(STEM_TAPS_CAPACITY);
+
+        // fader state
+        let volume = ExponentialSmoothedValue::new(1.0, sample_rate);
+        let panning = ExponentialSmoothedValue::new(0.0, sample_rate);
+        let mute_gain = ExponentialSmoothedValue::new(1.0, sample_rate);
+        let sources_gain = ExponentialSmoothedValue::new(1.0, sample_rate);
+
+        // latency compensation state
+        let sources_latency_compensator = LatencyCompensator::new(channel_count, sample_rate);
+        let latency = Arc::new(AtomicUsize::new(0));
 
         // processing state
         let thread_pool = None;
@@ -234,6 +580,8 @@ impl MixedSource {
         let message_queue = Arc::new(ArrayQueue::new(MESSAGE_QUEUE_SIZE));
         const EVENTS_CAPACITY: usize = 4096;
         let events = VecDeque::with_capacity(EVENTS_CAPACITY);
+        const AUTOMATIONS_CAPACITY: usize = 256;
+        let automations = Vec::with_capacity(AUTOMATIONS_CAPACITY);
 
         // create temp mix buffer
         let mix_buffer = vec![0.0; Self::MAX_MIX_BUFFER_SAMPLES];
@@ -244,9 +592,20 @@ impl MixedSource {
         Self {
             playing_sources,
             mixers,
+            pending_mixers,
             events,
+            automations,
             effects,
             effects_bypassed,
+            sends,
+            send_inputs,
+            stem_taps,
+            volume,
+            panning,
+            mute_gain,
+            sources_gain,
+            sources_latency_compensator,
+            latency,
             message_queue,
             channel_count,
             sample_rate,
@@ -262,6 +621,17 @@ impl MixedSource {
         self.message_queue.clone()
     }
 
+    /// Processing latency of the mixer's output in sample frames, as calculated in the last
+    /// processing run: the max latency of all mixer inputs plus the latency of all effects.
+    pub fn latency(&self) -> usize {
+        self.latency.load(Ordering::Relaxed)
+    }
+
+    /// Shared access to the mixer's processing latency, which can be read from other threads.
+    pub(crate) fn latency_state(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.latency)
+    }
+
     /// Configure thread pool for concurrent processing for this mixer.
     /// Should be called once during initialization *before any processing begins*.
     pub(crate) fn set_thread_pool(&mut self, thread_pool: Option<SubMixerThreadPool>) {
@@ -309,6 +679,7 @@ impl MixedSource {
                     playback_id,
                     playback_message_queue,
                     source,
+                    effects,
                     sample_time,
                 } => {
                     debug_assert_eq!(
@@ -336,6 +707,9 @@ impl MixedSource {
                             source,
                             start_time: sample_time,
                             stop_time: None,
+                            fade_gain: None,
+                            pending_move: None,
+                            effects,
                         },
                     );
                 }
@@ -400,6 +774,52 @@ impl MixedSource {
                 MixerMessage::RemoveSource { playback_id } => {
                     self.remove_matching_sources(|s| s.playback_id == playback_id);
                 }
+                MixerMessage::MoveSource {
+                    playback_id,
+                    target_mixer_queue,
+                    source_mixer_queue,
+                } => {
+                    let fade_samples = self.move_fade_samples();
+                    if let Some(source) = self
+                        .playing_sources
+                        .iter_mut()
+                        .find(|s| s.playback_id == playback_id)
+                    {
+                        // fade out sources which already started, then move them
+                        if source.start_time <= time.pos_in_frames {
+                            let fade_gain = source.fade_gain.get_or_insert_with(|| {
+                                LinearSmoothedValue::new(1.0, self.sample_rate)
+                            });
+                            fade_gain.set_target_with_duration(0.0, Some(fade_samples));
+                        }
+                        source.pending_move = Some(PendingSourceMove {
+                            target_mixer_queue,
+                            source_mixer_queue,
+                        });
+                    } else {
+                        log::warn!("Failed to move source {playback_id}: source is not playing");
+                    }
+                }
+                MixerMessage::AddMovedSource { mut source } => {
+                    // fade in sources which already started
+                    if source.start_time <= time.pos_in_frames {
+                        let fade_samples = self.move_fade_samples();
+                        let mut fade_gain = LinearSmoothedValue::new(0.0, self.sample_rate);
+                        fade_gain.set_target_with_duration(1.0, Some(fade_samples));
+                        source.fade_gain = Some(fade_gain);
+                    } else {
+                        source.fade_gain = None;
+                    }
+                    // sort playing_sources by start time
+                    let playing_sources = &mut self.playing_sources;
+                    let insert_pos = playing_sources
+                        .make_contiguous()
+                        .partition_point(|e| e.start_time < source.start_time);
+                    playing_sources.insert(insert_pos, source);
+                }
+                MixerMessage::AddMovedSourceEvent { event } => {
+                    self.insert_event(event);
+                }
                 // Generators
                 MixerMessage::TriggerGeneratorEvent {
                     playback_id,
@@ -412,15 +832,179 @@ impl MixedSource {
                         sample_time,
                     });
                 }
+                MixerMessage::AutomateGeneratorParameter {
+                    playback_id,
+                    parameter_id,
+                    lane,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::AutomateGeneratorParameter {
+                        playback_id,
+                        parameter_id,
+                        lane,
+                        sample_time,
+                    });
+                }
                 // Mixers
                 MixerMessage::AddMixer {
                     mixer_id,
                     mixer_processor: mixer_proessor,
                 } => {
                     self.mixers.push((mixer_id, mixer_proessor));
+                    self.sort_mixers_by_stage();
                 }
                 MixerMessage::RemoveMixer { mixer_id } => {
                     self.mixers.retain(|(id, _)| *id != mixer_id);
+                    self.pending_mixers.retain(|p| p.mixer_id != mixer_id);
+                }
+                MixerMessage::DetachMixer { mixer_id, handover } => {
+                    let fade_samples = self.move_fade_samples();
+                    if let Some((_, mixer)) = self.mixers.iter_mut().find(|(id, _)| *id == mixer_id)
+                    {
+                        // fade out the mixer, then hand it over
+                        mixer.fade_out(fade_samples, handover);
+                    } else if let Some(pending) = self
+                        .pending_mixers
+                        .iter_mut()
+                        .find(|p| p.mixer_id == mixer_id)
+                    {
+                        pending.forward_handover = Some(handover);
+                    } else {
+                        log::warn!("Mixer with id {mixer_id} not found for moving");
+                    }
+                }
+                MixerMessage::AttachMixer {
+                    mixer_id,
+                    stage,
+                    handover,
+                } => {
+                    self.pending_mixers.push(PendingSubMixer {
+                        mixer_id,
+                        stage,
+                        handover,
+                        forward_handover: None,
+                        direct_output: None,
+                    });
+                }
+                MixerMessage::SetMixerStage { mixer_id, stage } => {
+                    if let Some((_, mixer)) = self.mixers.iter_mut().find(|(id, _)| *id == mixer_id)
+                    {
+                        mixer.set_stage(stage);
+                        self.sort_mixers_by_stage();
+                    } else if let Some(pending) = self
+                        .pending_mixers
+                        .iter_mut()
+                        .find(|p| p.mixer_id == mixer_id)
+                    {
+                        pending.stage = stage;
+                    } else {
+                        log::warn!("Mixer with id {mixer_id} not found for stage update");
+                    }
+                }
+                MixerMessage::SetMixerDirectOutput { mixer_id, buffer } => {
+                    if let Some((_, mixer)) = self.mixers.iter_mut().find(|(id, _)| *id == mixer_id)
+                    {
+                        mixer.set_direct_output(buffer);
+                    } else if let Some(pending) = self
+                        .pending_mixers
+                        .iter_mut()
+                        .find(|p| p.mixer_id == mixer_id)
+                    {
+                        pending.direct_output = Some(buffer);
+                    } else {
+                        log::warn!("Mixer with id {mixer_id} not found for output update");
+                    }
+                }
+                // Fader
+                MixerMessage::SetMixerVolume {
+                    volume,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::SetMixerVolume {
+                        volume,
+                        sample_time,
+                    });
+                }
+                MixerMessage::SetMixerPanning {
+                    panning,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::SetMixerPanning {
+                        panning,
+                        sample_time,
+                    });
+                }
+                MixerMessage::SetMixerMuteState {
+                    muted,
+                    sources_muted,
+                    sample_time,
+                } => {
+                    // the new state replaces all mute states which are scheduled later on
+                    self.remove_matching_events(|event| {
+                        matches!(event, MixerEvent::SetMixerMuteState { .. })
+                            && event.sample_time() >= sample_time
+                    });
+                    self.insert_event(MixerEvent::SetMixerMuteState {
+                        muted,
+                        sources_muted,
+                        sample_time,
+                    });
+                }
+                // Sends
+                MixerMessage::AddSend {
+                    target,
+                    mode,
+                    level,
+                    buffer,
+                } => {
+                    self.sends.retain(|send| send.target() != target);
+                    self.sends.push(MixerSend::new(
+                        target,
+                        mode,
+                        level,
+                        self.sample_rate,
+                        buffer,
+                    ));
+                }
+                MixerMessage::RemoveSend { target } => {
+                    self.sends.retain(|send| send.target() != target);
+                }
+                MixerMessage::SetSendLevel {
+                    target_mixer_id,
+                    level,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::SetSendLevel {
+                        target_mixer_id,
+                        level,
+                        sample_time,
+                    });
+                }
+                MixerMessage::AddSendInput {
+                    source_mixer_id,
+                    buffer,
+                } => {
+                    self.send_inputs.retain(|(id, _)| *id != source_mixer_id);
+                    self.send_inputs.push((source_mixer_id, buffer));
+                }
+                MixerMessage::RemoveSendInput { source_mixer_id } => {
+                    self.send_inputs.retain(|(id, _)| *id != source_mixer_id);
+                }
+                // Stems
+                MixerMessage::AddStemTap {
+                    tap_id,
+                    mode,
+                    buffer,
+                } => {
+                    self.stem_taps.push(StemTap {
+                        tap_id,
+                        mode,
+                        gain: ExponentialSmoothedValue::new(1.0, self.sample_rate),
+                        buffer,
+                    });
+                }
+                MixerMessage::RemoveStemTap { tap_id } => {
+                    self.stem_taps.retain(|tap| tap.tap_id != tap_id);
                 }
                 // Effects
                 MixerMessage::AddEffect {
@@ -436,6 +1020,17 @@ impl MixedSource {
                         if self.effects.is_empty() {
                             self.effects_bypassed = true;
                         }
+                        let target = AutomationTarget::Effect(effect_id);
+                        self.automations.retain(|a| a.target() != target);
+                    }
+                }
+                MixerMessage::SetEffectSidechain { effect_id, buffer } => {
+                    if let Some((_, effect)) =
+                        self.effects.iter_mut().find(|(id, _)| *id == effect_id)
+                    {
+                        effect.set_sidechain(buffer);
+                    } else {
+                        log::warn!("Effect with id {effect_id} not found for sidechain operation");
                     }
                 }
                 MixerMessage::MoveEffect {
@@ -494,17 +1089,354 @@ impl MixedSource {
                         sample_time,
                     });
                 }
+                MixerMessage::AutomateEffectParameter {
+                    effect_id,
+                    parameter_id,
+                    lane,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::AutomateEffectParameter {
+                        effect_id,
+                        parameter_id,
+                        lane,
+                        sample_time,
+                    });
+                }
+                MixerMessage::SetEffectModulation {
+                    effect_id,
+                    source,
+                    target,
+                    amount,
+                    bipolar,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::SetEffectModulation {
+                        effect_id,
+                        source,
+                        target,
+                        amount,
+                        bipolar,
+                        sample_time,
+                    });
+                }
+                MixerMessage::ClearEffectModulation {
+                    effect_id,
+                    source,
+                    target,
+                    sample_time,
+                } => {
+                    self.insert_event(MixerEvent::ClearEffectModulation {
+                        effect_id,
+                        source,
+                        target,
+                        sample_time,
+                    });
+                }
+            }
+        }
+    }
+
+    // Duration of declicking fades in samples, when sources or sub-mixers move from or to other
+    // mixers.
+    fn move_fade_samples(&self) -> u32 {
+        let fade_frames = (Self::MOVE_FADE_TIME * self.sample_rate as f32).ceil() as u32;
+        fade_frames.max(1) * self.channel_count as u32
+    }
+
+    // Hand over sources which faded out after they got moved to another mixer, together with
+    // their pending events. This also redirects the source's handle queue to the new mixer.
+    fn process_source_moves(&mut self) {
+        let mut index = 0;
+        while index < self.playing_sources.len() {
+            let playing_source = &self.playing_sources[index];
+            let is_faded_out = playing_source
+                .fade_gain
+                .as_ref()
+                .is_none_or(|fade_gain| !fade_gain.need_ramp());
+            let Some(pending_move) = playing_source
+                .pending_move
+                .as_ref()
+                .filter(|_| playing_source.is_active && is_faded_out)
+            else {
+                index += 1;
+                continue;
+            };
+            // lock the source's queue, so no new source messages get pushed to us while moving.
+            // when it's currently locked by some handle, try again in the next run.
+            let source_mixer_queue = pending_move.source_mixer_queue.clone();
+            let Some(mut redirected_queue) = source_mixer_queue.try_lock_for_redirect() else {
+                index += 1;
+                continue;
+            };
+            let mut playing_source = self
+                .playing_sources
+                .remove(index)
+                .expect("Expecting a valid source index");
+            let playback_id = playing_source.playback_id;
+            let target_mixer_queue = playing_source
+                .pending_move
+                .take()
+                .expect("Expecting a pending source move")
+                .target_mixer_queue;
+            // move pending source and insert effect events
+            let mut event_index = 0;
+            while event_index < self.events.len() {
+                if playing_source.owns_event(&self.events[event_index]) {
+                    let event = self
+                        .events
+                        .remove(event_index)
+                        .expect("Expecting a valid event index");
+                    if target_mixer_queue
+                        .push(MixerMessage::AddMovedSourceEvent { event })
+                        .is_err()
+                    {
+                        log::warn!(
+                            "Failed to move source {playback_id} event: mixer's event queue is full"
+                        );
+                    }
+                } else {
+                    event_index += 1;
+                }
+            }
+            // move running automations of the source and its insert effects
+            let mut automation_index = 0;
+            while automation_index < self.automations.len() {
+                if playing_source.owns_automation(&self.automations[automation_index]) {
+                    let event = self.automations.swap_remove(automation_index).into_event();
+                    if target_mixer_queue
+                        .push(MixerMessage::AddMovedSourceEvent { event })
+                        .is_err()
+                    {
+                        log::warn!(
+                            "Failed to move source {playback_id} automation: \
+                            mixer's event queue is full"
+                        );
+                    }
+                } else {
+                    automation_index += 1;
+                }
+            }
+            // move source
+            if target_mixer_queue
+                .push(MixerMessage::AddMovedSource {
+                    source: playing_source,
+                })
+                .is_err()
+            {
+                log::warn!("Failed to move source {playback_id}: mixer's event queue is full");
+            }
+            // redirect the source's handles to the new mixer
+            *redirected_queue = target_mixer_queue;
+        }
+    }
+
+    // Hand over sub-mixers which faded out after they got moved to another parent mixer.
+    fn process_mixer_moves(&mut self) {
+        let mut index = 0;
+        while index < self.mixers.len() {
+            let Some(handover) = self.mixers[index].1.take_faded_out_handover() else {
+                index += 1;
+                continue;
+            };
+            let (mixer_id, mixer_processor) = self.mixers.remove(index);
+            if handover.push(mixer_processor).is_err() {
+                log::warn!("Failed to move mixer {mixer_id}: handover is occupied");
+            }
+        }
+    }
+
+    // Attach sub-mixers which arrived from their old parent mixers, or forward them to their new
+    // parent when they moved on in the meantime.
+    fn process_pending_mixers(&mut self) {
+        let mut index = 0;
+        while index < self.pending_mixers.len() {
+            let Some(mut mixer_processor) = self.pending_mixers[index].handover.pop() else {
+                index += 1;
+                continue;
+            };
+            let pending = self.pending_mixers.remove(index);
+            if let Some(direct_output) = pending.direct_output {
+                mixer_processor.set_direct_output(direct_output);
+            }
+            if let Some(forward_handover) = pending.forward_handover {
+                if forward_handover.push(mixer_processor).is_err() {
+                    let mixer_id = pending.mixer_id;
+                    log::warn!("Failed to move mixer {mixer_id}: handover is occupied");
+                }
+            } else {
+                mixer_processor.set_stage(pending.stage);
+                mixer_processor.fade_in(self.move_fade_samples());
+                self.mixers.push((pending.mixer_id, mixer_processor));
+                self.sort_mixers_by_stage();
+            }
+        }
+    }
+
+    // Find a mixer effect or an insert effect of a playing source with the given id.
+    fn effect_processor_mut(&mut self, effect_id: EffectId) -> Option<&mut EffectProcessor> {
+        if let Some((_, effect)) = self.effects.iter_mut().find(|(id, _)| *id == effect_id) {
+            return Some(effect);
+        }
+        self.playing_sources
+            .iter_mut()
+            .filter_map(|source| source.effects.as_mut())
+            .find_map(|effects| effects.effect_processor_mut(effect_id))
+    }
+
+    // Start a parameter automation, replacing running automations of the same parameter.
+    fn start_automation(&mut self, automation: ParameterAutomation) {
+        let (target, parameter_id) = (automation.target(), automation.parameter_id());
+        self.cancel_automations(target, |id| id == parameter_id);
+        self.automations.push(automation);
+    }
+
+    // Stop running automations of the given target's parameters which match the given filter.
+    fn cancel_automations<F>(&mut self, target: AutomationTarget, parameter_filter: F)
+    where
+        F: Fn(FourCC) -> bool,
+    {
+        self.automations
+            .retain(|a| a.target() != target || !parameter_filter(a.parameter_id()));
+    }
+
+    // Apply values of all running automations which are due at the given time, and drop
+    // finished automations and automations of targets which no longer exist.
+    fn process_automations(&mut self, current_time: u64) {
+        let mut index = 0;
+        while index < self.automations.len() {
+            let automation = &self.automations[index];
+            let (target, parameter_id) = (automation.target(), automation.parameter_id());
+            if let Some(value) = automation.pending_value(current_time) {
+                match self.apply_automation_value(target, parameter_id, value) {
+                    Some(true) => self.automations[index].set_applied_value(value),
+                    Some(false) => (), // retry in the next block
+                    None => {
+                        // target is gone or rejected the value
+                        self.automations.swap_remove(index);
+                        continue;
+                    }
+                }
             }
+            if self.automations[index].is_finished(current_time) {
+                self.automations.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    // Apply a normalized automation value to an effect or generator parameter. Returns `None`
+    // when the target no longer exists or rejected the value, else if the value got applied.
+    fn apply_automation_value(
+        &mut self,
+        target: AutomationTarget,
+        parameter_id: FourCC,
+        value: f32,
+    ) -> Option<bool> {
+        match target {
+            AutomationTarget::Effect(effect_id) => {
+                let effect_processor = self.effect_processor_mut(effect_id)?;
+                Self::apply_effect_automation_value(
+                    effect_processor,
+                    effect_id,
+                    parameter_id,
+                    value,
+                )
+            }
+            AutomationTarget::Generator(playback_id) => {
+                let source = self
+                    .playing_sources
+                    .iter()
+                    .find(|s| s.playback_id == playback_id)?;
+                Self::apply_generator_automation_value(
+                    &source.playback_message_queue,
+                    parameter_id,
+                    value,
+                )
+            }
+        }
+    }
+
+    // Apply a normalized automation value to the given effect's parameter.
+    // Returns `None` when the effect rejected the value, so the automation should get dropped.
+    fn apply_effect_automation_value(
+        effect_processor: &mut EffectProcessor,
+        effect_id: EffectId,
+        parameter_id: FourCC,
+        value: f32,
+    ) -> Option<bool> {
+        let effect = effect_processor.effect_mut();
+        let update = ParameterValueUpdate::Normalized(value);
+        if let Err(err) = effect.process_parameter_update(parameter_id, &update) {
+            log::error!(
+                "Failed to automate parameter '{parameter_id}' on effect {effect_id}: {err}"
+            );
+            return None;
         }
+        Some(true)
+    }
+
+    // Send a normalized automation value to the generator of the given playback message queue.
+    // Returns `None` when the source is no generator, else if the value got sent.
+    fn apply_generator_automation_value(
+        playback_message_queue: &PlaybackMessageQueue,
+        parameter_id: FourCC,
+        value: f32,
+    ) -> Option<bool> {
+        let PlaybackMessageQueue::Generator { playback, .. } = playback_message_queue else {
+            return None;
+        };
+        let event = GeneratorPlaybackEvent::SetNormalizedParameter {
+            id: parameter_id,
+            value,
+        };
+        Some(
+            playback
+                .push(GeneratorPlaybackMessage::Trigger { event })
+                .is_ok(),
+        )
+    }
+
+    // Keep sub-mixers sorted by their processing stage.
+    fn sort_mixers_by_stage(&mut self) {
+        // NB: unstable sort, as it doesn't allocate
+        self.mixers.sort_unstable_by_key(|(_, mixer)| mixer.stage());
     }
 
-    // Process all sub-mixers. This is using the thread pool if enabled, else processes
-    // all sub mixers sequentially in this thread.
+    // Process all sub-mixers, stage by stage, so that mixers which feed other mixers via sends
+    // get processed before their receivers. Sub-mixers are processed into their own output
+    // buffers: use `add_sub_mixer_outputs` to mix them down.
     //
     // Returns true if any sub-mixer produced audible output.
-    fn process_sub_mixers(&mut self, output: &mut [f32], time: &SourceTime) -> bool {
+    fn process_sub_mixers(&mut self, output_len: usize, time: &SourceTime) -> bool {
+        let mut produced_output = false;
+        let mut stage_start = 0;
+        while stage_start < self.mixers.len() {
+            let stage = self.mixers[stage_start].1.stage();
+            let stage_len = self.mixers[stage_start..]
+                .iter()
+                .take_while(|(_, mixer)| mixer.stage() == stage)
+                .count();
+            let stage_range = stage_start..stage_start + stage_len;
+            produced_output |= self.process_sub_mixer_stage(stage_range, output_len, time);
+            stage_start += stage_len;
+        }
+        produced_output
+    }
+
+    // Process all sub-mixers of a single stage. This is using the thread pool if enabled, else
+    // processes all sub mixers of the stage sequentially in this thread.
+    //
+    // Returns true if any sub-mixer produced audible output.
+    fn process_sub_mixer_stage(
+        &mut self,
+        stage_range: Range<usize>,
+        output_len: usize,
+        time: &SourceTime,
+    ) -> bool {
+        let mixers = &mut self.mixers[stage_range];
         if let Some(thread_pool) = self.thread_pool.as_mut().and_then(|pool| {
-            if pool.should_use_concurrent_processing(self.mixers.len()) {
+            if pool.should_use_concurrent_processing(mixers.len()) {
                 Some(pool)
             } else {
                 None
@@ -512,36 +1444,22 @@ impl MixedSource {
         }) {
             // Process all mixers in the thread pool's workers
             thread_pool.process(
-                &mut self.mixers,
+                mixers,
                 self.channel_count,
                 self.sample_rate,
-                output.len(),
+                output_len,
                 time,
                 &mut self.thread_pool_results,
             );
-            // Combine outputs from all processed audible mixers
-            let mut produced_output = false;
-            for process_result in &self.thread_pool_results {
-                if process_result.is_audible {
-                    // Find the mixer and read from its temp_output_buffer
-                    if let Some((_, mixer)) = self
-                        .mixers
-                        .iter()
-                        .find(|(id, _)| *id == process_result.mixer_id)
-                    {
-                        add_buffers(output, &mixer.output_buffer[..output.len()]);
-                        produced_output = true;
-                    }
-                }
-            }
-            produced_output
+            self.thread_pool_results
+                .iter()
+                .any(|process_result| process_result.is_audible)
         } else {
             // Process all mixers sequentially
             let mut produced_output = false;
-            for (_, sub_mixer) in &mut self.mixers {
-                let mix_buffer = &mut self.mix_buffer[..output.len()];
-                let is_audible = sub_mixer.process(
-                    output,
+            for (_, sub_mixer) in mixers {
+                let mix_buffer = &mut self.mix_buffer[..output_len];
+                let is_audible = sub_mixer.process_to_output_buffer(
                     mix_buffer,
                     self.channel_count,
                     self.sample_rate,
@@ -553,6 +1471,102 @@ impl MixedSource {
         }
     }
 
+    // Max latency of all sub-mixers and send inputs, which got processed in this cycle.
+    // Sub-mixers with direct outputs don't feed this mixer, so they are not included.
+    fn input_latency(&self) -> usize {
+        let sub_mixer_latency = self
+            .mixers
+            .iter()
+            .filter(|(_, mixer)| !mixer.has_direct_output())
+            .map(|(_, mixer)| mixer.latency())
+            .max()
+            .unwrap_or(0);
+        let send_input_latency = self
+            .send_inputs
+            .iter()
+            .map(|(_, buffer)| buffer.latency())
+            .max()
+            .unwrap_or(0);
+        sub_mixer_latency.max(send_input_latency)
+    }
+
+    // Sum of the latencies of all effects.
+    fn effects_latency(&self) -> usize {
+        self.effects
+            .iter()
+            .map(|(_, effect)| effect.effect().latency())
+            .sum()
+    }
+
+    // Add outputs of all processed sub-mixers into the given output buffer, delay-compensated
+    // to match the given input latency. Sub-mixers with direct outputs write into their direct
+    // output buffers instead.
+    fn add_sub_mixer_outputs(
+        &mut self,
+        output: &mut [f32],
+        time: &SourceTime,
+        input_latency: usize,
+    ) {
+        for (_, sub_mixer) in &mut self.mixers {
+            sub_mixer.add_output_buffer(output, time, input_latency);
+        }
+    }
+
+    // Add signals from other mixers which send to this mixer into the given output buffer,
+    // delay-compensated to match the given input latency.
+    // Returns true if any send input is audible.
+    fn process_send_inputs(
+        &mut self,
+        output: &mut [f32],
+        time: &SourceTime,
+        input_latency: usize,
+    ) -> bool {
+        let mut produced_output = false;
+        for (_, buffer) in &self.send_inputs {
+            let delay_frames = input_latency.saturating_sub(buffer.latency());
+            produced_output |= buffer.read(output, time, delay_frames);
+        }
+        produced_output
+    }
+
+    // Apply the mixer's volume and panning to the given output.
+    fn process_fader(&mut self, output: &mut [f32]) {
+        apply_smoothed_gain(output, &mut self.volume);
+        apply_smoothed_panning(output, self.channel_count, &mut self.panning);
+    }
+
+    // Feed the given output, which has the given latency, into all sends which use the given
+    // send mode.
+    fn process_sends(
+        &mut self,
+        output: &[f32],
+        time: &SourceTime,
+        latency: usize,
+        mode: MixerSendMode,
+    ) {
+        for send in &mut self.sends {
+            if send.mode() == mode {
+                send.process(output, time, latency);
+            }
+        }
+    }
+
+    // Feed the given output, which has the given latency, into all stem taps which use the given
+    // stem mode.
+    fn process_stem_taps(
+        &mut self,
+        output: &[f32],
+        time: &SourceTime,
+        latency: usize,
+        mode: StemMode,
+    ) {
+        for tap in &mut self.stem_taps {
+            if tap.mode == mode {
+                tap.buffer.write(output, time, latency, &mut tap.gain);
+            }
+        }
+    }
+
     // Write and mix down all playing sources into the given output buffer.
     // Returns true if any source produced audible output.
     fn process_sources(&mut self, output: &mut [f32], time: &SourceTime) -> bool {
@@ -576,7 +1590,6 @@ impl MixedSource {
             }
 
             // run and mix down the source
-            let source = &mut playing_source.source;
             'source: while total_written < output.len() {
                 let source_time =
                     time.with_added_frames((total_written / self.channel_count) as u64);
@@ -597,24 +1610,104 @@ impl MixedSource {
                     samples_until_stop = u64::MAX;
                 }
 
+                // apply due automation values of the source and its insert effects
+                let mut frames_until_automation = u64::MAX;
+                for automation in &mut self.automations {
+                    if !playing_source.owns_automation(automation) {
+                        continue;
+                    }
+                    let frames = match automation.target() {
+                        AutomationTarget::Generator(_) => {
+                            let queue = &playing_source.playback_message_queue;
+                            automation.process(source_time.pos_in_frames, |id, value| {
+                                Self::apply_generator_automation_value(queue, id, value)
+                            })
+                        }
+                        AutomationTarget::Effect(effect_id) => {
+                            let effects = &mut playing_source.effects;
+                            automation.process(source_time.pos_in_frames, |id, value| {
+                                effects
+                                    .as_mut()
+                                    .and_then(|effects| effects.effect_processor_mut(effect_id))
+                                    .and_then(|effect| {
+                                        Self::apply_effect_automation_value(
+                                            effect, effect_id, id, value,
+                                        )
+                                    })
+                            })
+                        }
+                    };
+                    frames_until_automation = frames_until_automation.min(frames);
+                }
+                let samples_until_automation =
+                    frames_until_automation.saturating_mul(self.channel_count as u64);
+
                 // run source on temp_out until we've filled up the whole slice
-                let remaining = (output.len() - total_written).min(samples_until_stop as usize);
+                let remaining = (output.len() - total_written)
+                    .min(samples_until_stop.min(samples_until_automation) as usize);
                 let to_write = remaining.min(self.mix_buffer.len());
-                let written = source.write(&mut self.mix_buffer[..to_write], &source_time);
+                let mut written = playing_source
+                    .source
+                    .write(&mut self.mix_buffer[..to_write], &source_time);
+
+                // apply insert effects. when the source produced no output, process effect tails
+                if let Some(effects) = &mut playing_source.effects {
+                    if written > 0 {
+                        let written_out = &mut self.mix_buffer[..written];
+                        effects.process(
+                            written_out,
+                            self.channel_count,
+                            self.sample_rate,
+                            false,
+                            &source_time,
+                        );
+                    } else {
+                        let tail_out = &mut self.mix_buffer[..to_write];
+                        clear_buffer(tail_out);
+                        if effects.process(
+                            tail_out,
+                            self.channel_count,
+                            self.sample_rate,
+                            true,
+                            &source_time,
+                        ) {
+                            written = to_write;
+                        }
+                    }
+                }
+                let effects_active = playing_source
+                    .effects
+                    .as_ref()
+                    .is_some_and(|effects| !effects.is_bypassed());
+
+                // apply declicking fades of moving sources
+                let written_out = &mut self.mix_buffer[..written];
+                if let Some(fade_gain) = &mut playing_source.fade_gain {
+                    apply_smoothed_gain(written_out, fade_gain);
+                    if fade_gain.target() == 1.0 && !fade_gain.need_ramp() {
+                        playing_source.fade_gain = None;
+                    }
+                }
 
                 // add output of the source to the final output slice
                 let remaining_out = &mut output[total_written..total_written + written];
-                let written_out = &self.mix_buffer[..written];
                 add_buffers(remaining_out, written_out);
                 total_written += written;
                 produced_output |= written > 0;
 
-                if playing_source.is_transient && source.is_exhausted() {
-                    // source is now exhausted: remove source
+                if playing_source.is_transient
+                    && playing_source.source.is_exhausted()
+                    && !effects_active
+                {
+                    // source is now exhausted and effect tails finished: remove source
                     playing_source.is_active = false;
                     break 'source;
                 } else if written == 0 {
                     // source produced no output but is not exhausted: just skip processing for now
+                    if let Some(fade_gain) = &mut playing_source.fade_gain {
+                        // there's nothing to fade: complete pending fades
+                        fade_gain.reset();
+                    }
                     break 'source;
                 }
             }
@@ -623,8 +1716,15 @@ impl MixedSource {
         produced_output
     }
 
-    // Process all effects with bypass logic based on source activity.
-    fn process_effects(&mut self, output: &mut [f32], time: &SourceTime, input_bypassed: bool) {
+    // Process all effects with bypass logic based on source activity. `input_latency` is the
+    // latency of the mixer's summed inputs, which effects use to align their sidechains.
+    fn process_effects(
+        &mut self,
+        output: &mut [f32],
+        time: &SourceTime,
+        input_bypassed: bool,
+        input_latency: usize,
+    ) {
         // Early return if all effects are bypassed and we got no audible input
         if self.effects_bypassed && input_bypassed {
             return;
@@ -633,21 +1733,45 @@ impl MixedSource {
         // Track if all effects are bypassed
         let mut all_bypassed = true;
         let mut input_bypassed = input_bypassed;
+        let mut input_latency = input_latency;
 
         // Apply effects with bypass logic
-        for (_, mixer_effect) in &mut self.effects {
-            let is_active = mixer_effect.process(
-                output,
-                self.channel_count,
-                self.sample_rate,
-                input_bypassed,
-                time,
-            );
+        let output_frame_count = output.len() / self.channel_count;
+        for (effect_id, mixer_effect) in &mut self.effects {
+            // process automated effects in chunks, until their automations need an update
+            let mut is_active = false;
+            let mut frames_processed = 0;
+            while frames_processed < output_frame_count {
+                let chunk_time = time.with_added_frames(frames_processed as u64);
+                let mut frames_until_automation = u64::MAX;
+                for automation in &mut self.automations {
+                    if automation.target() == AutomationTarget::Effect(*effect_id) {
+                        let frames = automation.process(chunk_time.pos_in_frames, |id, value| {
+                            Self::apply_effect_automation_value(mixer_effect, *effect_id, id, value)
+                        });
+                        frames_until_automation = frames_until_automation.min(frames);
+                    }
+                }
+                let frames_to_process = (output_frame_count - frames_processed)
+                    .min(frames_until_automation.min(usize::MAX as u64) as usize);
+                let chunk_output = &mut output[frames_processed * self.channel_count
+                    ..(frames_processed + frames_to_process) * self.channel_count];
+                is_active |= mixer_effect.process(
+                    chunk_output,
+                    self.channel_count,
+                    self.sample_rate,
+                    input_bypassed,
+                    input_latency,
+                    &chunk_time,
+                );
+                frames_processed += frames_to_process;
+            }
 
             if is_active {
                 input_bypassed = false;
                 all_bypassed = false;
             }
+            input_latency += mixer_effect.effect().latency();
         }
 
         // Update the global bypass flag
@@ -659,13 +1783,16 @@ impl Source for MixedSource {
     fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
         // Process all pending messages
         self.process_messages(time);
+        self.process_pending_mixers();
 
         // Return early and avoid touching the buffer if there's nothing to do
         if self.playing_sources.is_empty()
             && self.effects.is_empty()
             && self.mixers.is_empty()
+            && self.send_inputs.is_empty()
             && self.events.is_empty()
         {
+            self.latency.store(0, Ordering::Relaxed);
             return 0;
         }
 
@@ -679,8 +1806,9 @@ impl Source for MixedSource {
         while total_frames_written < output_frame_count {
             let current_time_in_frames = time.pos_in_frames + total_frames_written as u64;
 
-            // process pending events
+            // process pending events and running parameter automations
             self.process_events(current_time_in_frames);
+            self.process_automations(current_time_in_frames);
 
             // determine how many frames to process until the next event is due
             let frames_to_process = {
@@ -698,14 +1826,41 @@ impl Source for MixedSource {
                 let chunk_output = &mut output[total_frames_written * self.channel_count
                     ..(total_frames_written + frames_to_process) * self.channel_count];
 
-                // apply sub-mixers and track if they produced audible output
-                let mut audible_input = self.process_sub_mixers(chunk_output, &chunk_time);
+                // apply sources and track if they produced audible output
+                let mut audible_input = self.process_sources(chunk_output, &chunk_time);
+
+                // mute sources only, while soloed sub-mixers pass through this mixer
+                apply_smoothed_gain(chunk_output, &mut self.sources_gain);
+
+                // process sub-mixers
+                audible_input |= self.process_sub_mixers(chunk_output.len(), &chunk_time);
 
-                // apply sources
-                audible_input |= self.process_sources(chunk_output, &chunk_time);
+                // delay-compensate sources and apply sub-mixers and sends from other mixers
+                let input_latency = self.input_latency();
+                self.sources_latency_compensator
+                    .process(chunk_output, input_latency);
+                self.add_sub_mixer_outputs(chunk_output, &chunk_time, input_latency);
+                audible_input |= self.process_send_inputs(chunk_output, &chunk_time, input_latency);
+                self.process_stem_taps(
+                    chunk_output,
+                    &chunk_time,
+                    input_latency,
+                    StemMode::PreEffects,
+                );
 
                 // apply effects
-                self.process_effects(chunk_output, &chunk_time, !audible_input);
+                let latency = input_latency + self.effects_latency();
+                self.latency.store(latency, Ordering::Relaxed);
+                self.process_effects(chunk_output, &chunk_time, !audible_input, input_latency);
+
+                // apply mute and feed pre-fader sends
+                apply_smoothed_gain(chunk_output, &mut self.mute_gain);
+                self.process_sends(chunk_output, &chunk_time, latency, MixerSendMode::PreFader);
+
+                // apply fader and feed post-fader sends
+                self.process_fader(chunk_output);
+                self.process_sends(chunk_output, &chunk_time, latency, MixerSendMode::PostFader);
+                self.process_stem_taps(chunk_output, &chunk_time, latency, StemMode::PostEffects);
 
                 total_frames_written += frames_to_process;
             }
@@ -714,6 +1869,10 @@ impl Source for MixedSource {
         // drop all sources which finished playing in this iteration
         self.remove_matching_sources(|s| s.is_transient && !s.is_active);
 
+        // hand over sources and sub-mixers which moved to other mixers
+        self.process_source_moves();
+        self.process_mixer_moves();
+
         // Return output len as we've cleared the entire output before processing
         output.len()
     }
@@ -844,14 +2003,48 @@ impl EventProcessor for MixedSource {
                     }
                 }
             }
+            MixerEvent::SetMixerVolume {
+                volume,
+                sample_time: _,
+            } => {
+                self.volume.set_target(volume);
+            }
+            MixerEvent::SetMixerPanning {
+                panning,
+                sample_time: _,
+            } => {
+                self.panning.set_target(panning);
+            }
+            MixerEvent::SetMixerMuteState {
+                muted,
+                sources_muted,
+                sample_time: _,
+            } => {
+                self.mute_gain.set_target(if muted { 0.0 } else { 1.0 });
+                self.sources_gain
+                    .set_target(if sources_muted { 0.0 } else { 1.0 });
+            }
+            MixerEvent::SetSendLevel {
+                target_mixer_id,
+                level,
+                sample_time: _,
+            } => {
+                if let Some(send) = self
+                    .sends
+                    .iter_mut()
+                    .find(|send| send.target() == MixerSendTarget::Mixer(target_mixer_id))
+                {
+                    send.set_level(level);
+                } else {
+                    log::warn!("Send to mixer {target_mixer_id} not found for level update");
+                }
+            }
             MixerEvent::ProcessEffectMessage {
                 effect_id,
                 message,
                 sample_time: _,
             } => {
-                if let Some((_, mixer_effect)) =
-                    self.effects.iter_mut().find(|(id, _)| *id == effect_id)
-                {
+                if let Some(mixer_effect) = self.effect_processor_mut(effect_id) {
                     if let Err(err) = mixer_effect.effect_mut().process_message(&**message) {
                         log::error!("Failed to process message on effect {effect_id}: {err}");
                     }
@@ -865,9 +2058,9 @@ impl EventProcessor for MixedSource {
                 value,
                 sample_time: _,
             } => {
-                if let Some((_, mixer_effect)) =
-                    self.effects.iter_mut().find(|(id, _)| *id == effect_id)
-                {
+                let target = AutomationTarget::Effect(effect_id);
+                self.cancel_automations(target, |id| id == parameter_id);
+                if let Some(mixer_effect) = self.effect_processor_mut(effect_id) {
                     if let Err(err) = mixer_effect
                         .effect_mut()
                         .process_parameter_update(parameter_id, &value)
@@ -887,9 +2080,9 @@ impl EventProcessor for MixedSource {
                 values,
                 sample_time: _,
             } => {
-                if let Some((_, mixer_effect)) =
-                    self.effects.iter_mut().find(|(id, _)| *id == effect_id)
-                {
+                let target = AutomationTarget::Effect(effect_id);
+                self.cancel_automations(target, |id| values.iter().any(|(v, _)| *v == id));
+                if let Some(mixer_effect) = self.effect_processor_mut(effect_id) {
                     if let Err(err) = mixer_effect.effect_mut().process_parameter_updates(&values) {
                         log::error!("Failed to update parameters on effect {effect_id}: {err}",);
                     }
@@ -899,11 +2092,68 @@ impl EventProcessor for MixedSource {
                     );
                 }
             }
+            MixerEvent::AutomateEffectParameter {
+                effect_id,
+                parameter_id,
+                lane,
+                sample_time,
+            } => {
+                let target = AutomationTarget::Effect(effect_id);
+                self.start_automation(ParameterAutomation::new(
+                    target,
+                    parameter_id,
+                    lane,
+                    sample_time,
+                ));
+            }
+            MixerEvent::SetEffectModulation {
+                effect_id,
+                source,
+                target,
+                amount,
+                bipolar,
+                sample_time: _,
+            } => {
+                if let Some(mixer_effect) = self.effect_processor_mut(effect_id) {
+                    if let Err(err) = mixer_effect
+                        .effect_mut()
+                        .set_modulation(source, target, amount, bipolar)
+                    {
+                        log::error!("Failed to set modulation on effect {effect_id}: {err}");
+                    }
+                } else {
+                    log::warn!("Effect with id {effect_id} not found for modulation update");
+                }
+            }
+            MixerEvent::ClearEffectModulation {
+                effect_id,
+                source,
+                target,
+                sample_time: _,
+            } => {
+                if let Some(mixer_effect) = self.effect_processor_mut(effect_id) {
+                    if let Err(err) = mixer_effect.effect_mut().clear_modulation(source, target) {
+                        log::error!("Failed to clear modulation on effect {effect_id}: {err}");
+                    }
+                } else {
+                    log::warn!("Effect with id {effect_id} not found for modulation update");
+                }
+            }
             MixerEvent::TriggerGeneratorEvent {
                 playback_id,
                 event,
                 sample_time: _,
             } => {
+                let target = AutomationTarget::Generator(playback_id);
+                match &event {
+                    GeneratorPlaybackEvent::SetParameter { id, .. } => {
+                        self.cancel_automations(target, |parameter_id| parameter_id == *id);
+                    }
+                    GeneratorPlaybackEvent::SetParameters { values } => {
+                        self.cancel_automations(target, |id| values.iter().any(|(v, _)| *v == id));
+                    }
+                    _ => (),
+                }
                 if let Some(source) = self
                     .playing_sources
                     .iter()
@@ -920,6 +2170,20 @@ impl EventProcessor for MixedSource {
                     }
                 }
             }
+            MixerEvent::AutomateGeneratorParameter {
+                playback_id,
+                parameter_id,
+                lane,
+                sample_time,
+            } => {
+                let target = AutomationTarget::Generator(playback_id);
+                self.start_automation(ParameterAutomation::new(
+                    target,
+                    parameter_id,
+                    lane,
+                    sample_time,
+                ));
+            }
         }
     }
 }
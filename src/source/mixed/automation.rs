@@ -0,0 +1,158 @@
+use basedrop::Owned;
+use four_cc::FourCC;
+
+use crate::{parameter::AutomationLane, player::EffectId, PlaybackId};
+
+use super::MixerEvent;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Target of a parameter automation in a mixer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum AutomationTarget {
+    /// A mixer effect or an insert effect of a playing source.
+    Effect(EffectId),
+    /// A playing generator source.
+    Generator(PlaybackId),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Mixer internal struct to evaluate a running parameter automation lane.
+///
+/// Curves get evaluated in blocks of [`Self::BLOCK_FRAMES`] and at all breakpoints, so the mixer
+/// splits the processing of the automated effect or source at the times returned by
+/// [`Self::process`]. All other sources and effects get processed in full chunks.
+pub(crate) struct ParameterAutomation {
+    target: AutomationTarget,
+    parameter_id: FourCC,
+    lane: Owned<AutomationLane>,
+    start_time: u64,
+    applied_value: Option<f32>,
+    is_rejected: bool,
+}
+
+impl ParameterAutomation {
+    /// Max number of sample frames between two evaluations of a curve.
+    const BLOCK_FRAMES: u64 = 64;
+
+    pub fn new(
+        target: AutomationTarget,
+        parameter_id: FourCC,
+        lane: Owned<AutomationLane>,
+        start_time: u64,
+    ) -> Self {
+        Self {
+            target,
+            parameter_id,
+            lane,
+            start_time,
+            applied_value: None,
+            is_rejected: false,
+        }
+    }
+
+    /// The automated effect or generator.
+    pub fn target(&self) -> AutomationTarget {
+        self.target
+    }
+
+    /// The automated parameter's id.
+    pub fn parameter_id(&self) -> FourCC {
+        self.parameter_id
+    }
+
+    /// The lane's value at the given time, when it starts or changed since it got applied the
+    /// last time.
+    pub fn pending_value(&self, current_time: u64) -> Option<f32> {
+        if self.is_rejected {
+            return None;
+        }
+        let offset = current_time.checked_sub(self.start_time)?;
+        if offset < self.lane.start() {
+            return None;
+        }
+        self.lane
+            .value_at(offset)
+            .filter(|value| self.applied_value != Some(*value))
+    }
+
+    /// Mark the given value as applied.
+    pub fn set_applied_value(&mut self, value: f32) {
+        self.applied_value = Some(value);
+    }
+
+    /// Mark the automation as rejected by its target: it then no longer applies values and is
+    /// finished.
+    pub fn set_rejected(&mut self) {
+        self.is_rejected = true;
+    }
+
+    /// Apply the lane's pending value at the given time, if any, via the given function, which
+    /// returns `Some(true)` when the value got applied, `Some(false)` when it should be retried
+    /// later on and `None` when the target rejected it. Returns the number of sample frames
+    /// until the lane needs to be evaluated again.
+    pub fn process<F>(&mut self, current_time: u64, apply: F) -> u64
+    where
+        F: FnOnce(FourCC, f32) -> Option<bool>,
+    {
+        if let Some(value) = self.pending_value(current_time) {
+            match apply(self.parameter_id, value) {
+                Some(true) => self.set_applied_value(value),
+                Some(false) => (),
+                None => self.set_rejected(),
+            }
+        }
+        self.frames_until_next_update(current_time)
+    }
+
+    /// True when the lane ended at the given time and its last value got applied, or when the
+    /// target rejected the lane's values.
+    pub fn is_finished(&self, current_time: u64) -> bool {
+        self.is_rejected
+            || (current_time >= self.start_time + self.lane.duration()
+                && self.applied_value == self.lane.points().last().map(|p| p.value))
+    }
+
+    /// Number of sample frames from the given time until the lane needs to be evaluated again.
+    pub fn frames_until_next_update(&self, current_time: u64) -> u64 {
+        if self.is_rejected {
+            return u64::MAX;
+        }
+        let lane_start = self.start_time + self.lane.start();
+        if current_time < lane_start {
+            return lane_start - current_time;
+        }
+        let offset = current_time - self.start_time;
+        match self.lane.frames_until_next_point(offset) {
+            Some(frames) => {
+                let is_constant = self.lane.value_at(offset + frames) == self.applied_value;
+                if is_constant {
+                    frames
+                } else {
+                    frames.min(Self::BLOCK_FRAMES)
+                }
+            }
+            None => u64::MAX,
+        }
+    }
+
+    /// Convert the automation back into a mixer event, e.g. to move it to another mixer
+    /// together with its source.
+    pub fn into_event(self) -> MixerEvent {
+        match self.target {
+            AutomationTarget::Effect(effect_id) => MixerEvent::AutomateEffectParameter {
+                effect_id,
+                parameter_id: self.parameter_id,
+                lane: self.lane,
+                sample_time: self.start_time,
+            },
+            AutomationTarget::Generator(playback_id) => MixerEvent::AutomateGeneratorParameter {
+                playback_id,
+                parameter_id: self.parameter_id,
+                lane: self.lane,
+                sample_time: self.start_time,
+            },
+        }
+    }
+}
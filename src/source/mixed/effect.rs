@@ -1,4 +1,14 @@
-use crate::{utils::buffer::max_abs_sample, Effect, SourceTime};
+use std::time::{Duration, Instant};
+
+use basedrop::Shared;
+
+use crate::{
+    source::measured::SharedCpuLoadState,
+    utils::buffer::{clear_buffer, max_abs_sample},
+    Effect, EffectId, SourceTime,
+};
+
+use super::{MixedSource, MixerSendBuffer};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -7,11 +17,21 @@ use crate::{utils::buffer::max_abs_sample, Effect, SourceTime};
 /// Automatically bypasses effects when effect input is silent and the effect's tail has expired,
 /// calling `process_started` and `process_stopped` on state transitions. Tracks tail duration using
 /// `process_tail` or silence detection for effects that don't implement it.
+///
+/// Effects which support sidechains get the sidechain signal passed from a [`MixerSendBuffer`],
+/// when a sidechain source got assigned. The sidechain signal gets delayed to match the latency
+/// of the effect's main input, so both stay sample-aligned.
+///
+/// When a measurement state is set, the effect's processing time gets measured. Bypassed
+/// effects are measured as idle.
 pub(crate) struct EffectProcessor {
     effect: Box<dyn Effect>,
+    measurement_state: Option<SharedCpuLoadState>,
     bypassed: bool,
     tail_counter: usize,
     silence_counter: usize,
+    sidechain: Option<Shared<MixerSendBuffer>>,
+    sidechain_buffer: Vec<f32>,
 }
 
 impl EffectProcessor {
@@ -20,18 +40,26 @@ impl EffectProcessor {
     /// Number of seconds that we should let an effect running before treating it as bypassed
     pub const SILENCE_SECONDS: usize = 2;
 
-    pub fn new(effect: Box<dyn Effect>) -> Self {
+    pub fn new(effect: Box<dyn Effect>, measurement_state: Option<SharedCpuLoadState>) -> Self {
+        // prealloc sidechain read buffer for effects which may use it
+        let sidechain_buffer = if effect.supports_sidechain() {
+            vec![0.0; MixedSource::MAX_MIX_BUFFER_SAMPLES]
+        } else {
+            Vec::new()
+        };
         Self {
             effect,
+            measurement_state,
             bypassed: true,
             tail_counter: 0,
             silence_counter: usize::MAX,
+            sidechain: None,
+            sidechain_buffer,
         }
     }
 
     /// Access to the processor's effect.
     #[inline]
-    #[allow(unused)]
     pub fn effect(&self) -> &dyn Effect {
         self.effect.as_ref()
     }
@@ -42,6 +70,15 @@ impl EffectProcessor {
         self.effect.as_mut()
     }
 
+    /// Set or remove the effect's sidechain input buffer.
+    pub fn set_sidechain(&mut self, sidechain: Option<Shared<MixerSendBuffer>>) {
+        debug_assert!(
+            sidechain.is_none() || self.effect.supports_sidechain(),
+            "Effect does not support sidechains"
+        );
+        self.sidechain = sidechain;
+    }
+
     /// Mixers's weight, taking auto suspending into account.
     pub fn weight(&self) -> usize {
         if !self.bypassed {
@@ -51,7 +88,8 @@ impl EffectProcessor {
         }
     }
 
-    /// Process this effect with full bypass logic and tail management.
+    /// Process this effect with full bypass logic and tail management. `input_latency` is the
+    /// processing latency of the effect's input signal in sample frames.
     /// Returns true when the effect processed output.
     pub fn process(
         &mut self,
@@ -59,6 +97,7 @@ impl EffectProcessor {
         channel_count: usize,
         sample_rate: u32,
         input_bypassed: bool,
+        input_latency: usize,
         time: &SourceTime,
     ) -> bool {
         // Handle bypass state transitions
@@ -66,7 +105,20 @@ impl EffectProcessor {
 
         if !self.bypassed {
             // Process effect if not bypassed
-            self.effect.process(output, time);
+            let start_time = self.measurement_state.as_ref().map(|_| Instant::now());
+            if let Some(sidechain) = &self.sidechain {
+                let sidechain_buffer = &mut self.sidechain_buffer[..output.len()];
+                clear_buffer(sidechain_buffer);
+                let delay_frames = input_latency.saturating_sub(sidechain.latency());
+                sidechain.read(sidechain_buffer, time, delay_frames);
+                self.effect
+                    .process_with_sidechain(output, sidechain_buffer, time);
+            } else {
+                self.effect.process(output, time);
+            }
+            if let Some(start_time) = start_time {
+                self.record_processing_time(start_time.elapsed(), output.len() / channel_count);
+            }
 
             if input_bypassed {
                 // Sources are inactive, update tail counters to bypass in future calls
@@ -79,10 +131,22 @@ impl EffectProcessor {
             true
         } else {
             // is bypassed
+            if self.measurement_state.is_some() {
+                self.record_processing_time(Duration::ZERO, output.len() / channel_count);
+            }
             false
         }
     }
 
+    /// Update the CPU load measurement state, if any.
+    fn record_processing_time(&self, processing_time: Duration, frames: usize) {
+        if let Some(state) = &self.measurement_state {
+            if let Ok(mut state) = state.try_lock() {
+                state.record(processing_time, frames as u64);
+            }
+        }
+    }
+
     /// Check if this effect should be bypassed based on source activity and tail state.
     #[inline]
     fn should_bypass(&self, input_bypassed: bool) -> bool {
@@ -151,3 +215,75 @@ impl EffectProcessor {
         self.silence_counter = 0;
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+/// Insert effect chain of a single playing source in a mixer.
+///
+/// Effects are processed in series with the same auto-bypass logic as mixer effects, so the
+/// chain can tell when all effect tails finished after the source stopped.
+pub(crate) struct SourceEffectChain {
+    effects: Vec<(EffectId, EffectProcessor)>,
+    bypassed: bool,
+}
+
+impl SourceEffectChain {
+    pub fn new(effects: Vec<(EffectId, EffectProcessor)>) -> Self {
+        Self {
+            effects,
+            bypassed: true,
+        }
+    }
+
+    /// True when all effects got bypassed: the input is silent and all effect tails finished.
+    #[inline]
+    pub fn is_bypassed(&self) -> bool {
+        self.bypassed
+    }
+
+    /// True when the effect with the given id is part of the chain.
+    pub fn contains(&self, effect_id: EffectId) -> bool {
+        self.effects.iter().any(|(id, _)| *id == effect_id)
+    }
+
+    /// Mut access to the processor of the effect with the given id, if it's part of the chain.
+    pub fn effect_processor_mut(&mut self, effect_id: EffectId) -> Option<&mut EffectProcessor> {
+        self.effects
+            .iter_mut()
+            .find(|(id, _)| *id == effect_id)
+            .map(|(_, effect)| effect)
+    }
+
+    /// Process all effects of the chain. Returns true when some effect processed output.
+    pub fn process(
+        &mut self,
+        output: &mut [f32],
+        channel_count: usize,
+        sample_rate: u32,
+        input_bypassed: bool,
+        time: &SourceTime,
+    ) -> bool {
+        if self.bypassed && input_bypassed {
+            return false;
+        }
+        let mut input_bypassed = input_bypassed;
+        let mut input_latency = 0;
+        let mut all_bypassed = true;
+        for (_, effect) in &mut self.effects {
+            if effect.process(
+                output,
+                channel_count,
+                sample_rate,
+                input_bypassed,
+                input_latency,
+                time,
+            ) {
+                input_bypassed = false;
+                all_bypassed = false;
+            }
+            input_latency += effect.effect().latency();
+        }
+        self.bypassed = all_bypassed;
+        !all_bypassed
+    }
+}
@@ -0,0 +1,98 @@
+use crate::utils::buffer::clear_buffer;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Delays an interleaved signal path by a variable amount of sample frames, in order to align it
+/// with other, latent signal paths in a mixer.
+///
+/// Delays are limited to [`Self::MAX_DELAY_TIME`]. Buffers are allocated on construction, so
+/// changing the delay in the real-time thread won't allocate.
+pub(crate) struct LatencyCompensator {
+    buffer: Vec<f32>,
+    channel_count: usize,
+    write_pos: usize,
+    delay_frames: usize,
+}
+
+impl LatencyCompensator {
+    /// Max delay time in seconds which can be compensated.
+    pub const MAX_DELAY_TIME: f32 = 0.5;
+
+    /// Max delay in sample frames which can be compensated at the given sample rate.
+    pub fn max_delay_frames(sample_rate: u32) -> usize {
+        (Self::MAX_DELAY_TIME * sample_rate as f32).ceil() as usize
+    }
+
+    pub fn new(channel_count: usize, sample_rate: u32) -> Self {
+        debug_assert!(channel_count > 0, "Invalid channel count");
+        let buffer_frames = Self::max_delay_frames(sample_rate) + 1;
+        Self {
+            buffer: vec![0.0; buffer_frames * channel_count],
+            channel_count,
+            write_pos: 0,
+            delay_frames: 0,
+        }
+    }
+
+    /// Delay the given interleaved buffer in-place by the given number of frames.
+    ///
+    /// Changing the delay jumps to the new read position without crossfading, so this
+    /// should only happen rarely, e.g. when latent effects get added or reconfigured.
+    pub fn process(&mut self, output: &mut [f32], delay_frames: usize) {
+        let buffer_frames = self.buffer.len() / self.channel_count;
+        let delay_frames = delay_frames.min(buffer_frames - 1);
+        if delay_frames == 0 {
+            self.delay_frames = 0;
+            return;
+        }
+        if self.delay_frames == 0 {
+            // the buffer content is stale when we got bypassed before: start with silence
+            clear_buffer(&mut self.buffer);
+        }
+        self.delay_frames = delay_frames;
+
+        for frame in output.chunks_exact_mut(self.channel_count) {
+            let read_pos = (self.write_pos + buffer_frames - delay_frames) % buffer_frames;
+            let write_offset = self.write_pos * self.channel_count;
+            let read_offset = read_pos * self.channel_count;
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let input = *sample;
+                *sample = self.buffer[read_offset + channel];
+                self.buffer[write_offset + channel] = input;
+            }
+            self.write_pos = (self.write_pos + 1) % buffer_frames;
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay() {
+        let mut compensator = LatencyCompensator::new(2, 100);
+
+        // no delay: pass through
+        let mut buffer = [1.0, -1.0, 2.0, -2.0];
+        compensator.process(&mut buffer, 0);
+        assert_eq!(buffer, [1.0, -1.0, 2.0, -2.0]);
+
+        // delay across processing calls
+        let mut buffer = [1.0, -1.0, 2.0, -2.0, 3.0, -3.0];
+        compensator.process(&mut buffer, 2);
+        assert_eq!(buffer, [0.0, 0.0, 0.0, 0.0, 1.0, -1.0]);
+        let mut buffer = [4.0, -4.0, 5.0, -5.0];
+        compensator.process(&mut buffer, 2);
+        assert_eq!(buffer, [2.0, -2.0, 3.0, -3.0]);
+
+        // delays are limited to the max delay time
+        let max_delay_frames = LatencyCompensator::max_delay_frames(100);
+        let mut buffer = vec![7.0; (max_delay_frames + 1) * 2];
+        compensator.process(&mut buffer, usize::MAX);
+        assert!(buffer[..max_delay_frames * 2].iter().all(|s| *s != 7.0));
+        assert!(buffer[max_delay_frames * 2..].iter().all(|s| *s == 7.0));
+    }
+}
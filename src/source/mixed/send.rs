@@ -0,0 +1,197 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+use basedrop::Shared;
+
+use crate::{
+    player::{EffectId, MixerId, MixerSendMode},
+    utils::smoothing::{ExponentialSmoothedValue, SmoothedValue},
+    SourceTime,
+};
+
+use super::{EffectProcessor, LatencyCompensator, MixedSource};
+
+// -------------------------------------------------------------------------------------------------
+
+/// A shared ring buffer which passes audio from one mixer to another one within the mixer graph.
+///
+/// Frames are stored at their absolute sample frame positions, so writers and readers don't
+/// need to process the exact same chunks. Readers only see frames which got written in the
+/// current processing cycle: the player orders the mixer graph, so senders are processed
+/// before their receivers.
+///
+/// Writers also pass their processing latency, which readers use to delay-compensate the
+/// buffer's signal against their other inputs.
+///
+/// Samples and positions are stored in atomics, so writers and readers, which may run in
+/// different mixer threads, never block or skip each other. Writers publish new frames by
+/// updating the buffer's end position after writing them.
+pub(crate) struct MixerSendBuffer {
+    buffer: Vec<AtomicU32>,
+    channel_count: usize,
+    start_frame: AtomicU64,
+    end_frame: AtomicU64,
+    latency: AtomicUsize,
+}
+
+impl MixerSendBuffer {
+    /// Min ring buffer size in samples. Mixers never process more than `MAX_MIX_BUFFER_SAMPLES`
+    /// at once, so this leaves enough headroom for readers with different chunk sizes.
+    /// The buffer additionally holds enough frames to compensate max latencies.
+    const BUFFER_SAMPLES: usize = MixedSource::MAX_MIX_BUFFER_SAMPLES * 2;
+
+    pub fn new(channel_count: usize, sample_rate: u32) -> Self {
+        debug_assert!(channel_count > 0, "Invalid channel count");
+        let latency_samples = LatencyCompensator::max_delay_frames(sample_rate) * channel_count;
+        let buffer = (0..Self::BUFFER_SAMPLES + latency_samples)
+            .map(|_| AtomicU32::new(0))
+            .collect();
+        Self {
+            buffer,
+            channel_count,
+            start_frame: AtomicU64::new(0),
+            end_frame: AtomicU64::new(0),
+            latency: AtomicUsize::new(0),
+        }
+    }
+
+    /// Processing latency in sample frames of the last written signal.
+    pub fn latency(&self) -> usize {
+        self.latency.load(Ordering::Relaxed)
+    }
+
+    /// Write the given interleaved input at the given time, applying the given smoothed gain.
+    /// `latency` is the processing latency of the written signal in sample frames.
+    pub fn write(
+        &self,
+        input: &[f32],
+        time: &SourceTime,
+        latency: usize,
+        gain: &mut impl SmoothedValue,
+    ) {
+        let channel_count = self.channel_count;
+        let capacity_in_frames = self.buffer.len() / channel_count;
+        let frame_count = input.len() / channel_count;
+        debug_assert!(frame_count <= capacity_in_frames, "Send buffer too small");
+
+        let start_frame = time.pos_in_frames;
+        let end_frame = start_frame + frame_count as u64;
+        let mut valid_start_frame = self.start_frame.load(Ordering::Relaxed);
+        if start_frame != self.end_frame.load(Ordering::Relaxed) {
+            // not continuous: drop all previously written content
+            valid_start_frame = start_frame;
+        }
+        valid_start_frame =
+            valid_start_frame.max(end_frame.saturating_sub(capacity_in_frames as u64));
+
+        for (index, input_frame) in input.chunks_exact(channel_count).enumerate() {
+            let gain = gain.next();
+            let buffer_pos = ((start_frame as usize + index) % capacity_in_frames) * channel_count;
+            let buffer_frame = &self.buffer[buffer_pos..buffer_pos + channel_count];
+            for (o, i) in buffer_frame.iter().zip(input_frame) {
+                o.store((*i * gain).to_bits(), Ordering::Relaxed);
+            }
+        }
+
+        // publish written frames
+        self.latency.store(latency, Ordering::Relaxed);
+        self.start_frame.store(valid_start_frame, Ordering::Relaxed);
+        self.end_frame.store(end_frame, Ordering::Release);
+    }
+
+    /// Add frames which got written for the given time, delayed by the given number of frames,
+    /// into the given interleaved output.
+    ///
+    /// Returns true when the added frames are audible.
+    pub fn read(&self, output: &mut [f32], time: &SourceTime, delay_frames: usize) -> bool {
+        let channel_count = self.channel_count;
+        let capacity_in_frames = self.buffer.len() / channel_count;
+        let frame_count = output.len() / channel_count;
+
+        let max_delay_frames = capacity_in_frames - Self::BUFFER_SAMPLES / channel_count;
+        let delay_frames = delay_frames.min(max_delay_frames) as u64;
+
+        let buffer_end_frame = self.end_frame.load(Ordering::Acquire);
+        let buffer_start_frame = self.start_frame.load(Ordering::Relaxed);
+
+        let start_frame = (time.pos_in_frames.saturating_sub(delay_frames)).max(buffer_start_frame);
+        let end_frame = (time.pos_in_frames + frame_count as u64)
+            .saturating_sub(delay_frames)
+            .min(buffer_end_frame);
+        let mut max_sample = 0.0_f32;
+        for frame in start_frame..end_frame {
+            let output_pos = (frame + delay_frames - time.pos_in_frames) as usize * channel_count;
+            let buffer_pos = (frame as usize % capacity_in_frames) * channel_count;
+            let output_frame = &mut output[output_pos..output_pos + channel_count];
+            let buffer_frame = &self.buffer[buffer_pos..buffer_pos + channel_count];
+            for (o, i) in output_frame.iter_mut().zip(buffer_frame) {
+                let i = f32::from_bits(i.load(Ordering::Relaxed));
+                *o += i;
+                max_sample = max_sample.max(i.abs());
+            }
+        }
+        max_sample >= EffectProcessor::SILENCE_THRESHOLD
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// The receiver of a [`MixerSend`]'s signal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum MixerSendTarget {
+    /// A return mixer, which adds the send to its inputs.
+    Mixer(MixerId),
+    /// An effect, which uses the send as sidechain input.
+    EffectSidechain(EffectId),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A mixer's send slot, which feeds the mixer's signal with a smoothed level into a
+/// [`MixerSendBuffer`] of some other mixer or into an effect's sidechain input.
+pub(crate) struct MixerSend {
+    target: MixerSendTarget,
+    mode: MixerSendMode,
+    level: ExponentialSmoothedValue,
+    buffer: Shared<MixerSendBuffer>,
+}
+
+impl MixerSend {
+    pub fn new(
+        target: MixerSendTarget,
+        mode: MixerSendMode,
+        level: f32,
+        sample_rate: u32,
+        buffer: Shared<MixerSendBuffer>,
+    ) -> Self {
+        debug_assert!(level >= 0.0, "Invalid send level");
+        let level = ExponentialSmoothedValue::new(level, sample_rate);
+        Self {
+            target,
+            mode,
+            level,
+            buffer,
+        }
+    }
+
+    /// The mixer or sidechain effect which receives this send's signal.
+    #[inline]
+    pub fn target(&self) -> MixerSendTarget {
+        self.target
+    }
+
+    /// Where the send taps the mixer's signal.
+    #[inline]
+    pub fn mode(&self) -> MixerSendMode {
+        self.mode
+    }
+
+    /// Set a new target send level.
+    pub fn set_level(&mut self, level: f32) {
+        self.level.set_target(level);
+    }
+
+    /// Feed the given mixer output, which has the given latency, into the send buffer.
+    pub fn process(&mut self, output: &[f32], time: &SourceTime, latency: usize) {
+        self.buffer.write(output, time, latency, &mut self.level);
+    }
+}
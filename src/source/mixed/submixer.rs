@@ -1,10 +1,19 @@
+use basedrop::Shared;
+
 use crate::{
     source::{
         measured::MeasuredSource,
         metered::MeteredSource,
-        mixed::{EffectProcessor, MixedSource},
+        mixed::{
+            EffectProcessor, LatencyCompensator, MixedSource, MixerSendBuffer, SubMixerHandover,
+        },
+    },
+    utils::{
+        buffer::{add_buffers, max_abs_sample},
+        smoothing::{
+            apply_smoothed_gain, ExponentialSmoothedValue, LinearSmoothedValue, SmoothedValue,
+        },
     },
-    utils::buffer::{add_buffers, max_abs_sample},
     Source, SourceTime,
 };
 
@@ -20,18 +29,45 @@ pub(crate) use thread_pool::{SubMixerProcessingResult, SubMixerThreadPool};
 ///
 /// Tracks silence duration to determine if the sub-mixer is producing audible output,
 /// allowing the parent mixer to optimize effect processing.
+///
+/// Sub-mixers get processed into their own output buffers first. The parent mixer then adds
+/// them to its output, delayed to compensate latency differences to its other inputs.
+/// Sub-mixers with a direct output bypass their parent and write into the direct output's
+/// buffer instead, which the player plays on dedicated output channels.
+///
+/// When a sub-mixer moves to another parent mixer, it gets faded out in its old parent before
+/// it's handed over, and faded in again in its new parent.
 pub(crate) struct SubMixerProcessor {
     mixer: Box<MeasuredSource<MeteredSource<MixedSource>>>,
     silence_counter: usize,
-    /// Temporary output buffer for parallel processing.
-    pub(super) output_buffer: Vec<f32>,
+    stage: usize,
+    is_audible: bool,
+    latency_compensator: LatencyCompensator,
+    direct_output: Option<Shared<MixerSendBuffer>>,
+    direct_output_gain: ExponentialSmoothedValue,
+    /// Declicking fade, when the mixer moves in from or out to another parent mixer.
+    fade_gain: Option<LinearSmoothedValue>,
+    /// Set when the mixer moves to another parent mixer, after it faded out.
+    pending_handover: Option<SubMixerHandover>,
+    /// Temporary output buffer for sequential and parallel processing.
+    output_buffer: Vec<f32>,
 }
 
 impl SubMixerProcessor {
     pub fn new(mixer: Box<MeasuredSource<MeteredSource<MixedSource>>>) -> Self {
+        let latency_compensator =
+            LatencyCompensator::new(mixer.channel_count(), mixer.sample_rate());
+        let direct_output_gain = ExponentialSmoothedValue::new(1.0, mixer.sample_rate());
         Self {
             mixer,
             silence_counter: 0,
+            stage: 0,
+            is_audible: false,
+            latency_compensator,
+            direct_output: None,
+            direct_output_gain,
+            fade_gain: None,
+            pending_handover: None,
             output_buffer: vec![0.0; MixedSource::MAX_MIX_BUFFER_SAMPLES],
         }
     }
@@ -42,9 +78,130 @@ impl SubMixerProcessor {
         self.mixer.weight()
     }
 
-    /// Process the sub-mixer and check if it produced audible output.
+    /// Processing stage of the mixer within its parent mixer. Sub-mixers with lower stages get
+    /// processed first, so mixers which feed other mixers via sends run before their receivers.
+    #[inline]
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    /// Set a new processing stage. The parent mixer must re-sort its sub-mixers afterwards.
+    pub fn set_stage(&mut self, stage: usize) {
+        self.stage = stage;
+    }
+
+    /// Processing latency of the mixer's output in sample frames, as calculated in the last
+    /// processing run.
+    #[inline]
+    pub fn latency(&self) -> usize {
+        self.mixer.source().source().latency()
+    }
+
+    /// True when the mixer bypasses its parent mixer and plays on dedicated output channels.
+    #[inline]
+    pub fn has_direct_output(&self) -> bool {
+        self.direct_output.is_some()
+    }
+
+    /// Set or remove the buffer which receives the mixer's output instead of the parent mixer.
+    pub fn set_direct_output(&mut self, buffer: Option<Shared<MixerSendBuffer>>) {
+        self.direct_output = buffer;
+    }
+
+    /// Start fading in the mixer's output, after it moved in from another parent mixer.
+    pub fn fade_in(&mut self, fade_samples: u32) {
+        let mut fade_gain = LinearSmoothedValue::new(0.0, self.mixer.sample_rate());
+        fade_gain.set_target_with_duration(1.0, Some(fade_samples));
+        self.fade_gain = Some(fade_gain);
+    }
+
+    /// Start fading out the mixer's output, in order to move it to another parent mixer via the
+    /// given handover. See [`Self::take_faded_out_handover`].
+    pub fn fade_out(&mut self, fade_samples: u32, handover: SubMixerHandover) {
+        let sample_rate = self.mixer.sample_rate();
+        let fade_gain = self
+            .fade_gain
+            .get_or_insert_with(|| LinearSmoothedValue::new(1.0, sample_rate));
+        fade_gain.set_target_with_duration(0.0, Some(fade_samples));
+        self.pending_handover = Some(handover);
+    }
+
+    /// Returns the pending handover of a moving mixer, once the mixer faded out.
+    pub fn take_faded_out_handover(&mut self) -> Option<SubMixerHandover> {
+        let is_faded_out = self
+            .fade_gain
+            .as_ref()
+            .is_none_or(|fade_gain| !fade_gain.need_ramp());
+        if is_faded_out {
+            self.pending_handover.take()
+        } else {
+            None
+        }
+    }
+
+    /// Process the sub-mixer into its output buffer and check if it produced audible output.
     /// Returns true if the sub-mixer is producing audible audio.
-    pub fn process(
+    pub fn process_to_output_buffer(
+        &mut self,
+        mix_buffer: &mut [f32],
+        channel_count: usize,
+        sample_rate: u32,
+        time: &SourceTime,
+    ) -> bool {
+        // Temporarily take ownership of mixer's output buffer
+        let mut output_buffer = std::mem::take(&mut self.output_buffer);
+
+        let output = &mut output_buffer[..mix_buffer.len()];
+        output.fill(0.0);
+        self.is_audible = self.process(output, mix_buffer, channel_count, sample_rate, time);
+
+        // Move back mixer buffer to mixer
+        self.output_buffer = output_buffer;
+        self.is_audible
+    }
+
+    /// Add the output buffer content of the last `process_to_output_buffer` call to the given
+    /// output, when it's audible. The sub-mixer's output gets delayed, so that its latency
+    /// matches the given target latency.
+    ///
+    /// Mixers with a direct output leave the given output untouched and write into their direct
+    /// output buffer instead, which delay-compensates the signal on its own.
+    pub fn add_output_buffer(
+        &mut self,
+        output: &mut [f32],
+        time: &SourceTime,
+        target_latency: usize,
+    ) {
+        if let Some(direct_output) = &self.direct_output {
+            let latency = self.latency();
+            let output_buffer = &mut self.output_buffer[..output.len()];
+            Self::apply_fade(output_buffer, &mut self.fade_gain);
+            direct_output.write(output_buffer, time, latency, &mut self.direct_output_gain);
+            return;
+        }
+        let delay_frames = target_latency.saturating_sub(self.latency());
+        let output_buffer = &mut self.output_buffer[..output.len()];
+        self.latency_compensator
+            .process(output_buffer, delay_frames);
+        Self::apply_fade(output_buffer, &mut self.fade_gain);
+        if self.is_audible {
+            add_buffers(output, output_buffer);
+        }
+    }
+
+    /// Apply a running declicking fade to the given output and drop completed fade-ins.
+    fn apply_fade(output: &mut [f32], fade_gain: &mut Option<LinearSmoothedValue>) {
+        if let Some(gain) = fade_gain {
+            apply_smoothed_gain(output, gain);
+            if gain.target() == 1.0 && !gain.need_ramp() {
+                *fade_gain = None;
+            }
+        }
+    }
+
+    /// Process the sub-mixer, add its output to the given output buffer and check if it
+    /// produced audible output. Returns true if the sub-mixer is producing audible audio.
+    fn process(
         &mut self,
         output: &mut [f32],
         mix_buffer: &mut [f32],
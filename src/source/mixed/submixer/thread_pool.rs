@@ -104,7 +104,7 @@ impl WorkerTaskBatcher {
             }));
 
         // Sort by weight descending (largest first for better packing)
-        self.mixers.sort_by(|a, b| b.weight.cmp(&a.weight));
+        self.mixers.sort_by_key(|m| std::cmp::Reverse(m.weight));
 
         // Greedy assignment: assign each mixer to worker with lowest current weight
         for mixer_weight in &self.mixers {
@@ -243,6 +243,7 @@ impl WorkerState {
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct SubMixerProcessingResult {
     /// The mixer ID that was processed.
+    #[allow(unused)]
     pub mixer_id: MixerId,
     /// Whether the mixer produced audible output.
     pub is_audible: bool,
@@ -342,6 +343,10 @@ impl SubMixerThreadPool {
     /// - Waits for all workers to complete
     /// - Collects results into the provided buffer
     ///
+    /// All passed mixers run concurrently, so they must not depend on each other's output via
+    /// sends or effect sidechains. The parent mixer thus only passes mixers of a single
+    /// processing stage here.
+    ///
     /// # Safety
     /// The mixers slice must remain valid and unmodified for the duration of this call.
     ///
@@ -521,20 +526,10 @@ impl SubMixerThreadPool {
         // NonNull guarantees the pointer is non-null.
         let mixer = unsafe { &mut *mixer_ptr.as_ptr() };
 
-        // Temporarily take ownership of mixer's output buffer
-        let mut output_buffer = std::mem::take(&mut mixer.output_buffer);
-
-        let output = &mut output_buffer[..output_len];
+        // Process the mixer into its output buffer
         let mix_buffer = &mut mix_buffer[..output_len];
-
-        // Clear buffers
-        output.fill(0.0);
-
-        // Process the mixer
-        let is_audible = mixer.process(output, mix_buffer, channel_count, sample_rate, time);
-
-        // Move back mixer buffer to mixer
-        mixer.output_buffer = std::mem::take(&mut output_buffer);
+        let is_audible =
+            mixer.process_to_output_buffer(mix_buffer, channel_count, sample_rate, time);
 
         // Return result metadata only (no data copy)
         SubMixerProcessingResult {
@@ -95,6 +95,13 @@ impl<InputSource: Source + 'static> ResampledSource<InputSource> {
             output_buffer,
         }
     }
+
+    /// Delay in output sample frames, which the resampler adds to the resampled signal.
+    pub fn latency(&self) -> usize {
+        self.resampler
+            .as_ref()
+            .map_or(0, |resampler| resampler.latency())
+    }
 }
 
 impl<InputSource: Source + 'static> Source for ResampledSource<InputSource> {
@@ -113,14 +120,22 @@ impl<InputSource: Source + 'static> Source for ResampledSource<InputSource> {
                             self.input_buffer.reset_range();
                             let input_read =
                                 self.source.write(self.input_buffer.get_mut(), &source_time);
+                            if input_read == 0 && self.source.is_exhausted() {
+                                // source produced no more input: we're done
+                                self.input_buffer.clear_range();
+                                self.output_buffer.clear_range();
+                                break;
+                            }
                             // pad, fill up up missing inputs with zeros if the resampler has an input buffer constrain
                             // this should only happen for exhausted sources...
                             if input_read < self.input_buffer.len() {
                                 let required_input_len =
                                     resampler.required_input_buffer_size().unwrap_or(0);
-                                if self.input_buffer.len() < required_input_len {
+                                if input_read < required_input_len {
                                     self.input_buffer.set_range(0, required_input_len);
                                     clear_buffer(&mut self.input_buffer.get_mut()[input_read..]);
+                                } else {
+                                    self.input_buffer.set_range(0, input_read);
                                 }
                             }
                         }
@@ -168,3 +183,73 @@ impl<InputSource: Source + 'static> Source for ResampledSource<InputSource> {
         self.source.weight() + resampler_weight
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mono source which plays a constant signal for the given number of frames.
+    struct FiniteSource {
+        frames_left: usize,
+        sample_rate: u32,
+    }
+
+    impl Source for FiniteSource {
+        fn channel_count(&self) -> usize {
+            1
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        fn is_exhausted(&self) -> bool {
+            self.frames_left == 0
+        }
+
+        fn weight(&self) -> usize {
+            1
+        }
+
+        fn write(&mut self, output: &mut [f32], _time: &SourceTime) -> usize {
+            let written = output.len().min(self.frames_left);
+            output[..written].fill(1.0);
+            self.frames_left -= written;
+            written
+        }
+    }
+
+    #[test]
+    fn end_of_input() {
+        // rubato's filter delay does not get flushed at the end of the input
+        for (quality, tolerance) in [
+            (ResamplingQuality::Default, 2),
+            (ResamplingQuality::HighQuality, 128),
+        ] {
+            let source = FiniteSource {
+                frames_left: 1000,
+                sample_rate: 44100,
+            };
+            let mut resampled = ResampledSource::new(source, 48000, quality);
+
+            // partial and final source reads must not resample stale input
+            let mut output = vec![0.0; 256];
+            let mut total_written = 0;
+            for _ in 0..100 {
+                let written = resampled.write(&mut output, &SourceTime::default());
+                if written == 0 {
+                    break;
+                }
+                total_written += written;
+            }
+            assert!(resampled.is_exhausted());
+            let expected_written = 1000 * 48000 / 44100;
+            assert!(
+                total_written.abs_diff(expected_written) < tolerance,
+                "{quality:?} resampler wrote {total_written} frames, expected {expected_written}"
+            );
+        }
+    }
+}
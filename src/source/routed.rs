@@ -0,0 +1,261 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use basedrop::Shared;
+use crossbeam_queue::ArrayQueue;
+
+use crate::{
+    source::{
+        mixed::{LatencyCompensator, MixedSource, MixerSendBuffer},
+        Source, SourceTime,
+    },
+    utils::buffer::{clear_buffer, remap_buffer_channels},
+    MixerId,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Messages send from the player to a [`RoutedSource`] to change output routings.
+pub(crate) enum RoutedSourceMessage {
+    /// Play a mixer's output, which gets written into the given buffer, on the given output
+    /// channels. Replaces existing routes of the mixer.
+    AddRoute {
+        mixer_id: MixerId,
+        first_channel: usize,
+        channel_count: usize,
+        buffer: Shared<MixerSendBuffer>,
+    },
+    RemoveRoute {
+        mixer_id: MixerId,
+    },
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A mixer's output, which bypasses the main mix and plays on dedicated output channels.
+struct OutputRoute {
+    mixer_id: MixerId,
+    first_channel: usize,
+    channel_count: usize,
+    buffer: Shared<MixerSendBuffer>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A source which maps the player's main mix to the output device's channel layout and adds
+/// the outputs of directly routed mixers to their assigned output channels.
+///
+/// Routed mixers get processed within the main mixer's graph and write their output into
+/// shared buffers, which are read after the main mix got rendered. The main mix and all routed
+/// outputs get delay-compensated, so they stay sample-aligned.
+pub(crate) struct RoutedSource<InputSource: Source + 'static> {
+    source: InputSource,
+    source_latency: Arc<AtomicUsize>,
+    latency: Arc<AtomicUsize>,
+    latency_compensator: LatencyCompensator,
+    input_channels: usize,
+    output_channels: usize,
+    input_buffer: Vec<f32>,
+    route_buffer: Vec<f32>,
+    routes: Vec<OutputRoute>,
+    message_queue: Arc<ArrayQueue<RoutedSourceMessage>>,
+}
+
+impl<InputSource: Source + 'static> RoutedSource<InputSource> {
+    /// Create a new routed source for the given main mix source with the given processing
+    /// latency state and the given output channel count.
+    pub fn new(
+        source: InputSource,
+        source_latency: Arc<AtomicUsize>,
+        output_channels: usize,
+    ) -> Self {
+        let input_channels = source.channel_count();
+        assert!(input_channels != 0, "Input channel count must be > 0");
+        assert!(output_channels != 0, "Output channel count must be > 0");
+
+        let latency = Arc::new(AtomicUsize::new(0));
+        let latency_compensator = LatencyCompensator::new(input_channels, source.sample_rate());
+
+        // prealloc buffers and routes
+        let input_buffer = vec![0.0; MixedSource::MAX_MIX_BUFFER_SAMPLES];
+        let route_buffer = vec![0.0; MixedSource::MAX_MIX_BUFFER_SAMPLES];
+        const ROUTES_CAPACITY: usize = 16;
+        let routes = Vec::with_capacity(ROUTES_CAPACITY);
+        const MESSAGE_QUEUE_SIZE: usize = 256;
+        let message_queue = Arc::new(ArrayQueue::new(MESSAGE_QUEUE_SIZE));
+
+        Self {
+            source,
+            source_latency,
+            latency,
+            latency_compensator,
+            input_channels,
+            output_channels,
+            input_buffer,
+            route_buffer,
+            routes,
+            message_queue,
+        }
+    }
+
+    /// Shared processing latency of the routed output in sample frames: the latency of the main
+    /// mix or routed mixer output with the highest latency.
+    pub fn latency_state(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.latency)
+    }
+
+    /// Allows changing routings by pushing messages into this event queue.
+    pub fn message_queue(&self) -> Arc<ArrayQueue<RoutedSourceMessage>> {
+        Arc::clone(&self.message_queue)
+    }
+
+    /// Change the output channel count, e.g. when the source moves to another output device.
+    /// Routes which no longer fit into the output channels get removed.
+    pub fn set_output_channels(&mut self, output_channels: usize) {
+        assert!(output_channels != 0, "Output channel count must be > 0");
+        self.process_messages();
+        self.output_channels = output_channels;
+        self.routes
+            .retain(|route| route.first_channel + route.channel_count <= output_channels);
+    }
+
+    fn process_messages(&mut self) {
+        while let Some(message) = self.message_queue.pop() {
+            match message {
+                RoutedSourceMessage::AddRoute {
+                    mixer_id,
+                    first_channel,
+                    channel_count,
+                    buffer,
+                } => {
+                    debug_assert!(
+                        first_channel + channel_count <= self.output_channels,
+                        "Invalid output route channels"
+                    );
+                    self.routes.retain(|route| route.mixer_id != mixer_id);
+                    self.routes.push(OutputRoute {
+                        mixer_id,
+                        first_channel,
+                        channel_count,
+                        buffer,
+                    });
+                }
+                RoutedSourceMessage::RemoveRoute { mixer_id } => {
+                    self.routes.retain(|route| route.mixer_id != mixer_id);
+                }
+            }
+        }
+    }
+
+    // Add the given routed mixer output to the route's output channels.
+    fn add_route_output(
+        input: &[f32],
+        input_channels: usize,
+        output: &mut [f32],
+        output_channels: usize,
+        route: &OutputRoute,
+    ) {
+        let input_frames = input.chunks_exact(input_channels);
+        let output_frames = output.chunks_exact_mut(output_channels);
+        let route_channels = route.first_channel..route.first_channel + route.channel_count;
+        for (i, o) in input_frames.zip(output_frames) {
+            let o = &mut o[route_channels.clone()];
+            match (input_channels, route.channel_count) {
+                (1, _) => o.iter_mut().for_each(|o| *o += i[0]),
+                (_, 1) => o[0] += i.iter().sum::<f32>() / input_channels as f32,
+                _ => o.iter_mut().zip(i).for_each(|(o, i)| *o += *i),
+            }
+        }
+    }
+}
+
+impl<InputSource: Source + 'static> Source for RoutedSource<InputSource> {
+    fn channel_count(&self) -> usize {
+        self.output_channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.source.is_exhausted()
+    }
+
+    fn weight(&self) -> usize {
+        self.source.weight()
+    }
+
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        self.process_messages();
+
+        if output.is_empty()
+            || (self.routes.is_empty() && self.input_channels == self.output_channels)
+        {
+            // no routing needed, or pass empty buffers as they are to process messages only.
+            // NB: resets the compensator's delay, so it won't play stale content when rerouting
+            self.latency_compensator.process(&mut [], 0);
+            let written = self.source.write(output, time);
+            self.latency.store(
+                self.source_latency.load(Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+            return written;
+        }
+
+        let output_frame_count = output.len() / self.output_channels;
+        let max_chunk_frames = self.input_buffer.len() / self.input_channels;
+        let mut total_frames_written = 0;
+        while total_frames_written < output_frame_count {
+            let frames_to_process =
+                (output_frame_count - total_frames_written).min(max_chunk_frames);
+            let chunk_time = time.with_added_frames(total_frames_written as u64);
+            let chunk_input = &mut self.input_buffer[..frames_to_process * self.input_channels];
+            let chunk_output = &mut output[total_frames_written * self.output_channels
+                ..(total_frames_written + frames_to_process) * self.output_channels];
+
+            // render the main mix: mixers output silence when they have nothing to do
+            let written = self.source.write(chunk_input, &chunk_time);
+            clear_buffer(&mut chunk_input[written..]);
+
+            // delay-compensate the main mix and all routes to the slowest signal path
+            let source_latency = self.source_latency.load(Ordering::Relaxed);
+            let latency = self
+                .routes
+                .iter()
+                .map(|route| route.buffer.latency())
+                .fold(source_latency, usize::max);
+            self.latency.store(latency, Ordering::Relaxed);
+            self.latency_compensator
+                .process(chunk_input, latency - source_latency);
+
+            // map the main mix into the output's channel layout
+            remap_buffer_channels(
+                chunk_input,
+                self.input_channels,
+                chunk_output,
+                self.output_channels,
+            );
+
+            // add routed mixer outputs
+            for route in &self.routes {
+                let route_input = &mut self.route_buffer[..frames_to_process * self.input_channels];
+                clear_buffer(route_input);
+                let delay_frames = latency - route.buffer.latency();
+                route.buffer.read(route_input, &chunk_time, delay_frames);
+                Self::add_route_output(
+                    route_input,
+                    self.input_channels,
+                    chunk_output,
+                    self.output_channels,
+                    route,
+                );
+            }
+
+            total_frames_written += frames_to_process;
+        }
+        output.len()
+    }
+}
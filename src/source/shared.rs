@@ -0,0 +1,161 @@
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex, Weak,
+};
+
+use super::{Source, SourceTime};
+use crate::utils::buffer::clear_buffer;
+
+// -------------------------------------------------------------------------------------------------
+
+/// A source which can be moved from one output device to another one without interrupting it.
+///
+/// Output devices play a `SharedSource`, which renders the wrapped source. A
+/// [`SharedSourceHandle`] can reconnect the wrapped source to a new output device at any time:
+/// previously connected shared sources then go silent and no longer touch the wrapped source.
+///
+/// The wrapped source gets its own, continuous sample time, which doesn't depend on the output
+/// device's playback position, so sample times stay valid when switching devices.
+pub(crate) struct SharedSource<InputSource: Source + 'static> {
+    source: Arc<Mutex<InputSource>>,
+    connection: usize,
+    current_connection: Arc<AtomicUsize>,
+    position: Arc<AtomicU64>,
+    channel_count: usize,
+    sample_rate: u32,
+}
+
+impl<InputSource: Source + 'static> SharedSource<InputSource> {
+    /// Create a new shared source and a handle to reconnect it later on.
+    pub fn new(source: InputSource) -> (Self, SharedSourceHandle<InputSource>) {
+        let channel_count = source.channel_count();
+        let sample_rate = source.sample_rate();
+        let source = Arc::new(Mutex::new(source));
+        let handle = SharedSourceHandle {
+            source: Arc::downgrade(&source),
+            current_connection: Arc::new(AtomicUsize::new(0)),
+            position: Arc::new(AtomicU64::new(0)),
+        };
+        let shared = Self {
+            source,
+            connection: 0,
+            current_connection: Arc::clone(&handle.current_connection),
+            position: Arc::clone(&handle.position),
+            channel_count,
+            sample_rate,
+        };
+        (shared, handle)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.current_connection.load(Ordering::Acquire) == self.connection
+    }
+}
+
+impl<InputSource: Source + 'static> Source for SharedSource<InputSource> {
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn is_exhausted(&self) -> bool {
+        // NB: disconnected or locked sources are silent but not exhausted
+        match self.source.try_lock() {
+            Ok(source) if self.is_connected() => source.is_exhausted(),
+            _ => false,
+        }
+    }
+
+    fn weight(&self) -> usize {
+        match self.source.try_lock() {
+            Ok(source) if self.is_connected() => source.weight(),
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        // NB: the source only gets locked by the handle while reconnecting it
+        let Ok(mut source) = self.source.try_lock() else {
+            clear_buffer(output);
+            return output.len();
+        };
+        if !self.is_connected() {
+            clear_buffer(output);
+            return output.len();
+        }
+        let source_time = SourceTime {
+            pos_in_frames: self.position.load(Ordering::Relaxed),
+            pos_instant: time.pos_instant,
+        };
+        let written = source.write(output, &source_time);
+        self.position.fetch_add(
+            (output.len() / self.channel_count) as u64,
+            Ordering::Relaxed,
+        );
+        written
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Controls a [`SharedSource`] from outside of the output device.
+///
+/// The handle does not keep the wrapped source alive: it gets dropped along with the last
+/// shared source which is still owned by an output device.
+pub(crate) struct SharedSourceHandle<InputSource: Source + 'static> {
+    source: Weak<Mutex<InputSource>>,
+    current_connection: Arc<AtomicUsize>,
+    position: Arc<AtomicU64>,
+}
+
+impl<InputSource: Source + 'static> SharedSourceHandle<InputSource> {
+    /// The wrapped source's sample time in sample frames.
+    pub fn position(&self) -> u64 {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    /// True when the wrapped source is still alive, so it can get connected again.
+    pub fn is_alive(&self) -> bool {
+        self.source.strong_count() > 0
+    }
+
+    /// Silence all connected shared sources. When this returns, no shared source accesses the
+    /// wrapped source anymore, so it can be safely reconfigured via [`Self::with_source`].
+    pub fn disconnect(&self) {
+        self.current_connection.fetch_add(1, Ordering::AcqRel);
+        // wait until a pending write of the previous connection finished
+        if let Some(source) = self.source.upgrade() {
+            drop(source.lock());
+        }
+    }
+
+    /// Access the wrapped source, when it's still alive. Blocks the connected shared source
+    /// while accessing it, so this should be used on disconnected sources only.
+    pub fn with_source<R>(&self, f: impl FnOnce(&mut InputSource) -> R) -> Option<R> {
+        let source = self.source.upgrade()?;
+        let mut source = source.lock().unwrap_or_else(|err| err.into_inner());
+        Some(f(&mut source))
+    }
+
+    /// Disconnect all previously connected shared sources and create a new one, which continues
+    /// rendering the wrapped source. Returns `None` when the wrapped source got dropped.
+    pub fn connect(&self) -> Option<SharedSource<InputSource>> {
+        self.disconnect();
+        let source = self.source.upgrade()?;
+        let (channel_count, sample_rate) = {
+            let source = source.lock().unwrap_or_else(|err| err.into_inner());
+            (source.channel_count(), source.sample_rate())
+        };
+        Some(SharedSource {
+            source,
+            connection: self.current_connection.load(Ordering::Acquire),
+            current_connection: Arc::clone(&self.current_connection),
+            position: Arc::clone(&self.position),
+            channel_count,
+            sample_rate,
+        })
+    }
+}
@@ -0,0 +1,430 @@
+//! Musical timing: tempo, time signature and beat based scheduling.
+
+use std::{
+    ops::{Add, Sub},
+    sync::{Arc, RwLock},
+};
+
+use crate::error::Error;
+
+// -------------------------------------------------------------------------------------------------
+
+/// A musical time position or duration in quarter note beats.
+#[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Beats(pub f64);
+
+impl Add for Beats {
+    type Output = Beats;
+    fn add(self, rhs: Self) -> Self::Output {
+        Beats(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Beats {
+    type Output = Beats;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Beats(self.0 - rhs.0)
+    }
+}
+
+/// A musical time position or duration in bars of the transport's [`TimeSignature`].
+/// Bar positions start counting at 0.
+#[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Bars(pub f64);
+
+// -------------------------------------------------------------------------------------------------
+
+/// A musical time signature, e.g. 3/4 or 6/8.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeSignature {
+    /// Number of note values per bar.
+    pub numerator: u32,
+    /// The note value of a single beat: 4 is a quarter note, 8 an eighth note.
+    pub denominator: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            numerator: 4,
+            denominator: 4,
+        }
+    }
+}
+
+impl TimeSignature {
+    /// Create a new time signature. Numerator and denominator must be > 0.
+    pub fn new(numerator: u32, denominator: u32) -> Result<Self, Error> {
+        if numerator == 0 || denominator == 0 {
+            return Err(Error::ParameterError(format!(
+                "Invalid time signature: {numerator}/{denominator}"
+            )));
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    /// Length of a single bar in quarter note beats.
+    pub fn beats_per_bar(&self) -> f64 {
+        self.numerator as f64 * 4.0 / self.denominator as f64
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// When to apply a scheduled playback event or parameter change.
+///
+/// All functions in the player and playback handles which accept a time, accept everything
+/// which converts into a `ScheduleTime`: `None` to apply changes immediately, a sample time in
+/// output sample frames as `u64` or `Some(u64)`, or a musical position in [`Beats`] or [`Bars`],
+/// which gets converted to a sample time via the player's [`Transport`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum ScheduleTime {
+    /// Apply immediately.
+    #[default]
+    Immediately,
+    /// Apply at the given absolute output sample frame time.
+    SampleTime(u64),
+    /// Apply at the given transport position in beats.
+    Beats(Beats),
+    /// Apply at the given transport position in bars.
+    Bars(Bars),
+}
+
+impl From<u64> for ScheduleTime {
+    fn from(sample_time: u64) -> Self {
+        Self::SampleTime(sample_time)
+    }
+}
+
+impl From<Option<u64>> for ScheduleTime {
+    fn from(sample_time: Option<u64>) -> Self {
+        sample_time.map_or(Self::Immediately, Self::SampleTime)
+    }
+}
+
+impl From<Beats> for ScheduleTime {
+    fn from(position: Beats) -> Self {
+        Self::Beats(position)
+    }
+}
+
+impl From<Bars> for ScheduleTime {
+    fn from(position: Bars) -> Self {
+        Self::Bars(position)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A tempo map with step-wise tempo changes at beat positions.
+#[derive(Debug, Clone, PartialEq)]
+struct TempoMap {
+    initial_tempo: f64,
+    /// Beat positions and tempos, sorted by position. All positions are > 0.
+    changes: Vec<(f64, f64)>,
+}
+
+impl TempoMap {
+    fn new(tempo: f64) -> Self {
+        Self {
+            initial_tempo: tempo,
+            changes: Vec::new(),
+        }
+    }
+
+    fn tempo_at(&self, beat: f64) -> f64 {
+        self.changes
+            .iter()
+            .take_while(|(position, _)| *position <= beat)
+            .last()
+            .map_or(self.initial_tempo, |(_, tempo)| *tempo)
+    }
+
+    fn set_tempo_change(&mut self, beat: f64, tempo: f64) {
+        if beat <= 0.0 {
+            self.initial_tempo = tempo;
+            return;
+        }
+        match self
+            .changes
+            .binary_search_by(|(position, _)| position.total_cmp(&beat))
+        {
+            Ok(index) => self.changes[index].1 = tempo,
+            Err(index) => self.changes.insert(index, (beat, tempo)),
+        }
+    }
+
+    /// Time in seconds from beat 0 to the given beat position.
+    fn seconds_at(&self, beat: f64) -> f64 {
+        let mut seconds = 0.0;
+        let mut segment_start = 0.0;
+        let mut tempo = self.initial_tempo;
+        for (position, next_tempo) in &self.changes {
+            if *position >= beat {
+                break;
+            }
+            seconds += (position - segment_start) * 60.0 / tempo;
+            segment_start = *position;
+            tempo = *next_tempo;
+        }
+        seconds + (beat - segment_start) * 60.0 / tempo
+    }
+
+    /// Beat position at the given time in seconds from beat 0. Inverse of `seconds_at`.
+    fn beat_at(&self, seconds: f64) -> f64 {
+        let mut segment_seconds = 0.0;
+        let mut segment_start = 0.0;
+        let mut tempo = self.initial_tempo;
+        for (position, next_tempo) in &self.changes {
+            let segment_end_seconds = segment_seconds + (position - segment_start) * 60.0 / tempo;
+            if segment_end_seconds >= seconds {
+                break;
+            }
+            segment_seconds = segment_end_seconds;
+            segment_start = *position;
+            tempo = *next_tempo;
+        }
+        segment_start + (seconds - segment_seconds) * tempo / 60.0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+struct TransportState {
+    sample_rate: u32,
+    tempo_map: TempoMap,
+    time_signature: TimeSignature,
+    is_running: bool,
+    /// Beat position at the last start, stop or locate.
+    anchor_position: f64,
+    /// Sample time of the anchor position, while running.
+    anchor_sample_time: u64,
+}
+
+impl TransportState {
+    fn position_at(&self, sample_time: u64) -> f64 {
+        if self.is_running {
+            let elapsed_seconds =
+                (sample_time as f64 - self.anchor_sample_time as f64) / self.sample_rate as f64;
+            let anchor_seconds = self.tempo_map.seconds_at(self.anchor_position);
+            self.tempo_map.beat_at(anchor_seconds + elapsed_seconds)
+        } else {
+            self.anchor_position
+        }
+    }
+
+    fn sample_time_at(&self, position: f64) -> Result<u64, Error> {
+        if !self.is_running {
+            return Err(Error::TransportNotRunning);
+        }
+        let seconds =
+            self.tempo_map.seconds_at(position) - self.tempo_map.seconds_at(self.anchor_position);
+        let frames = (seconds * self.sample_rate as f64).round();
+        Ok((self.anchor_sample_time as f64 + frames).max(0.0) as u64)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// The player's musical transport: a tempo map with tempo changes, a time signature and a
+/// play position, which converts musical [`Beats`] and [`Bars`] positions to sample times.
+///
+/// The transport is a timing reference only: starting or stopping it does not start or stop any
+/// sources, but beat and bar positions can only be scheduled while it's running.
+///
+/// Tempo changes are defined at beat positions, so changing the tempo map while the transport is
+/// running keeps the beat position of the last start or locate at its sample time and moves all
+/// following beats.
+///
+/// Transports are `Send` and `Sync` so they can be sent across threads. Get the player's transport
+/// via [`Player::transport`](crate::Player::transport).
+#[derive(Debug, Clone)]
+pub struct Transport {
+    state: Arc<RwLock<TransportState>>,
+}
+
+impl Transport {
+    /// Default tempo of new transports in beats per minute.
+    pub const DEFAULT_TEMPO: f64 = 120.0;
+
+    pub(crate) fn new(sample_rate: u32) -> Self {
+        let state = Arc::new(RwLock::new(TransportState {
+            sample_rate,
+            tempo_map: TempoMap::new(Self::DEFAULT_TEMPO),
+            time_signature: TimeSignature::default(),
+            is_running: false,
+            anchor_position: 0.0,
+            anchor_sample_time: 0,
+        }));
+        Self { state }
+    }
+
+    /// Initial tempo at beat 0 in beats per minute.
+    pub fn tempo(&self) -> f64 {
+        self.state.read().unwrap().tempo_map.initial_tempo
+    }
+
+    /// Set the initial tempo at beat 0 in beats per minute. Tempo changes are kept.
+    pub fn set_tempo(&self, tempo: f64) -> Result<(), Error> {
+        self.set_tempo_change(Beats(0.0), tempo)
+    }
+
+    /// Tempo at the given beat position in beats per minute.
+    pub fn tempo_at(&self, position: Beats) -> f64 {
+        self.state.read().unwrap().tempo_map.tempo_at(position.0)
+    }
+
+    /// Add or replace a tempo change at the given beat position. The new tempo applies until the
+    /// next tempo change.
+    pub fn set_tempo_change(&self, position: Beats, tempo: f64) -> Result<(), Error> {
+        if !(tempo.is_finite() && tempo > 0.0) {
+            return Err(Error::ParameterError(format!(
+                "Invalid tempo: {tempo}, must be > 0"
+            )));
+        }
+        let mut state = self.state.write().unwrap();
+        state.tempo_map.set_tempo_change(position.0, tempo);
+        Ok(())
+    }
+
+    /// Remove all tempo changes, keeping the initial tempo only.
+    pub fn clear_tempo_changes(&self) {
+        let mut state = self.state.write().unwrap();
+        state.tempo_map.changes.clear();
+    }
+
+    /// The transport's time signature.
+    pub fn time_signature(&self) -> TimeSignature {
+        self.state.read().unwrap().time_signature
+    }
+
+    /// Set the time signature, which is used to convert [`Bars`] into [`Beats`].
+    pub fn set_time_signature(&self, time_signature: TimeSignature) {
+        let mut state = self.state.write().unwrap();
+        state.time_signature = time_signature;
+    }
+
+    /// Convert a bar position to a beat position, using the transport's time signature.
+    pub fn bars_to_beats(&self, position: Bars) -> Beats {
+        Beats(position.0 * self.time_signature().beats_per_bar())
+    }
+
+    /// Returns true when the transport got started.
+    pub fn is_running(&self) -> bool {
+        self.state.read().unwrap().is_running
+    }
+
+    /// Start running from the current position at the given output sample time.
+    /// See also [`Player::start_transport`](crate::Player::start_transport).
+    pub fn start(&self, sample_time: u64) {
+        let mut state = self.state.write().unwrap();
+        if !state.is_running {
+            state.is_running = true;
+            state.anchor_sample_time = sample_time;
+        }
+    }
+
+    /// Stop running at the given output sample time, keeping the position at that time.
+    /// See also [`Player::stop_transport`](crate::Player::stop_transport).
+    pub fn stop(&self, sample_time: u64) {
+        let mut state = self.state.write().unwrap();
+        if state.is_running {
+            state.anchor_position = state.position_at(sample_time);
+            state.is_running = false;
+        }
+    }
+
+    /// Move the play position to the given beat position at the given output sample time.
+    /// See also [`Player::locate_transport`](crate::Player::locate_transport).
+    pub fn locate(&self, position: Beats, sample_time: u64) {
+        let mut state = self.state.write().unwrap();
+        state.anchor_position = position.0;
+        state.anchor_sample_time = sample_time;
+    }
+
+    /// The transport's beat position at the given output sample time.
+    pub fn position_at(&self, sample_time: u64) -> Beats {
+        Beats(self.state.read().unwrap().position_at(sample_time))
+    }
+
+    /// Convert the given beat position to an output sample time.
+    /// Returns a `TransportNotRunning` error when the transport is stopped.
+    pub fn beats_to_sample_time(&self, position: Beats) -> Result<u64, Error> {
+        self.state.read().unwrap().sample_time_at(position.0)
+    }
+
+    /// Convert the given schedule time to an optional sample time, as used in mixer events.
+    pub(crate) fn resolve<T: Into<ScheduleTime>>(&self, time: T) -> Result<Option<u64>, Error> {
+        match time.into() {
+            ScheduleTime::Immediately => Ok(None),
+            ScheduleTime::SampleTime(sample_time) => Ok(Some(sample_time)),
+            ScheduleTime::Beats(position) => self.beats_to_sample_time(position).map(Some),
+            ScheduleTime::Bars(position) => self
+                .beats_to_sample_time(self.bars_to_beats(position))
+                .map(Some),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tempo_map() {
+        let mut tempo_map = TempoMap::new(120.0);
+        assert_eq!(tempo_map.seconds_at(4.0), 2.0);
+        assert_eq!(tempo_map.beat_at(2.0), 4.0);
+
+        // 120 bpm for 4 beats, then 60 bpm for 4 beats, then 240 bpm
+        tempo_map.set_tempo_change(8.0, 240.0);
+        tempo_map.set_tempo_change(4.0, 60.0);
+        assert_eq!(tempo_map.tempo_at(3.9), 120.0);
+        assert_eq!(tempo_map.tempo_at(4.0), 60.0);
+        assert_eq!(tempo_map.tempo_at(10.0), 240.0);
+        assert_eq!(tempo_map.seconds_at(4.0), 2.0);
+        assert_eq!(tempo_map.seconds_at(6.0), 4.0);
+        assert_eq!(tempo_map.seconds_at(8.0), 6.0);
+        assert_eq!(tempo_map.seconds_at(12.0), 7.0);
+        for beat in [0.0, 1.5, 4.0, 5.0, 8.0, 11.0, 100.0] {
+            assert!((tempo_map.beat_at(tempo_map.seconds_at(beat)) - beat).abs() < 1e-9);
+        }
+
+        // negative positions use the initial tempo
+        assert_eq!(tempo_map.seconds_at(-2.0), -1.0);
+        assert_eq!(tempo_map.beat_at(-1.0), -2.0);
+    }
+
+    #[test]
+    fn transport() {
+        let transport = Transport::new(48000);
+        transport.set_time_signature(TimeSignature::new(3, 4).unwrap());
+        assert!(transport.beats_to_sample_time(Beats(1.0)).is_err());
+        assert_eq!(transport.resolve(None).unwrap(), None);
+        assert_eq!(transport.resolve(123).unwrap(), Some(123));
+
+        // start at sample 1000: one beat at 120 bpm is 24000 frames
+        transport.start(1000);
+        assert_eq!(transport.resolve(Beats(1.0)).unwrap(), Some(25000));
+        assert_eq!(transport.resolve(Bars(1.0)).unwrap(), Some(73000));
+        assert_eq!(transport.position_at(49000), Beats(2.0));
+
+        // tempo changes move following beats
+        transport.set_tempo_change(Beats(2.0), 60.0).unwrap();
+        assert_eq!(transport.resolve(Beats(3.0)).unwrap(), Some(97000));
+
+        // stop and locate
+        transport.stop(97000);
+        assert_eq!(transport.position_at(200000), Beats(3.0));
+        transport.locate(Beats(8.0), 200000);
+        transport.start(200000);
+        assert_eq!(transport.resolve(Beats(9.0)).unwrap(), Some(248000));
+        assert_eq!(transport.resolve(Beats(0.0)).unwrap(), Some(0));
+    }
+}
@@ -10,6 +10,7 @@ pub(crate) mod time;
 
 pub mod ahdsr;
 pub mod buffer;
+pub mod dither;
 #[cfg(feature = "fundsp")]
 pub mod fundsp;
 pub mod smoothing;
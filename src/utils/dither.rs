@@ -0,0 +1,152 @@
+//! Bit depth reduction with dithering and noise shaping.
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Dithering modes, which are applied when reducing the bit depth of audio signals.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DitherMode {
+    /// Plain rounding without dither. Causes audible, signal correlated distortion on quiet
+    /// signals, so this should only be used when the signal already got dithered.
+    None,
+    /// Triangular probability density function dither with a peak amplitude of one LSB, which
+    /// turns quantization distortion into a constant, signal independent noise floor.
+    #[default]
+    Tpdf,
+    /// TPDF dither with first order noise shaping, which moves quantization noise to higher
+    /// frequencies where the ear is less sensitive to it.
+    TpdfNoiseShaped,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Quantizes interleaved float sample buffers to a lower, integer bit depth.
+///
+/// Quantized samples stay float samples in range `[-1.0, 1.0 - LSB]`, but are exact multiples
+/// of the target format's LSB, so they can be losslessly converted to integer samples by
+/// multiplying them with `2^(bit_depth - 1)`. Samples out of range get clipped.
+///
+/// The dither noise generator uses a fixed seed, so quantizations are reproducible.
+#[derive(Debug, Clone)]
+pub struct Quantizer {
+    mode: DitherMode,
+    scale: f32,
+    min_value: f32,
+    max_value: f32,
+    errors: Vec<f32>,
+    rng: SmallRng,
+}
+
+impl Quantizer {
+    /// Create a new quantizer for the given target bit depth and interleaved channel layout.
+    ///
+    /// Panics when the bit depth is not in range `[2, 24]` or the channel count is zero.
+    pub fn new(bit_depth: u32, channel_count: usize, mode: DitherMode) -> Self {
+        assert!((2..=24).contains(&bit_depth), "Invalid bit depth");
+        assert!(channel_count > 0, "Invalid channel count");
+        let scale = (1_u32 << (bit_depth - 1)) as f32;
+        Self {
+            mode,
+            scale,
+            min_value: -scale,
+            max_value: scale - 1.0,
+            errors: vec![0.0; channel_count],
+            rng: SmallRng::seed_from_u64(0x5EED),
+        }
+    }
+
+    /// The quantizer's dither mode.
+    pub fn mode(&self) -> DitherMode {
+        self.mode
+    }
+
+    /// Quantize the given interleaved buffer in place. The buffer must contain whole frames.
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        debug_assert!(
+            buffer.len().is_multiple_of(self.errors.len()),
+            "Expecting a whole number of frames in the buffer"
+        );
+        let channel_count = self.errors.len();
+        match self.mode {
+            DitherMode::None => {
+                for sample in buffer.iter_mut() {
+                    *sample = self.clip((*sample * self.scale).round()) / self.scale;
+                }
+            }
+            DitherMode::Tpdf => {
+                for sample in buffer.iter_mut() {
+                    let dither = self.tpdf();
+                    *sample = self.clip((*sample * self.scale + dither).round()) / self.scale;
+                }
+            }
+            DitherMode::TpdfNoiseShaped => {
+                for frame in buffer.chunks_exact_mut(channel_count) {
+                    for (sample, channel) in frame.iter_mut().zip(0..channel_count) {
+                        // feed back the previous quantization error: E(z) * (1 - z^-1)
+                        let value = *sample * self.scale - self.errors[channel];
+                        let quantized = (value + self.tpdf()).round();
+                        self.errors[channel] = quantized - value;
+                        *sample = self.clip(quantized) / self.scale;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reset noise shaping state, e.g. when starting a new, unrelated signal.
+    pub fn reset(&mut self) {
+        self.errors.fill(0.0);
+    }
+
+    #[inline]
+    fn tpdf(&mut self) -> f32 {
+        self.rng.random::<f32>() - self.rng.random::<f32>()
+    }
+
+    #[inline]
+    fn clip(&self, value: f32) -> f32 {
+        value.clamp(self.min_value, self.max_value)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantize() {
+        let input = (0..4096)
+            .map(|index| (index as f32 * 0.01).sin() * 0.5)
+            .collect::<Vec<_>>();
+        for mode in [
+            DitherMode::None,
+            DitherMode::Tpdf,
+            DitherMode::TpdfNoiseShaped,
+        ] {
+            let mut quantizer = Quantizer::new(8, 2, mode);
+            let mut output = input.clone();
+            quantizer.process(&mut output);
+            // noise shaping adds the current and subtracts the previous quantization error
+            let max_error = match mode {
+                DitherMode::None => 0.5,
+                DitherMode::Tpdf => 1.5,
+                DitherMode::TpdfNoiseShaped => 3.0,
+            } / 128.0;
+            for (i, o) in input.iter().zip(&output) {
+                // samples are on the 8 bit grid
+                assert_eq!((o * 128.0).fract(), 0.0);
+                // and close to the input
+                assert!((i - o).abs() <= max_error, "{mode:?}: {i} -> {o}");
+            }
+        }
+
+        // clips out of range samples
+        let mut quantizer = Quantizer::new(16, 1, DitherMode::Tpdf);
+        let mut output = [2.0, -2.0];
+        quantizer.process(&mut output);
+        assert_eq!(output, [32767.0 / 32768.0, -1.0]);
+    }
+}
@@ -2,5 +2,6 @@
 
 pub mod delay;
 pub mod envelope;
+pub mod fft;
 pub mod filters;
 pub mod lfo;
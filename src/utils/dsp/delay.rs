@@ -264,6 +264,11 @@ impl<const CHANNELS: usize> LookupDelayLine<CHANNELS> {
         delayed_frame
     }
 
+    /// Returns the delay line's delay in sample frames.
+    pub fn delay_frames(&self) -> usize {
+        self.delay_frames
+    }
+
     /// Returns the absolute peak value in the delay line from all channels.
     pub fn peak_value(&self) -> f32 {
         self.peak_value as f32
@@ -0,0 +1,80 @@
+//! Fast Fourier transforms for signal analysis.
+
+use std::f64::consts::PI;
+
+// -------------------------------------------------------------------------------------------------
+
+/// In-place, iterative radix-2 complex FFT with precomputed twiddle factors.
+///
+/// Only supports power of two sizes. All tables get allocated when creating the FFT, so
+/// processing does not allocate and can be used in real-time threads.
+#[derive(Debug, Clone)]
+pub struct Fft {
+    size: usize,
+    twiddles: Vec<(f32, f32)>,
+    bit_reversed: Vec<usize>,
+}
+
+impl Fft {
+    /// Create a new forward FFT of the given power of two size.
+    pub fn new(size: usize) -> Self {
+        assert!(
+            size >= 2 && size.is_power_of_two(),
+            "FFT size must be a power of two"
+        );
+        let twiddles = (0..size / 2)
+            .map(|index| {
+                let phase = -2.0 * PI * index as f64 / size as f64;
+                (phase.cos() as f32, phase.sin() as f32)
+            })
+            .collect();
+        let bits = size.trailing_zeros();
+        let bit_reversed = (0..size)
+            .map(|index| index.reverse_bits() >> (usize::BITS - bits))
+            .collect();
+        Self {
+            size,
+            twiddles,
+            bit_reversed,
+        }
+    }
+
+    /// The FFT's size.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Transform the given real and imaginary parts in place. Both buffers must have the FFT's
+    /// size.
+    pub fn process(&self, real: &mut [f32], imag: &mut [f32]) {
+        assert!(
+            real.len() == self.size && imag.len() == self.size,
+            "Invalid FFT buffer size"
+        );
+        for (index, &reversed) in self.bit_reversed.iter().enumerate() {
+            if index < reversed {
+                real.swap(index, reversed);
+                imag.swap(index, reversed);
+            }
+        }
+        let mut length = 2;
+        while length <= self.size {
+            let half_length = length / 2;
+            let twiddle_step = self.size / length;
+            for start in (0..self.size).step_by(length) {
+                for offset in 0..half_length {
+                    let (twiddle_re, twiddle_im) = self.twiddles[offset * twiddle_step];
+                    let even = start + offset;
+                    let odd = even + half_length;
+                    let odd_re = real[odd] * twiddle_re - imag[odd] * twiddle_im;
+                    let odd_im = real[odd] * twiddle_im + imag[odd] * twiddle_re;
+                    real[odd] = real[even] - odd_re;
+                    imag[odd] = imag[even] - odd_im;
+                    real[even] += odd_re;
+                    imag[even] += odd_im;
+                }
+            }
+            length *= 2;
+        }
+    }
+}
@@ -54,6 +54,9 @@ pub trait AudioResampler: Send + Sync {
     /// returns ResamplerError or (input_consumed, output_written) on success.
     fn process(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize), Error>;
 
+    /// Delay in output sample frames, which the resampler's filters add to the resampled signal.
+    fn latency(&self) -> usize;
+
     /// Update resampler rates.
     fn update(&mut self, input_rate: u32, output_rate: u32) -> Result<(), Error>;
 
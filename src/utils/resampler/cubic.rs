@@ -185,6 +185,11 @@ impl AudioResampler for CubicResampler {
         Ok(result)
     }
 
+    fn latency(&self) -> usize {
+        // interpolates between the current and next input frames, so there's no delay
+        0
+    }
+
     fn update(&mut self, input_rate: u32, output_rate: u32) -> Result<(), Error> {
         self.spec.input_rate = input_rate;
         self.spec.output_rate = output_rate;
@@ -133,6 +133,14 @@ impl AudioResampler for RubatoResampler {
         }
     }
 
+    fn latency(&self) -> usize {
+        if self.spec.input_rate == self.spec.output_rate {
+            0
+        } else {
+            self.resampler.output_delay()
+        }
+    }
+
     fn update(&mut self, input_rate: u32, output_rate: u32) -> Result<(), Error> {
         self.spec.input_rate = input_rate;
         self.spec.output_rate = output_rate;